@@ -1,7 +1,11 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use ahash::HashMap;
 use anyhow::Context;
@@ -9,9 +13,13 @@ use async_trait::async_trait;
 use bls_signatures::{PublicKey, Serialize, Signature};
 use byteorder::{BigEndian, WriteBytesExt};
 use forest_shim::version::NetworkVersion;
-use forest_utils::net::{https_client, HyperBodyExt};
+use forest_utils::{
+    db::KeyValueStore,
+    net::{https_client, HyperBodyExt},
+};
 use fvm_shared::clock::ChainEpoch;
-use parking_lot::RwLock;
+use log::warn;
+use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize as SerdeDeserialize, Serialize as SerdeSerialize};
 use sha2::Digest;
 
@@ -21,6 +29,18 @@ use super::beacon_entries::BeaconEntry;
 /// `LOTUS_IGNORE_DRAND`
 pub const IGNORE_DRAND_VAR: &str = "IGNORE_DRAND";
 
+/// Prefix of the keys of the beacon entries persisted to the database.
+const DRAND_KEY_PREFIX: &[u8] = b"drand/";
+
+/// Timeout of the requests to a `Drand` endpoint, after which the next one is
+/// tried.
+const DRAND_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before retrying an endpoint after a failure, doubled on each
+/// consecutive failure up to [`ENDPOINT_BACKOFF_MAX`].
+const ENDPOINT_BACKOFF_BASE: Duration = Duration::from_secs(1);
+const ENDPOINT_BACKOFF_MAX: Duration = Duration::from_secs(300);
+
 /// Coefficients of the publicly available `Drand` keys.
 /// This is shared by all participants on the `Drand` network.
 #[derive(Clone, Debug, SerdeSerialize, SerdeDeserialize)]
@@ -47,8 +67,8 @@ pub enum DrandNetwork {
 #[derive(Clone)]
 /// Configuration used when initializing a `Drand` beacon.
 pub struct DrandConfig<'a> {
-    /// URL endpoint to send JSON HTTP requests to.
-    pub server: &'static str,
+    /// URL endpoints to send JSON HTTP requests to, tried in order.
+    pub servers: &'a [&'a str],
     /// Info about the beacon chain, used to verify correctness of endpoint.
     pub chain_info: ChainInfo<'a>,
     /// Network type
@@ -177,10 +197,40 @@ pub struct BeaconEntryJson {
     previous_signature: String,
 }
 
+/// Health of a `Drand` endpoint. Endpoints that failed are backed off, and
+/// only tried once the healthy ones failed too.
+#[derive(Debug, Default)]
+struct EndpointHealth {
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl EndpointHealth {
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.retry_at.map_or(true, |retry_at| now >= retry_at)
+    }
+
+    fn record_success(&mut self) {
+        *self = Self::default();
+    }
+
+    fn record_failure(&mut self, now: Instant) {
+        self.failures = self.failures.saturating_add(1);
+        let backoff = ENDPOINT_BACKOFF_BASE
+            .saturating_mul(2u32.saturating_pow(self.failures - 1))
+            .min(ENDPOINT_BACKOFF_MAX);
+        self.retry_at = Some(now + backoff);
+    }
+}
+
 /// `Drand` randomness beacon that can be used to generate randomness for the
 /// Filecoin chain. Primary use is to satisfy the [Beacon] trait.
 pub struct DrandBeacon {
-    url: &'static str,
+    servers: Vec<String>,
+    health: Mutex<Vec<EndpointHealth>>,
+    /// Prefix of the keys of the persisted entries, distinguishing the
+    /// `Drand` chains by public key.
+    key_prefix: Vec<u8>,
 
     pub_key: DrandPublic,
     /// Interval between beacons, in seconds.
@@ -191,6 +241,8 @@ pub struct DrandBeacon {
 
     /// Keeps track of computed beacon entries.
     local_cache: RwLock<HashMap<u64, BeaconEntry>>,
+    /// Persists the verified beacon entries, if set.
+    store: RwLock<Option<Arc<dyn KeyValueStore>>>,
 }
 
 impl DrandBeacon {
@@ -205,9 +257,10 @@ impl DrandBeacon {
         }
 
         let chain_info = &config.chain_info;
+        anyhow::ensure!(!config.servers.is_empty(), "No drand endpoint configured");
 
         if cfg!(debug_assertions) && config.network_type == DrandNetwork::Mainnet {
-            let server = config.server;
+            let server = config.servers[0].to_owned();
             let remote_chain_info = std::thread::spawn(move || {
                 let rt = tokio::runtime::Runtime::new()?;
                 rt.block_on(async {
@@ -228,7 +281,9 @@ impl DrandBeacon {
         }
 
         Ok(Self {
-            url: config.server,
+            servers: config.servers.iter().map(|s| s.to_string()).collect(),
+            health: Mutex::new(config.servers.iter().map(|_| Default::default()).collect()),
+            key_prefix: [DRAND_KEY_PREFIX, chain_info.public_key.as_bytes(), b"/"].concat(),
             pub_key: DrandPublic {
                 coefficient: hex::decode(chain_info.public_key.as_ref())?,
             },
@@ -237,8 +292,84 @@ impl DrandBeacon {
             fil_round_time: interval,
             fil_gen_time: genesis_ts,
             local_cache: Default::default(),
+            store: Default::default(),
         })
     }
+
+    /// Persists the verified beacon entries to `store`, and looks entries up
+    /// in it before fetching them.
+    pub fn set_entry_store(&self, store: Arc<dyn KeyValueStore>) {
+        *self.store.write() = Some(store);
+    }
+
+    fn entry_key(&self, round: u64) -> Vec<u8> {
+        [self.key_prefix.as_slice(), &round.to_be_bytes()].concat()
+    }
+
+    fn read_stored_entry(&self, round: u64) -> Option<BeaconEntry> {
+        let store = self.store.read().clone()?;
+        match store.read_value(&self.entry_key(round)) {
+            Ok(data) => data.map(|data| BeaconEntry::new(round, data)),
+            Err(e) => {
+                warn!("Failed to read drand round {round} from the database: {e}");
+                None
+            }
+        }
+    }
+
+    fn store_entry(&self, entry: &BeaconEntry) {
+        if let Some(store) = self.store.read().as_ref() {
+            if let Err(e) = store.write_value(&self.entry_key(entry.round()), entry.data()) {
+                warn!(
+                    "Failed to write drand round {} to the database: {e}",
+                    entry.round()
+                );
+            }
+        }
+    }
+
+    /// Fetches an entry from the endpoints, trying the healthy ones in order
+    /// first, then the ones backing off.
+    async fn fetch_entry(&self, round: u64) -> anyhow::Result<BeaconEntry> {
+        let now = Instant::now();
+        let (healthy, backing_off): (Vec<_>, Vec<_>) = {
+            let health = self.health.lock();
+            (0..self.servers.len()).partition(|&i| health[i].is_healthy(now))
+        };
+        let mut last_error = None;
+        for i in healthy.into_iter().chain(backing_off) {
+            let server = &self.servers[i];
+            match fetch_entry_from(server, round).await {
+                Ok(entry) => {
+                    self.health.lock()[i].record_success();
+                    return Ok(entry);
+                }
+                Err(e) => {
+                    warn!("Failed to fetch drand round {round} from {server}: {e}");
+                    self.health.lock()[i].record_failure(Instant::now());
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| anyhow::anyhow!("No drand endpoint configured"))
+            .context(format!(
+                "Failed to fetch drand round {round} from any endpoint"
+            )))
+    }
+}
+
+async fn fetch_entry_from(server: &str, round: u64) -> anyhow::Result<BeaconEntry> {
+    let url = format!("{server}/public/{round}");
+    let client = https_client();
+    let request = async {
+        let body = client.get(url.try_into()?).await?.into_body();
+        Ok::<_, anyhow::Error>(body.json::<BeaconEntryJson>().await?)
+    };
+    let resp = tokio::time::timeout(DRAND_REQUEST_TIMEOUT, request)
+        .await
+        .context("Request timed out")??;
+    Ok(BeaconEntry::new(resp.round, hex::decode(resp.signature)?))
 }
 
 #[async_trait]
@@ -259,31 +390,26 @@ impl Beacon for DrandBeacon {
         let sig = Signature::from_bytes(curr.data())?;
         let sig_match = bls_signatures::verify_messages(&sig, &[&digest], &[self.pub_key.key()?]);
 
-        // Cache the result
+        // Cache and persist the result
         let contains_curr = self.local_cache.read().contains_key(&curr.round());
         if sig_match && !contains_curr {
             self.local_cache.write().insert(curr.round(), curr.clone());
+            self.store_entry(curr);
         }
         Ok(sig_match)
     }
 
     async fn entry(&self, round: u64) -> Result<BeaconEntry, anyhow::Error> {
         let cached: Option<BeaconEntry> = self.local_cache.read().get(&round).cloned();
-        match cached {
-            Some(cached_entry) => Ok(cached_entry),
-            None => {
-                let url = format!("{}/public/{}", self.url, round);
-                let client = https_client();
-                let resp: BeaconEntryJson = client
-                    .get(url.try_into()?)
-                    .await?
-                    .into_body()
-                    .json()
-                    .await
-                    .map_err(|e| anyhow::anyhow!("{}", e))?;
-                Ok(BeaconEntry::new(resp.round, hex::decode(resp.signature)?))
-            }
+        if let Some(cached_entry) = cached {
+            return Ok(cached_entry);
+        }
+        // Persisted entries have been verified
+        if let Some(stored_entry) = self.read_stored_entry(round) {
+            self.local_cache.write().insert(round, stored_entry.clone());
+            return Ok(stored_entry);
         }
+        self.fetch_entry(round).await
     }
 
     fn max_beacon_round_for_epoch(
@@ -1,8 +1,14 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+mod drand_fixture;
+
+use std::{collections::HashMap, sync::Arc};
+
+use drand_fixture::DrandFixture;
 use forest_beacon::{Beacon, ChainInfo, DrandBeacon, DrandConfig};
-use serde::{Deserialize, Serialize};
+use forest_utils::db::KeyValueStore;
+use parking_lot::Mutex;
 
 fn new_beacon() -> DrandBeacon {
     DrandBeacon::new(
@@ -10,7 +16,7 @@ fn new_beacon() -> DrandBeacon {
         25,
         // TODO this could maybe be referencing existing config
         &DrandConfig {
-            server: "https://pl-us.incentinet.drand.sh",
+            servers: &["https://pl-us.incentinet.drand.sh"],
             chain_info: ChainInfo {
                 public_key: "922a2e93828ff83345bae533f5172669a26c02dc76d6bf59c80892e12ab1455c229211886f35bb56af6d5bea981024df"
                     .into(),
@@ -22,12 +28,22 @@ fn new_beacon() -> DrandBeacon {
     .unwrap()
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct BeaconEntryJson {
-    round: u64,
-    randomness: String,
-    signature: String,
-    previous_signature: String,
+fn fixture_beacon(fixture: &DrandFixture, servers: &[&str]) -> DrandBeacon {
+    DrandBeacon::new(15904451751, 25, &fixture.config(servers)).unwrap()
+}
+
+#[derive(Default)]
+struct MemoryStore(Mutex<HashMap<Vec<u8>, Vec<u8>>>);
+
+impl KeyValueStore for MemoryStore {
+    fn read_value(&self, key: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.0.lock().get(key).cloned())
+    }
+
+    fn write_values(&self, values: Vec<(Vec<u8>, Vec<u8>)>) -> anyhow::Result<()> {
+        self.0.lock().extend(values);
+        Ok(())
+    }
 }
 
 #[test]
@@ -35,11 +51,71 @@ fn construct_drand_beacon() {
     new_beacon();
 }
 
+#[tokio::test]
+async fn ask_and_verify_beacon_entry() {
+    let fixture = DrandFixture::new(3);
+    let server = fixture.serve();
+    let beacon = fixture_beacon(&fixture, &[server.url.as_str()]);
+
+    let e2 = beacon.entry(2).await.unwrap();
+    let e3 = beacon.entry(3).await.unwrap();
+    assert!(beacon.verify_entry(&e3, &e2).unwrap());
+}
+
 #[tokio::test]
 async fn ask_and_verify_beacon_entry_fail() {
-    let beacon = new_beacon();
+    let fixture = DrandFixture::new(3);
+    let server = fixture.serve();
+    let beacon = fixture_beacon(&fixture, &[server.url.as_str()]);
 
     let e2 = beacon.entry(2).await.unwrap();
     let e3 = beacon.entry(3).await.unwrap();
     assert!(!beacon.verify_entry(&e2, &e3).unwrap());
 }
+
+#[tokio::test]
+async fn fail_over_to_next_endpoint() {
+    let fixture = DrandFixture::new(3);
+    let primary = fixture.serve();
+    let secondary = fixture.serve();
+    primary.set_failing(true);
+    let beacon = fixture_beacon(&fixture, &[primary.url.as_str(), secondary.url.as_str()]);
+
+    beacon.entry(1).await.unwrap();
+    assert_eq!((primary.requests(), secondary.requests()), (1, 1));
+
+    // The failed endpoint is backing off, and tried last
+    beacon.entry(2).await.unwrap();
+    assert_eq!((primary.requests(), secondary.requests()), (1, 2));
+
+    // Endpoints backing off are still tried when all others fail
+    primary.set_failing(false);
+    secondary.set_failing(true);
+    beacon.entry(3).await.unwrap();
+    assert_eq!((primary.requests(), secondary.requests()), (2, 3));
+
+    primary.set_failing(true);
+    assert!(beacon.entry(3).await.is_err());
+}
+
+#[tokio::test]
+async fn verified_entries_are_persisted() {
+    let fixture = DrandFixture::new(3);
+    let server = fixture.serve();
+    let store = Arc::new(MemoryStore::default());
+
+    let beacon = fixture_beacon(&fixture, &[server.url.as_str()]);
+    beacon.set_entry_store(store.clone());
+    let e2 = beacon.entry(2).await.unwrap();
+    let e3 = beacon.entry(3).await.unwrap();
+    assert!(beacon.verify_entry(&e3, &e2).unwrap());
+
+    // A restarted beacon reads the verified entry back without fetching it
+    server.set_failing(true);
+    let beacon = fixture_beacon(&fixture, &[server.url.as_str()]);
+    beacon.set_entry_store(store);
+    let requests = server.requests();
+    assert_eq!(beacon.entry(3).await.unwrap(), e3);
+    assert_eq!(server.requests(), requests);
+    assert!(beacon.entry(2).await.is_err());
+}
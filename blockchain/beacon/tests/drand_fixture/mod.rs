@@ -0,0 +1,138 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Local stand-in for a `Drand` HTTP endpoint, serving a chain of entries
+//! signed with a fixture key, so that the beacon can be tested offline.
+
+use std::{
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use bls_signatures::{PrivateKey, Serialize as _};
+use byteorder::{BigEndian, WriteBytesExt};
+use forest_beacon::{ChainInfo, DrandConfig, DrandNetwork};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BeaconEntryJson {
+    round: u64,
+    randomness: String,
+    signature: String,
+    previous_signature: String,
+}
+
+/// Chain of `Drand` entries, from round 1 up to a given round.
+pub struct DrandFixture {
+    public_key: String,
+    entries: Vec<BeaconEntryJson>,
+}
+
+impl DrandFixture {
+    pub fn new(rounds: u64) -> Self {
+        let key = PrivateKey::new([42; 32]);
+        let mut previous_signature = vec![];
+        let mut entries = vec![];
+        for round in 1..=rounds {
+            // The signature of round `r` is over H(signature of round `r - 1` | r)
+            let mut msg = previous_signature.clone();
+            msg.write_u64::<BigEndian>(round).unwrap();
+            let signature = key.sign(sha2::Sha256::digest(&msg)).as_bytes();
+            entries.push(BeaconEntryJson {
+                round,
+                randomness: hex::encode(sha2::Sha256::digest(&signature)),
+                signature: hex::encode(&signature),
+                previous_signature: hex::encode(&previous_signature),
+            });
+            previous_signature = signature;
+        }
+        Self {
+            public_key: hex::encode(key.public_key().as_bytes()),
+            entries,
+        }
+    }
+
+    /// Configuration of a beacon fetching the entries from `servers`.
+    pub fn config<'a>(&self, servers: &'a [&'a str]) -> DrandConfig<'a> {
+        DrandConfig {
+            servers,
+            chain_info: ChainInfo {
+                public_key: self.public_key.clone().into(),
+                period: 30,
+                ..Default::default()
+            },
+            network_type: DrandNetwork::Incentinet,
+        }
+    }
+
+    /// Serves the entries on a local port, until the runtime shuts down.
+    pub fn serve(&self) -> FixtureServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(ServerState {
+            entries: self.entries.clone(),
+            failing: AtomicBool::new(false),
+            requests: AtomicUsize::new(0),
+        });
+        let app = Router::new()
+            .route("/public/:round", get(public_round))
+            .with_state(state.clone());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+        FixtureServer { url, state }
+    }
+}
+
+struct ServerState {
+    entries: Vec<BeaconEntryJson>,
+    failing: AtomicBool,
+    requests: AtomicUsize,
+}
+
+async fn public_round(
+    State(state): State<Arc<ServerState>>,
+    Path(round): Path<u64>,
+) -> Result<Json<BeaconEntryJson>, StatusCode> {
+    state.requests.fetch_add(1, Ordering::SeqCst);
+    if state.failing.load(Ordering::SeqCst) {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    let index = round.checked_sub(1).ok_or(StatusCode::NOT_FOUND)?;
+    state
+        .entries
+        .get(index as usize)
+        .cloned()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Handle on a running fixture endpoint.
+pub struct FixtureServer {
+    pub url: String,
+    state: Arc<ServerState>,
+}
+
+impl FixtureServer {
+    /// Makes the endpoint answer every request with an error.
+    pub fn set_failing(&self, failing: bool) {
+        self.state.failing.store(failing, Ordering::SeqCst);
+    }
+
+    /// Number of entries requested from the endpoint.
+    pub fn requests(&self) -> usize {
+        self.state.requests.load(Ordering::SeqCst)
+    }
+}
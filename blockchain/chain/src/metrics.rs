@@ -0,0 +1,57 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use lazy_static::lazy_static;
+use prometheus::{
+    core::{AtomicU64, GenericCounter, Opts},
+    Histogram, HistogramOpts,
+};
+
+lazy_static! {
+    pub static ref CHAIN_REORGS_TOTAL: Box<GenericCounter<AtomicU64>> = {
+        let chain_reorgs_total = Box::new(
+            GenericCounter::<AtomicU64>::new(
+                "chain_reorgs_total",
+                "Total number of re-orgs of the heaviest chain",
+            )
+            .expect("Defining the chain_reorgs_total metric must succeed"),
+        );
+        prometheus::default_registry()
+            .register(chain_reorgs_total.clone())
+            .expect(
+                "Registering the chain_reorgs_total metric with the metrics registry must succeed",
+            );
+        chain_reorgs_total
+    };
+    pub static ref CHAIN_REORGS_REFUSED_TOTAL: Box<GenericCounter<AtomicU64>> = {
+        let chain_reorgs_refused_total = Box::new(
+            GenericCounter::<AtomicU64>::new(
+                "chain_reorgs_refused_total",
+                "Total number of heavier forks refused for reverting finalized tipsets",
+            )
+            .expect("Defining the chain_reorgs_refused_total metric must succeed"),
+        );
+        prometheus::default_registry()
+            .register(chain_reorgs_refused_total.clone())
+            .expect("Registering the chain_reorgs_refused_total metric with the metrics registry must succeed");
+        chain_reorgs_refused_total
+    };
+    pub static ref CHAIN_REORG_DEPTH: Box<Histogram> = {
+        let chain_reorg_depth = Box::new(
+            Histogram::with_opts(HistogramOpts {
+                common_opts: Opts::new(
+                    "chain_reorg_depth",
+                    "Number of epochs reverted by re-orgs of the heaviest chain",
+                ),
+                buckets: vec![1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 900.0],
+            })
+            .expect("Defining the chain_reorg_depth metric must succeed"),
+        );
+        prometheus::default_registry()
+            .register(chain_reorg_depth.clone())
+            .expect(
+                "Registering the chain_reorg_depth metric with the metrics registry must succeed",
+            );
+        chain_reorg_depth
+    };
+}
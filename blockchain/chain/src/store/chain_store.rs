@@ -37,7 +37,7 @@ use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_car::CarHeader;
 use fvm_ipld_encoding::Cbor;
 use fvm_shared::clock::ChainEpoch;
-use log::{debug, info, trace, warn};
+use log::{debug, error, info, trace, warn};
 use lru::LruCache;
 use parking_lot::Mutex;
 use serde::{de::DeserializeOwned, Serialize};
@@ -52,8 +52,9 @@ use tokio_util::compat::TokioAsyncWriteCompatExt;
 
 use super::{
     index::{checkpoint_tipsets, ChainIndex},
+    reorg::{reorg_path, ReorgHistory},
     tipset_tracker::TipsetTracker,
-    Error,
+    Error, EventIndex, MessageIndex, ReorgRecord,
 };
 use crate::Scale;
 
@@ -99,6 +100,21 @@ pub struct ChainStore<DB> {
 
     /// File backed validated blocks
     file_backed_validated_blocks: Mutex<FileBacked<HashSet<Cid>>>,
+
+    /// Index of the tipsets messages are included in
+    message_index: Option<Arc<MessageIndex>>,
+
+    /// Index of the events emitted by actors
+    event_index: Option<Arc<EventIndex>>,
+
+    /// Maximum number of epochs a re-org may revert
+    max_reorg_depth: ChainEpoch,
+
+    /// Epoch of the trusted checkpoint, below which re-orgs are refused
+    checkpoint_epoch: Option<ChainEpoch>,
+
+    /// Recent re-orgs of the heaviest chain
+    reorgs: Mutex<ReorgHistory>,
 }
 
 impl<DB> BitswapStoreRead for ChainStore<DB>
@@ -155,6 +171,7 @@ where
             Some(SYNC_PERIOD),
         )?);
 
+        let max_reorg_depth = chain_config.policy.chain_finality;
         let cs = Self {
             publisher,
             chain_index: ChainIndex::new(ts_cache.clone(), db.clone()),
@@ -164,6 +181,11 @@ where
             file_backed_genesis,
             file_backed_heaviest_tipset_keys,
             file_backed_validated_blocks,
+            message_index: None,
+            event_index: None,
+            max_reorg_depth,
+            checkpoint_epoch: None,
+            reorgs: Mutex::new(ReorgHistory::default()),
         };
 
         cs.set_genesis(genesis_block_header)?;
@@ -171,6 +193,57 @@ where
         Ok(cs)
     }
 
+    /// Enables the index of the tipsets messages are included in, kept up to
+    /// date with the head by [`message_index_loop`](super::message_index_loop).
+    pub fn with_message_index(mut self, message_index: MessageIndex) -> Self {
+        self.message_index = Some(Arc::new(message_index));
+        self
+    }
+
+    /// Returns the message index, if enabled.
+    pub fn message_index(&self) -> Option<&Arc<MessageIndex>> {
+        self.message_index.as_ref()
+    }
+
+    /// Enables the index of actor events, recorded when tipsets are executed
+    /// and kept up to date with the head by
+    /// [`event_index_loop`](super::event_index_loop).
+    pub fn with_event_index(mut self, event_index: EventIndex) -> Self {
+        self.event_index = Some(Arc::new(event_index));
+        self
+    }
+
+    /// Returns the event index, if enabled.
+    pub fn event_index(&self) -> Option<&Arc<EventIndex>> {
+        self.event_index.as_ref()
+    }
+
+    /// Sets the maximum number of epochs a re-org may revert, which defaults
+    /// to the chain finality. Heavier forks reverting older tipsets are
+    /// refused.
+    pub fn with_max_reorg_depth(mut self, max_reorg_depth: ChainEpoch) -> Self {
+        self.max_reorg_depth = max_reorg_depth;
+        self
+    }
+
+    /// Returns the maximum number of epochs a re-org may revert.
+    pub fn max_reorg_depth(&self) -> ChainEpoch {
+        self.max_reorg_depth
+    }
+
+    /// Sets the epoch of the trusted checkpoint. Once the head is past it,
+    /// heavier forks reverting the checkpoint tipset are refused.
+    pub fn with_checkpoint_epoch(mut self, checkpoint_epoch: ChainEpoch) -> Self {
+        self.checkpoint_epoch = Some(checkpoint_epoch);
+        self
+    }
+
+    /// Returns the records of the recent re-orgs of the heaviest chain, oldest
+    /// first.
+    pub fn reorgs(&self) -> Vec<ReorgRecord> {
+        self.reorgs.lock().records()
+    }
+
     /// Sets heaviest tipset within `ChainStore` and store its tipset keys in
     /// `{forest_chain_store}/HEAD`
     pub fn set_heaviest_tipset(&self, ts: Arc<Tipset>) -> Result<(), Error> {
@@ -264,19 +337,86 @@ where
         S: Scale,
     {
         // Calculate heaviest weight before matching to avoid deadlock with mutex
-        let heaviest_weight = S::weight(self.blockstore(), &self.heaviest_tipset())?;
+        let head = self.heaviest_tipset();
+        let heaviest_weight = S::weight(self.blockstore(), &head)?;
 
         let new_weight = S::weight(self.blockstore(), ts.as_ref())?;
         let curr_weight = heaviest_weight;
 
         if new_weight > curr_weight {
-            // TODO potentially need to deal with re-orgs here
+            match reorg_path(
+                self,
+                &head,
+                &ts,
+                self.max_reorg_depth,
+                self.checkpoint_epoch,
+            ) {
+                Ok((dropped, ancestor)) if !dropped.is_empty() => {
+                    self.record_reorg(&head, &ts, &ancestor, dropped)
+                }
+                Ok(_) => {}
+                Err(e @ Error::ReorgTooDeep(_)) => {
+                    error!(
+                        "Refusing heavier tipset {:?} at epoch {}, its fork reverts finalized tipsets of the head {:?} at epoch {}",
+                        ts.key(),
+                        ts.epoch(),
+                        head.key(),
+                        head.epoch()
+                    );
+                    crate::metrics::CHAIN_REORGS_REFUSED_TOTAL.inc();
+                    return Err(e);
+                }
+                Err(e @ Error::ReorgBelowCheckpoint(_)) => {
+                    error!(
+                        "Refusing heavier tipset {:?} at epoch {}, its fork reverts the trusted checkpoint below the head {:?} at epoch {}",
+                        ts.key(),
+                        ts.epoch(),
+                        head.key(),
+                        head.epoch()
+                    );
+                    crate::metrics::CHAIN_REORGS_REFUSED_TOTAL.inc();
+                    return Err(e);
+                }
+                Err(e) => return Err(e),
+            }
             info!("New heaviest tipset: {:?}", ts.key());
             self.set_heaviest_tipset(ts)?;
         }
         Ok(())
     }
 
+    fn record_reorg(
+        &self,
+        head: &Tipset,
+        ts: &Tipset,
+        ancestor: &Tipset,
+        dropped: Vec<Arc<Tipset>>,
+    ) {
+        let depth = head.epoch() - ancestor.epoch();
+        warn!(
+            "Re-org of {depth} epochs from {:?} at epoch {} to {:?} at epoch {}",
+            head.key(),
+            head.epoch(),
+            ts.key(),
+            ts.epoch()
+        );
+        crate::metrics::CHAIN_REORGS_TOTAL.inc();
+        crate::metrics::CHAIN_REORG_DEPTH.observe(depth as f64);
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        self.reorgs.lock().push(ReorgRecord {
+            timestamp,
+            depth,
+            old_head: head.key().clone(),
+            old_epoch: head.epoch(),
+            new_head: ts.key().clone(),
+            new_epoch: ts.epoch(),
+            dropped: dropped.iter().map(|ts| ts.key().clone()).collect(),
+        });
+    }
+
     /// Checks metadata file if block has already been validated.
     pub fn is_block_validated(&self, cid: &Cid) -> Result<bool, Error> {
         let validated = self
@@ -8,6 +8,7 @@ use forest_blocks::Error as BlkErr;
 use forest_db::Error as DbErr;
 use fvm_ipld_amt::Error as AmtErr;
 use fvm_ipld_encoding::Error as EncErr;
+use fvm_shared::clock::ChainEpoch;
 use thiserror::Error;
 
 /// Chain error
@@ -37,6 +38,12 @@ pub enum Error {
     /// Amt error
     #[error("State error: {0}")]
     State(String),
+    /// Heavier fork reverting finalized tipsets
+    #[error("Fork reverts tipsets finalized {0} epochs behind the head")]
+    ReorgTooDeep(ChainEpoch),
+    /// Heavier fork reverting the trusted checkpoint
+    #[error("Fork reverts the trusted checkpoint at epoch {0}")]
+    ReorgBelowCheckpoint(ChainEpoch),
     /// Other chain error
     #[error("{0}")]
     Other(String),
@@ -0,0 +1,802 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Index of the events emitted by actors during the execution of tipsets, to
+//! filter them by epoch, emitter and indexed values without executing the
+//! tipsets again.
+//!
+//! The events of a tipset are recorded when the tipset is executed, or read
+//! from the events roots of its receipts when it was not executed locally,
+//! e.g. below a snapshot or a trusted checkpoint. The index follows the last
+//! executed tipset of the chain, i.e. the parent of the head of the
+//! [`ChainStore`]: the canonical tipset of every epoch is kept along with a
+//! summary of its events, so that reverted tipsets are not matched by filters
+//! anymore, and the changes are pushed to the installed filters.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use ahash::{HashMap, HashMapExt};
+use cid::Cid;
+use forest_blocks::{Tipset, TipsetKeys};
+use forest_shim::executor::{Receipt_v3, StampedEvent};
+use forest_utils::db::KeyValueStore;
+use fvm_ipld_amt::{Amt, Amtv0};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{Cbor, RawBytes};
+use fvm_shared::{clock::ChainEpoch, ActorID};
+use log::{debug, warn};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use super::{msg_index::head_change_path, ChainStore, Error};
+
+/// Maximum number of epochs a single query may cover.
+pub const MAX_EVENT_FILTER_RANGE: ChainEpoch = 2880;
+
+/// Flag of the event entries whose value is indexed, the only ones filters
+/// can match on.
+pub const FLAG_INDEXED_VALUE: u64 = 0b10;
+
+/// Maximum number of changes kept for a filter between two polls.
+const MAX_FILTER_CHANGES: usize = 10_000;
+
+/// Duration after which filters that are not polled are removed.
+const FILTER_TTL: Duration = Duration::from_secs(5 * 60);
+
+const EVENTS_KEY_PREFIX: &[u8] = b"event_index/events/";
+const EPOCH_KEY_PREFIX: &[u8] = b"event_index/epoch/";
+const HASH_KEY_PREFIX: &[u8] = b"event_index/hash/";
+const RECORDED_KEY_PREFIX: &[u8] = b"event_index/recorded/";
+const HEAD_KEY: &[u8] = b"event_index_head";
+
+/// Entry of an actor event.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventEntry {
+    pub flags: u64,
+    pub key: String,
+    pub value: RawBytes,
+}
+
+impl EventEntry {
+    /// Returns true if filters can match the value of the entry.
+    pub fn is_indexed(&self) -> bool {
+        self.flags & FLAG_INDEXED_VALUE != 0
+    }
+}
+
+/// Event emitted by an actor while executing a message.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActorEvent {
+    /// ID of the actor that emitted the event.
+    pub emitter: ActorID,
+    /// The message whose execution emitted the event.
+    pub message: Cid,
+    /// Index of the message in the messages of the tipset.
+    pub message_index: u64,
+    pub entries: Vec<EventEntry>,
+}
+
+/// Emitters and indexed values of the events of the canonical tipset at an
+/// epoch, to skip the tipsets without events matching a filter.
+struct EpochSummary {
+    tipset: TipsetKeys,
+    emitters: Vec<ActorID>,
+    values: Vec<RawBytes>,
+}
+
+impl EpochSummary {
+    fn new(tipset: TipsetKeys, events: &[ActorEvent]) -> Self {
+        let mut emitters: Vec<_> = events.iter().map(|event| event.emitter).collect();
+        emitters.sort_unstable();
+        emitters.dedup();
+        let mut values: Vec<_> = events
+            .iter()
+            .flat_map(|event| &event.entries)
+            .filter(|entry| entry.is_indexed())
+            .map(|entry| entry.value.clone())
+            .collect();
+        values.sort_unstable_by(|a, b| a.bytes().cmp(b.bytes()));
+        values.dedup();
+        Self {
+            tipset,
+            emitters,
+            values,
+        }
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        Ok(fvm_ipld_encoding::to_vec(&(
+            &self.tipset,
+            &self.emitters,
+            &self.values,
+        ))?)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let (tipset, emitters, values) = fvm_ipld_encoding::from_slice(bytes)?;
+        Ok(Self {
+            tipset,
+            emitters,
+            values,
+        })
+    }
+}
+
+/// Filter of actor events. Empty lists match everything.
+#[derive(Clone, Debug, Default)]
+pub struct EventFilter {
+    /// First epoch to match. Queries start at the last executed epoch if
+    /// unset, installed filters are not bounded.
+    pub from_epoch: Option<ChainEpoch>,
+    /// Last epoch to match. Queries end at the last executed epoch if unset,
+    /// installed filters are not bounded.
+    pub to_epoch: Option<ChainEpoch>,
+    /// Only match the events of a tipset, in place of the epoch range.
+    pub tipset: Option<TipsetKeys>,
+    /// IDs of the emitters to match.
+    pub emitters: Vec<ActorID>,
+    /// Keys of the indexed entries to match, with the values to match for
+    /// each of them.
+    pub fields: Vec<(String, Vec<Vec<u8>>)>,
+}
+
+impl EventFilter {
+    /// Returns true if the event matches the emitters and fields of the filter.
+    pub fn matches(&self, event: &ActorEvent) -> bool {
+        (self.emitters.is_empty() || self.emitters.contains(&event.emitter))
+            && self.fields.iter().all(|(key, values)| {
+                event.entries.iter().any(|entry| {
+                    entry.is_indexed()
+                        && &entry.key == key
+                        && (values.is_empty()
+                            || values
+                                .iter()
+                                .any(|value| value[..] == entry.value.bytes()[..]))
+                })
+            })
+    }
+
+    /// Returns false if none of the events summarized can match the filter.
+    fn may_match(&self, summary: &EpochSummary) -> bool {
+        (self.emitters.is_empty()
+            || self
+                .emitters
+                .iter()
+                .any(|emitter| summary.emitters.contains(emitter)))
+            && self.fields.iter().all(|(_, values)| {
+                values.is_empty()
+                    || values.iter().any(|value| {
+                        summary
+                            .values
+                            .iter()
+                            .any(|indexed| value[..] == indexed.bytes()[..])
+                    })
+            })
+    }
+
+    /// Returns true if changes at an epoch are relevant to the filter, when
+    /// installed.
+    fn follows(&self, tipset: &TipsetKeys, epoch: ChainEpoch) -> bool {
+        match &self.tipset {
+            Some(key) => key == tipset,
+            None => {
+                self.from_epoch.map_or(true, |from| epoch >= from)
+                    && self.to_epoch.map_or(true, |to| epoch <= to)
+            }
+        }
+    }
+}
+
+/// Event matched by a filter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventMatch {
+    pub event: ActorEvent,
+    /// Index of the event in the events of the tipset.
+    pub index: u64,
+    /// Keys of the tipset whose execution emitted the event.
+    pub tipset: TipsetKeys,
+    pub epoch: ChainEpoch,
+    /// True if the tipset has been reverted.
+    pub reverted: bool,
+}
+
+struct InstalledFilter {
+    filter: EventFilter,
+    changes: VecDeque<EventMatch>,
+    last_polled: Instant,
+}
+
+/// Persistent index of actor events, with the filters polled for changes.
+pub struct EventIndex {
+    store: Box<dyn KeyValueStore>,
+    /// Number of epochs the events are kept for.
+    retention: ChainEpoch,
+    /// Last epoch whose events have expired, the events of older tipsets are
+    /// not recorded.
+    expired_epoch: AtomicI64,
+    /// Serializes updates of the index.
+    update_lock: Mutex<()>,
+    filters: Mutex<HashMap<u64, InstalledFilter>>,
+    next_filter_id: AtomicU64,
+}
+
+impl EventIndex {
+    pub fn new(store: impl KeyValueStore + 'static, retention: ChainEpoch) -> Self {
+        Self {
+            store: Box::new(store),
+            retention,
+            expired_epoch: AtomicI64::new(ChainEpoch::MIN),
+            update_lock: Mutex::new(()),
+            filters: Mutex::new(HashMap::new()),
+            next_filter_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Returns the number of epochs the events are kept for.
+    pub fn retention(&self) -> ChainEpoch {
+        self.retention
+    }
+
+    /// Records the events emitted by the execution of a tipset.
+    pub fn record(&self, tipset: &Tipset, events: &[ActorEvent]) -> Result<(), Error> {
+        if events.is_empty() || tipset.epoch() <= self.expired_epoch.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let _guard = self.update_lock.lock();
+        // The tipsets recorded at each epoch, canonical or not, are kept to
+        // drop their events once they expire
+        let mut recorded = self.recorded(tipset.epoch())?;
+        if !recorded.contains(tipset.key()) {
+            recorded.push(tipset.key().clone());
+        }
+        self.store.write_values(vec![
+            (
+                events_key(tipset.key())?,
+                fvm_ipld_encoding::to_vec(&events)?,
+            ),
+            (
+                recorded_key(tipset.epoch()),
+                fvm_ipld_encoding::to_vec(&recorded)?,
+            ),
+        ])?;
+        Ok(())
+    }
+
+    /// Returns the keys of the tipsets whose events were recorded at an epoch.
+    fn recorded(&self, epoch: ChainEpoch) -> Result<Vec<TipsetKeys>, Error> {
+        match self.store.read_value(&recorded_key(epoch))? {
+            Some(bytes) if !bytes.is_empty() => Ok(fvm_ipld_encoding::from_slice(&bytes)?),
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Returns the events recorded for a tipset.
+    pub fn tipset_events(&self, tipset: &TipsetKeys) -> Result<Vec<ActorEvent>, Error> {
+        match self.store.read_value(&events_key(tipset)?)? {
+            Some(bytes) if !bytes.is_empty() => Ok(fvm_ipld_encoding::from_slice(&bytes)?),
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Returns the keys of the executed tipset the index is up to date with.
+    pub fn head(&self) -> Result<Option<TipsetKeys>, Error> {
+        match self.store.read_value(HEAD_KEY)? {
+            Some(bytes) => Ok(Some(fvm_ipld_encoding::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the keys of an indexed tipset from the hash of its keys, i.e.
+    /// the digest of their [`Cid`].
+    pub fn tipset_by_hash(&self, hash: &[u8]) -> Result<Option<TipsetKeys>, Error> {
+        match self.store.read_value(&[HASH_KEY_PREFIX, hash].concat())? {
+            Some(bytes) if !bytes.is_empty() => Ok(Some(fvm_ipld_encoding::from_slice(&bytes)?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn summary(&self, epoch: ChainEpoch) -> Result<Option<EpochSummary>, Error> {
+        match self.store.read_value(&epoch_key(epoch))? {
+            Some(bytes) if !bytes.is_empty() => Ok(Some(EpochSummary::decode(&bytes)?)),
+            // Empty values are reverted or expired epochs
+            _ => Ok(None),
+        }
+    }
+
+    /// Brings the index up to date with the last executed tipset, the parent
+    /// of `head`, reverting the tipsets of the previous one that are not in
+    /// its chain, and applying the ones that were not indexed yet.
+    pub fn update<DB>(&self, cs: &ChainStore<DB>, head: &Arc<Tipset>) -> Result<(), Error>
+    where
+        DB: Blockstore + Send + Sync,
+    {
+        if head.epoch() == 0 {
+            return Ok(());
+        }
+        let executed = cs.tipset_from_keys(head.parents())?;
+        let _guard = self.update_lock.lock();
+
+        let previous = match self.head()? {
+            Some(key) if &key == executed.key() => return Ok(()),
+            Some(key) => cs.tipset_from_keys(&key).ok(),
+            None => None,
+        };
+        // Last epoch whose events were dropped by the previous updates
+        let mut expired_epoch = previous.as_ref().unwrap_or(&executed).epoch() - self.retention;
+        let (reverts, applies) = head_change_path(cs, previous, executed.clone(), self.retention);
+
+        let mut batch = HashMap::new();
+        let mut changes = vec![];
+        for ts in &reverts {
+            batch.insert(epoch_key(ts.epoch()), vec![]);
+            batch.insert(events_key(ts.key())?, vec![]);
+            batch.insert(hash_key(ts.key())?, vec![]);
+            changes.extend(self.tipset_matches(ts, true)?);
+        }
+        for (i, ts) in applies.iter().enumerate().rev() {
+            let mut events = self.tipset_events(ts.key())?;
+            if events.is_empty() {
+                // The receipts of a tipset are in its child
+                let child = if i == 0 { head } else { &applies[i - 1] };
+                events = receipt_events(cs, ts, child).unwrap_or_else(|e| {
+                    debug!("No events read from receipts at epoch {}: {e}", ts.epoch());
+                    vec![]
+                });
+                if !events.is_empty() {
+                    batch.insert(events_key(ts.key())?, fvm_ipld_encoding::to_vec(&events)?);
+                }
+            }
+            batch.insert(
+                epoch_key(ts.epoch()),
+                EpochSummary::new(ts.key().clone(), &events).encode()?,
+            );
+            batch.insert(hash_key(ts.key())?, fvm_ipld_encoding::to_vec(ts.key())?);
+            changes.extend(event_matches(ts, events, false));
+
+            // Drop the events that are now older than the retention, of the
+            // canonical tipsets and of the forks alike
+            for expired in (expired_epoch + 1).max(0)..=ts.epoch() - self.retention {
+                let summary = match batch.get(&epoch_key(expired)) {
+                    Some(bytes) if !bytes.is_empty() => Some(EpochSummary::decode(bytes)?),
+                    Some(_) => None,
+                    None => self.summary(expired)?,
+                };
+                let recorded = self.recorded(expired)?;
+                for tipset in summary
+                    .map(|summary| summary.tipset)
+                    .iter()
+                    .chain(&recorded)
+                {
+                    batch.insert(events_key(tipset)?, vec![]);
+                    batch.insert(hash_key(tipset)?, vec![]);
+                }
+                batch.insert(epoch_key(expired), vec![]);
+                batch.insert(recorded_key(expired), vec![]);
+            }
+            expired_epoch = expired_epoch.max(ts.epoch() - self.retention);
+        }
+        debug!(
+            "Indexed events of {} tipsets, reverted {} tipsets",
+            applies.len(),
+            reverts.len()
+        );
+
+        let mut values: Vec<_> = batch.into_iter().collect();
+        values.push((
+            HEAD_KEY.to_vec(),
+            fvm_ipld_encoding::to_vec(executed.key())?,
+        ));
+        self.store.write_values(values)?;
+        self.expired_epoch.store(expired_epoch, Ordering::Relaxed);
+
+        self.push_changes(changes);
+        Ok(())
+    }
+
+    fn tipset_matches(&self, ts: &Tipset, reverted: bool) -> Result<Vec<EventMatch>, Error> {
+        Ok(event_matches(ts, self.tipset_events(ts.key())?, reverted))
+    }
+
+    fn push_changes(&self, changes: Vec<EventMatch>) {
+        let now = Instant::now();
+        let mut filters = self.filters.lock();
+        filters.retain(|_, installed| now.duration_since(installed.last_polled) < FILTER_TTL);
+        for installed in filters.values_mut() {
+            for change in &changes {
+                if installed.filter.follows(&change.tipset, change.epoch)
+                    && installed.filter.matches(&change.event)
+                {
+                    if installed.changes.len() == MAX_FILTER_CHANGES {
+                        installed.changes.pop_front();
+                    }
+                    installed.changes.push_back(change.clone());
+                }
+            }
+        }
+    }
+
+    /// Returns the indexed events matching a filter, from the oldest.
+    pub fn events<DB>(
+        &self,
+        cs: &ChainStore<DB>,
+        filter: &EventFilter,
+    ) -> Result<Vec<EventMatch>, Error>
+    where
+        DB: Blockstore + Send + Sync,
+    {
+        if let Some(key) = &filter.tipset {
+            let ts = cs.tipset_from_keys(key)?;
+            return Ok(self
+                .tipset_matches(&ts, false)?
+                .into_iter()
+                .filter(|m| filter.matches(&m.event))
+                .collect());
+        }
+
+        let head_epoch = match self.head()? {
+            Some(key) => cs.tipset_from_keys(&key)?.epoch(),
+            None => return Ok(vec![]),
+        };
+        let from = filter.from_epoch.unwrap_or(head_epoch);
+        let to = filter.to_epoch.unwrap_or(head_epoch).min(head_epoch);
+        if to - from > MAX_EVENT_FILTER_RANGE {
+            return Err(Error::Other(format!(
+                "Epoch range {from} to {to} is larger than the maximum of {MAX_EVENT_FILTER_RANGE} epochs"
+            )));
+        }
+
+        let mut matches = vec![];
+        for epoch in from.max(head_epoch - self.retention + 1)..=to {
+            let summary = match self.summary(epoch)? {
+                Some(summary) if filter.may_match(&summary) => summary,
+                _ => continue,
+            };
+            for (index, event) in self.tipset_events(&summary.tipset)?.into_iter().enumerate() {
+                if filter.matches(&event) {
+                    matches.push(EventMatch {
+                        event,
+                        index: index as u64,
+                        tipset: summary.tipset.clone(),
+                        epoch,
+                        reverted: false,
+                    });
+                }
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Installs a filter, whose matching changes are collected until polled,
+    /// and returns its ID.
+    pub fn install_filter(&self, filter: EventFilter) -> u64 {
+        let id = self.next_filter_id.fetch_add(1, Ordering::Relaxed);
+        self.filters.lock().insert(
+            id,
+            InstalledFilter {
+                filter,
+                changes: VecDeque::new(),
+                last_polled: Instant::now(),
+            },
+        );
+        id
+    }
+
+    /// Removes a filter, returning true if it was installed.
+    pub fn uninstall_filter(&self, id: u64) -> bool {
+        self.filters.lock().remove(&id).is_some()
+    }
+
+    /// Returns the changes matched by a filter since it was last polled, or
+    /// `None` if the filter is not installed.
+    pub fn filter_changes(&self, id: u64) -> Option<Vec<EventMatch>> {
+        let mut filters = self.filters.lock();
+        let installed = filters.get_mut(&id)?;
+        installed.last_polled = Instant::now();
+        Some(installed.changes.drain(..).collect())
+    }
+}
+
+fn event_matches(ts: &Tipset, events: Vec<ActorEvent>, reverted: bool) -> Vec<EventMatch> {
+    events
+        .into_iter()
+        .enumerate()
+        .map(|(index, event)| EventMatch {
+            event,
+            index: index as u64,
+            tipset: ts.key().clone(),
+            epoch: ts.epoch(),
+            reverted,
+        })
+        .collect()
+}
+
+/// Reads the events emitted by the execution of a tipset from the events
+/// roots of its receipts, which are in the next tipset `child`.
+fn receipt_events<DB>(
+    cs: &ChainStore<DB>,
+    ts: &Tipset,
+    child: &Tipset,
+) -> Result<Vec<ActorEvent>, Error>
+where
+    DB: Blockstore + Send + Sync,
+{
+    let db = cs.blockstore();
+    let messages = cs.messages_for_tipset(ts)?;
+    let receipts = Amtv0::<Receipt_v3, _>::load(
+        child
+            .blocks()
+            .first()
+            .expect("tipsets have blocks")
+            .message_receipts(),
+        db,
+    )?;
+    let mut events = vec![];
+    for (message_index, message) in messages.iter().enumerate() {
+        let events_root = match receipts.get(message_index as u64)? {
+            Some(Receipt_v3 {
+                events_root: Some(root),
+                ..
+            }) => *root,
+            _ => continue,
+        };
+        let stamped_events = Amt::<StampedEvent, _>::load(&events_root, db)?;
+        for i in 0..stamped_events.count() {
+            let stamped = match stamped_events.get(i)? {
+                Some(stamped) => stamped,
+                None => continue,
+            };
+            events.push(ActorEvent {
+                emitter: stamped.emitter,
+                message: message.cid()?,
+                message_index: message_index as u64,
+                entries: stamped
+                    .event
+                    .entries
+                    .iter()
+                    .map(|entry| EventEntry {
+                        flags: entry.flags.bits(),
+                        key: entry.key.clone(),
+                        value: RawBytes::new(entry.value.to_vec()),
+                    })
+                    .collect(),
+            });
+        }
+    }
+    Ok(events)
+}
+
+fn events_key(tipset: &TipsetKeys) -> Result<Vec<u8>, Error> {
+    Ok([EVENTS_KEY_PREFIX, &tipset.cid()?.to_bytes()].concat())
+}
+
+fn hash_key(tipset: &TipsetKeys) -> Result<Vec<u8>, Error> {
+    Ok([HASH_KEY_PREFIX, tipset.cid()?.hash().digest()].concat())
+}
+
+fn epoch_key(epoch: ChainEpoch) -> Vec<u8> {
+    [EPOCH_KEY_PREFIX, &epoch.to_be_bytes()[..]].concat()
+}
+
+fn recorded_key(epoch: ChainEpoch) -> Vec<u8> {
+    [RECORDED_KEY_PREFIX, &epoch.to_be_bytes()[..]].concat()
+}
+
+/// Keeps the event index of the [`ChainStore`] up to date with the parent of
+/// its head, the last tipset whose events are known.
+pub async fn event_index_loop<DB>(cs: Arc<ChainStore<DB>>) -> anyhow::Result<()>
+where
+    DB: Blockstore + Send + Sync,
+{
+    let index = match cs.event_index() {
+        Some(index) => index.clone(),
+        None => return Ok(()),
+    };
+    let mut subscriber = cs.publisher().subscribe();
+    loop {
+        if let Err(e) = index.update(&cs, &cs.heaviest_tipset()) {
+            warn!("Failed to update the event index: {e}");
+        }
+        match subscriber.recv().await {
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::{Code::Blake2b256, MultihashDigest};
+    use forest_db::MemoryDB;
+    use fvm_ipld_encoding::DAG_CBOR;
+    use fvm_shared3::{
+        error::ExitCode,
+        event::{ActorEvent as ActorEvent_v3, Entry, Flags},
+    };
+
+    use super::*;
+    use crate::store::test_chain::{message, TestChain};
+
+    fn event(emitter: ActorID, entries: &[(u64, &str, &[u8])]) -> ActorEvent {
+        ActorEvent {
+            emitter,
+            message: Cid::new_v1(DAG_CBOR, Blake2b256.digest(b"message")),
+            message_index: 0,
+            entries: entries
+                .iter()
+                .map(|&(flags, key, value)| EventEntry {
+                    flags,
+                    key: key.to_string(),
+                    value: RawBytes::new(value.to_vec()),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn filter_matches_emitters_and_indexed_fields() {
+        let events = [
+            event(
+                1001,
+                &[(FLAG_INDEXED_VALUE, "t1", b"transfer"), (0, "d", b"data")],
+            ),
+            event(1002, &[(FLAG_INDEXED_VALUE, "t1", b"approval")]),
+        ];
+        let summary = EpochSummary::new(TipsetKeys::new(vec![]), &events);
+
+        let filter = EventFilter {
+            emitters: vec![1001],
+            fields: vec![("t1".into(), vec![b"transfer".to_vec()])],
+            ..Default::default()
+        };
+        assert!(filter.may_match(&summary));
+        assert!(filter.matches(&events[0]));
+        assert!(!filter.matches(&events[1]));
+
+        // Entries whose value is not indexed are not matched
+        let filter = EventFilter {
+            fields: vec![("d".into(), vec![b"data".to_vec()])],
+            ..Default::default()
+        };
+        assert!(!filter.may_match(&summary));
+        assert!(!filter.matches(&events[0]));
+
+        let filter = EventFilter {
+            emitters: vec![1003],
+            ..Default::default()
+        };
+        assert!(!filter.may_match(&summary));
+        assert!(EventFilter::default().matches(&events[1]));
+    }
+
+    fn emitters(matches: &[EventMatch]) -> Vec<(ActorID, bool)> {
+        matches
+            .iter()
+            .map(|m| (m.event.emitter, m.reverted))
+            .collect()
+    }
+
+    #[test]
+    fn update_applies_and_reverts() {
+        let chain = TestChain::new();
+        let index = EventIndex::new(MemoryDB::default(), 100);
+        let a1 = chain.child(&chain.genesis, 1, &[]);
+        let a2 = chain.child(&a1, 1, &[]);
+        let a3 = chain.chain(&a2, 1, 1);
+        index.record(&a1, &[event(1001, &[])]).unwrap();
+        index.record(&a2, &[event(1002, &[])]).unwrap();
+        let filter = EventFilter {
+            from_epoch: Some(0),
+            ..Default::default()
+        };
+        let id = index.install_filter(filter.clone());
+
+        // The head is not executed yet, its parent is the last executed tipset
+        index.update(&chain.cs, &a3).unwrap();
+        assert_eq!(index.head().unwrap().as_ref(), Some(a2.key()));
+        let matches = index.events(&chain.cs, &filter).unwrap();
+        assert_eq!(emitters(&matches), [(1001, false), (1002, false)]);
+        assert_eq!(matches[1].epoch, 2);
+        assert_eq!(
+            emitters(&index.filter_changes(id).unwrap()),
+            [(1001, false), (1002, false)]
+        );
+
+        // A heavier fork reverting both tipsets
+        let b1 = chain.child(&chain.genesis, 2, &[]);
+        index.record(&b1, &[event(1003, &[])]).unwrap();
+        let b4 = chain.chain(&b1, 2, 3);
+        index.update(&chain.cs, &b4).unwrap();
+        let matches = index.events(&chain.cs, &filter).unwrap();
+        assert_eq!(emitters(&matches), [(1003, false)]);
+        assert_eq!(matches[0].tipset, *b1.key());
+        let mut changes = emitters(&index.filter_changes(id).unwrap());
+        changes.sort();
+        assert_eq!(changes, [(1001, true), (1002, true), (1003, false)]);
+        let b1_hash = b1.key().cid().unwrap().hash().digest().to_vec();
+        assert_eq!(
+            index.tipset_by_hash(&b1_hash).unwrap().as_ref(),
+            Some(b1.key())
+        );
+
+        // The events of the reverted tipsets are dropped
+        assert!(index.tipset_events(a1.key()).unwrap().is_empty());
+        let a1_hash = a1.key().cid().unwrap().hash().digest().to_vec();
+        assert_eq!(index.tipset_by_hash(&a1_hash).unwrap(), None);
+    }
+
+    #[test]
+    fn expired_events_are_dropped() {
+        let chain = TestChain::new();
+        let index = EventIndex::new(MemoryDB::default(), 2);
+        let a1 = chain.child(&chain.genesis, 1, &[]);
+        let a2 = chain.child(&a1, 1, &[]);
+        index.record(&a1, &[event(1001, &[])]).unwrap();
+        index.record(&a2, &[event(1002, &[])]).unwrap();
+        index.update(&chain.cs, &chain.child(&a2, 1, &[])).unwrap();
+
+        // A fork tipset executed but never part of the indexed chain
+        let b1 = chain.child(&chain.genesis, 2, &[]);
+        index.record(&b1, &[event(1003, &[])]).unwrap();
+
+        // Epoch 1 expires once epoch 3 is executed
+        let a4 = chain.chain(&a2, 1, 2);
+        index.update(&chain.cs, &a4).unwrap();
+        assert!(index.tipset_events(a1.key()).unwrap().is_empty());
+        assert!(index.tipset_events(b1.key()).unwrap().is_empty());
+        assert_eq!(index.tipset_events(a2.key()).unwrap().len(), 1);
+
+        // Tipsets older than the retention are not recorded anymore
+        index.record(&b1, &[event(1003, &[])]).unwrap();
+        assert!(index.tipset_events(b1.key()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn events_of_receipts() {
+        let chain = TestChain::new();
+        let index = EventIndex::new(MemoryDB::default(), 100);
+        let messages = [message(10, 0), message(11, 0)];
+        let a1 = chain.child(&chain.genesis, 1, &messages);
+        let stamped = StampedEvent::new(
+            1001,
+            ActorEvent_v3::from(vec![Entry {
+                flags: Flags::FLAG_INDEXED_ALL,
+                key: "t1".into(),
+                value: b"transfer".to_vec(),
+            }]),
+        );
+        let events_root = Amt::new_from_iter(chain.cs.blockstore(), [stamped]).unwrap();
+        let receipt = |events_root| Receipt_v3 {
+            exit_code: ExitCode::OK,
+            return_data: Default::default(),
+            gas_used: 0,
+            events_root,
+        };
+        let a2 =
+            chain.child_with_receipts(&a1, 1, &[], &[receipt(None), receipt(Some(events_root))]);
+
+        // The first tipset was not executed locally, its events are read from
+        // the receipts in the second one
+        index.update(&chain.cs, &a2).unwrap();
+        let events = index.tipset_events(a1.key()).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].emitter, 1001);
+        assert_eq!(events[0].message_index, 1);
+        assert_eq!(
+            events[0].message,
+            chain.cs.messages_for_tipset(&a1).unwrap()[1].cid().unwrap()
+        );
+        let filter = EventFilter {
+            fields: vec![("t1".into(), vec![b"transfer".to_vec()])],
+            ..Default::default()
+        };
+        assert_eq!(index.events(&chain.cs, &filter).unwrap().len(), 1);
+    }
+}
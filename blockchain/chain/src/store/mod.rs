@@ -4,7 +4,19 @@
 pub mod base_fee;
 mod chain_store;
 mod errors;
+mod event_index;
 mod index;
+mod msg_index;
+mod reorg;
+#[cfg(test)]
+mod test_chain;
 mod tipset_tracker;
 
-pub use self::{base_fee::*, chain_store::*, errors::*};
+pub use self::{
+    base_fee::*,
+    chain_store::*,
+    errors::*,
+    event_index::*,
+    msg_index::*,
+    reorg::{ReorgRecord, MAX_REORG_RECORDS},
+};
@@ -0,0 +1,410 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Index of the tipsets messages are included in, keyed by message [`Cid`], so
+//! that the messages already on chain are found without walking the chain
+//! back from the head.
+//!
+//! The index follows the head of the [`ChainStore`]: on every head change, the
+//! messages of the tipsets dropped from the chain are removed from the index
+//! and the messages of the tipsets added to it are indexed.
+
+use std::sync::Arc;
+
+use ahash::{HashMap, HashMapExt};
+use cid::Cid;
+use forest_blocks::{Tipset, TipsetKeys};
+use forest_shim::clock::EPOCHS_IN_DAY;
+use forest_utils::db::KeyValueStore;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::Cbor;
+use fvm_shared::clock::ChainEpoch;
+use log::{debug, warn};
+use parking_lot::Mutex;
+use tokio::sync::broadcast::error::RecvError;
+
+use super::{ChainStore, Error};
+
+/// Maximum number of epochs indexed behind the head, when catching up with
+/// the head or backfilling the index of a new node.
+pub const MESSAGE_INDEX_DEPTH: ChainEpoch = 2 * EPOCHS_IN_DAY;
+
+const MESSAGE_KEY_PREFIX: &[u8] = b"msg_index/";
+const HEAD_KEY: &[u8] = b"msg_index_head";
+/// Number of tipsets indexed between two writes when backfilling the index.
+const BACKFILL_BATCH_TIPSETS: usize = 100;
+
+/// Location of a message on chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageLocation {
+    /// Keys of the tipset the message is included in.
+    pub tipset: TipsetKeys,
+    /// Epoch of the tipset the message is included in.
+    pub epoch: ChainEpoch,
+    /// Index of the message in the messages of the tipset, which is also the
+    /// index of its receipt in the next tipset.
+    pub index: u64,
+}
+
+/// Persistent index from message [`Cid`] to [`MessageLocation`].
+pub struct MessageIndex {
+    store: Box<dyn KeyValueStore>,
+    /// Serializes updates of the index.
+    update_lock: Mutex<()>,
+}
+
+impl MessageIndex {
+    pub fn new(store: impl KeyValueStore + 'static) -> Self {
+        Self {
+            store: Box::new(store),
+            update_lock: Mutex::new(()),
+        }
+    }
+
+    /// Returns the location of a message, if it has been indexed.
+    pub fn get(&self, message: &Cid) -> Result<Option<MessageLocation>, Error> {
+        match self.store.read_value(&message_key(message))? {
+            Some(bytes) => decode_location(&bytes),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the keys of the head the index is up to date with.
+    pub fn head(&self) -> Result<Option<TipsetKeys>, Error> {
+        match self.store.read_value(HEAD_KEY)? {
+            Some(bytes) => Ok(Some(fvm_ipld_encoding::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Brings the index up to date with a new head, reverting the tipsets of
+    /// the previous head that are not in the chain of the new head, and
+    /// applying the ones that were not indexed yet.
+    pub fn update<DB>(&self, cs: &ChainStore<DB>, head: &Arc<Tipset>) -> Result<(), Error>
+    where
+        DB: Blockstore + Send + Sync,
+    {
+        let _guard = self.update_lock.lock();
+
+        let previous = match self.head()? {
+            Some(key) if &key == head.key() => return Ok(()),
+            Some(key) => cs.tipset_from_keys(&key).ok(),
+            None => None,
+        };
+        let (reverts, applies) = head_change_path(cs, previous, head.clone(), MESSAGE_INDEX_DEPTH);
+
+        let mut batch = HashMap::new();
+        for ts in &reverts {
+            self.revert(cs, ts, &mut batch)?;
+        }
+        for ts in applies.iter().rev() {
+            if let Err(e) = self.apply(cs, ts, &mut batch) {
+                // The messages of older tipsets are not in the store, e.g. after
+                // importing a snapshot
+                debug!("Skip indexing messages at epoch {}: {e}", ts.epoch());
+            }
+        }
+        debug!(
+            "Indexed messages of {} tipsets, reverted {} tipsets",
+            applies.len(),
+            reverts.len()
+        );
+
+        let mut values: Vec<_> = batch.into_iter().collect();
+        values.push((HEAD_KEY.to_vec(), fvm_ipld_encoding::to_vec(head.key())?));
+        self.store.write_values(values)?;
+        Ok(())
+    }
+
+    /// Indexes the messages of the chain of `head`, from its epoch down to
+    /// `to_epoch`, so that messages included more than
+    /// [`MESSAGE_INDEX_DEPTH`] epochs behind the head are found too. Returns
+    /// the number of tipsets indexed.
+    pub fn backfill<DB>(
+        &self,
+        cs: &ChainStore<DB>,
+        head: Arc<Tipset>,
+        to_epoch: ChainEpoch,
+    ) -> Result<usize, Error>
+    where
+        DB: Blockstore + Send + Sync,
+    {
+        let mut batch = HashMap::new();
+        let mut indexed = 0;
+        let mut ts = head;
+        while ts.epoch() >= to_epoch.max(1) {
+            for (index, message) in cs.messages_for_tipset(&ts)?.iter().enumerate() {
+                let cid = message.cid()?;
+                let key = message_key(&cid);
+                // The chain is walked backwards, keep the earliest inclusion
+                let location = match batch.get(&key) {
+                    Some(bytes) => decode_location(bytes)?,
+                    None => self.get(&cid)?,
+                };
+                if !matches!(location, Some(location) if location.epoch <= ts.epoch()) {
+                    batch.insert(key, encode_location(&ts, index)?);
+                }
+            }
+            indexed += 1;
+            if indexed % BACKFILL_BATCH_TIPSETS == 0 {
+                let _guard = self.update_lock.lock();
+                self.store.write_values(batch.drain().collect())?;
+            }
+            ts = cs.tipset_from_keys(ts.parents())?;
+        }
+        let _guard = self.update_lock.lock();
+        self.store.write_values(batch.into_iter().collect())?;
+        debug!("Backfilled the message index with {indexed} tipsets");
+        Ok(indexed)
+    }
+
+    fn apply<DB>(
+        &self,
+        cs: &ChainStore<DB>,
+        ts: &Tipset,
+        batch: &mut HashMap<Vec<u8>, Vec<u8>>,
+    ) -> Result<(), Error>
+    where
+        DB: Blockstore + Send + Sync,
+    {
+        for (index, message) in cs.messages_for_tipset(ts)?.iter().enumerate() {
+            let key = message_key(&message.cid()?);
+            // Messages included again in later tipsets are not executed again,
+            // keep the first inclusion
+            let indexed = match batch.get(&key) {
+                Some(bytes) => !bytes.is_empty(),
+                None => self.get(&message.cid()?)?.is_some(),
+            };
+            if !indexed {
+                batch.insert(key, encode_location(ts, index)?);
+            }
+        }
+        Ok(())
+    }
+
+    fn revert<DB>(
+        &self,
+        cs: &ChainStore<DB>,
+        ts: &Tipset,
+        batch: &mut HashMap<Vec<u8>, Vec<u8>>,
+    ) -> Result<(), Error>
+    where
+        DB: Blockstore + Send + Sync,
+    {
+        for message in cs.messages_for_tipset(ts)? {
+            let cid = message.cid()?;
+            if let Some(location) = self.get(&cid)? {
+                if &location.tipset == ts.key() {
+                    batch.insert(message_key(&cid), vec![]);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn message_key(message: &Cid) -> Vec<u8> {
+    [MESSAGE_KEY_PREFIX, &message.to_bytes()].concat()
+}
+
+fn encode_location(ts: &Tipset, index: usize) -> Result<Vec<u8>, Error> {
+    Ok(fvm_ipld_encoding::to_vec(&(
+        ts.key(),
+        ts.epoch(),
+        index as u64,
+    ))?)
+}
+
+fn decode_location(bytes: &[u8]) -> Result<Option<MessageLocation>, Error> {
+    // Empty values are messages removed from the index
+    if bytes.is_empty() {
+        return Ok(None);
+    }
+    let (tipset, epoch, index) = fvm_ipld_encoding::from_slice(bytes)?;
+    Ok(Some(MessageLocation {
+        tipset,
+        epoch,
+        index,
+    }))
+}
+
+/// Returns the tipsets to revert, from the previous head down, and the
+/// tipsets to apply, from the new head down, to go from the previous head to
+/// the new one. At most `depth` epochs are applied.
+pub(super) fn head_change_path<DB>(
+    cs: &ChainStore<DB>,
+    previous: Option<Arc<Tipset>>,
+    head: Arc<Tipset>,
+    depth: ChainEpoch,
+) -> (Vec<Arc<Tipset>>, Vec<Arc<Tipset>>)
+where
+    DB: Blockstore + Send + Sync,
+{
+    let lowest_epoch = head.epoch() - depth;
+    let mut reverts = vec![];
+    let mut applies = vec![];
+    let mut old = previous;
+    let mut new = Some(head);
+    while let Some(new_ts) = new.clone() {
+        if new_ts.epoch() < lowest_epoch {
+            // The previous head is too far behind, the reverts are not
+            // relevant anymore
+            reverts.clear();
+            break;
+        }
+        match &old {
+            Some(old_ts) if old_ts.key() == new_ts.key() => break,
+            Some(old_ts) if old_ts.epoch() >= new_ts.epoch() => {
+                reverts.push(old_ts.clone());
+                old = if old_ts.epoch() > 0 {
+                    cs.tipset_from_keys(old_ts.parents()).ok()
+                } else {
+                    None
+                };
+            }
+            _ => {
+                if new_ts.epoch() == 0 {
+                    // Genesis has no messages
+                    break;
+                }
+                new = cs.tipset_from_keys(new_ts.parents()).ok();
+                applies.push(new_ts);
+            }
+        }
+    }
+    (reverts, applies)
+}
+
+/// Keeps the message index of the [`ChainStore`] up to date with its head.
+pub async fn message_index_loop<DB>(cs: Arc<ChainStore<DB>>) -> anyhow::Result<()>
+where
+    DB: Blockstore + Send + Sync,
+{
+    let index = match cs.message_index() {
+        Some(index) => index.clone(),
+        None => return Ok(()),
+    };
+    let mut subscriber = cs.publisher().subscribe();
+    loop {
+        // Only the latest head matters, the index follows it whatever the
+        // events in between were
+        if let Err(e) = index.update(&cs, &cs.heaviest_tipset()) {
+            warn!("Failed to update the message index: {e}");
+        }
+        match subscriber.recv().await {
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::{Code::Blake2b256, MultihashDigest};
+    use forest_db::MemoryDB;
+    use fvm_ipld_encoding::DAG_CBOR;
+
+    use super::*;
+    use crate::store::test_chain::{message, TestChain};
+
+    fn cid(chain: &TestChain, ts: &Tipset, index: usize) -> Cid {
+        chain.cs.messages_for_tipset(ts).unwrap()[index]
+            .cid()
+            .unwrap()
+    }
+
+    #[test]
+    fn update_applies_and_reverts() {
+        let chain = TestChain::new();
+        let a1 = chain.child(&chain.genesis, 1, &[message(10, 0)]);
+        let a2 = chain.child(&a1, 1, &[message(10, 1), message(11, 0)]);
+        let (m1, m2, m3) = (
+            cid(&chain, &a1, 0),
+            cid(&chain, &a2, 0),
+            cid(&chain, &a2, 1),
+        );
+
+        let index = MessageIndex::new(MemoryDB::default());
+        index.update(&chain.cs, &a2).unwrap();
+        assert_eq!(index.head().unwrap().as_ref(), Some(a2.key()));
+        let location = index.get(&m1).unwrap().unwrap();
+        assert_eq!((&location.tipset, location.epoch), (a1.key(), 1));
+        assert_eq!(index.get(&m3).unwrap().unwrap().index, 1);
+
+        // A heavier fork including the first message at another epoch
+        let b2 = chain.child(&chain.chain(&chain.genesis, 2, 1), 2, &[message(10, 0)]);
+        let b3 = chain.chain(&b2, 2, 1);
+        index.update(&chain.cs, &b3).unwrap();
+        assert_eq!(index.head().unwrap().as_ref(), Some(b3.key()));
+        let location = index.get(&m1).unwrap().unwrap();
+        assert_eq!((&location.tipset, location.epoch), (b2.key(), 2));
+        assert_eq!(index.get(&m2).unwrap(), None);
+        assert_eq!(index.get(&m3).unwrap(), None);
+
+        // Back to the first chain, extended
+        let a4 = chain.chain(&a2, 1, 2);
+        index.update(&chain.cs, &a4).unwrap();
+        assert_eq!(index.get(&m1).unwrap().unwrap().tipset, *a1.key());
+        assert_eq!(index.get(&m2).unwrap().unwrap().tipset, *a2.key());
+    }
+
+    #[test]
+    fn update_is_bounded() {
+        let chain = TestChain::new();
+        let a1 = chain.child(&chain.genesis, 1, &[message(10, 0)]);
+        let m1 = cid(&chain, &a1, 0);
+        let head = chain.chain(&a1, 1, MESSAGE_INDEX_DEPTH as usize + 1);
+
+        let index = MessageIndex::new(MemoryDB::default());
+        index.update(&chain.cs, &head).unwrap();
+        assert_eq!(index.get(&m1).unwrap(), None);
+
+        assert_eq!(
+            index.backfill(&chain.cs, head.clone(), 0).unwrap(),
+            head.epoch() as usize
+        );
+        assert_eq!(index.get(&m1).unwrap().unwrap().tipset, *a1.key());
+    }
+
+    #[test]
+    fn backfill_keeps_first_inclusion() {
+        let chain = TestChain::new();
+        let a1 = chain.child(&chain.genesis, 1, &[message(10, 0)]);
+        let a2 = chain.child(&a1, 1, &[message(10, 0), message(11, 0)]);
+        let a3 = chain.chain(&a2, 1, 1);
+        let (m1, m2) = (cid(&chain, &a1, 0), cid(&chain, &a2, 1));
+
+        let index = MessageIndex::new(MemoryDB::default());
+        assert_eq!(index.backfill(&chain.cs, a3.clone(), 2).unwrap(), 2);
+        assert_eq!(index.get(&m1).unwrap().unwrap().tipset, *a2.key());
+        assert_eq!(index.backfill(&chain.cs, a3, 1).unwrap(), 3);
+        assert_eq!(index.get(&m1).unwrap().unwrap().tipset, *a1.key());
+        assert_eq!(index.get(&m2).unwrap().unwrap().index, 1);
+    }
+
+    #[test]
+    fn location_roundtrip() {
+        let index = MessageIndex::new(MemoryDB::default());
+        let cid = Cid::new_v1(DAG_CBOR, Blake2b256.digest(b"message"));
+        assert_eq!(index.get(&cid).unwrap(), None);
+
+        let location = MessageLocation {
+            tipset: TipsetKeys::new(vec![Cid::new_v1(DAG_CBOR, Blake2b256.digest(b"block"))]),
+            epoch: 42,
+            index: 7,
+        };
+        let value =
+            fvm_ipld_encoding::to_vec(&(&location.tipset, location.epoch, location.index)).unwrap();
+        index
+            .store
+            .write_values(vec![(message_key(&cid), value)])
+            .unwrap();
+        assert_eq!(index.get(&cid).unwrap(), Some(location));
+
+        index
+            .store
+            .write_values(vec![(message_key(&cid), vec![])])
+            .unwrap();
+        assert_eq!(index.get(&cid).unwrap(), None);
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Re-orgs of the heaviest chain.
+//!
+//! A heavier tipset that is not a descendant of the head re-organizes the
+//! chain: the tipsets of the head down to the common ancestor are dropped.
+//! Re-orgs dropping tipsets more than the maximum re-org depth behind the
+//! head would revert finalized tipsets, and are refused, as are re-orgs
+//! reverting the trusted checkpoint once the head is past it.
+
+use std::{collections::VecDeque, sync::Arc};
+
+use forest_blocks::{Tipset, TipsetKeys};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::clock::ChainEpoch;
+
+use super::{ChainStore, Error};
+
+/// Number of re-orgs the [`ChainStore`] keeps a record of.
+pub const MAX_REORG_RECORDS: usize = 1000;
+
+/// Record of a re-org of the heaviest chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReorgRecord {
+    /// Seconds since the Unix epoch at which the re-org happened.
+    pub timestamp: u64,
+    /// Number of epochs between the previous head and the common ancestor.
+    pub depth: ChainEpoch,
+    /// Keys of the previous head.
+    pub old_head: TipsetKeys,
+    /// Epoch of the previous head.
+    pub old_epoch: ChainEpoch,
+    /// Keys of the new head.
+    pub new_head: TipsetKeys,
+    /// Epoch of the new head.
+    pub new_epoch: ChainEpoch,
+    /// Keys of the tipsets dropped from the heaviest chain, from the previous
+    /// head down.
+    pub dropped: Vec<TipsetKeys>,
+}
+
+/// Bounded history of re-orgs, oldest first.
+#[derive(Default)]
+pub(super) struct ReorgHistory(VecDeque<ReorgRecord>);
+
+impl ReorgHistory {
+    pub fn push(&mut self, record: ReorgRecord) {
+        if self.0.len() == MAX_REORG_RECORDS {
+            self.0.pop_front();
+        }
+        self.0.push_back(record);
+    }
+
+    pub fn records(&self) -> Vec<ReorgRecord> {
+        self.0.iter().cloned().collect()
+    }
+}
+
+/// Returns the tipsets of the chain of `head` dropped when switching to the
+/// chain of `ts`, from `head` down, and the tipset the chains have in common.
+/// Tipsets the new chain only adds blocks to are not dropped. Fails with
+/// [`Error::ReorgTooDeep`] if a dropped tipset is `max_depth` or more epochs
+/// behind `head`, and with [`Error::ReorgBelowCheckpoint`] if a dropped tipset
+/// is at or below the `checkpoint` epoch `head` is past.
+pub(super) fn reorg_path<DB>(
+    cs: &ChainStore<DB>,
+    head: &Arc<Tipset>,
+    ts: &Arc<Tipset>,
+    max_depth: ChainEpoch,
+    checkpoint: Option<ChainEpoch>,
+) -> Result<(Vec<Arc<Tipset>>, Arc<Tipset>), Error>
+where
+    DB: Blockstore + Send + Sync,
+{
+    let finalized_epoch = head.epoch() - max_depth;
+    let checkpoint = checkpoint.filter(|checkpoint| head.epoch() >= *checkpoint);
+    let mut old = head.clone();
+    // Skip the tipsets of the new chain above the head in one go
+    let mut new = if ts.epoch() > head.epoch() {
+        cs.tipset_by_height(head.epoch(), ts.clone(), true)?
+    } else {
+        ts.clone()
+    };
+    let mut dropped = vec![];
+    while old.key() != new.key() {
+        if old.epoch() == new.epoch() && old.cids().iter().all(|cid| new.cids().contains(cid)) {
+            // The new chain only adds blocks to this tipset, which is kept
+            break;
+        }
+        if old.epoch() >= new.epoch() {
+            if old.epoch() <= finalized_epoch {
+                return Err(Error::ReorgTooDeep(max_depth));
+            }
+            if let Some(checkpoint) = checkpoint.filter(|checkpoint| old.epoch() <= *checkpoint) {
+                return Err(Error::ReorgBelowCheckpoint(checkpoint));
+            }
+            if old.epoch() == 0 {
+                return Err(Error::Other("Fork at genesis".to_owned()));
+            }
+            let parent = cs.tipset_from_keys(old.parents())?;
+            dropped.push(std::mem::replace(&mut old, parent));
+        } else {
+            new = cs.tipset_from_keys(new.parents())?;
+        }
+    }
+    Ok((dropped, old))
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::{
+        multihash::{Code::Blake2b256, MultihashDigest},
+        Cid,
+    };
+    use fvm_ipld_encoding::DAG_CBOR;
+
+    use super::*;
+    use crate::store::test_chain::TestChain;
+
+    fn record(epoch: ChainEpoch) -> ReorgRecord {
+        let tsk = TipsetKeys::new(vec![Cid::new_v1(
+            DAG_CBOR,
+            Blake2b256.digest(&epoch.to_be_bytes()),
+        )]);
+        ReorgRecord {
+            timestamp: 0,
+            depth: 1,
+            old_head: tsk.clone(),
+            old_epoch: epoch,
+            new_head: tsk.clone(),
+            new_epoch: epoch,
+            dropped: vec![tsk],
+        }
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = ReorgHistory::default();
+        for epoch in 0..(MAX_REORG_RECORDS as ChainEpoch + 10) {
+            history.push(record(epoch));
+        }
+        let records = history.records();
+        assert_eq!(records.len(), MAX_REORG_RECORDS);
+        assert_eq!(records[0], record(10));
+        assert_eq!(
+            records.last(),
+            Some(&record(MAX_REORG_RECORDS as ChainEpoch + 9))
+        );
+    }
+
+    #[test]
+    fn reorg_below_checkpoint_is_refused() {
+        let chain = TestChain::new();
+        let checkpoint = chain.chain(&chain.genesis, 1, 5);
+        let head = chain.chain(&checkpoint, 1, 3);
+
+        let fork = chain.chain(&checkpoint, 2, 4);
+        let (dropped, ancestor) = reorg_path(&chain.cs, &head, &fork, 900, Some(5)).unwrap();
+        assert_eq!(dropped.len(), 3);
+        assert_eq!(ancestor, checkpoint);
+
+        let parent = chain.cs.tipset_from_keys(checkpoint.parents()).unwrap();
+        let fork = chain.chain(&parent, 2, 5);
+        assert!(matches!(
+            reorg_path(&chain.cs, &head, &fork, 900, Some(5)),
+            Err(Error::ReorgBelowCheckpoint(5))
+        ));
+        // The checkpoint is not enforced before the head is past it
+        let grandparent = chain.cs.tipset_from_keys(parent.parents()).unwrap();
+        let head = chain.child(&grandparent, 3, &[]);
+        let (dropped, _) = reorg_path(&chain.cs, &head, &fork, 900, Some(5)).unwrap();
+        assert_eq!(dropped, vec![head]);
+    }
+
+    #[test]
+    fn reorg_depth_is_bounded() {
+        let chain = TestChain::new();
+        let head = chain.chain(&chain.genesis, 1, 10);
+        let tipset_at = |epoch| {
+            chain
+                .cs
+                .tipset_by_height(epoch, head.clone(), true)
+                .unwrap()
+        };
+
+        // Re-org of 5 epochs, dropping tipsets down to 4 epochs behind the head
+        let fork = chain.chain(&tipset_at(5), 2, 6);
+        let (dropped, ancestor) = reorg_path(&chain.cs, &head, &fork, 5, None).unwrap();
+        assert_eq!(dropped.len(), 5);
+        assert_eq!(ancestor, tipset_at(5));
+
+        // Re-org of 6 epochs, dropping a tipset 5 epochs behind the head
+        let fork = chain.chain(&tipset_at(4), 2, 7);
+        assert!(matches!(
+            reorg_path(&chain.cs, &head, &fork, 5, None),
+            Err(Error::ReorgTooDeep(5))
+        ));
+    }
+}
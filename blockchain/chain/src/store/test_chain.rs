@@ -0,0 +1,105 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Chains of single block tipsets built in a memory store, for the tests of
+//! the indexes following the head.
+
+use std::sync::Arc;
+
+use cid::{multihash::Code::Blake2b256, Cid};
+use forest_blocks::{BlockHeader, Tipset, TxMeta};
+use forest_db::MemoryDB;
+use forest_networks::ChainConfig;
+use forest_shim::{
+    address::Address,
+    executor::Receipt_v3,
+    message::{Message, Message_v3},
+};
+use forest_utils::db::BlockstoreExt;
+use fvm_ipld_amt::Amtv0 as Amt;
+use tempfile::TempDir;
+
+use super::ChainStore;
+
+pub(super) struct TestChain {
+    pub cs: ChainStore<MemoryDB>,
+    pub genesis: Arc<Tipset>,
+    _chain_data_root: TempDir,
+}
+
+impl TestChain {
+    pub fn new() -> Self {
+        let genesis = BlockHeader::builder()
+            .miner_address(Address::new_id(0))
+            .build()
+            .unwrap();
+        let chain_data_root = TempDir::new().unwrap();
+        let cs = ChainStore::new(
+            MemoryDB::default(),
+            Arc::new(ChainConfig::default()),
+            &genesis,
+            chain_data_root.path(),
+        )
+        .unwrap();
+        cs.blockstore().put_obj(&genesis, Blake2b256).unwrap();
+        Self {
+            cs,
+            genesis: Arc::new(Tipset::from(genesis)),
+            _chain_data_root: chain_data_root,
+        }
+    }
+
+    /// Puts a child of `parent` mined by `miner` and including `messages` in
+    /// the store.
+    pub fn child(&self, parent: &Tipset, miner: u64, messages: &[Message]) -> Arc<Tipset> {
+        self.child_with_receipts(parent, miner, messages, &[])
+    }
+
+    /// Puts a child of `parent` in the store, as [`TestChain::child`], with
+    /// the receipts of the messages of `parent`.
+    pub fn child_with_receipts(
+        &self,
+        parent: &Tipset,
+        miner: u64,
+        messages: &[Message],
+        receipts: &[Receipt_v3],
+    ) -> Arc<Tipset> {
+        let db = self.cs.blockstore();
+        let cids: Vec<Cid> = messages
+            .iter()
+            .map(|message| db.put_obj(message, Blake2b256).unwrap())
+            .collect();
+        let meta = TxMeta {
+            bls_message_root: Amt::new_from_iter(db, cids).unwrap(),
+            secp_message_root: Amt::new_from_iter(db, Vec::<Cid>::new()).unwrap(),
+        };
+        let header = BlockHeader::builder()
+            .parents(parent.key().clone())
+            .epoch(parent.epoch() + 1)
+            .weight(parent.weight() + 1)
+            .messages(db.put_obj(&meta, Blake2b256).unwrap())
+            .message_receipts(Amt::new_from_iter(db, receipts.iter().cloned()).unwrap())
+            .miner_address(Address::new_id(miner))
+            .build()
+            .unwrap();
+        db.put_obj(&header, Blake2b256).unwrap();
+        Arc::new(Tipset::from(header))
+    }
+
+    /// Puts a chain of `len` tipsets mined by `miner` on top of `parent` in
+    /// the store, returning its head.
+    pub fn chain(&self, parent: &Arc<Tipset>, miner: u64, len: usize) -> Arc<Tipset> {
+        (0..len).fold(parent.clone(), |ts, _| self.child(&ts, miner, &[]))
+    }
+}
+
+/// Returns a message of `from` with the given sequence.
+pub(super) fn message(from: u64, sequence: u64) -> Message {
+    Message_v3 {
+        from: Address::new_id(from).into(),
+        to: Address::new_id(1).into(),
+        sequence,
+        ..Message_v3::default()
+    }
+    .into()
+}
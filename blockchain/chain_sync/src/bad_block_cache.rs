@@ -1,18 +1,133 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::num::NonZeroUsize;
+use std::{
+    fmt,
+    num::NonZeroUsize,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use cid::Cid;
+use forest_json::cid::CidJson;
+use forest_utils::db::file_backed_obj::{FileBacked, FileBackedObject, SYNC_PERIOD};
+use log::warn;
 use lru::LruCache;
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+/// Number of bad block records kept on disk.
+const MAX_PERSISTED_BAD_BLOCKS: usize = 1 << 12;
+
+/// Validation check a block failed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct FailedCheck {
+    /// Name of the check, after the kind of validation error
+    pub check: String,
+    /// Error the check failed with
+    pub error: String,
+}
+
+/// Record of why a block was marked bad.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BadBlockRecord {
+    /// Summary of why the block is bad
+    pub reason: String,
+    /// Checks the block failed, empty if it was not validated itself
+    pub failed_checks: Vec<FailedCheck>,
+    /// Bad block the block descends from, if it is bad because of it
+    #[serde(with = "forest_json::cid::opt")]
+    pub bad_ancestor: Option<Cid>,
+    /// Unix timestamp of when the block was marked bad
+    pub marked_at: u64,
+}
+
+impl BadBlockRecord {
+    pub fn new(reason: impl Into<String>, failed_checks: Vec<FailedCheck>) -> Self {
+        Self {
+            reason: reason.into(),
+            failed_checks,
+            bad_ancestor: None,
+            marked_at: now(),
+        }
+    }
+
+    /// Record of a block descending from the bad block `ancestor`.
+    pub fn descendant_of(ancestor: Cid) -> Self {
+        Self {
+            reason: format!("chain contained {ancestor}"),
+            failed_checks: vec![],
+            bad_ancestor: Some(ancestor),
+            marked_at: now(),
+        }
+    }
+}
+
+impl fmt::Display for BadBlockRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Retrieved system time before UNIX epoch")
+        .as_secs()
+}
+
+/// Bad block records kept on disk, oldest first.
+#[derive(Default)]
+struct PersistedBadBlocks(Vec<(Cid, BadBlockRecord)>);
+
+impl PersistedBadBlocks {
+    fn push(&mut self, c: Cid, record: BadBlockRecord) {
+        self.0.retain(|(cid, _)| cid != &c);
+        self.0.push((c, record));
+        if self.0.len() > MAX_PERSISTED_BAD_BLOCKS {
+            self.0.drain(..self.0.len() - MAX_PERSISTED_BAD_BLOCKS);
+        }
+    }
+
+    fn remove(&mut self, c: &Cid) {
+        self.0.retain(|(cid, _)| cid != c);
+    }
+}
+
+impl FileBackedObject for PersistedBadBlocks {
+    fn serialize(&self) -> anyhow::Result<Vec<u8>> {
+        let records: Vec<_> = self.0.iter().map(|(c, r)| (CidJson(*c), r)).collect();
+        Ok(serde_json::to_vec(&records)?)
+    }
+
+    fn deserialize(bytes: &[u8]) -> anyhow::Result<Self> {
+        let records: Vec<(CidJson, BadBlockRecord)> = serde_json::from_slice(bytes)?;
+        Ok(Self(records.into_iter().map(|(c, r)| (c.0, r)).collect()))
+    }
+}
 
 /// Thread-safe cache for tracking bad blocks.
 /// This cache is checked before validating a block, to ensure no duplicate
 /// work.
-#[derive(Debug)]
+///
+/// When persisted, the records of the blocks that are bad in their own right,
+/// as opposed to descending from a bad block, survive restarts. New records are
+/// written at most once per [`SYNC_PERIOD`], and the others on
+/// [`BadBlockCache::flush`].
 pub struct BadBlockCache {
-    cache: Mutex<LruCache<Cid, String>>,
+    cache: Mutex<LruCache<Cid, BadBlockRecord>>,
+    persisted: Option<Mutex<FileBacked<PersistedBadBlocks>>>,
+}
+
+impl fmt::Debug for BadBlockCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BadBlockCache")
+            .field("len", &self.cache.lock().len())
+            .field("persisted", &self.persisted.is_some())
+            .finish()
+    }
 }
 
 impl Default for BadBlockCache {
@@ -25,23 +140,170 @@ impl BadBlockCache {
     pub fn new(cap: NonZeroUsize) -> Self {
         Self {
             cache: Mutex::new(LruCache::new(cap)),
+            persisted: None,
         }
     }
 
-    /// Puts a bad block `Cid` in the cache with a given reason.
-    pub fn put(&self, c: Cid, reason: String) -> Option<String> {
-        self.cache.lock().put(c, reason)
+    /// Creates a default sized cache persisting its records to the file at
+    /// `path`, and loads the ones recorded there before.
+    pub fn load(path: PathBuf) -> anyhow::Result<Self> {
+        let persisted =
+            FileBacked::load_from_file_or_create(path, Default::default, Some(SYNC_PERIOD))?;
+        let mut cache = Self::default();
+        for (c, record) in &persisted.inner().0 {
+            cache.cache.get_mut().put(*c, record.clone());
+        }
+        cache.persisted = Some(Mutex::new(persisted));
+        Ok(cache)
+    }
+
+    /// Puts a bad block `Cid` in the cache with the record of why it is bad.
+    pub fn put(&self, c: Cid, record: BadBlockRecord) -> Option<BadBlockRecord> {
+        if let (Some(persisted), None) = (&self.persisted, record.bad_ancestor) {
+            let result = persisted
+                .lock()
+                .with_inner(|records| records.push(c, record.clone()));
+            if let Err(e) = result {
+                warn!("Failed to persist the record of bad block {c}: {e}");
+            }
+        }
+        self.cache.lock().put(c, record)
+    }
+
+    /// Removes the record of a bad block, and of the blocks marked bad for
+    /// descending from it. Returns the record of the block, if it was bad.
+    pub fn remove(&self, c: &Cid) -> Option<BadBlockRecord> {
+        if let Some(persisted) = &self.persisted {
+            let mut persisted = persisted.lock();
+            persisted.inner_mut().remove(c);
+            // Unmarked by hand, so written right away
+            if let Err(e) = persisted.sync() {
+                warn!("Failed to persist the removal of bad block {c}: {e}");
+            }
+        }
+        let mut cache = self.cache.lock();
+        let descendants: Vec<Cid> = cache
+            .iter()
+            .filter(|(_, record)| record.bad_ancestor.as_ref() == Some(c))
+            .map(|(cid, _)| *cid)
+            .collect();
+        for descendant in descendants {
+            cache.pop(&descendant);
+        }
+        cache.pop(c)
     }
 
-    /// Returns `Some` with the reason if the block CID is in bad block cache.
+    /// Removes all the records.
+    pub fn clear(&self) {
+        if let Some(persisted) = &self.persisted {
+            let mut persisted = persisted.lock();
+            persisted.inner_mut().0.clear();
+            if let Err(e) = persisted.sync() {
+                warn!("Failed to persist the removal of the bad blocks: {e}");
+            }
+        }
+        self.cache.lock().clear();
+    }
+
+    /// Writes the records not written yet, e.g. on shutdown.
+    pub fn flush(&self) -> anyhow::Result<()> {
+        match &self.persisted {
+            Some(persisted) => persisted.lock().sync(),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns `Some` with the record if the block CID is in bad block cache.
     /// This also updates the key to the head of the cache.
-    pub fn get(&self, c: &Cid) -> Option<String> {
+    pub fn get(&self, c: &Cid) -> Option<BadBlockRecord> {
         self.cache.lock().get(c).cloned()
     }
 
-    /// Returns `Some` with the reason if the block CID is in bad block cache.
+    /// Returns `Some` with the record if the block CID is in bad block cache.
     /// This function does not update the head position of the `Cid` key.
-    pub fn peek(&self, c: &Cid) -> Option<String> {
+    pub fn peek(&self, c: &Cid) -> Option<BadBlockRecord> {
         self.cache.lock().peek(c).cloned()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::{Code::Identity, MultihashDigest};
+
+    use super::*;
+
+    fn cid(i: u8) -> Cid {
+        Cid::new_v1(fvm_ipld_encoding::DAG_CBOR, Identity.digest(&[i]))
+    }
+
+    #[test]
+    fn persisted_records_survive_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("BAD_BLOCKS");
+        let record = BadBlockRecord::new(
+            "bad",
+            vec![FailedCheck {
+                check: "BlockWithoutSignature".into(),
+                error: "Block must have a signature".into(),
+            }],
+        );
+
+        let cache = BadBlockCache::load(path.clone()).unwrap();
+        cache.put(cid(1), record.clone());
+        cache.put(cid(2), BadBlockRecord::descendant_of(cid(1)));
+        cache.flush().unwrap();
+        drop(cache);
+
+        let cache = BadBlockCache::load(path).unwrap();
+        assert_eq!(cache.peek(&cid(1)), Some(record));
+        // Descendants are marked bad again when synced
+        assert_eq!(cache.peek(&cid(2)), None);
+    }
+
+    #[test]
+    fn records_are_written_periodically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("BAD_BLOCKS");
+
+        let cache = BadBlockCache::load(path.clone()).unwrap();
+        cache.put(cid(1), BadBlockRecord::new("bad", vec![]));
+        cache.put(cid(2), BadBlockRecord::new("bad", vec![]));
+        assert_eq!(
+            BadBlockCache::load(path.clone()).unwrap().peek(&cid(1)),
+            None
+        );
+
+        cache.flush().unwrap();
+        let reloaded = BadBlockCache::load(path).unwrap();
+        assert!(reloaded.peek(&cid(1)).is_some());
+        assert!(reloaded.peek(&cid(2)).is_some());
+    }
+
+    #[test]
+    fn unmarked_blocks_are_removed_with_their_descendants() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("BAD_BLOCKS");
+
+        let cache = BadBlockCache::load(path.clone()).unwrap();
+        cache.put(cid(1), BadBlockRecord::new("bad", vec![]));
+        cache.put(cid(2), BadBlockRecord::descendant_of(cid(1)));
+        cache.put(cid(3), BadBlockRecord::new("bad", vec![]));
+        cache.put(cid(4), BadBlockRecord::new("bad", vec![]));
+        cache.flush().unwrap();
+
+        assert!(cache.remove(&cid(1)).is_some());
+        assert_eq!(cache.remove(&cid(1)), None);
+        assert_eq!(cache.peek(&cid(2)), None);
+        assert!(cache.peek(&cid(3)).is_some());
+        // Removals are written right away
+        let reloaded = BadBlockCache::load(path.clone()).unwrap();
+        assert_eq!(reloaded.peek(&cid(1)), None);
+        assert!(reloaded.peek(&cid(3)).is_some());
+
+        cache.clear();
+        assert_eq!(cache.peek(&cid(3)), None);
+        let reloaded = BadBlockCache::load(path).unwrap();
+        assert_eq!(reloaded.peek(&cid(3)), None);
+        assert_eq!(reloaded.peek(&cid(4)), None);
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::{
+    path::PathBuf,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
@@ -26,6 +27,7 @@ use futures::{
     try_join, StreamExt,
 };
 use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::clock::ChainEpoch;
 use log::{debug, error, info, trace, warn};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
@@ -33,12 +35,15 @@ use thiserror::Error;
 
 use crate::{
     bad_block_cache::BadBlockCache,
+    checkpoint::TrustedCheckpoint,
     consensus::Consensus,
     metrics,
     network_context::SyncNetworkContext,
+    slasher::Slasher,
     sync_state::SyncState,
     tipset_syncer::{
-        TipsetProcessor, TipsetProcessorError, TipsetRangeSyncer, TipsetRangeSyncerError,
+        BlockExplainer, ExplainBlock, TipsetProcessor, TipsetProcessorError, TipsetRangeSyncer,
+        TipsetRangeSyncerError,
     },
     validation::{TipsetValidationError, TipsetValidator},
 };
@@ -69,16 +74,32 @@ pub enum ChainMuxerError<C: Consensus> {
     Block(#[from] ForestBlockError),
     #[error("Following network unexpectedly failed: {0}")]
     NetworkFollowingFailure(String),
+    #[error("Loading trusted checkpoint failed: {0}")]
+    Checkpoint(String),
 }
 
 /// Structure that defines syncing configuration options
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(default)]
 pub struct SyncConfig {
     /// Request window length for tipsets during chain exchange
     pub req_window: i64,
     /// Sample size of tipsets to acquire before determining what the network
     /// head is
     pub tipset_sample_size: usize,
+    /// Signed file to read the trusted checkpoint from, when none is set
+    pub checkpoint_file: Option<PathBuf>,
+    /// Address of the key checkpoint files must be signed with
+    pub checkpoint_signer: Option<String>,
+    /// Trusted checkpoint the chain is synced from, instead of validating it
+    /// back to genesis
+    pub checkpoint: Option<TrustedCheckpoint>,
+    /// Number of epochs the events emitted by actors are kept for in the
+    /// event index
+    pub event_retention: i64,
+    /// Maximum number of epochs a re-org may revert, the chain finality if
+    /// unset
+    pub max_reorg_depth: Option<ChainEpoch>,
 }
 
 impl Default for SyncConfig {
@@ -86,6 +107,28 @@ impl Default for SyncConfig {
         Self {
             req_window: 200,
             tipset_sample_size: 5,
+            checkpoint_file: None,
+            checkpoint_signer: None,
+            checkpoint: None,
+            event_retention: 5760, // 2 days of epochs
+            max_reorg_depth: None,
+        }
+    }
+}
+
+impl SyncConfig {
+    /// Returns the configured checkpoint, or the one of the checkpoint file
+    /// once its signature is verified.
+    pub fn trusted_checkpoint(&self) -> anyhow::Result<Option<TrustedCheckpoint>> {
+        if let Some(checkpoint) = &self.checkpoint {
+            return Ok(Some(checkpoint.clone()));
+        }
+        match (&self.checkpoint_file, &self.checkpoint_signer) {
+            (Some(path), Some(signer)) => {
+                Ok(Some(TrustedCheckpoint::from_signed_file(path, signer)?))
+            }
+            (Some(_), None) => anyhow::bail!("A checkpoint file requires a checkpoint signer"),
+            (None, _) => Ok(None),
         }
     }
 }
@@ -154,6 +197,13 @@ pub struct ChainMuxer<DB, M, C: Consensus> {
 
     /// Syncing configurations
     sync_config: SyncConfig,
+
+    /// Trusted checkpoint headers are synced back to
+    checkpoint: Option<Arc<TrustedCheckpoint>>,
+
+    /// Detector of the consensus faults of the blocks received from the
+    /// network
+    slasher: Option<Arc<Slasher>>,
 }
 
 impl<DB, M, C> ChainMuxer<DB, M, C>
@@ -180,6 +230,15 @@ where
             peer_manager,
             state_manager.blockstore().clone(),
         );
+        let checkpoint = cfg
+            .trusted_checkpoint()
+            .map_err(|e| ChainMuxerError::Checkpoint(e.to_string()))?;
+        if let Some(checkpoint) = &checkpoint {
+            info!(
+                "Syncing from trusted checkpoint at epoch {}: {:?}",
+                checkpoint.epoch, checkpoint.tipset
+            );
+        }
 
         Ok(Self {
             state: ChainMuxerState::Idle,
@@ -194,15 +253,39 @@ where
             tipset_sender,
             tipset_receiver,
             sync_config: cfg,
+            checkpoint: checkpoint.map(Arc::new),
+            slasher: None,
         })
     }
 
+    /// Feeds the headers of the blocks passing validation to a consensus fault
+    /// detector.
+    pub fn with_slasher(mut self, slasher: Arc<Slasher>) -> Self {
+        self.slasher = Some(slasher);
+        self
+    }
+
+    /// Tracks the bad blocks in the given cache, e.g. one persisting them.
+    pub fn with_bad_blocks(mut self, bad_blocks: Arc<BadBlockCache>) -> Self {
+        self.bad_blocks = bad_blocks;
+        self
+    }
+
     /// Returns a clone of the bad blocks cache to be used outside of chain
     /// sync.
     pub fn bad_blocks_cloned(&self) -> Arc<BadBlockCache> {
         self.bad_blocks.clone()
     }
 
+    /// Returns a validator explaining why blocks are bad outside of chain
+    /// sync.
+    pub fn block_explainer(&self) -> Arc<dyn ExplainBlock> {
+        Arc::new(BlockExplainer::new(
+            self.consensus.clone(),
+            self.state_manager.clone(),
+        ))
+    }
+
     /// Returns a cloned `Arc` of the sync worker state.
     pub fn sync_state_cloned(&self) -> WorkerState {
         self.worker_state.clone()
@@ -606,6 +689,8 @@ where
         let trs_network = self.network.clone();
         let trs_tracker = self.worker_state.clone();
         let trs_genesis = self.genesis.clone();
+        let trs_checkpoint = self.checkpoint.clone();
+        let trs_slasher = self.slasher.clone();
         let tipset_range_syncer: ChainMuxerFuture<(), ChainMuxerError<C>> = Box::pin(async move {
             let network_head_epoch = network_head.epoch();
             let tipset_range_syncer = match TipsetRangeSyncer::new(
@@ -618,6 +703,8 @@ where
                 trs_chain_store,
                 trs_bad_block_cache,
                 trs_genesis,
+                trs_checkpoint,
+                trs_slasher,
             ) {
                 Ok(tipset_range_syncer) => tipset_range_syncer,
                 Err(why) => {
@@ -705,6 +792,8 @@ where
         let tp_tipset_receiver = self.tipset_receiver.clone();
         let tp_tracker = self.worker_state.clone();
         let tp_genesis = self.genesis.clone();
+        let tp_checkpoint = self.checkpoint.clone();
+        let tp_slasher = self.slasher.clone();
         enum UnexpectedReturnKind {
             TipsetProcessor,
         }
@@ -719,6 +808,8 @@ where
                     tp_chain_store,
                     tp_bad_block_cache,
                     tp_genesis,
+                    tp_checkpoint,
+                    tp_slasher,
                 )
                 .await
                 .map_err(ChainMuxerError::TipsetProcessor)?;
@@ -0,0 +1,116 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Trusted checkpoints, from which the chain is synced without validating it
+//! back to genesis ("weak subjectivity").
+//!
+//! A checkpoint is either set in the configuration, or read from a JSON file
+//! signed by a key the operator trusts. Headers are only synced back to the
+//! checkpoint tipset, whose state is taken as is, and forks reorganizing the
+//! chain below the checkpoint are refused.
+
+use std::{path::Path, str::FromStr};
+
+use cid::Cid;
+use forest_blocks::{tipset_keys_json, Tipset, TipsetKeys};
+use forest_json::signature::json::SignatureJson;
+use forest_shim::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use serde::{Deserialize, Serialize};
+
+/// Tipset trusted to be part of the canonical chain, along with the state
+/// root computed by executing it.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct TrustedCheckpoint {
+    /// Epoch of the checkpoint tipset
+    pub epoch: ChainEpoch,
+    /// Keys of the checkpoint tipset
+    #[serde(with = "tipset_keys_json")]
+    pub tipset: TipsetKeys,
+    /// State root after executing the checkpoint tipset, which is the parent
+    /// state of its children
+    #[serde(with = "forest_json::cid")]
+    pub state_root: Cid,
+}
+
+/// Checkpoint file, signed by the operator trusted to publish checkpoints.
+#[derive(Deserialize, Serialize)]
+pub struct SignedCheckpoint {
+    pub checkpoint: TrustedCheckpoint,
+    pub signature: SignatureJson,
+}
+
+impl TrustedCheckpoint {
+    /// Bytes covered by the signature of a checkpoint file.
+    pub fn signing_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(fvm_ipld_encoding::to_vec(&(
+            self.epoch,
+            &self.tipset,
+            &self.state_root,
+        ))?)
+    }
+
+    /// Reads a checkpoint file, and verifies it is signed by `signer`.
+    pub fn from_signed_file(path: &Path, signer: &str) -> anyhow::Result<Self> {
+        let signer = Address::from_str(signer)?;
+        let file = std::fs::read(path)?;
+        let SignedCheckpoint {
+            checkpoint,
+            signature: SignatureJson(signature),
+        } = serde_json::from_slice(&file)?;
+        signature
+            .verify(&checkpoint.signing_bytes()?, &signer.into())
+            .map_err(|e| {
+                anyhow::anyhow!("Invalid signature of checkpoint {}: {e}", path.display())
+            })?;
+        Ok(checkpoint)
+    }
+
+    /// Returns true if a chain synced from `from` up to `to` crosses the
+    /// checkpoint, in which case headers are synced only back to it.
+    pub fn is_within(&self, from: &Tipset, to: &Tipset) -> bool {
+        from.epoch() < self.epoch && self.epoch <= to.epoch()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::{Code::Blake2b256, MultihashDigest};
+    use forest_key_management::{generate_key, sign};
+    use forest_shim::crypto::SignatureType;
+    use fvm_ipld_encoding::DAG_CBOR;
+
+    use super::*;
+
+    #[test]
+    fn signed_checkpoint_roundtrip() {
+        let checkpoint = TrustedCheckpoint {
+            epoch: 42,
+            tipset: TipsetKeys::new(vec![Cid::new_v1(DAG_CBOR, Blake2b256.digest(b"block"))]),
+            state_root: Cid::new_v1(DAG_CBOR, Blake2b256.digest(b"state")),
+        };
+        let key = generate_key(SignatureType::Secp256k1).unwrap();
+        let signature = sign(
+            SignatureType::Secp256k1,
+            key.key_info.private_key(),
+            &checkpoint.signing_bytes().unwrap(),
+        )
+        .unwrap();
+        let file = SignedCheckpoint {
+            checkpoint: checkpoint.clone(),
+            signature: SignatureJson(signature),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+        std::fs::write(&path, serde_json::to_vec(&file).unwrap()).unwrap();
+
+        let signer = key.address.to_string();
+        assert_eq!(
+            TrustedCheckpoint::from_signed_file(&path, &signer).unwrap(),
+            checkpoint
+        );
+
+        let other = generate_key(SignatureType::Secp256k1).unwrap();
+        assert!(TrustedCheckpoint::from_signed_file(&path, &other.address.to_string()).is_err());
+    }
+}
@@ -32,7 +32,7 @@ use tokio::task::JoinSet;
 /// outside by the host system during chain synchronization.
 #[async_trait]
 pub trait Consensus: Scale + Debug + Send + Sync + Unpin + 'static {
-    type Error: Debug + Display + Send + Sync;
+    type Error: Debug + Display + Send + Sync + ValidationCheck;
 
     /// Perform block validation asynchronously and return all encountered
     /// errors if failed.
@@ -48,6 +48,28 @@ pub trait Consensus: Scale + Debug + Send + Sync + Unpin + 'static {
         DB: Blockstore + Clone + Sync + Send + 'static;
 }
 
+/// Error of a block validation, telling which check failed so that the
+/// failures recorded for bad blocks can be told apart.
+pub trait ValidationCheck {
+    /// Name of the failed check.
+    fn check(&self) -> &'static str;
+
+    /// Whether the block breaks a consensus rule, as opposed to the node
+    /// failing to run the check, e.g. on a state it couldn't load. Only the
+    /// blocks breaking a rule are marked bad.
+    fn is_rule_violation(&self) -> bool;
+}
+
+impl<T: ValidationCheck + ?Sized> ValidationCheck for Box<T> {
+    fn check(&self) -> &'static str {
+        (**self).check()
+    }
+
+    fn is_rule_violation(&self) -> bool {
+        (**self).is_rule_violation()
+    }
+}
+
 /// Helper function to collect errors from async validations.
 pub async fn collect_errs<E>(
     mut handles: FuturesUnordered<tokio::task::JoinHandle<Result<(), E>>>,
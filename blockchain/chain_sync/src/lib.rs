@@ -5,17 +5,25 @@
 
 mod bad_block_cache;
 mod chain_muxer;
+mod checkpoint;
 pub mod consensus;
 mod metrics;
 mod network_context;
+mod slasher;
 mod sync_state;
 mod tipset_syncer;
 mod validation;
 
 pub use self::{
-    bad_block_cache::BadBlockCache,
+    bad_block_cache::{BadBlockCache, BadBlockRecord, FailedCheck},
     chain_muxer::{ChainMuxer, SyncConfig},
-    consensus::{collect_errs, Consensus},
+    checkpoint::{SignedCheckpoint, TrustedCheckpoint},
+    consensus::{collect_errs, Consensus, ValidationCheck},
+    slasher::{
+        ConsensusFault, ConsensusFaultType, Slasher, SlasherConfig, REPORT_CONSENSUS_FAULT_METHOD,
+        SLASHER_WINDOW,
+    },
     sync_state::{SyncStage, SyncState},
+    tipset_syncer::ExplainBlock,
     validation::TipsetValidator,
 };
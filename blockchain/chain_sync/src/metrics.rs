@@ -62,6 +62,27 @@ lazy_static! {
         );
         block_validation_tasks_time
     };
+    pub static ref SYNC_STAGE_TIME: Box<HistogramVec> = {
+        let sync_stage_time = Box::new(
+            HistogramVec::new(
+                HistogramOpts {
+                    common_opts: Opts::new(
+                        "sync_stage_time",
+                        "Duration of the stages of syncing tipsets",
+                    ),
+                    buckets: vec![],
+                },
+                &[labels::SYNC_STAGE],
+            )
+            .expect("Defining the sync_stage_time metric must succeed"),
+        );
+        prometheus::default_registry()
+            .register(sync_stage_time.clone())
+            .expect(
+                "Registering the sync_stage_time metric with the metrics registry must succeed",
+            );
+        sync_stage_time
+    };
     pub static ref LIBP2P_MESSAGE_TOTAL: Box<GenericCounterVec<AtomicU64>> = {
         let libp2p_message_total = Box::new(
             GenericCounterVec::<AtomicU64>::new(
@@ -201,6 +222,7 @@ lazy_static! {
 
 pub mod labels {
     pub const GOSSIPSUB_MESSAGE_KIND: &str = "libp2p_message_kind";
+    pub const SYNC_STAGE: &str = "stage";
 }
 
 pub mod values {
@@ -222,6 +244,12 @@ pub mod values {
     pub const BASE_FEE_CHECK: &str = "base_fee_check";
     pub const PARENT_WEIGHT_CAL: &str = "parent_weight_check";
     pub const BLOCK_SIGNATURE_CHECK: &str = "block_signature_check";
+
+    // sync stages
+    pub const HEADERS_STAGE: &str = "headers";
+    pub const MESSAGES_STAGE: &str = "messages";
+    pub const VALIDATION_STAGE: &str = "validation";
+    pub const STATE_COMPUTATION_STAGE: &str = "state_computation";
 }
 
 #[cfg(test)]
@@ -244,6 +272,7 @@ mod tests {
     #[test]
     fn metrics_defined_and_registered() {
         test_counter!(TIPSET_PROCESSING_TIME);
+        test_counter_vec!(SYNC_STAGE_TIME);
         test_counter_vec!(LIBP2P_MESSAGE_TOTAL);
         test_counter!(INVALID_TIPSET_TOTAL);
         test_counter!(TIPSET_RANGE_SYNC_FAILURE_TOTAL);
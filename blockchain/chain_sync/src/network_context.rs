@@ -10,6 +10,7 @@ use std::{
     time::{Duration, SystemTime},
 };
 
+use ahash::HashSet;
 use anyhow::Context;
 use cid::Cid;
 use forest_blocks::{FullTipset, Tipset, TipsetKeys};
@@ -18,9 +19,10 @@ use forest_libp2p::{
         ChainExchangeRequest, ChainExchangeResponse, CompactedMessages, TipsetBundle, HEADERS,
         MESSAGES,
     },
+    graphsync::fetch_dag,
     hello::{HelloRequest, HelloResponse},
     rpc::RequestResponseError,
-    NetworkMessage, PeerId, PeerManager, BITSWAP_TIMEOUT,
+    BitswapSessionId, NetworkMessage, PeerId, PeerManager, BITSWAP_TIMEOUT,
 };
 use forest_utils::db::BlockstoreExt;
 use fvm_ipld_blockstore::Blockstore;
@@ -50,6 +52,10 @@ pub(crate) struct SyncNetworkContext<DB> {
     /// respective peers.
     pub peer_manager: Arc<PeerManager>,
     db: Box<DB>,
+
+    /// Bitswap session shared by the content fetched for sync, so that the
+    /// peers that sent some of it are asked for the rest first.
+    bitswap_session: BitswapSessionId,
 }
 
 impl<DB: Clone> Clone for SyncNetworkContext<DB> {
@@ -58,6 +64,7 @@ impl<DB: Clone> Clone for SyncNetworkContext<DB> {
             network_send: self.network_send.clone(),
             peer_manager: self.peer_manager.clone(),
             db: self.db.clone(),
+            bitswap_session: self.bitswap_session,
         }
     }
 }
@@ -75,6 +82,7 @@ where
             network_send,
             peer_manager,
             db: Box::new(db),
+            bitswap_session: BitswapSessionId::generate(),
         }
     }
 
@@ -129,6 +137,24 @@ where
         Ok(fts.remove(0))
     }
 
+    /// Fetches the blocks reachable from `root` that are missing from the
+    /// `BlockStore`, such as the state tree of a trusted checkpoint, over
+    /// graphsync from a set of shuffled peers. The blocks in `visited` are not
+    /// walked again. Returns the number of blocks fetched.
+    pub async fn graphsync_dag(
+        &self,
+        visited: &mut HashSet<Cid>,
+        root: Cid,
+    ) -> Result<usize, String>
+    where
+        DB: Sync,
+    {
+        let peers = self.peer_manager.top_peers_shuffled().await;
+        fetch_dag(&self.network_send, self.db.as_ref(), visited, &peers, root)
+            .await
+            .map_err(|e| format!("Graphsync of {root} failed: {e}"))
+    }
+
     /// Requests that some content with a particular `Cid` get fetched over
     /// `Bitswap` if it doesn't exist in the `BlockStore`.
     pub async fn bitswap_get<TMessage: DeserializeOwned>(
@@ -148,6 +174,7 @@ where
             .send_async(NetworkMessage::BitswapRequest {
                 epoch,
                 cid: content,
+                session: Some(self.bitswap_session),
                 response_channel: tx,
             })
             .await
@@ -0,0 +1,326 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Detection of the consensus faults of miners ("slasher"), from the headers
+//! of the blocks received from the network once they pass validation, so that
+//! headers with forged signatures cannot frame a miner.
+//!
+//! The faults are the ones punished by the `ReportConsensusFault` method of
+//! the miner actor:
+//! - double-fork mining: two blocks mined at the same epoch,
+//! - time-offset mining: two blocks mined on the same parents,
+//! - parent grinding: a block mined omitting the miner's own block of the
+//!   previous epoch from its parents, while including a sibling of it.
+
+use std::{
+    collections::VecDeque,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ahash::{HashMap, HashMapExt};
+use cid::Cid;
+use forest_blocks::{BlockHeader, TipsetKeys};
+use forest_shim::address::Address;
+use fvm_ipld_encoding::{Cbor, RawBytes};
+use fvm_shared::clock::ChainEpoch;
+use log::warn;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+/// Number of epochs headers are tracked for, behind the highest one seen.
+/// Faults older than the chain finality cannot be reported.
+pub const SLASHER_WINDOW: ChainEpoch = 900;
+
+/// Number of detected faults kept.
+const MAX_FAULTS: usize = 1000;
+
+/// Method number of `ReportConsensusFault` on the miner actor.
+pub const REPORT_CONSENSUS_FAULT_METHOD: u64 = 15;
+
+/// Configuration of the consensus fault detector.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct SlasherConfig {
+    /// Detects the consensus faults of the blocks received from the network
+    pub enabled: bool,
+    /// Address the detected faults are reported to the miner actors from, not
+    /// reported if unset
+    pub reporter: Option<String>,
+}
+
+/// Kind of consensus fault, numbered as in the miner actor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConsensusFaultType {
+    DoubleForkMining = 1,
+    ParentGrinding = 2,
+    TimeOffsetMining = 3,
+}
+
+/// Consensus fault committed by a miner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsensusFault {
+    pub fault_type: ConsensusFaultType,
+    pub miner: Address,
+    /// Epoch of the first block header of the fault
+    pub epoch: ChainEpoch,
+    pub header1: BlockHeader,
+    pub header2: BlockHeader,
+    /// Sibling of the first block included by the second one, for parent
+    /// grinding faults
+    pub header_extra: Option<BlockHeader>,
+    /// Seconds since the Unix epoch the fault was detected at
+    pub detected_at: u64,
+}
+
+impl ConsensusFault {
+    /// Returns the parameters of the `ReportConsensusFault` method of the
+    /// miner actor reporting this fault.
+    pub fn report_params(&self) -> anyhow::Result<RawBytes> {
+        let header_extra = match &self.header_extra {
+            Some(header) => header.marshal_cbor()?,
+            None => vec![],
+        };
+        Ok(RawBytes::serialize((
+            RawBytes::new(self.header1.marshal_cbor()?),
+            RawBytes::new(self.header2.marshal_cbor()?),
+            RawBytes::new(header_extra),
+        ))?)
+    }
+}
+
+#[derive(Default)]
+struct SlasherState {
+    headers: HashMap<Cid, BlockHeader>,
+    by_epoch: HashMap<(Address, ChainEpoch), Cid>,
+    by_parents: HashMap<(Address, TipsetKeys), Cid>,
+    highest_epoch: ChainEpoch,
+    faults: VecDeque<ConsensusFault>,
+}
+
+/// Tracks the recent block headers of every miner to detect their consensus
+/// faults.
+pub struct Slasher {
+    state: Mutex<SlasherState>,
+    faults_tx: flume::Sender<ConsensusFault>,
+    faults_rx: flume::Receiver<ConsensusFault>,
+}
+
+impl Default for Slasher {
+    fn default() -> Self {
+        let (faults_tx, faults_rx) = flume::bounded(MAX_FAULTS);
+        Self {
+            state: Default::default(),
+            faults_tx,
+            faults_rx,
+        }
+    }
+}
+
+impl Slasher {
+    /// Tracks a block header, returning the faults it reveals.
+    pub fn observe(&self, header: &BlockHeader) -> Vec<ConsensusFault> {
+        let mut state = self.state.lock();
+        if state.headers.contains_key(header.cid())
+            || header.epoch() < state.highest_epoch - SLASHER_WINDOW
+        {
+            return vec![];
+        }
+        let miner = *header.miner_address();
+        let mut faults = vec![];
+        let mut fault = |fault_type, header1: &BlockHeader, header2: &BlockHeader, extra| {
+            faults.push(ConsensusFault {
+                fault_type,
+                miner,
+                epoch: header1.epoch(),
+                header1: header1.clone(),
+                header2: header2.clone(),
+                header_extra: extra,
+                detected_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default(),
+            })
+        };
+
+        if let Some(other) = state.mined_at(&miner, header.epoch()) {
+            fault(ConsensusFaultType::DoubleForkMining, other, header, None);
+        }
+        if let Some(other) = state
+            .by_parents
+            .get(&(miner, header.parents().clone()))
+            .and_then(|cid| state.headers.get(cid))
+        {
+            if other.epoch() != header.epoch() {
+                fault(ConsensusFaultType::TimeOffsetMining, other, header, None);
+            }
+        }
+        // The header omits the block the miner mined at the previous epoch
+        if let Some(previous) = state.mined_at(&miner, header.epoch() - 1) {
+            if let Some(sibling) = state.grinding_witness(previous, header) {
+                fault(
+                    ConsensusFaultType::ParentGrinding,
+                    previous,
+                    header,
+                    Some(sibling.clone()),
+                );
+            }
+        }
+        // The block the miner mined at the next epoch omits the header
+        if let Some(next) = state.mined_at(&miner, header.epoch() + 1) {
+            if let Some(sibling) = state.grinding_witness(header, next) {
+                fault(
+                    ConsensusFaultType::ParentGrinding,
+                    header,
+                    next,
+                    Some(sibling.clone()),
+                );
+            }
+        }
+
+        state.insert(header);
+        for fault in &faults {
+            warn!(
+                "Detected {:?} consensus fault of miner {} at epoch {}: blocks {} and {}",
+                fault.fault_type,
+                fault.miner,
+                fault.epoch,
+                fault.header1.cid(),
+                fault.header2.cid()
+            );
+            if state.faults.len() == MAX_FAULTS {
+                state.faults.pop_front();
+            }
+            state.faults.push_back(fault.clone());
+            // Faults are dropped when they are not consumed
+            let _ = self.faults_tx.try_send(fault.clone());
+        }
+        faults
+    }
+
+    /// Returns the recently detected faults, oldest first.
+    pub fn faults(&self) -> Vec<ConsensusFault> {
+        self.state.lock().faults.iter().cloned().collect()
+    }
+
+    /// Returns a receiver of the faults as they are detected.
+    pub fn fault_receiver(&self) -> flume::Receiver<ConsensusFault> {
+        self.faults_rx.clone()
+    }
+}
+
+impl SlasherState {
+    fn mined_at(&self, miner: &Address, epoch: ChainEpoch) -> Option<&BlockHeader> {
+        self.by_epoch
+            .get(&(*miner, epoch))
+            .and_then(|cid| self.headers.get(cid))
+    }
+
+    /// Returns the sibling of `omitted` that `child` includes in its parents
+    /// instead of it, if any.
+    fn grinding_witness(&self, omitted: &BlockHeader, child: &BlockHeader) -> Option<&BlockHeader> {
+        if child.parents().cids().contains(omitted.cid()) {
+            return None;
+        }
+        child
+            .parents()
+            .cids()
+            .iter()
+            .filter_map(|cid| self.headers.get(cid))
+            .find(|sibling| {
+                sibling.epoch() == omitted.epoch() && sibling.parents() == omitted.parents()
+            })
+    }
+
+    fn insert(&mut self, header: &BlockHeader) {
+        let miner = *header.miner_address();
+        self.by_epoch
+            .entry((miner, header.epoch()))
+            .or_insert(*header.cid());
+        self.by_parents
+            .entry((miner, header.parents().clone()))
+            .or_insert(*header.cid());
+        self.headers.insert(*header.cid(), header.clone());
+
+        if header.epoch() > self.highest_epoch {
+            self.highest_epoch = header.epoch();
+            let lowest_epoch = self.highest_epoch - SLASHER_WINDOW;
+            self.headers
+                .retain(|_, header| header.epoch() >= lowest_epoch);
+            let headers = &self.headers;
+            self.by_epoch.retain(|_, cid| headers.contains_key(cid));
+            self.by_parents.retain(|_, cid| headers.contains_key(cid));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::{Code::Blake2b256, MultihashDigest};
+    use fvm_ipld_encoding::DAG_CBOR;
+
+    use super::*;
+
+    fn parents(seed: &[u8]) -> TipsetKeys {
+        TipsetKeys::new(vec![Cid::new_v1(DAG_CBOR, Blake2b256.digest(seed))])
+    }
+
+    fn header(miner: u64, epoch: ChainEpoch, parents: TipsetKeys, timestamp: u64) -> BlockHeader {
+        let empty = Cid::new_v1(DAG_CBOR, Blake2b256.digest(&[]));
+        BlockHeader::builder()
+            .messages(empty)
+            .message_receipts(empty)
+            .state_root(empty)
+            .miner_address(Address::new_id(miner))
+            .epoch(epoch)
+            .parents(parents)
+            .timestamp(timestamp)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn double_fork_mining() {
+        let slasher = Slasher::default();
+        assert!(slasher.observe(&header(1, 10, parents(b"a"), 0)).is_empty());
+        // Blocks of other miners at the same epoch are fine
+        assert!(slasher.observe(&header(2, 10, parents(b"a"), 0)).is_empty());
+        let faults = slasher.observe(&header(1, 10, parents(b"b"), 1));
+        assert_eq!(faults.len(), 1);
+        assert_eq!(faults[0].fault_type, ConsensusFaultType::DoubleForkMining);
+        assert_eq!(slasher.faults(), faults);
+        assert_eq!(slasher.fault_receiver().try_recv().unwrap(), faults[0]);
+    }
+
+    #[test]
+    fn time_offset_mining() {
+        let slasher = Slasher::default();
+        assert!(slasher.observe(&header(1, 10, parents(b"a"), 0)).is_empty());
+        let faults = slasher.observe(&header(1, 11, parents(b"a"), 0));
+        assert_eq!(faults.len(), 1);
+        assert_eq!(faults[0].fault_type, ConsensusFaultType::TimeOffsetMining);
+    }
+
+    #[test]
+    fn parent_grinding() {
+        let slasher = Slasher::default();
+        let omitted = header(1, 10, parents(b"a"), 0);
+        let sibling = header(2, 10, parents(b"a"), 0);
+        let child = header(1, 11, TipsetKeys::new(vec![*sibling.cid()]), 0);
+        assert!(slasher.observe(&omitted).is_empty());
+        assert!(slasher.observe(&sibling).is_empty());
+        let faults = slasher.observe(&child);
+        assert_eq!(faults.len(), 1);
+        assert_eq!(faults[0].fault_type, ConsensusFaultType::ParentGrinding);
+        assert_eq!(faults[0].header1, omitted);
+        assert_eq!(faults[0].header2, child);
+        assert_eq!(faults[0].header_extra, Some(sibling));
+    }
+
+    #[test]
+    fn old_headers_are_pruned() {
+        let slasher = Slasher::default();
+        slasher.observe(&header(1, 10, parents(b"a"), 0));
+        slasher.observe(&header(2, 10 + SLASHER_WINDOW + 1, parents(b"b"), 0));
+        assert!(slasher.observe(&header(1, 10, parents(b"c"), 1)).is_empty());
+    }
+}
@@ -12,6 +12,8 @@ use std::{
 };
 
 use ahash::{HashMap, HashMapExt, HashSet};
+use anyhow::Context as _;
+use async_trait::async_trait;
 use cid::Cid;
 use forest_blocks::{
     Block, BlockHeader, Error as ForestBlockError, FullTipset, Tipset, TipsetKeys,
@@ -24,7 +26,7 @@ use forest_shim::{
     address::Address, gas::price_list_by_network_version, message::Message, state_tree::StateTree,
 };
 use forest_state_manager::{is_valid_for_sending, Error as StateManagerError, StateManager};
-use forest_utils::io::ProgressBar;
+use forest_utils::{db::BlockstoreExt, io::ProgressBar};
 use futures::{stream::FuturesUnordered, Stream, StreamExt, TryFutureExt};
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::Cbor;
@@ -38,10 +40,12 @@ use num::BigInt;
 use thiserror::Error;
 
 use crate::{
-    bad_block_cache::BadBlockCache,
-    consensus::{collect_errs, Consensus},
+    bad_block_cache::{BadBlockCache, BadBlockRecord, FailedCheck},
+    checkpoint::TrustedCheckpoint,
+    consensus::{collect_errs, Consensus, ValidationCheck},
     metrics,
     network_context::SyncNetworkContext,
+    slasher::Slasher,
     sync_state::SyncStage,
     validation::TipsetValidator,
 };
@@ -76,6 +80,16 @@ pub enum TipsetRangeSyncerError<C: Consensus> {
     TipsetRangeWithBadBlock(Cid, String),
     #[error("Validation error: {0}")]
     Validation(String),
+    #[error("Validation error: {}", join_errors(.0))]
+    FailedChecks(Vec<TipsetRangeSyncerError<C>>),
+    #[error("Base fee doesn't match: {0} (header), {1} (computed)")]
+    BaseFeeMismatch(String, String),
+    #[error("Parent weight doesn't match: {0} (header), {1} (computed)")]
+    ParentWeightMismatch(String, String),
+    #[error("Parent state root did not match computed state: {0} (header), {1} (computed)")]
+    StateRootMismatch(Cid, Cid),
+    #[error("Parent receipt root did not match computed root: {0} (header), {1} (computed)")]
+    ReceiptRootMismatch(Cid, Cid),
     #[error("Processing error: {0}")]
     Calculation(String),
     #[error("Chain store error: {0}")]
@@ -90,10 +104,16 @@ pub enum TipsetRangeSyncerError<C: Consensus> {
     ChainForkLengthExceedsFinalityThreshold,
     #[error("Chain for block forked from local chain at genesis, refusing to sync block: {0}")]
     ForkAtGenesisBlock(String),
+    #[error("Chain forked from local chain below the trusted checkpoint at epoch {0}")]
+    ForkBelowCheckpoint(ChainEpoch),
+    #[error("Chain does not include the trusted checkpoint: expected {0}, found {1}")]
+    CheckpointMismatch(String, String),
     #[error("Querying tipsets from the network failed: {0}")]
     NetworkTipsetQueryFailed(String),
     #[error("Query tipset messages from the network failed: {0}")]
     NetworkMessageQueryFailed(String),
+    #[error("Querying the state from the network failed: {0}")]
+    NetworkStateQueryFailed(String),
     #[error("BLS aggregate signature {0} was invalid for msgs {1}")]
     BlsAggregateSignatureInvalid(String, String),
     #[error("Message signature invalid: {0}")]
@@ -125,17 +145,112 @@ impl<C: Consensus> From<tokio::task::JoinError> for TipsetRangeSyncerError<C> {
 }
 
 impl<C: Consensus> TipsetRangeSyncerError<C> {
-    /// Concatenate all validation error messages into one comma separated
-    /// version.
+    /// Gathers the errors of several validation checks into one.
     fn concat(errs: NonEmpty<TipsetRangeSyncerError<C>>) -> Self {
-        let msg = errs
-            .iter()
-            .map(|e| e.to_string())
-            .collect::<Vec<_>>()
-            .join(", ");
+        TipsetRangeSyncerError::FailedChecks(errs.into_iter().collect())
+    }
+
+    /// Returns the validation checks behind the error.
+    pub fn failed_checks(&self) -> Vec<FailedCheck> {
+        match self {
+            TipsetRangeSyncerError::FailedChecks(errs) => {
+                errs.iter().flat_map(Self::failed_checks).collect()
+            }
+            err => vec![FailedCheck {
+                check: err.check().to_owned(),
+                error: err.to_string(),
+            }],
+        }
+    }
+}
 
-        TipsetRangeSyncerError::Validation(msg)
+impl<C: Consensus> ValidationCheck for TipsetRangeSyncerError<C> {
+    fn check(&self) -> &'static str {
+        match self {
+            Self::InvalidTipsetRangeLength => "InvalidTipsetRangeLength",
+            Self::InvalidTipsetEpoch => "InvalidTipsetEpoch",
+            Self::InvalidTipsetParent => "InvalidTipsetParent",
+            Self::BlockWithoutSignature => "BlockWithoutSignature",
+            Self::BlockWithoutBlsAggregate => "BlockWithoutBlsAggregate",
+            Self::TimeTravellingBlock(_, _) => "TimeTravellingBlock",
+            Self::TipsetRangeWithBadBlock(_, _) => "TipsetRangeWithBadBlock",
+            Self::Validation(_) => "Validation",
+            Self::FailedChecks(_) => "FailedChecks",
+            Self::BaseFeeMismatch(_, _) => "BaseFeeMismatch",
+            Self::ParentWeightMismatch(_, _) => "ParentWeightMismatch",
+            Self::StateRootMismatch(_, _) => "StateRootMismatch",
+            Self::ReceiptRootMismatch(_, _) => "ReceiptRootMismatch",
+            Self::Calculation(_) => "Calculation",
+            Self::ChainStore(_) => "ChainStore",
+            Self::StateManager(_) => "StateManager",
+            Self::BlockError(_) => "BlockError",
+            Self::ChainForkLengthExceedsMaximum => "ChainForkLengthExceedsMaximum",
+            Self::ChainForkLengthExceedsFinalityThreshold => {
+                "ChainForkLengthExceedsFinalityThreshold"
+            }
+            Self::ForkAtGenesisBlock(_) => "ForkAtGenesisBlock",
+            Self::ForkBelowCheckpoint(_) => "ForkBelowCheckpoint",
+            Self::CheckpointMismatch(_, _) => "CheckpointMismatch",
+            Self::NetworkTipsetQueryFailed(_) => "NetworkTipsetQueryFailed",
+            Self::NetworkMessageQueryFailed(_) => "NetworkMessageQueryFailed",
+            Self::NetworkStateQueryFailed(_) => "NetworkStateQueryFailed",
+            Self::BlsAggregateSignatureInvalid(_, _) => "BlsAggregateSignatureInvalid",
+            Self::MessageSignatureInvalid(_) => "MessageSignatureInvalid",
+            Self::BlockMessageRootInvalid(_, _) => "BlockMessageRootInvalid",
+            Self::ComputingMessageRoot(_) => "ComputingMessageRoot",
+            Self::ResolvingAddressFromMessage(_) => "ResolvingAddressFromMessage",
+            Self::GeneratingTipsetFromTipsetBundle(_) => "GeneratingTipsetFromTipsetBundle",
+            Self::TipsetParentNotFound(_) => "TipsetParentNotFound",
+            Self::ConsensusError(err) => err.check(),
+        }
     }
+
+    fn is_rule_violation(&self) -> bool {
+        match self {
+            Self::FailedChecks(errs) => errs.iter().any(Self::is_rule_violation),
+            Self::ConsensusError(err) => err.is_rule_violation(),
+            Self::BlockWithoutSignature
+            | Self::BlockWithoutBlsAggregate
+            | Self::Validation(_)
+            | Self::BaseFeeMismatch(_, _)
+            | Self::ParentWeightMismatch(_, _)
+            | Self::StateRootMismatch(_, _)
+            | Self::ReceiptRootMismatch(_, _)
+            | Self::BlockError(_)
+            | Self::BlsAggregateSignatureInvalid(_, _)
+            | Self::MessageSignatureInvalid(_)
+            | Self::BlockMessageRootInvalid(_, _) => true,
+            // Blocks from the future may be valid later on, and the other
+            // errors are the node's own failures or about tipset ranges.
+            Self::InvalidTipsetRangeLength
+            | Self::InvalidTipsetEpoch
+            | Self::InvalidTipsetParent
+            | Self::TimeTravellingBlock(_, _)
+            | Self::TipsetRangeWithBadBlock(_, _)
+            | Self::Calculation(_)
+            | Self::ChainStore(_)
+            | Self::StateManager(_)
+            | Self::ChainForkLengthExceedsMaximum
+            | Self::ChainForkLengthExceedsFinalityThreshold
+            | Self::ForkAtGenesisBlock(_)
+            | Self::ForkBelowCheckpoint(_)
+            | Self::CheckpointMismatch(_, _)
+            | Self::NetworkTipsetQueryFailed(_)
+            | Self::NetworkMessageQueryFailed(_)
+            | Self::NetworkStateQueryFailed(_)
+            | Self::ComputingMessageRoot(_)
+            | Self::ResolvingAddressFromMessage(_)
+            | Self::GeneratingTipsetFromTipsetBundle(_)
+            | Self::TipsetParentNotFound(_) => false,
+        }
+    }
+}
+
+fn join_errors<E: std::fmt::Display>(errs: &[E]) -> String {
+    errs.iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 struct TipsetGroup {
@@ -257,6 +372,8 @@ pub(crate) struct TipsetProcessor<DB, C: Consensus> {
     chain_store: Arc<ChainStore<DB>>,
     bad_block_cache: Arc<BadBlockCache>,
     genesis: Arc<Tipset>,
+    checkpoint: Option<Arc<TrustedCheckpoint>>,
+    slasher: Option<Arc<Slasher>>,
 }
 
 impl<DB, C> TipsetProcessor<DB, C>
@@ -274,6 +391,8 @@ where
         chain_store: Arc<ChainStore<DB>>,
         bad_block_cache: Arc<BadBlockCache>,
         genesis: Arc<Tipset>,
+        checkpoint: Option<Arc<TrustedCheckpoint>>,
+        slasher: Option<Arc<Slasher>>,
     ) -> Self {
         Self {
             state: TipsetProcessorState::Idle,
@@ -285,6 +404,8 @@ where
             chain_store,
             bad_block_cache,
             genesis,
+            checkpoint,
+            slasher,
         }
     }
 
@@ -299,6 +420,8 @@ where
         let bad_block_cache = self.bad_block_cache.clone();
         let tracker = self.tracker.clone();
         let genesis = self.genesis.clone();
+        let checkpoint = self.checkpoint.clone();
+        let slasher = self.slasher.clone();
         Box::pin(async move {
             // Define the low end of the range
             // Unwrapping is safe here because the store always has at least one tipset
@@ -321,6 +444,8 @@ where
                 chain_store,
                 bad_block_cache,
                 genesis,
+                checkpoint,
+                slasher,
             )?;
             for tipset in tipset_group.tipsets() {
                 tipset_range_syncer.add_tipset(tipset)?;
@@ -633,6 +758,7 @@ pub(crate) struct TipsetRangeSyncer<DB, C: Consensus> {
     bad_block_cache: Arc<BadBlockCache>,
     genesis: Arc<Tipset>,
     consensus: Arc<C>,
+    slasher: Option<Arc<Slasher>>,
 }
 
 impl<DB, C> TipsetRangeSyncer<DB, C>
@@ -651,6 +777,8 @@ where
         chain_store: Arc<ChainStore<DB>>,
         bad_block_cache: Arc<BadBlockCache>,
         genesis: Arc<Tipset>,
+        checkpoint: Option<Arc<TrustedCheckpoint>>,
+        slasher: Option<Arc<Slasher>>,
     ) -> Result<Self, TipsetRangeSyncerError<C>> {
         let tipset_tasks = Box::pin(FuturesUnordered::new());
         let tipset_range_length = proposed_head.epoch() - current_head.epoch();
@@ -673,6 +801,8 @@ where
             network.clone(),
             bad_block_cache.clone(),
             genesis.clone(),
+            checkpoint,
+            slasher.clone(),
         ));
 
         let tipsets_included = HashSet::from_iter([proposed_head.key().clone()]);
@@ -687,6 +817,7 @@ where
             chain_store,
             bad_block_cache,
             genesis,
+            slasher,
         })
     }
 
@@ -724,6 +855,7 @@ where
             self.network.clone(),
             self.bad_block_cache.clone(),
             self.genesis.clone(),
+            self.slasher.clone(),
         ));
         Ok(true)
     }
@@ -760,6 +892,10 @@ where
 /// missing tipsets from the network. Once headers are available, download
 /// messages going forward on the chain and validate each extension. Finally set
 /// the proposed head as the heaviest tipset.
+///
+/// When the range crosses the trusted checkpoint, headers are only synced back
+/// to the checkpoint, and the tipsets above it are validated from its trusted
+/// state.
 #[allow(clippy::too_many_arguments)]
 fn sync_tipset_range<DB: Blockstore + Clone + Sync + Send + 'static, C: Consensus>(
     proposed_head: Arc<Tipset>,
@@ -772,13 +908,18 @@ fn sync_tipset_range<DB: Blockstore + Clone + Sync + Send + 'static, C: Consensu
     network: SyncNetworkContext<DB>,
     bad_block_cache: Arc<BadBlockCache>,
     genesis: Arc<Tipset>,
+    checkpoint: Option<Arc<TrustedCheckpoint>>,
+    slasher: Option<Arc<Slasher>>,
 ) -> TipsetRangeSyncerFuture<C> {
     Box::pin(async move {
         tracker
             .write()
             .init(current_head.clone(), proposed_head.clone());
 
-        let parent_tipsets = match sync_headers_in_reverse(
+        let headers_timer = metrics::SYNC_STAGE_TIME
+            .with_label_values(&[metrics::values::HEADERS_STAGE])
+            .start_timer();
+        let headers = sync_headers_in_reverse(
             tracker.clone(),
             tipset_range_length,
             proposed_head.clone(),
@@ -786,9 +927,11 @@ fn sync_tipset_range<DB: Blockstore + Clone + Sync + Send + 'static, C: Consensu
             &bad_block_cache,
             &chain_store,
             network.clone(),
+            checkpoint.as_deref(),
         )
-        .await
-        {
+        .await;
+        headers_timer.observe_duration();
+        let mut parent_tipsets = match headers {
             Ok(parent_tipsets) => parent_tipsets,
             Err(why) => {
                 tracker.write().error(why.to_string());
@@ -804,6 +947,53 @@ fn sync_tipset_range<DB: Blockstore + Clone + Sync + Send + 'static, C: Consensu
             return Err(why.into());
         };
 
+        // The checkpoint tipset is not validated, its children are validated
+        // against its trusted state
+        if let Some(checkpoint) = &checkpoint {
+            if parent_tipsets.len() > 1
+                && parent_tipsets.last().map(|ts| ts.key()) == Some(&checkpoint.tipset)
+            {
+                // Unwrapping is safe here because the checkpoint and its
+                // child are in the vector
+                let checkpoint_ts = parent_tipsets.pop().unwrap();
+                let child = parent_tipsets.last().unwrap();
+                let receipt_root = *child.min_ticket_block().message_receipts();
+                let lookback_states = match sync_checkpoint_lookback(
+                    &state_manager,
+                    &chain_store,
+                    &network,
+                    &checkpoint_ts,
+                    &parent_tipsets,
+                )
+                .await
+                {
+                    Ok(lookback_states) => lookback_states,
+                    Err(why) => {
+                        tracker.write().error(why.to_string());
+                        return Err(why);
+                    }
+                };
+                // The trusted states are fetched whole if they are missing from
+                // the store, walking the blocks they share once
+                let mut visited = HashSet::default();
+                for root in [checkpoint.state_root, receipt_root]
+                    .into_iter()
+                    .chain(lookback_states)
+                {
+                    if let Err(why) = network.graphsync_dag(&mut visited, root).await {
+                        let why = TipsetRangeSyncerError::NetworkStateQueryFailed(why);
+                        tracker.write().error(why.to_string());
+                        return Err(why);
+                    }
+                }
+                state_manager.trust_tipset_state(
+                    &checkpoint_ts,
+                    checkpoint.state_root,
+                    receipt_root,
+                );
+            }
+        }
+
         //  Sync and validate messages from the tipsets
         tracker.write().set_stage(SyncStage::Messages);
         if let Err(why) = sync_messages_check_state(
@@ -816,6 +1006,7 @@ fn sync_tipset_range<DB: Blockstore + Clone + Sync + Send + 'static, C: Consensu
             parent_tipsets,
             &genesis,
             InvalidBlockStrategy::Strict,
+            slasher.as_deref(),
         )
         .await
         {
@@ -849,6 +1040,11 @@ fn sync_tipset_range<DB: Blockstore + Clone + Sync + Send + 'static, C: Consensu
 /// Download headers between the proposed head and the current one available
 /// locally. If they turn out to be on different forks, download more headers up
 /// to a certain limit to try to find a common ancestor.
+///
+/// When the trusted checkpoint is between the current head and the proposed
+/// one, headers are only downloaded back to the checkpoint, which the proposed
+/// chain must include.
+#[allow(clippy::too_many_arguments)]
 async fn sync_headers_in_reverse<DB: Blockstore + Clone + Sync + Send + 'static, C: Consensus>(
     tracker: crate::chain_muxer::WorkerState,
     tipset_range_length: u64,
@@ -857,13 +1053,19 @@ async fn sync_headers_in_reverse<DB: Blockstore + Clone + Sync + Send + 'static,
     bad_block_cache: &BadBlockCache,
     chain_store: &ChainStore<DB>,
     network: SyncNetworkContext<DB>,
+    checkpoint: Option<&TrustedCheckpoint>,
 ) -> Result<Vec<Arc<Tipset>>, TipsetRangeSyncerError<C>> {
     let mut parent_blocks: Vec<Cid> = vec![];
     let mut parent_tipsets = Vec::with_capacity(tipset_range_length as usize + 1);
     parent_tipsets.push(proposed_head.clone());
     tracker.write().set_epoch(current_head.epoch());
 
-    let total_size = proposed_head.epoch() - current_head.epoch();
+    // Lowest epoch headers are synced back to
+    let sync_to =
+        checkpoint.filter(|checkpoint| checkpoint.is_within(current_head, &proposed_head));
+    let lowest_epoch = sync_to.map_or(current_head.epoch(), |checkpoint| checkpoint.epoch);
+
+    let total_size = proposed_head.epoch() - lowest_epoch;
     let pb = ProgressBar::new(total_size as u64);
     pb.message("Downloading headers ");
     pb.set_max_refresh_rate(Some(std::time::Duration::from_millis(500)));
@@ -872,12 +1074,12 @@ async fn sync_headers_in_reverse<DB: Blockstore + Clone + Sync + Send + 'static,
         // Unwrapping is safe here because the tipset vector always
         // has at least one element
         let oldest_parent = parent_tipsets.last().unwrap();
-        let work_to_be_done = oldest_parent.epoch() - current_head.epoch();
+        let work_to_be_done = oldest_parent.epoch() - lowest_epoch;
         pb.set((work_to_be_done - total_size).unsigned_abs());
         validate_tipset_against_cache(bad_block_cache, oldest_parent.parents(), &parent_blocks)?;
 
         // Check if we are at the end of the range
-        if oldest_parent.epoch() <= current_head.epoch() {
+        if oldest_parent.epoch() <= lowest_epoch {
             // Current tipset epoch is less than or equal to the epoch of
             // Tipset we a synchronizing toward, stop.
             break;
@@ -890,7 +1092,7 @@ async fn sync_headers_in_reverse<DB: Blockstore + Clone + Sync + Send + 'static,
         }
 
         // TODO: Tweak request window when socket frame is tested
-        let epoch_diff = oldest_parent.epoch() - current_head.epoch();
+        let epoch_diff = oldest_parent.epoch() - lowest_epoch;
         let window = min(epoch_diff, MAX_TIPSETS_TO_REQUEST as i64);
         let network_tipsets = network
             .chain_exchange_headers(None, oldest_parent.parents(), window as u64)
@@ -899,7 +1101,7 @@ async fn sync_headers_in_reverse<DB: Blockstore + Clone + Sync + Send + 'static,
 
         for tipset in network_tipsets {
             // Break if have already traversed the entire tipset range
-            if tipset.epoch() < current_head.epoch() {
+            if tipset.epoch() < lowest_epoch {
                 break 'sync;
             }
             validate_tipset_against_cache(bad_block_cache, tipset.key(), &parent_blocks)?;
@@ -913,6 +1115,24 @@ async fn sync_headers_in_reverse<DB: Blockstore + Clone + Sync + Send + 'static,
     // Unwrapping is safe here because we assume that the tipset
     // vector was initialized with a tipset that will not be removed
     let oldest_tipset = parent_tipsets.last().unwrap().clone();
+    if let Some(checkpoint) = sync_to {
+        if oldest_tipset.key() != &checkpoint.tipset {
+            return Err(TipsetRangeSyncerError::CheckpointMismatch(
+                format!("{:?}", checkpoint.tipset.cids()),
+                format!("{:?}", oldest_tipset.cids()),
+            ));
+        }
+        // The children of the checkpoint must build on its trusted state
+        if let Some(child) = parent_tipsets.iter().rev().nth(1) {
+            if child.parent_state() != &checkpoint.state_root {
+                return Err(TipsetRangeSyncerError::CheckpointMismatch(
+                    format!("state root {}", checkpoint.state_root),
+                    format!("state root {}", child.parent_state()),
+                ));
+            }
+        }
+        return Ok(parent_tipsets);
+    }
     // Determine if the local chain was a fork.
     // If it was, then sync the fork tipset range by iteratively walking back
     // from the oldest tipset synced until we find a common ancestor
@@ -933,6 +1153,17 @@ async fn sync_headers_in_reverse<DB: Blockstore + Clone + Sync + Send + 'static,
                     oldest_tipset.cids()
                 )));
             }
+            // Forks cannot reorganize the local chain below the trusted
+            // checkpoint, once it is synced past it
+            if let Some(checkpoint) =
+                checkpoint.filter(|checkpoint| current_head.epoch() >= checkpoint.epoch)
+            {
+                if fork_tipsets[i].epoch() < checkpoint.epoch {
+                    return Err(TipsetRangeSyncerError::ForkBelowCheckpoint(
+                        checkpoint.epoch,
+                    ));
+                }
+            }
             if potential_common_ancestor == fork_tipsets[i] {
                 // Remove elements from the vector since the Drain
                 // iterator is immediately dropped
@@ -966,6 +1197,80 @@ async fn sync_headers_in_reverse<DB: Blockstore + Clone + Sync + Send + 'static,
     Ok(parent_tipsets)
 }
 
+/// Syncs the headers below the trusted checkpoint that the tipsets validated
+/// from its state look back to, and returns the state roots read at these
+/// lookbacks, which are not computed locally.
+async fn sync_checkpoint_lookback<DB, C>(
+    state_manager: &StateManager<DB>,
+    chain_store: &ChainStore<DB>,
+    network: &SyncNetworkContext<DB>,
+    checkpoint: &Arc<Tipset>,
+    tipsets: &[Arc<Tipset>],
+) -> Result<HashSet<Cid>, TipsetRangeSyncerError<C>>
+where
+    DB: Blockstore + Clone + Sync + Send + 'static,
+    C: Consensus,
+{
+    // Rounds at or above the checkpoint look back to states computed locally
+    let rounds: Vec<ChainEpoch> = tipsets
+        .iter()
+        .map(|ts| (ts.epoch() - state_manager.lookback_for_round(ts.epoch())).max(0))
+        .filter(|round| *round < checkpoint.epoch())
+        .collect();
+    let lowest = match rounds.iter().min() {
+        Some(lowest) => *lowest,
+        None => return Ok(HashSet::default()),
+    };
+    sync_headers_down_to(chain_store, network, checkpoint.clone(), lowest).await?;
+
+    // The state of a lookback round is the parent state of the first tipset
+    // above it
+    let mut states = HashSet::default();
+    for round in rounds {
+        let next_ts = chain_store.tipset_by_height(round + 1, checkpoint.clone(), false)?;
+        states.insert(*next_ts.parent_state());
+    }
+    Ok(states)
+}
+
+/// Syncs the headers of the chain of `tipset` back to `epoch`, requesting the
+/// ones missing from the store from the network.
+async fn sync_headers_down_to<DB, C>(
+    chain_store: &ChainStore<DB>,
+    network: &SyncNetworkContext<DB>,
+    tipset: Arc<Tipset>,
+    epoch: ChainEpoch,
+) -> Result<(), TipsetRangeSyncerError<C>>
+where
+    DB: Blockstore + Clone + Sync + Send + 'static,
+    C: Consensus,
+{
+    let mut oldest = tipset;
+    while oldest.epoch() > epoch {
+        if let Ok(parent) = chain_store.tipset_from_keys(oldest.parents()) {
+            oldest = parent;
+            continue;
+        }
+        let window = min(oldest.epoch() - epoch, MAX_TIPSETS_TO_REQUEST as i64);
+        let network_tipsets = network
+            .chain_exchange_headers(None, oldest.parents(), window as u64)
+            .await
+            .map_err(TipsetRangeSyncerError::NetworkTipsetQueryFailed)?;
+        let headers: Vec<&BlockHeader> = network_tipsets.iter().flat_map(|t| t.blocks()).collect();
+        persist_objects(chain_store.blockstore(), &headers)?;
+        oldest = match network_tipsets.last() {
+            Some(tipset) => tipset.clone(),
+            None => {
+                return Err(TipsetRangeSyncerError::NetworkTipsetQueryFailed(format!(
+                    "No headers returned for {:?}",
+                    oldest.parents()
+                )))
+            }
+        };
+    }
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 fn sync_tipset<DB: Blockstore + Clone + Sync + Send + 'static, C: Consensus>(
     proposed_head: Arc<Tipset>,
@@ -975,6 +1280,7 @@ fn sync_tipset<DB: Blockstore + Clone + Sync + Send + 'static, C: Consensus>(
     network: SyncNetworkContext<DB>,
     bad_block_cache: Arc<BadBlockCache>,
     genesis: Arc<Tipset>,
+    slasher: Option<Arc<Slasher>>,
 ) -> TipsetRangeSyncerFuture<C> {
     Box::pin(async move {
         // Persist the blocks from the proposed tipsets into the store
@@ -993,6 +1299,7 @@ fn sync_tipset<DB: Blockstore + Clone + Sync + Send + 'static, C: Consensus>(
             vec![proposed_head.clone()],
             &genesis,
             InvalidBlockStrategy::Forgiving,
+            slasher.as_deref(),
         )
         .await
         {
@@ -1029,10 +1336,14 @@ async fn fetch_batch<DB: Blockstore + Clone + Send + Sync + 'static, C: Consensu
 
         debug!("ChainExchange message sync tipsets: epoch: {epoch}, len: {len}");
 
+        let messages_timer = metrics::SYNC_STAGE_TIME
+            .with_label_values(&[metrics::values::MESSAGES_STAGE])
+            .start_timer();
         let compacted_messages = network
             .chain_exchange_messages(None, head.key(), len as u64)
             .await
             .map_err(TipsetRangeSyncerError::NetworkMessageQueryFailed)?;
+        messages_timer.observe_duration();
 
         // Since the bundle only has messages, we have to put the headers in them
         for (messages, tipset) in compacted_messages.into_iter().rev().zip(batch.iter()) {
@@ -1074,6 +1385,7 @@ async fn sync_messages_check_state<DB: Blockstore + Clone + Send + Sync + 'stati
     tipsets: Vec<Arc<Tipset>>,
     genesis: &Tipset,
     invalid_block_strategy: InvalidBlockStrategy,
+    slasher: Option<&Slasher>,
 ) -> Result<(), TipsetRangeSyncerError<C>> {
     // Sync the messages for one or many tipsets @ a time
     const REQUEST_WINDOW: usize = 4;
@@ -1116,6 +1428,9 @@ async fn sync_messages_check_state<DB: Blockstore + Clone + Send + Sync + 'stati
         let current_epoch = full_tipset.epoch();
         {
             let _timer = metrics::TIPSET_PROCESSING_TIME.start_timer();
+            let _stage_timer = metrics::SYNC_STAGE_TIME
+                .with_label_values(&[metrics::values::VALIDATION_STAGE])
+                .start_timer();
             validate_tipset::<_, C>(
                 consensus.clone(),
                 state_manager.clone(),
@@ -1124,6 +1439,7 @@ async fn sync_messages_check_state<DB: Blockstore + Clone + Send + Sync + 'stati
                 full_tipset,
                 genesis,
                 invalid_block_strategy,
+                slasher,
             )
             .await?;
         }
@@ -1135,9 +1451,9 @@ async fn sync_messages_check_state<DB: Blockstore + Clone + Send + Sync + 'stati
 }
 
 /// Validates full blocks in the tipset in parallel (since the messages are not
-/// executed), adding the successful ones to the tipset tracker, and the failed
-/// ones to the bad block cache, depending on strategy. Any bad block fails
-/// validation.
+/// executed), adding the successful ones to the tipset tracker and the slasher,
+/// and the failed ones to the bad block cache, depending on strategy. Any bad
+/// block fails validation.
 async fn validate_tipset<DB: Blockstore + Clone + Send + Sync + 'static, C: Consensus>(
     consensus: Arc<C>,
     state_manager: Arc<StateManager<DB>>,
@@ -1146,6 +1462,7 @@ async fn validate_tipset<DB: Blockstore + Clone + Send + Sync + 'static, C: Cons
     full_tipset: FullTipset,
     genesis: &Tipset,
     invalid_block_strategy: InvalidBlockStrategy,
+    slasher: Option<&Slasher>,
 ) -> Result<(), TipsetRangeSyncerError<C>> {
     if full_tipset.key().eq(genesis.key()) {
         trace!("Skipping genesis tipset validation");
@@ -1177,6 +1494,9 @@ async fn validate_tipset<DB: Blockstore + Clone + Send + Sync + 'static, C: Cons
         match result? {
             Ok(block) => {
                 chainstore.add_to_tipset_tracker(block.header());
+                if let Some(slasher) = slasher {
+                    slasher.observe(block.header());
+                }
             }
             Err((cid, why)) => {
                 warn!(
@@ -1186,14 +1506,14 @@ async fn validate_tipset<DB: Blockstore + Clone + Send + Sync + 'static, C: Cons
                     why
                 );
                 // Only do bad block accounting if the function was called with
-                // `is_strict` = true
+                // `is_strict` = true, and only for blocks breaking a consensus
+                // rule, as the node may succeed in checking the others later.
                 if let InvalidBlockStrategy::Strict = invalid_block_strategy {
-                    match &why {
-                        TipsetRangeSyncerError::TimeTravellingBlock(_, _)
-                        | TipsetRangeSyncerError::TipsetParentNotFound(_) => (),
-                        why => {
-                            bad_block_cache.put(cid, why.to_string());
-                        }
+                    if why.is_rule_violation() {
+                        bad_block_cache.put(
+                            cid,
+                            BadBlockRecord::new(why.to_string(), why.failed_checks()),
+                        );
                     }
                 }
                 return Err(why);
@@ -1252,37 +1572,72 @@ async fn validate_block<DB: Blockstore + Clone + Sync + Send + 'static, C: Conse
 
     let _timer = metrics::BLOCK_VALIDATION_TIME.start_timer();
 
-    let header = block.header();
+    check_block(consensus, state_manager, block.clone())
+        .await
+        .map_err(|why| (*block_cid, why))?;
 
-    // Check to ensure all optional values exist
-    block_sanity_checks(header).map_err(|e| (*block_cid, e))?;
-    block_timestamp_checks(header).map_err(|e| (*block_cid, e))?;
-
-    let base_tipset = chain_store
-        .tipset_from_keys(header.parents())
-        // The parent tipset will always be there when calling validate_block
-        // as part of the sync_tipset_range flow because all of the headers in the range
-        // have been committed to the store. When validate_block is called from sync_tipset
-        // this guarantee does not exist, so we create a specific error to inform the caller
-        // not to add this block to the bad blocks cache.
-        .map_err(|why| {
+    chain_store
+        .mark_block_as_validated(block_cid)
+        .map_err(|e| {
             (
                 *block_cid,
-                TipsetRangeSyncerError::TipsetParentNotFound(why),
+                TipsetRangeSyncerError::<C>::Calculation(format!(
+                    "failed to mark block {block_cid} as validated {e}"
+                )),
             )
         })?;
 
-    // Retrieve lookback tipset for validation
-    let lookback_state = state_manager
-        .get_lookback_tipset_for_round(base_tipset.clone(), block.header().epoch())
-        .map_err(|e| (*block_cid, e.into()))
-        .map(|(_, s)| Arc::new(s))?;
+    Ok(block)
+}
+
+/// Runs the checks of [`validate_block`], failing on the first unmet
+/// precondition of the checks, such as an unknown parent tipset, along with
+/// the failed header checks, or else with the errors of all the failed checks.
+async fn check_block<DB: Blockstore + Clone + Sync + Send + 'static, C: Consensus>(
+    consensus: Arc<C>,
+    state_manager: Arc<StateManager<DB>>,
+    block: Arc<Block>,
+) -> Result<(), TipsetRangeSyncerError<C>> {
+    let chain_store = state_manager.chain_store().clone();
+    let header = block.header();
 
-    // Work address needed for async validations, so necessary
-    // to do sync to avoid duplication
-    let work_addr = state_manager
-        .get_miner_work_addr(*lookback_state, header.miner_address())
-        .map_err(|e| (*block_cid, e.into()))?;
+    // Check to ensure all optional values exist, and the clock drift. These
+    // don't stop the other checks, so that all the failed ones are reported.
+    let mut header_errs = block_sanity_checks(header);
+    header_errs.extend(block_timestamp_checks(header).err());
+
+    let preconditions = || -> Result<_, TipsetRangeSyncerError<C>> {
+        let base_tipset = chain_store
+            .tipset_from_keys(header.parents())
+            // The parent tipset will always be there when calling validate_block
+            // as part of the sync_tipset_range flow because all of the headers in the range
+            // have been committed to the store. When validate_block is called from sync_tipset
+            // this guarantee does not exist, so we create a specific error to inform the caller
+            // not to add this block to the bad blocks cache.
+            .map_err(TipsetRangeSyncerError::TipsetParentNotFound)?;
+
+        // Retrieve lookback tipset for validation
+        let (_, lookback_state) =
+            state_manager.get_lookback_tipset_for_round(base_tipset.clone(), header.epoch())?;
+
+        // Work address needed for async validations, so necessary
+        // to do sync to avoid duplication
+        let work_addr =
+            state_manager.get_miner_work_addr(lookback_state, header.miner_address())?;
+        Ok((base_tipset, work_addr))
+    };
+    let (base_tipset, work_addr) = match preconditions() {
+        Ok(preconditions) => preconditions,
+        Err(why) => {
+            return Err(match NonEmpty::from_vec(header_errs) {
+                Some(mut errs) => {
+                    errs.push(why);
+                    TipsetRangeSyncerError::<C>::concat(errs)
+                }
+                None => why,
+            })
+        }
+    };
 
     // Async validations
     let validations = FuturesUnordered::new();
@@ -1305,13 +1660,14 @@ async fn validate_block<DB: Blockstore + Clone + Sync + Send + 'static, C: Conse
             .start_timer();
         let base_fee = forest_chain::compute_base_fee(&v_block_store, &v_base_tipset, smoke_height)
             .map_err(|e| {
-                TipsetRangeSyncerError::<C>::Validation(format!("Could not compute base fee: {e}"))
+                TipsetRangeSyncerError::<C>::Calculation(format!("Could not compute base fee: {e}"))
             })?;
         let parent_base_fee = v_block.header.parent_base_fee();
         if base_fee != parent_base_fee.clone() {
-            return Err(TipsetRangeSyncerError::<C>::Validation(format!(
-                "base fee doesn't match: {parent_base_fee} (header), {base_fee} (computed)"
-            )));
+            return Err(TipsetRangeSyncerError::<C>::BaseFeeMismatch(
+                parent_base_fee.to_string(),
+                base_fee.to_string(),
+            ));
         }
         Ok(())
     }));
@@ -1328,9 +1684,10 @@ async fn validate_block<DB: Blockstore + Clone + Sync + Send + 'static, C: Conse
             TipsetRangeSyncerError::Calculation(format!("Error calculating weight: {e}"))
         })?;
         if weight != calc_weight {
-            return Err(TipsetRangeSyncerError::<C>::Validation(format!(
-                "Parent weight doesn't match: {weight} (header), {calc_weight} (computed)"
-            )));
+            return Err(TipsetRangeSyncerError::<C>::ParentWeightMismatch(
+                weight.to_string(),
+                calc_weight.to_string(),
+            ));
         }
         Ok(())
     }));
@@ -1341,27 +1698,29 @@ async fn validate_block<DB: Blockstore + Clone + Sync + Send + 'static, C: Conse
     let v_block = Arc::clone(&block);
     validations.push(tokio::task::spawn(async move {
         let header = v_block.header();
+        let state_timer = metrics::SYNC_STAGE_TIME
+            .with_label_values(&[metrics::values::STATE_COMPUTATION_STAGE])
+            .start_timer();
         let (state_root, receipt_root) = v_state_manager
             .tipset_state(&v_base_tipset)
             .await
             .map_err(|e| {
                 TipsetRangeSyncerError::Calculation(format!("Failed to calculate state: {e}"))
             })?;
+        state_timer.observe_duration();
 
         if &state_root != header.state_root() {
-            return Err(TipsetRangeSyncerError::<C>::Validation(format!(
-                "Parent state root did not match computed state: {} (header), {} (computed)",
-                header.state_root(),
+            return Err(TipsetRangeSyncerError::<C>::StateRootMismatch(
+                *header.state_root(),
                 state_root,
-            )));
+            ));
         }
 
         if &receipt_root != header.message_receipts() {
-            return Err(TipsetRangeSyncerError::<C>::Validation(format!(
-                "Parent receipt root did not match computed root: {} (header), {} (computed)",
-                header.message_receipts(),
-                receipt_root
-            )));
+            return Err(TipsetRangeSyncerError::<C>::ReceiptRootMismatch(
+                *header.message_receipts(),
+                receipt_root,
+            ));
         }
         Ok(())
     }));
@@ -1382,11 +1741,8 @@ async fn validate_block<DB: Blockstore + Clone + Sync + Send + 'static, C: Conse
         consensus
             .validate_block(state_manager, v_block)
             .map_err(|errs| {
-                // NOTE: Concatenating errors here means the wrapper type of error
-                // never surfaces, yet we always pay the cost of the generic argument.
-                // But there's no reason `validate_block` couldn't return a list of all
-                // errors instead of a single one that has all the error messages,
-                // removing the caller's ability to distinguish between them.
+                // The errors are kept apart, for the bad block records to tell
+                // which of the consensus checks failed.
                 let errs = errs.map(|err| TipsetRangeSyncerError::<C>::ConsensusError(err));
 
                 TipsetRangeSyncerError::<C>::concat(errs)
@@ -1395,22 +1751,67 @@ async fn validate_block<DB: Blockstore + Clone + Sync + Send + 'static, C: Conse
     }));
 
     // Collect the errors from the async validations
-    if let Err(errs) = collect_errs(validations).await {
-        return Err((*block_cid, TipsetRangeSyncerError::<C>::concat(errs)));
+    let errs = collect_errs(validations).await.err().into_iter().flatten();
+    match NonEmpty::from_vec(header_errs.into_iter().chain(errs).collect()) {
+        Some(errs) => Err(TipsetRangeSyncerError::<C>::concat(errs)),
+        None => Ok(()),
     }
+}
 
-    chain_store
-        .mark_block_as_validated(block_cid)
-        .map_err(|e| {
-            (
-                *block_cid,
-                TipsetRangeSyncerError::<C>::Validation(format!(
-                    "failed to mark block {block_cid} as validated {e}"
-                )),
-            )
-        })?;
+/// Explains why blocks are bad, by validating them again.
+#[async_trait]
+pub trait ExplainBlock: Send + Sync {
+    /// Validates the block with the given CID, whether it was validated or
+    /// marked bad before, and returns the checks it fails. The block and its
+    /// messages must be in the store.
+    async fn explain_block(&self, block_cid: Cid) -> anyhow::Result<Vec<FailedCheck>>;
+}
 
-    Ok(block)
+/// Runs the checks of the chain sync on blocks outside of it, see
+/// [`ExplainBlock`].
+pub(crate) struct BlockExplainer<DB, C> {
+    consensus: Arc<C>,
+    state_manager: Arc<StateManager<DB>>,
+}
+
+impl<DB, C> BlockExplainer<DB, C> {
+    pub fn new(consensus: Arc<C>, state_manager: Arc<StateManager<DB>>) -> Self {
+        Self {
+            consensus,
+            state_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl<DB, C> ExplainBlock for BlockExplainer<DB, C>
+where
+    DB: Blockstore + Clone + Sync + Send + 'static,
+    C: Consensus,
+{
+    async fn explain_block(&self, block_cid: Cid) -> anyhow::Result<Vec<FailedCheck>> {
+        let db = self.state_manager.blockstore();
+        let header = db
+            .get_obj::<BlockHeader>(&block_cid)?
+            .with_context(|| format!("Block {block_cid} not found"))?;
+        let (bls_messages, secp_messages) = forest_chain::block_messages(db, &header)
+            .with_context(|| format!("Messages of block {block_cid} not found"))?;
+        let block = Block {
+            header,
+            bls_messages,
+            secp_messages,
+        };
+        let result = check_block(
+            self.consensus.clone(),
+            self.state_manager.clone(),
+            Arc::new(block),
+        )
+        .await;
+        Ok(result
+            .err()
+            .map(|why| why.failed_checks())
+            .unwrap_or_default())
+    }
 }
 
 /// Validate messages in a full block, relative to the parent tipset.
@@ -1572,17 +1973,17 @@ async fn check_block_messages<DB: Blockstore + Clone + Send + Sync + 'static, C:
 
 /// Checks optional values in header.
 ///
-/// It only looks for fields which are common to all consensus types.
-fn block_sanity_checks<C: Consensus>(
-    header: &BlockHeader,
-) -> Result<(), TipsetRangeSyncerError<C>> {
+/// It only looks for fields which are common to all consensus types, and
+/// returns an error for each missing one.
+fn block_sanity_checks<C: Consensus>(header: &BlockHeader) -> Vec<TipsetRangeSyncerError<C>> {
+    let mut errs = Vec::new();
     if header.signature().is_none() {
-        return Err(TipsetRangeSyncerError::BlockWithoutSignature);
+        errs.push(TipsetRangeSyncerError::BlockWithoutSignature);
     }
     if header.bls_aggregate().is_none() {
-        return Err(TipsetRangeSyncerError::BlockWithoutBlsAggregate);
+        errs.push(TipsetRangeSyncerError::BlockWithoutBlsAggregate);
     }
-    Ok(())
+    errs
 }
 
 /// Check the clock drift.
@@ -1617,12 +2018,15 @@ fn validate_tipset_against_cache<C: Consensus>(
     descendant_blocks: &[Cid],
 ) -> Result<(), TipsetRangeSyncerError<C>> {
     for cid in tipset.cids() {
-        if let Some(reason) = bad_block_cache.get(cid) {
+        if let Some(record) = bad_block_cache.get(cid) {
+            // Descendants are marked bad after the block that failed validation
+            let bad_block = record.bad_ancestor.unwrap_or(*cid);
             for block_cid in descendant_blocks {
-                bad_block_cache.put(*block_cid, format!("chain contained {cid}"));
+                bad_block_cache.put(*block_cid, BadBlockRecord::descendant_of(bad_block));
             }
             return Err(TipsetRangeSyncerError::TipsetRangeWithBadBlock(
-                *cid, reason,
+                *cid,
+                record.to_string(),
             ));
         }
     }
@@ -1633,11 +2037,93 @@ fn validate_tipset_against_cache<C: Consensus>(
 mod test {
     use cid::Cid;
     use forest_blocks::{BlockHeader, ElectionProof, Ticket, Tipset};
+    use forest_chain::{Scale, Weight};
+    use forest_db::MemoryDB;
     use forest_json::vrf::VRFProof;
-    use forest_shim::address::Address;
+    use forest_libp2p::{
+        chain_exchange::make_chain_exchange_response, NetworkMessage, PeerId, PeerManager,
+    };
+    use forest_networks::ChainConfig;
+    use forest_shim::{address::Address, crypto::Signature};
     use num_bigint::BigInt;
+    use parking_lot::RwLock;
+    use tempfile::TempDir;
 
     use super::*;
+    use crate::sync_state::SyncState;
+
+    #[derive(Debug, Error)]
+    #[error("Test consensus error")]
+    struct TestConsensusError;
+
+    impl ValidationCheck for TestConsensusError {
+        fn check(&self) -> &'static str {
+            "test"
+        }
+
+        fn is_rule_violation(&self) -> bool {
+            true
+        }
+    }
+
+    /// Consensus accepting every block, weighing tipsets by their header.
+    #[derive(Debug)]
+    struct TestConsensus;
+
+    impl Scale for TestConsensus {
+        fn weight<DB>(_: &DB, ts: &Tipset) -> anyhow::Result<Weight>
+        where
+            DB: Blockstore,
+        {
+            Ok(ts.weight().clone())
+        }
+    }
+
+    #[async_trait]
+    impl Consensus for TestConsensus {
+        type Error = TestConsensusError;
+
+        async fn validate_block<DB>(
+            &self,
+            _: Arc<StateManager<DB>>,
+            _: Arc<Block>,
+        ) -> Result<(), NonEmpty<Self::Error>>
+        where
+            DB: Blockstore + Clone + Sync + Send + 'static,
+        {
+            Ok(())
+        }
+    }
+
+    fn chain_store(genesis: &BlockHeader, chain_data_root: &TempDir) -> ChainStore<MemoryDB> {
+        ChainStore::new(
+            MemoryDB::default(),
+            Arc::new(ChainConfig::default()),
+            genesis,
+            chain_data_root.path(),
+        )
+        .unwrap()
+    }
+
+    fn state_manager(
+        genesis: &BlockHeader,
+        chain_data_root: &TempDir,
+    ) -> Arc<StateManager<MemoryDB>> {
+        let chain_store = chain_store(genesis, chain_data_root);
+        chain_store.set_genesis(genesis).unwrap();
+        Arc::new(
+            StateManager::new(
+                Arc::new(chain_store),
+                Arc::new(ChainConfig::default()),
+                Arc::new(forest_interpreter::RewardActorMessageCalc),
+            )
+            .unwrap(),
+        )
+    }
+
+    fn checks(failed_checks: &[FailedCheck]) -> Vec<&str> {
+        failed_checks.iter().map(|c| c.check.as_str()).collect()
+    }
 
     pub fn mock_block(id: u64, weight: u64, ticket_sequence: u64) -> BlockHeader {
         let addr = Address::new_id(id);
@@ -1684,4 +2170,235 @@ mod test {
         assert_eq!(index, 2);
         assert_eq!(weight, &BigInt::from(10));
     }
+
+    #[test]
+    fn failed_checks_of_nested_errors() {
+        let err = TipsetRangeSyncerError::<TestConsensus>::concat(NonEmpty {
+            head: TipsetRangeSyncerError::BlockWithoutSignature,
+            tail: vec![
+                TipsetRangeSyncerError::ConsensusError(TestConsensusError),
+                TipsetRangeSyncerError::concat(NonEmpty::new(
+                    TipsetRangeSyncerError::BaseFeeMismatch("1".into(), "2".into()),
+                )),
+            ],
+        });
+        let failed_checks = err.failed_checks();
+        assert_eq!(
+            checks(&failed_checks),
+            ["BlockWithoutSignature", "test", "BaseFeeMismatch"]
+        );
+        assert_eq!(
+            failed_checks[2].error,
+            "Base fee doesn't match: 1 (header), 2 (computed)"
+        );
+    }
+
+    #[test]
+    fn rule_violations() {
+        type Error = TipsetRangeSyncerError<TestConsensus>;
+
+        assert!(Error::StateRootMismatch(Cid::default(), Cid::default()).is_rule_violation());
+        assert!(Error::ConsensusError(TestConsensusError).is_rule_violation());
+        assert!(!Error::Calculation("no state".into()).is_rule_violation());
+        assert!(!Error::TimeTravellingBlock(0, 100).is_rule_violation());
+        // A block is bad if any of the checks found it breaking a rule
+        assert!(Error::concat(NonEmpty {
+            head: Error::Calculation("no state".into()),
+            tail: vec![Error::BlockWithoutSignature],
+        })
+        .is_rule_violation());
+        assert!(!Error::concat(NonEmpty {
+            head: Error::Calculation("no state".into()),
+            tail: vec![Error::TimeTravellingBlock(0, 100)],
+        })
+        .is_rule_violation());
+    }
+
+    #[tokio::test]
+    async fn only_rule_violations_are_marked_bad() {
+        let genesis = BlockHeader::builder()
+            .miner_address(Address::new_id(0))
+            .timestamp(7777)
+            .build()
+            .unwrap();
+        let genesis_ts = Tipset::from(&genesis);
+        let chain_data_root = TempDir::new().unwrap();
+        let state_manager = state_manager(&genesis, &chain_data_root);
+        let chain_store = state_manager.chain_store().clone();
+        let bad_block_cache = BadBlockCache::default();
+        let validate = |header: BlockHeader| {
+            validate_tipset::<_, TestConsensus>(
+                Arc::new(TestConsensus),
+                state_manager.clone(),
+                &chain_store,
+                &bad_block_cache,
+                FullTipset::new(vec![Block {
+                    header,
+                    bls_messages: vec![],
+                    secp_messages: vec![],
+                }])
+                .unwrap(),
+                &genesis_ts,
+                InvalidBlockStrategy::Strict,
+                None,
+            )
+        };
+
+        // The node can't load the lookback state of a block on top of the
+        // genesis, which doesn't make the block bad
+        let unchecked = BlockHeader::builder()
+            .parents(genesis_ts.key().clone())
+            .epoch(1)
+            .miner_address(Address::new_id(1))
+            .signature(Some(Signature::new_bls(vec![])))
+            .bls_aggregate(Some(Signature::new_bls(vec![])))
+            .build()
+            .unwrap();
+        let unchecked_cid = *unchecked.cid();
+        assert!(matches!(
+            validate(unchecked).await,
+            Err(TipsetRangeSyncerError::StateManager(_))
+        ));
+        assert_eq!(bad_block_cache.peek(&unchecked_cid), None);
+
+        // A block without signatures is
+        let unsigned = BlockHeader::builder()
+            .parents(genesis_ts.key().clone())
+            .epoch(1)
+            .miner_address(Address::new_id(2))
+            .build()
+            .unwrap();
+        let unsigned_cid = *unsigned.cid();
+        assert!(validate(unsigned).await.is_err());
+        let record = bad_block_cache.peek(&unsigned_cid).unwrap();
+        assert_eq!(
+            checks(&record.failed_checks),
+            [
+                "BlockWithoutSignature",
+                "BlockWithoutBlsAggregate",
+                "StateManager"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn explain_block_reports_every_failed_check() {
+        let genesis = BlockHeader::builder()
+            .miner_address(Address::new_id(0))
+            .timestamp(7777)
+            .build()
+            .unwrap();
+        let chain_data_root = TempDir::new().unwrap();
+        let state_manager = state_manager(&genesis, &chain_data_root);
+        let db = state_manager.blockstore();
+        let messages = TipsetValidator::compute_msg_root(db, &[], &[]).unwrap();
+        let header = BlockHeader::builder()
+            .parents(Tipset::from(&genesis).key().clone())
+            .epoch(1)
+            .miner_address(Address::new_id(1))
+            .messages(messages)
+            .timestamp(u64::MAX)
+            .build()
+            .unwrap();
+        persist_objects(db, &[&header]).unwrap();
+
+        let explainer = BlockExplainer::new(Arc::new(TestConsensus), state_manager.clone());
+        let failed_checks = explainer.explain_block(*header.cid()).await.unwrap();
+        assert_eq!(
+            checks(&failed_checks),
+            [
+                "BlockWithoutSignature",
+                "BlockWithoutBlsAggregate",
+                "TimeTravellingBlock",
+                "StateManager"
+            ]
+        );
+        assert!(explainer.explain_block(Cid::default()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn sync_from_checkpoint_on_empty_store() {
+        let genesis = BlockHeader::builder()
+            .miner_address(Address::new_id(0))
+            .build()
+            .unwrap();
+        let genesis_ts = Arc::new(Tipset::from(genesis.clone()));
+
+        // Chain of 40 epochs known to the network, checkpointed at epoch 30
+        let remote_root = TempDir::new().unwrap();
+        let remote = chain_store(&genesis, &remote_root);
+        let mut chain = vec![genesis_ts.clone()];
+        for epoch in 1..=40 {
+            let parent = chain.last().unwrap();
+            let header = BlockHeader::builder()
+                .parents(parent.key().clone())
+                .epoch(epoch)
+                .weight(parent.weight() + 1)
+                .miner_address(Address::new_id(1))
+                .build()
+                .unwrap();
+            persist_objects(remote.blockstore(), &[&header]).unwrap();
+            chain.push(Arc::new(Tipset::from(header)));
+        }
+        let head = chain[40].clone();
+        let checkpoint = TrustedCheckpoint {
+            epoch: 30,
+            tipset: chain[30].key().clone(),
+            state_root: *chain[31].parent_state(),
+        };
+
+        // The network answers chain exchange requests from the remote store
+        let (network_send, network_recv) = flume::unbounded();
+        tokio::spawn(async move {
+            while let Ok(message) = network_recv.recv_async().await {
+                if let NetworkMessage::ChainExchangeRequest {
+                    request,
+                    response_channel,
+                    ..
+                } = message
+                {
+                    let response = make_chain_exchange_response(&remote, &request);
+                    _ = response_channel.send(Ok(response));
+                }
+            }
+        });
+        let peer_manager = Arc::new(PeerManager::default());
+        peer_manager
+            .update_peer_head(PeerId::random(), head.clone())
+            .await;
+
+        let local_root = TempDir::new().unwrap();
+        let local = chain_store(&genesis, &local_root);
+        let network =
+            SyncNetworkContext::new(network_send, peer_manager, local.blockstore().clone());
+
+        // Headers are synced back to the checkpoint only
+        let tipsets = sync_headers_in_reverse::<_, TestConsensus>(
+            Arc::new(RwLock::new(SyncState::default())),
+            40,
+            head.clone(),
+            &genesis_ts,
+            &BadBlockCache::default(),
+            &local,
+            network.clone(),
+            Some(&checkpoint),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            tipsets,
+            chain[30..].iter().rev().cloned().collect::<Vec<_>>()
+        );
+        let headers: Vec<&BlockHeader> = tipsets.iter().flat_map(|t| t.blocks()).collect();
+        persist_objects(local.blockstore(), &headers).unwrap();
+
+        // Then back to the lookback of the tipsets validated from the checkpoint
+        sync_headers_down_to::<_, TestConsensus>(&local, &network, chain[30].clone(), 20)
+            .await
+            .unwrap();
+        for ts in &chain[20..] {
+            assert_eq!(local.tipset_from_keys(ts.key()).unwrap(), *ts);
+        }
+        assert!(local.tipset_from_keys(chain[19].key()).is_err());
+    }
 }
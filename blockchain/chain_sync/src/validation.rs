@@ -80,7 +80,7 @@ impl<'a> TipsetValidator<'a> {
             if let Some(bad) = bad_block_cache.peek(block.cid()) {
                 return Err(Box::new(TipsetValidationError::InvalidBlock(
                     *block.cid(),
-                    bad,
+                    bad.to_string(),
                 )));
             }
         }
@@ -6,7 +6,7 @@ use anyhow::anyhow;
 use async_trait::async_trait;
 use forest_blocks::{Block, Tipset};
 use forest_chain::{Error as ChainStoreError, Scale, Weight};
-use forest_chain_sync::consensus::Consensus;
+use forest_chain_sync::consensus::{Consensus, ValidationCheck};
 use forest_key_management::KeyStore;
 use forest_shim::address::Address;
 use forest_state_manager::{Error as StateManagerError, StateManager};
@@ -40,6 +40,32 @@ pub enum DelegatedConsensusError {
     ForestEncoding(#[from] ForestEncodingError),
 }
 
+impl ValidationCheck for DelegatedConsensusError {
+    fn check(&self) -> &'static str {
+        match self {
+            Self::BlockWithElectionProof => "BlockWithElectionProof",
+            Self::BlockWithTicket => "BlockWithTicket",
+            Self::UnequalBlockTimestamps(_, _) => "UnequalBlockTimestamps",
+            Self::MinerNotEligibleToMine(_, _) => "MinerNotEligibleToMine",
+            Self::UnknownMiner(_) => "UnknownMiner",
+            Self::ChainStore(_) => "ChainStore",
+            Self::StateManager(_) => "StateManager",
+            Self::ForestEncoding(_) => "ForestEncoding",
+        }
+    }
+
+    fn is_rule_violation(&self) -> bool {
+        match self {
+            Self::BlockWithElectionProof
+            | Self::BlockWithTicket
+            | Self::UnequalBlockTimestamps(_, _)
+            | Self::MinerNotEligibleToMine(_, _)
+            | Self::UnknownMiner(_) => true,
+            Self::ChainStore(_) | Self::StateManager(_) | Self::ForestEncoding(_) => false,
+        }
+    }
+}
+
 impl From<forest_chain::Error> for Box<DelegatedConsensusError> {
     fn from(err: forest_chain::Error) -> Self {
         Box::new(Into::into(err))
@@ -7,7 +7,7 @@ use async_trait::async_trait;
 use forest_beacon::{Beacon, BeaconSchedule};
 use forest_blocks::{Block, Tipset};
 use forest_chain::{Error as ChainStoreError, Scale, Weight};
-use forest_chain_sync::Consensus;
+use forest_chain_sync::{consensus::ValidationCheck, Consensus};
 use forest_state_manager::{Error as StateManagerError, StateManager};
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::Error as ForestEncodingError;
@@ -63,6 +63,56 @@ pub enum FilecoinConsensusError {
     ForestEncoding(#[from] ForestEncodingError),
 }
 
+impl ValidationCheck for FilecoinConsensusError {
+    fn check(&self) -> &'static str {
+        match self {
+            Self::BlockWithoutElectionProof => "BlockWithoutElectionProof",
+            Self::BlockWithoutTicket => "BlockWithoutTicket",
+            Self::UnequalBlockTimestamps(_, _) => "UnequalBlockTimestamps",
+            Self::TipsetWithoutTicket => "TipsetWithoutTicket",
+            Self::NotClaimingWin => "NotClaimingWin",
+            Self::InvalidOrSlashedMiner => "InvalidOrSlashedMiner",
+            Self::MinerPowerNotAvailable => "MinerPowerNotAvailable",
+            Self::MinerWinClaimsIncorrect(_, _) => "MinerWinClaimsIncorrect",
+            Self::DrawingChainRandomness(_) => "DrawingChainRandomness",
+            Self::MinerNotEligibleToMine => "MinerNotEligibleToMine",
+            Self::MinerPowerUnavailable(_) => "MinerPowerUnavailable",
+            Self::PowerActorUnavailable => "PowerActorUnavailable",
+            Self::VrfValidation(_) => "VrfValidation",
+            Self::BeaconValidation(_) => "BeaconValidation",
+            Self::WinningPoStValidation(_) => "WinningPoStValidation",
+            Self::InsecurePostValidation(_) => "InsecurePostValidation",
+            Self::ChainStore(_) => "ChainStore",
+            Self::StateManager(_) => "StateManager",
+            Self::ForestEncoding(_) => "ForestEncoding",
+        }
+    }
+
+    fn is_rule_violation(&self) -> bool {
+        match self {
+            Self::BlockWithoutElectionProof
+            | Self::BlockWithoutTicket
+            | Self::UnequalBlockTimestamps(_, _)
+            | Self::TipsetWithoutTicket
+            | Self::NotClaimingWin
+            | Self::InvalidOrSlashedMiner
+            | Self::MinerWinClaimsIncorrect(_, _)
+            | Self::MinerNotEligibleToMine
+            | Self::VrfValidation(_)
+            | Self::BeaconValidation(_)
+            | Self::WinningPoStValidation(_)
+            | Self::InsecurePostValidation(_) => true,
+            Self::MinerPowerNotAvailable
+            | Self::DrawingChainRandomness(_)
+            | Self::MinerPowerUnavailable(_)
+            | Self::PowerActorUnavailable
+            | Self::ChainStore(_)
+            | Self::StateManager(_)
+            | Self::ForestEncoding(_) => false,
+        }
+    }
+}
+
 pub struct FilecoinConsensus<B> {
     /// `Drand` randomness beacon
     ///
@@ -385,7 +385,8 @@ fn verify_winning_post_proof<DB: Blockstore + Clone + Send + Sync + 'static>(
             header.miner_address(),
             Randomness::new(rand.to_vec()),
         )
-        .map_err(|e| FilecoinConsensusError::WinningPoStValidation(e.to_string()))?;
+        // Failing to load the sectors doesn't make the proof invalid
+        .map_err(|e| FilecoinConsensusError::StateManager(e.into()))?;
 
     verify_winning_post(
         Randomness::new(rand.to_vec()).into(),
@@ -10,6 +10,7 @@ use forest_beacon::{Beacon, BeaconEntry, BeaconSchedule, DrandBeacon};
 use forest_blocks::{Tipset, TipsetKeys};
 use forest_chain::ChainStore;
 use forest_networks::ChainConfig;
+use forest_shim::version::NetworkVersion;
 use forest_utils::encoding::blake2b_256;
 use fvm::externs::Rand as Rand_v2;
 use fvm3::externs::Rand as Rand_v3;
@@ -53,6 +54,42 @@ where
         }
     }
 
+    /// Draws randomness from the ticket chain the way the actors do at epoch
+    /// `round`: with look-back before network version 13.
+    pub fn get_chain_randomness_for_round(
+        &self,
+        blocks: &TipsetKeys,
+        pers: i64,
+        round: ChainEpoch,
+        entropy: &[u8],
+    ) -> anyhow::Result<[u8; 32]> {
+        if self.chain_config.network_version(round) >= NetworkVersion::V13 {
+            self.get_chain_randomness_v2(blocks, pers, round, entropy)
+        } else {
+            self.get_chain_randomness(blocks, pers, round, entropy, true)
+        }
+    }
+
+    /// Draws randomness from the beacon entries the way the actors do at epoch
+    /// `round`: with look-back before network version 13, and from the beacon
+    /// entry of `round` from network version 14.
+    pub fn get_beacon_randomness_for_round(
+        &self,
+        blocks: &TipsetKeys,
+        pers: i64,
+        round: ChainEpoch,
+        entropy: &[u8],
+    ) -> anyhow::Result<[u8; 32]> {
+        let network_version = self.chain_config.network_version(round);
+        if network_version >= NetworkVersion::V14 {
+            self.get_beacon_randomness_v3(blocks, pers, round, entropy)
+        } else if network_version == NetworkVersion::V13 {
+            self.get_beacon_randomness_v2(blocks, pers, round, entropy)
+        } else {
+            self.get_beacon_randomness(blocks, pers, round, entropy, true)
+        }
+    }
+
     /// Gets 32 bytes of randomness for `ChainRand` parameterized by the
     /// `DomainSeparationTag`, `ChainEpoch`, Entropy from the ticket chain.
     pub fn get_chain_randomness(
@@ -250,3 +287,163 @@ pub fn draw_randomness(
     ret.clone_from_slice(state.finalize().as_bytes());
     Ok(ret)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use forest_beacon::{BeaconPoint, ChainInfo, DrandConfig, DrandNetwork};
+    use forest_blocks::{BlockHeader, Ticket};
+    use forest_db::MemoryDB;
+    use forest_json::vrf::VRFProof;
+    use forest_networks::{Height, HeightInfo};
+    use forest_shim::address::Address;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Genesis time of the beacon, and of the chain, so that the beacon round
+    /// of an epoch is the previous epoch.
+    const GENESIS_TIME: u64 = 1_000_000;
+
+    static DRAND_CONFIG: DrandConfig<'static> = DrandConfig {
+        servers: &["http://localhost"],
+        chain_info: ChainInfo {
+            public_key: Cow::Borrowed("8cad0c72c606ab27d36ee06de1d5b2db1faf92e447025ca37575ab3a8aac2eaae83192f846fc9e158bc738423753d000"),
+            period: 30,
+            genesis_time: GENESIS_TIME as i32,
+            hash: Cow::Borrowed(""),
+            group_hash: Cow::Borrowed(""),
+        },
+        network_type: DrandNetwork::Incentinet,
+    };
+
+    fn ticket(epoch: ChainEpoch) -> Vec<u8> {
+        format!("ticket {epoch}").into_bytes()
+    }
+
+    fn beacon_entry(round: u64) -> BeaconEntry {
+        BeaconEntry::new(round, format!("beacon {round}").into_bytes())
+    }
+
+    fn hex(s: &str) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        bytes
+    }
+
+    /// Randomness drawn at the boundaries of network versions 13 (epoch 11)
+    /// and 14 (epoch 21), which are null rounds along with the epochs before
+    /// them so that look-backs draw from other tipsets.
+    #[test]
+    fn randomness_for_round() {
+        let chain_config = Arc::new(ChainConfig {
+            height_infos: vec![
+                HeightInfo {
+                    height: Height::Hyperdrive,
+                    epoch: 10,
+                },
+                HeightInfo {
+                    height: Height::Chocolate,
+                    epoch: 20,
+                },
+            ],
+            ..ChainConfig::default()
+        });
+        let genesis = BlockHeader::builder()
+            .miner_address(Address::new_id(0))
+            .timestamp(GENESIS_TIME)
+            .build()
+            .unwrap();
+        let chain_data_root = TempDir::new().unwrap();
+        let cs = Arc::new(
+            ChainStore::new(
+                MemoryDB::default(),
+                chain_config.clone(),
+                &genesis,
+                chain_data_root.path(),
+            )
+            .unwrap(),
+        );
+
+        // Blocks carry the beacon entries of their epoch and of the null rounds
+        // before them
+        let mut head = Arc::new(Tipset::from(genesis));
+        for epoch in (1..=30).filter(|epoch| ![10, 11, 20, 21].contains(epoch)) {
+            let header = BlockHeader::builder()
+                .parents(head.key().clone())
+                .epoch(epoch)
+                .weight(head.weight() + 1)
+                .miner_address(Address::new_id(1))
+                .ticket(Some(Ticket::new(VRFProof::new(ticket(epoch)))))
+                .beacon_entries(
+                    (head.epoch() as u64..epoch as u64)
+                        .map(beacon_entry)
+                        .collect(),
+                )
+                .build()
+                .unwrap();
+            forest_chain::persist_objects(cs.blockstore(), &[&header]).unwrap();
+            head = Arc::new(Tipset::from(header));
+        }
+
+        let beacon = BeaconSchedule(vec![BeaconPoint {
+            height: 0,
+            beacon: Arc::new(DrandBeacon::new(GENESIS_TIME, 30, &DRAND_CONFIG).unwrap()),
+        }]);
+        let chain_rand = ChainRand::new(chain_config, head.key().clone(), cs, Arc::new(beacon));
+        let chain_randomness = |round| {
+            chain_rand
+                .get_chain_randomness_for_round(head.key(), 2, round, b"entropy")
+                .unwrap()
+        };
+        let beacon_randomness = |round| {
+            chain_rand
+                .get_beacon_randomness_for_round(head.key(), 2, round, b"entropy")
+                .unwrap()
+        };
+
+        // v1, ticket of epoch 9
+        assert_eq!(
+            chain_randomness(10),
+            hex("7be830b87175a191e0e747eb169be3aae140fd14f6613ffb655439305841f588")
+        );
+        // v2, ticket of epoch 12
+        assert_eq!(
+            chain_randomness(11),
+            hex("d86d82fe29ddcd37a4fd6cceb8aecfe2676fea09d16f6d76de691913e71dee5d")
+        );
+        // v2, ticket of epoch 22
+        assert_eq!(
+            chain_randomness(20),
+            hex("cf8243bbb7ccdf0b8e5768dd7c0cae6134e53501ad17cb765f8f519584f7354a")
+        );
+        assert_eq!(
+            chain_randomness(21),
+            hex("686de6574d26669ce0d199d1a8fb26488dce925e223f78567086dec6e51a5d66")
+        );
+
+        // v1, latest beacon entry of epoch 9
+        assert_eq!(
+            beacon_randomness(10),
+            hex("035a39121fdcc157e184f5c6c92aa9dd531b8acd2c649518b4b570d8a567c4eb")
+        );
+        // v2, latest beacon entry of epoch 12
+        assert_eq!(
+            beacon_randomness(11),
+            hex("d7c3982bdb2a0e2b18089e2a2602d46c07ec41db4387242f191187f260476ffe")
+        );
+        // v2, latest beacon entry of epoch 22
+        assert_eq!(
+            beacon_randomness(20),
+            hex("734a7923f7fb2b6325d5def6dd936808b6f9fdb1233f3eef0005ab1ca1581946")
+        );
+        // v3, beacon entry of round 20, for epoch 21
+        assert_eq!(
+            beacon_randomness(21),
+            hex("3add1352c64c0dde326bd396fdeff5d5438307e4d2f298f15c4dc3ba25d8d5a5")
+        );
+    }
+}
@@ -4,6 +4,7 @@
 pub mod chain_rand;
 mod errors;
 mod metrics;
+pub mod multisig;
 mod utils;
 pub use utils::is_valid_for_sending;
 
@@ -18,8 +19,9 @@ use fil_actor_interface::*;
 use fil_actors_runtime_v9::runtime::Policy;
 use forest_beacon::{BeaconSchedule, DrandBeacon};
 use forest_blocks::{BlockHeader, Tipset, TipsetKeys};
-use forest_chain::{ChainStore, HeadChange};
+use forest_chain::{ActorEvent, ChainStore, EventEntry, HeadChange};
 use forest_interpreter::{resolve_to_key_addr, BlockMessages, RewardCalc, VM};
+use forest_ipld::DEFAULT_RECENT_STATE_ROOTS;
 use forest_json::message_receipt;
 use forest_message::{ChainMessage, Message as MessageTrait};
 use forest_networks::{ChainConfig, Height};
@@ -31,13 +33,12 @@ use forest_shim::{
     state_tree::{ActorState, StateTree},
     version::NetworkVersion,
 };
-use forest_utils::db::BlockstoreExt;
-use futures::{channel::oneshot, select, FutureExt};
+use forest_utils::db::{BlockstoreExt, KeyValueStore};
 use fvm::externs::Rand;
 use fvm3::externs::Rand as Rand_v3;
 use fvm_ipld_amt::Amtv0 as Amt;
 use fvm_ipld_blockstore::Blockstore;
-use fvm_ipld_encoding::Cbor;
+use fvm_ipld_encoding::{Cbor, RawBytes};
 use fvm_shared::clock::ChainEpoch;
 use lru::LruCache;
 use num::BigInt;
@@ -45,7 +46,7 @@ use num_traits::identities::Zero;
 use once_cell::unsync::Lazy;
 use parking_lot::Mutex as SyncMutex;
 use serde::{Deserialize, Serialize};
-use tokio::sync::{broadcast::error::RecvError, Mutex as TokioMutex, RwLock};
+use tokio::sync::{broadcast::error::RecvError, Mutex as TokioMutex};
 use tracing::{debug, error, info, instrument, trace, warn};
 use vm_circ_supply::GenesisInfo;
 
@@ -54,6 +55,8 @@ pub use self::errors::*;
 const DEFAULT_TIPSET_CACHE_SIZE: NonZeroUsize =
     forest_utils::const_option!(NonZeroUsize::new(1024));
 
+const TIPSET_STATE_KEY_PREFIX: &[u8] = b"tipset_state/";
+
 /// Intermediary for retrieving state objects and updating actor states.
 type CidPair = (Cid, Cid);
 
@@ -171,6 +174,10 @@ impl TipsetStateCache {
     }
 }
 
+fn tipset_state_key(key: &TipsetKeys) -> anyhow::Result<Vec<u8>> {
+    Ok([TIPSET_STATE_KEY_PREFIX, &key.cid()?.to_bytes()].concat())
+}
+
 /// Type to represent invocation of state call results.
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -203,6 +210,8 @@ pub struct StateManager<DB> {
 
     /// This is a cache which indexes tipsets to their calculated state.
     cache: TipsetStateCache,
+    /// Persisted states of recent tipsets, consulted on cache misses.
+    state_store: Option<Box<dyn KeyValueStore>>,
     genesis_info: GenesisInfo,
     beacon: Arc<forest_beacon::BeaconSchedule<DrandBeacon>>,
     chain_config: Arc<ChainConfig>,
@@ -226,6 +235,7 @@ where
         Ok(Self {
             cs,
             cache: TipsetStateCache::new(),
+            state_store: None,
             genesis_info: GenesisInfo::from_chain_config(&chain_config),
             beacon,
             chain_config,
@@ -239,6 +249,25 @@ where
         })
     }
 
+    /// Persists the states computed for recent tipsets, so that they are not
+    /// computed again after a restart. Entries are written to the current
+    /// generation of a rolling database, and dropped along with it by the
+    /// garbage collector.
+    pub fn with_tipset_state_store(mut self, store: impl KeyValueStore + 'static) -> Self {
+        self.state_store = Some(Box::new(store));
+        self
+    }
+
+    /// Persists the verified `Drand` beacon entries, so that they are not
+    /// fetched again after a restart.
+    pub fn with_beacon_entry_store(self, store: impl KeyValueStore + 'static) -> Self {
+        let store: Arc<dyn KeyValueStore> = Arc::new(store);
+        for point in &self.beacon.0 {
+            point.beacon.set_entry_store(store.clone());
+        }
+        self
+    }
+
     pub fn beacon_schedule(&self) -> Arc<BeaconSchedule<DrandBeacon>> {
         self.beacon.clone()
     }
@@ -425,8 +454,48 @@ where
 
         let mut vm = create_vm(parent_state, epoch, tipset.min_timestamp())?;
 
+        // Record the events emitted by the messages of the tipset, if indexed
+        let event_index = self.chain_store().event_index();
+        let mut message_indices = HashMap::new();
+        if event_index.is_some() {
+            for msg in messages.iter().flat_map(|block| &block.messages) {
+                let next_index = message_indices.len() as u64;
+                message_indices.entry(msg.cid()?).or_insert(next_index);
+            }
+        }
+        let mut events = vec![];
+        let record_events = |cid: &Cid, msg: &ChainMessage, ret: &ApplyRet| {
+            if let Some(&message_index) = message_indices.get(cid) {
+                events.extend(ret.events().into_iter().map(|stamped| {
+                    ActorEvent {
+                        emitter: stamped.emitter,
+                        message: *cid,
+                        message_index,
+                        entries: stamped
+                            .event
+                            .entries
+                            .into_iter()
+                            .map(|entry| EventEntry {
+                                flags: entry.flags.bits(),
+                                key: entry.key,
+                                value: RawBytes::new(entry.value.to_vec()),
+                            })
+                            .collect(),
+                    }
+                }));
+            }
+            match &mut callback {
+                Some(callback) => callback(cid, msg, ret),
+                None => Ok(()),
+            }
+        };
+
         // Apply tipset messages
-        let receipts = vm.apply_block_messages(messages, epoch, callback)?;
+        let receipts = vm.apply_block_messages(messages, epoch, Some(record_events))?;
+
+        if let Some(event_index) = event_index {
+            event_index.record(tipset, &events)?;
+        }
 
         // Construct receipt root from receipts
         let receipt_root = Amt::new_from_iter(self.blockstore(), receipts)?;
@@ -455,12 +524,15 @@ where
                     })?;
 
                     (*tipset.parent_state(), *message_receipts.message_receipts())
+                } else if let Some(ts_state) = self.load_tipset_state(tipset)? {
+                    ts_state
                 } else {
                     // generic constants are not implemented yet this is a lowcost method for now
                     let no_func =
                         None::<fn(&Cid, &ChainMessage, &ApplyRet) -> Result<(), anyhow::Error>>;
                     let ts_state = self.compute_tipset_state(tipset, no_func).await?;
                     debug!("Completed tipset state calculation {:?}", tipset.cids());
+                    self.persist_tipset_state(tipset, ts_state);
                     ts_state
                 };
 
@@ -469,6 +541,57 @@ where
             .await
     }
 
+    /// Returns true if the states of a tipset are kept by the garbage
+    /// collector, which only keeps the state roots of recent tipsets.
+    fn is_recent_tipset(&self, tipset: &Tipset) -> bool {
+        tipset.epoch() > self.cs.heaviest_tipset().epoch() - DEFAULT_RECENT_STATE_ROOTS
+    }
+
+    /// Returns the persisted state of a recent tipset, if any.
+    fn load_tipset_state(&self, tipset: &Tipset) -> anyhow::Result<Option<CidPair>> {
+        let store = match &self.state_store {
+            Some(store) if self.is_recent_tipset(tipset) => store,
+            _ => return Ok(None),
+        };
+        let bytes = match store.read_value(&tipset_state_key(tipset.key())?)? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        let (state_root, receipt_root): CidPair = fvm_ipld_encoding::from_slice(&bytes)?;
+        // Entries are not written atomically with the states they point to
+        if self.blockstore().has(&state_root)? && self.blockstore().has(&receipt_root)? {
+            Ok(Some((state_root, receipt_root)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Persists the computed state of a recent tipset.
+    fn persist_tipset_state(&self, tipset: &Tipset, (state_root, receipt_root): CidPair) {
+        let store = match &self.state_store {
+            Some(store) if self.is_recent_tipset(tipset) => store,
+            _ => return,
+        };
+        let result = tipset_state_key(tipset.key()).and_then(|key| {
+            let value = fvm_ipld_encoding::to_vec(&(state_root, receipt_root))?;
+            Ok(store.write_value(&key, &value)?)
+        });
+        if let Err(e) = result {
+            warn!(
+                "Failed to persist the state of the tipset at epoch {}: {e}",
+                tipset.epoch()
+            );
+        }
+    }
+
+    /// Sets the state of a tipset that is trusted instead of computed, such as
+    /// the tipset of a trusted checkpoint the chain is synced from.
+    pub fn trust_tipset_state(&self, tipset: &Tipset, state_root: Cid, receipt_root: Cid) {
+        self.cache
+            .insert(tipset.key().clone(), (state_root, receipt_root));
+        self.persist_tipset_state(tipset, (state_root, receipt_root));
+    }
+
     #[instrument(skip(self, rand))]
     fn call_raw(
         self: &Arc<Self>,
@@ -628,6 +751,16 @@ where
         Ok((out_mes, out_ret))
     }
 
+    /// Returns the number of epochs blocks of `round` look back to, for the
+    /// tipset given by [`StateManager::get_lookback_tipset_for_round`].
+    pub fn lookback_for_round(&self, round: ChainEpoch) -> ChainEpoch {
+        if self.get_network_version(round) <= NetworkVersion::V3 {
+            ChainEpoch::from(10)
+        } else {
+            self.chain_config.policy.chain_finality
+        }
+    }
+
     /// Gets look-back tipset for block validations.
     ///
     /// The look-back tipset for a round is the tipset with epoch `round -
@@ -639,13 +772,7 @@ where
         tipset: Arc<Tipset>,
         round: ChainEpoch,
     ) -> Result<(Arc<Tipset>, Cid), Error> {
-        let version = self.get_network_version(round);
-        let lb = if version <= NetworkVersion::V3 {
-            ChainEpoch::from(10)
-        } else {
-            self.chain_config.policy.chain_finality
-        };
-        let lbr = (round - lb).max(0);
+        let lbr = (round - self.lookback_for_round(round)).max(0);
 
         // More null blocks than lookback
         if lbr >= tipset.epoch() {
@@ -928,6 +1055,68 @@ where
         Ok(message_receipt)
     }
 
+    /// Looks a message up in the message index of the chain store, returning
+    /// the tipset it was executed in and its receipt. Returns `None` if the
+    /// message is not indexed, or if its entry is not on the chain of the
+    /// current head anymore.
+    fn search_message_index(&self, msg_cid: Cid) -> Result<Option<(Arc<Tipset>, Receipt)>, Error> {
+        let location = match self.cs.message_index() {
+            Some(index) => match index
+                .get(&msg_cid)
+                .map_err(|e| Error::Other(e.to_string()))?
+            {
+                Some(location) => location,
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+        let head = self.cs.heaviest_tipset();
+        if location.epoch >= head.epoch() {
+            // Included in the head, not executed yet
+            return Ok(None);
+        }
+        // The receipts of the messages of a tipset are in the next tipset
+        let tipset = self
+            .cs
+            .tipset_by_height(location.epoch + 1, head, false)
+            .map_err(|e| Error::Other(e.to_string()))?;
+        if tipset.parents() != &location.tipset {
+            return Ok(None);
+        }
+        let receipt = forest_chain::get_parent_reciept(
+            self.blockstore(),
+            tipset.blocks().first().expect("tipsets have blocks"),
+            location.index as usize,
+        )
+        .map_err(|e| Error::Other(e.to_string()))?;
+        Ok(receipt.map(|receipt| (tipset, receipt)))
+    }
+
+    /// Returns the tipset a message was executed in on the chain of the
+    /// current head and its receipt, from the message index when the message
+    /// is indexed, or by searching the chain back from the head.
+    pub fn search_for_message(
+        &self,
+        msg_cid: Cid,
+    ) -> Result<Option<(Arc<Tipset>, Receipt)>, Error> {
+        if let Some(found) = self.search_message_index(msg_cid)? {
+            return Ok(Some(found));
+        }
+        let message = forest_chain::get_chain_message(self.blockstore(), &msg_cid)
+            .map_err(|err| Error::Other(format!("failed to load message {err:}")))?;
+        let current_tipset = self.cs.heaviest_tipset();
+        let message_var = (&message.from(), &message.sequence());
+        if let Some(receipt) =
+            self.tipset_executed_message(&current_tipset, msg_cid, message_var)?
+        {
+            return Ok(Some((current_tipset, receipt)));
+        }
+        self.search_back_for_message(
+            current_tipset,
+            (&message.from(), &msg_cid, &message.sequence()),
+        )
+    }
+
     /// `WaitForMessage` blocks until a message appears on chain. It looks
     /// backwards in the chain to see if this has already happened. It
     /// guarantees that the message has been on chain for at least
@@ -941,133 +1130,55 @@ where
         DB: Blockstore + Clone + Send + Sync + 'static,
     {
         let mut subscriber = self.cs.publisher().subscribe();
-        let (sender, mut receiver) = oneshot::channel::<()>();
         let message = forest_chain::get_chain_message(self.blockstore(), &msg_cid)
             .map_err(|err| Error::Other(format!("failed to load message {err:}")))?;
-
         let message_var = (&message.from(), &message.sequence());
-        let current_tipset = self.cs.heaviest_tipset();
-        let maybe_message_reciept =
-            self.tipset_executed_message(&current_tipset, msg_cid, message_var)?;
-        if let Some(r) = maybe_message_reciept {
-            return Ok((Some(current_tipset.clone()), Some(r)));
-        }
-
-        let mut candidate_tipset: Option<Arc<Tipset>> = None;
-        let mut candidate_receipt: Option<Receipt> = None;
-
-        let sm_cloned = Arc::clone(self);
-        let cid = message
-            .cid()
-            .map_err(|e| Error::Other(format!("Could not get cid from message {e:?}")))?;
-
-        let cid_for_task = cid;
-        let address_for_task = message.from();
-        let sequence_for_task = message.sequence();
-        let height_of_head = current_tipset.epoch();
-        let task = tokio::task::spawn(async move {
-            let back_tuple = sm_cloned.search_back_for_message(
-                current_tipset,
-                (&address_for_task, &cid_for_task, &sequence_for_task),
-            )?;
-            sender
-                .send(())
-                .map_err(|e| Error::Other(format!("Could not send to channel {e:?}")))?;
-            Ok::<_, Error>(back_tuple)
-        });
 
-        let reverts: Arc<RwLock<HashMap<TipsetKeys, bool>>> = Arc::new(RwLock::new(HashMap::new()));
-        let block_revert = reverts.clone();
-        let sm_cloned = Arc::clone(self);
-
-        // Wait for message to be included in head change.
-        let mut subscriber_poll = tokio::task::spawn(async move {
-            loop {
-                match subscriber.recv().await {
-                    Ok(subscriber) => match subscriber {
-                        HeadChange::Revert(_tipset) => {
-                            if candidate_tipset.is_some() {
-                                candidate_tipset = None;
-                                candidate_receipt = None;
-                            }
-                        }
-                        HeadChange::Apply(tipset) => {
-                            if candidate_tipset
-                                .as_ref()
-                                .map(|s| tipset.epoch() >= s.epoch() + confidence)
-                                .unwrap_or_default()
-                            {
-                                return Ok((candidate_tipset, candidate_receipt));
-                            }
-                            let poll_receiver = receiver.try_recv();
-                            if let Ok(Some(_)) = poll_receiver {
-                                block_revert
-                                    .write()
-                                    .await
-                                    .insert(tipset.key().to_owned(), true);
-                            }
-
-                            let message_var = (&message.from(), &message.sequence());
-                            let maybe_receipt =
-                                sm_cloned.tipset_executed_message(&tipset, msg_cid, message_var)?;
-                            if let Some(receipt) = maybe_receipt {
-                                if confidence == 0 {
-                                    return Ok((Some(tipset), Some(receipt)));
-                                }
-                                candidate_tipset = Some(tipset);
-                                candidate_receipt = Some(receipt)
-                            }
-                        }
-                        _ => (),
-                    },
-                    Err(RecvError::Lagged(i)) => {
-                        warn!(
-                            "wait for message head change subscriber lagged, skipped {} events",
-                            i
-                        );
-                    }
-                    Err(RecvError::Closed) => break,
-                }
-            }
-            Ok((None, None))
-        })
-        .fuse();
-
-        // Search backwards for message.
-        let mut search_back_poll = tokio::task::spawn(async move {
-            let back_tuple = task.await.map_err(|e| {
-                Error::Other(format!("Could not search backwards for message {e}"))
-            })??;
-            if let Some((back_tipset, back_receipt)) = back_tuple {
-                let should_revert = *reverts
-                    .read()
-                    .await
-                    .get(back_tipset.key())
-                    .unwrap_or(&false);
-                let larger_height_of_head = height_of_head >= back_tipset.epoch() + confidence;
-                if !should_revert && larger_height_of_head {
-                    return Ok::<_, Error>((Some(back_tipset), Some(back_receipt)));
+        // Searching back the chain may load many tipsets, keep it off the
+        // async runtime
+        let sm = self.clone();
+        let mut found = tokio::task::spawn_blocking(move || sm.search_for_message(msg_cid))
+            .await
+            .map_err(|e| Error::Other(format!("failed to search for message: {e}")))??;
+        loop {
+            let head = self.cs.heaviest_tipset();
+            if let Some((tipset, receipt)) = &found {
+                if head.epoch() >= tipset.epoch() + confidence {
+                    return Ok((Some(tipset.clone()), Some(receipt.clone())));
                 }
-                return Ok((None, None));
             }
-            Ok((None, None))
-        })
-        .fuse();
 
-        // Await on first future to finish.
-        // TODO this should be a future race. I don't think the task is being cancelled
-        // here This seems like it will keep the other task running even though
-        // it's unneeded.
-        loop {
-            select! {
-                res = subscriber_poll => {
-                    return res?
-                }
-                res = search_back_poll => {
-                    if let Ok((Some(ts), Some(rct))) = res? {
-                        return Ok((Some(ts), Some(rct)));
+            match subscriber.recv().await {
+                Ok(HeadChange::Apply(tipset)) => {
+                    // The tipset the message was found in may have been reverted
+                    if let Some((found_tipset, _)) = &found {
+                        let on_chain = found_tipset.epoch() <= tipset.epoch()
+                            && self
+                                .cs
+                                .tipset_by_height(found_tipset.epoch(), tipset.clone(), false)
+                                .map(|ts| ts.key() == found_tipset.key())
+                                .unwrap_or_default();
+                        if !on_chain {
+                            found = None;
+                        }
                     }
+                    if found.is_none() {
+                        found = match self.search_message_index(msg_cid)? {
+                            Some(found) => Some(found),
+                            None => self
+                                .tipset_executed_message(&tipset, msg_cid, message_var)?
+                                .map(|receipt| (tipset, receipt)),
+                        };
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(i)) => {
+                    warn!(
+                        "wait for message head change subscriber lagged, skipped {} events",
+                        i
+                    );
                 }
+                Err(RecvError::Closed) => return Ok((None, None)),
             }
         }
     }
@@ -1247,6 +1358,35 @@ where
         Ok(())
     }
 
+    /// Draws randomness from the ticket chain of the tipset `blocks`, the way
+    /// the actors do at epoch `round`: with look-back before network version
+    /// 13.
+    pub fn get_chain_randomness(
+        &self,
+        blocks: &TipsetKeys,
+        pers: i64,
+        round: ChainEpoch,
+        entropy: &[u8],
+    ) -> anyhow::Result<[u8; 32]> {
+        self.chain_rand(blocks.clone())
+            .get_chain_randomness_for_round(blocks, pers, round, entropy)
+    }
+
+    /// Draws randomness from the beacon entries of the chain of the tipset
+    /// `blocks`, the way the actors do at epoch `round`: with look-back before
+    /// network version 13, and from the beacon entry of `round` from network
+    /// version 14.
+    pub fn get_beacon_randomness(
+        &self,
+        blocks: &TipsetKeys,
+        pers: i64,
+        round: ChainEpoch,
+        entropy: &[u8],
+    ) -> anyhow::Result<[u8; 32]> {
+        self.chain_rand(blocks.clone())
+            .get_beacon_randomness_for_round(blocks, pers, round, entropy)
+    }
+
     fn chain_rand(&self, blocks: TipsetKeys) -> ChainRand<DB> {
         ChainRand::new(
             self.chain_config.clone(),
@@ -1269,3 +1409,64 @@ where
         Ok(st)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::Code::Blake2b256;
+    use forest_db::MemoryDB;
+    use forest_interpreter::RewardActorMessageCalc;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn tipset_state_is_persisted() {
+        let chain_config = Arc::new(ChainConfig::default());
+        let genesis = BlockHeader::builder()
+            .miner_address(Address::new_id(0))
+            .build()
+            .unwrap();
+        let chain_data_root = TempDir::new().unwrap();
+        let cs = Arc::new(
+            ChainStore::new(
+                MemoryDB::default(),
+                chain_config.clone(),
+                &genesis,
+                chain_data_root.path(),
+            )
+            .unwrap(),
+        );
+        let header = BlockHeader::builder()
+            .parents(TipsetKeys::new(vec![*genesis.cid()]))
+            .epoch(1)
+            .miner_address(Address::new_id(1))
+            .build()
+            .unwrap();
+        forest_chain::persist_objects(cs.blockstore(), &[&genesis, &header]).unwrap();
+        let tipset = Arc::new(Tipset::from(header));
+        // The roots only need to be in the store to be trusted
+        let state_root = cs.blockstore().put_obj(&"state", Blake2b256).unwrap();
+        let receipt_root = cs.blockstore().put_obj(&"receipts", Blake2b256).unwrap();
+
+        let state_store = MemoryDB::default();
+        let state_manager = || {
+            Arc::new(
+                StateManager::new(
+                    cs.clone(),
+                    chain_config.clone(),
+                    Arc::new(RewardActorMessageCalc),
+                )
+                .unwrap()
+                .with_tipset_state_store(state_store.clone()),
+            )
+        };
+        // Written once the state is computed
+        state_manager().persist_tipset_state(&tipset, (state_root, receipt_root));
+
+        // The tipset cannot be executed, its state comes from the store
+        assert_eq!(
+            state_manager().tipset_state(&tipset).await.unwrap(),
+            (state_root, receipt_root)
+        );
+    }
+}
@@ -0,0 +1,335 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Read-only helpers for inspecting multisig actors and building the messages
+//! that drive them. Actor states are loaded through `fil_actor_interface`;
+//! the pending transactions and method parameters, whose layout has been
+//! stable since actors v2, are decoded here directly.
+
+use anyhow::Context;
+use cid::Cid;
+use fil_actor_interface::{multisig, system};
+use forest_blocks::Tipset;
+use forest_shim::{address::Address, econ::TokenAmount, state_tree::ActorState};
+use forest_utils::db::BlockstoreExt;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding3::{strict_bytes, RawBytes};
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared::{clock::ChainEpoch, MethodNum};
+use num_traits::Zero;
+use serde_tuple::{self, Deserialize_tuple, Serialize_tuple};
+
+use crate::{Error, StateManager};
+
+/// Bit width of the pending transactions HAMT.
+const HAMT_BIT_WIDTH: u32 = 5;
+
+/// Name of the multisig actor in the builtin actors manifest.
+const MULTISIG_ACTOR_NAME: &str = "multisig";
+
+/// Multisig actor method numbers.
+pub mod method {
+    use fvm_shared::MethodNum;
+
+    pub const PROPOSE: MethodNum = 2;
+    pub const APPROVE: MethodNum = 3;
+    pub const CANCEL: MethodNum = 4;
+}
+
+/// `Exec` method number of the init actor.
+const INIT_EXEC_METHOD: MethodNum = 2;
+
+/// Multisig actor state, independent of the actors version.
+#[derive(Debug, Clone)]
+pub struct MultisigState {
+    pub signers: Vec<Address>,
+    pub num_approvals_threshold: u64,
+    pub next_tx_id: i64,
+    pub initial_balance: TokenAmount,
+    pub start_epoch: ChainEpoch,
+    pub unlock_duration: ChainEpoch,
+    pub pending_txs: Cid,
+}
+
+impl From<multisig::State> for MultisigState {
+    fn from(state: multisig::State) -> Self {
+        macro_rules! convert {
+            ($st:expr) => {
+                Self {
+                    signers: $st.signers.iter().map(Address::from).collect(),
+                    num_approvals_threshold: $st.num_approvals_threshold,
+                    next_tx_id: $st.next_tx_id.0,
+                    initial_balance: $st.initial_balance.into(),
+                    start_epoch: $st.start_epoch,
+                    unlock_duration: $st.unlock_duration,
+                    pending_txs: $st.pending_txs,
+                }
+            };
+        }
+        match state {
+            multisig::State::V8(st) => convert!(st),
+            multisig::State::V9(st) => convert!(st),
+            multisig::State::V10(st) => convert!(st),
+        }
+    }
+}
+
+impl MultisigState {
+    /// Returns the amount still locked by the vesting schedule at the given
+    /// epoch.
+    pub fn amount_locked(&self, epoch: ChainEpoch) -> TokenAmount {
+        let elapsed_epoch = epoch - self.start_epoch;
+        if elapsed_epoch >= self.unlock_duration {
+            return TokenAmount::zero();
+        }
+        if elapsed_epoch <= 0 {
+            return self.initial_balance.clone();
+        }
+        let remaining_lock_duration = self.unlock_duration - elapsed_epoch;
+        // Multiply before dividing, as the actor does since v2
+        let numerator = self.initial_balance.atto() * remaining_lock_duration;
+        TokenAmount::from_atto(numerator / self.unlock_duration)
+    }
+}
+
+/// A pending multisig transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct MultisigTransaction {
+    pub to: Address,
+    pub value: TokenAmount,
+    pub method: MethodNum,
+    pub params: RawBytes,
+    pub approved: Vec<Address>,
+}
+
+/// Constructor parameters of the multisig actor.
+#[derive(Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct ConstructorParams {
+    pub signers: Vec<Address>,
+    pub num_approvals_threshold: u64,
+    pub unlock_duration: ChainEpoch,
+    pub start_epoch: ChainEpoch,
+}
+
+/// Parameters of the multisig `Propose` method.
+#[derive(Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct ProposeParams {
+    pub to: Address,
+    pub value: TokenAmount,
+    pub method: MethodNum,
+    pub params: RawBytes,
+}
+
+/// Parameters of the multisig `Approve` and `Cancel` methods. An empty
+/// `proposal_hash` skips the proposal hash check.
+#[derive(Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct TxnIdParams {
+    pub id: i64,
+    #[serde(with = "strict_bytes")]
+    pub proposal_hash: Vec<u8>,
+}
+
+/// Parameters of the init actor `Exec` method.
+#[derive(Debug, Serialize_tuple, Deserialize_tuple)]
+struct ExecParams {
+    code_cid: Cid,
+    constructor_params: RawBytes,
+}
+
+/// Decodes a pending transaction key. Keys are zig-zag varint encoded
+/// transaction identifiers.
+fn decode_txn_id(key: &BytesKey) -> anyhow::Result<i64> {
+    let (n, rest) = unsigned_varint::decode::u64(key.0.as_slice())
+        .map_err(|e| anyhow::anyhow!("invalid transaction id key: {e}"))?;
+    anyhow::ensure!(rest.is_empty(), "trailing bytes in transaction id key");
+    Ok((n >> 1) as i64 ^ -((n & 1) as i64))
+}
+
+impl<DB> StateManager<DB>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+{
+    /// Loads the multisig actor and its state at the parent state of the given
+    /// tipset.
+    pub fn load_multisig(
+        &self,
+        addr: &Address,
+        ts: &Tipset,
+    ) -> anyhow::Result<(ActorState, MultisigState)> {
+        let actor = self
+            .get_actor(addr, *ts.parent_state())?
+            .ok_or_else(|| Error::State(format!("Multisig actor {addr} could not be resolved")))?;
+        let state = multisig::State::load(self.blockstore(), &actor.clone().into())?;
+        Ok((actor, state.into()))
+    }
+
+    /// Returns the pending transactions of a multisig actor, ordered by
+    /// transaction identifier.
+    pub fn multisig_pending_transactions(
+        &self,
+        state: &MultisigState,
+    ) -> anyhow::Result<Vec<(i64, MultisigTransaction)>> {
+        let pending: Hamt<_, MultisigTransaction> =
+            Hamt::load_with_bit_width(&state.pending_txs, self.blockstore(), HAMT_BIT_WIDTH)?;
+        let mut out = Vec::new();
+        pending.for_each(|key, txn| {
+            out.push((decode_txn_id(key)?, txn.clone()));
+            Ok(())
+        })?;
+        out.sort_by_key(|(id, _)| *id);
+        Ok(out)
+    }
+
+    /// Returns the balance of a multisig actor that is not locked by its
+    /// vesting schedule.
+    pub fn multisig_available_balance(
+        &self,
+        addr: &Address,
+        ts: &Tipset,
+    ) -> anyhow::Result<TokenAmount> {
+        let (actor, state) = self.load_multisig(addr, ts)?;
+        let locked = state.amount_locked(ts.epoch());
+        let balance = TokenAmount::from(&actor.balance);
+        if balance < locked {
+            return Ok(TokenAmount::zero());
+        }
+        Ok(balance - &locked)
+    }
+
+    /// Looks up the code CID of a builtin actor in the manifest referenced by
+    /// the system actor. Only actors v8 and later keep a manifest in the
+    /// system actor state.
+    pub fn builtin_actor_code(&self, name: &str, ts: &Tipset) -> anyhow::Result<Cid> {
+        let system = self
+            .get_actor(&Address::SYSTEM_ACTOR, *ts.parent_state())?
+            .ok_or_else(|| Error::State("System actor could not be resolved".to_string()))?;
+        let builtin_actors = match system::State::load(self.blockstore(), &system.into())
+            .context("Builtin actors manifest requires actors v8 or later")?
+        {
+            system::State::V8(st) => st.builtin_actors,
+            system::State::V9(st) => st.builtin_actors,
+            system::State::V10(st) => st.builtin_actors,
+        };
+        let manifest: Vec<(String, Cid)> = self
+            .blockstore()
+            .get_obj(&builtin_actors)?
+            .context("Builtin actors manifest not found")?;
+        manifest
+            .into_iter()
+            .find_map(|(actor, code)| (actor == name).then_some(code))
+            .with_context(|| format!("Actor {name} is missing from the manifest"))
+    }
+
+    /// Builds the init actor message creating a new multisig actor.
+    #[allow(clippy::too_many_arguments)]
+    pub fn multisig_create_message(
+        &self,
+        ts: &Tipset,
+        from: Address,
+        signers: Vec<Address>,
+        threshold: u64,
+        unlock_duration: ChainEpoch,
+        start_epoch: ChainEpoch,
+        value: TokenAmount,
+    ) -> anyhow::Result<forest_shim::message::Message> {
+        anyhow::ensure!(!signers.is_empty(), "Must provide at least one signer");
+        anyhow::ensure!(
+            threshold >= 1 && threshold as usize <= signers.len(),
+            "Threshold must be between 1 and the number of signers"
+        );
+        let constructor_params = RawBytes::serialize(ConstructorParams {
+            signers,
+            num_approvals_threshold: threshold,
+            unlock_duration,
+            start_epoch,
+        })?;
+        let params = RawBytes::serialize(ExecParams {
+            code_cid: self.builtin_actor_code(MULTISIG_ACTOR_NAME, ts)?,
+            constructor_params,
+        })?;
+        Ok(fvm_shared3::message::Message {
+            from: from.into(),
+            to: Address::INIT_ACTOR.into(),
+            value: value.into(),
+            method_num: INIT_EXEC_METHOD,
+            params,
+            ..Default::default()
+        }
+        .into())
+    }
+}
+
+/// Builds a message proposing a transaction to a multisig actor.
+pub fn multisig_propose_message(
+    multisig: Address,
+    from: Address,
+    params: ProposeParams,
+) -> anyhow::Result<forest_shim::message::Message> {
+    Ok(fvm_shared3::message::Message {
+        from: from.into(),
+        to: multisig.into(),
+        method_num: method::PROPOSE,
+        params: RawBytes::serialize(params)?,
+        ..Default::default()
+    }
+    .into())
+}
+
+/// Builds a message approving or cancelling a pending multisig transaction,
+/// depending on `method_num`.
+pub fn multisig_txn_message(
+    multisig: Address,
+    from: Address,
+    method_num: MethodNum,
+    id: i64,
+) -> anyhow::Result<forest_shim::message::Message> {
+    Ok(fvm_shared3::message::Message {
+        from: from.into(),
+        to: multisig.into(),
+        method_num,
+        params: RawBytes::serialize(TxnIdParams {
+            id,
+            proposal_hash: Vec::new(),
+        })?,
+        ..Default::default()
+    }
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn txn_id_key(id: i64) -> BytesKey {
+        let zigzag = ((id << 1) ^ (id >> 63)) as u64;
+        let mut buf = unsigned_varint::encode::u64_buffer();
+        unsigned_varint::encode::u64(zigzag, &mut buf)
+            .to_vec()
+            .into()
+    }
+
+    #[test]
+    fn txn_id_key_round_trip() {
+        for id in [0, 1, 2, 63, 64, 1_000_000, i64::MAX, -1, i64::MIN] {
+            assert_eq!(decode_txn_id(&txn_id_key(id)).unwrap(), id);
+        }
+    }
+
+    #[test]
+    fn amount_locked_follows_vesting_schedule() {
+        let state = MultisigState {
+            signers: vec![],
+            num_approvals_threshold: 1,
+            next_tx_id: 0,
+            initial_balance: TokenAmount::from_atto(1000),
+            start_epoch: 100,
+            unlock_duration: 10,
+            pending_txs: Cid::default(),
+        };
+        assert_eq!(state.amount_locked(50), TokenAmount::from_atto(1000));
+        assert_eq!(state.amount_locked(100), TokenAmount::from_atto(1000));
+        assert_eq!(state.amount_locked(105), TokenAmount::from_atto(500));
+        assert_eq!(state.amount_locked(110), TokenAmount::zero());
+        assert_eq!(state.amount_locked(1000), TokenAmount::zero());
+    }
+}
@@ -8,6 +8,7 @@ use clap::Subcommand;
 use forest_blocks::TipsetKeys;
 use forest_json::cid::CidJson;
 use forest_rpc_client::chain_ops::*;
+use fvm_shared::clock::ChainEpoch;
 
 use super::*;
 
@@ -34,6 +35,20 @@ pub enum ChainCommands {
     /// hashes
     ValidateTipsetCheckpoints,
 
+    /// Prints out the recent re-orgs of the chain
+    Reorgs,
+
+    /// Indexes the messages of the chain between two epochs, so that messages
+    /// included further back than the indexed epochs are looked up quickly
+    BackfillMessageIndex {
+        /// Epoch to index from, down to `to`
+        #[arg(long)]
+        from: ChainEpoch,
+        /// Lowest epoch to index
+        #[arg(long)]
+        to: ChainEpoch,
+    },
+
     /// Reads and prints out a message referenced by the specified CID from the
     /// chain block store
     Message {
@@ -84,6 +99,12 @@ impl ChainCommands {
                 let result = chain_validate_tipset_checkpoints((), &config.client.rpc_token).await;
                 print_rpc_res(result)
             }
+            Self::Reorgs => print_rpc_res_pretty(chain_reorgs((), &config.client.rpc_token).await),
+            Self::BackfillMessageIndex { from, to } => print_rpc_res(
+                chain_backfill_message_index((*from, *to), &config.client.rpc_token)
+                    .await
+                    .map(|indexed| format!("Indexed the messages of {indexed} tipsets")),
+            ),
             Self::Message { cid } => {
                 let cid: Cid = cid.parse()?;
                 print_rpc_res_pretty(
@@ -4,7 +4,7 @@
 use chrono::Utc;
 use clap::Subcommand;
 use forest_cli_shared::{chain_path, cli::Config};
-use forest_db::db_engine::db_root;
+use forest_db::db_engine::{db_root, index_db_root};
 use forest_rpc_client::db_ops::db_gc;
 use log::error;
 
@@ -64,16 +64,15 @@ impl DBCommands {
                     println!("Aborted.");
                     return Ok(());
                 }
-                match fs_extra::dir::remove(&dir) {
-                    Ok(_) => {
-                        println!("Deleted {}", dir.display());
-                        Ok(())
-                    }
-                    Err(err) => {
-                        error!("{err}");
-                        Ok(())
+                // The indexes are built from the chain and are deleted along
+                // with it
+                for dir in [dir, index_db_root(&chain_path(config))] {
+                    match fs_extra::dir::remove(&dir) {
+                        Ok(_) => println!("Deleted {}", dir.display()),
+                        Err(err) => error!("{err}"),
                     }
                 }
+                Ok(())
             }
         }
     }
@@ -0,0 +1,84 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::{io::Write, path::PathBuf};
+
+use clap::Subcommand;
+use forest_rpc_client::wallet_ops::*;
+use rpassword::read_password;
+
+use super::{handle_rpc_err, Config};
+
+#[derive(Debug, Subcommand)]
+pub enum KeystoreCommands {
+    /// Re-encrypt the keystore of the node with a new passphrase
+    ChangePassphrase,
+    /// Convert the clear text keystore of the node into an encrypted one
+    Encrypt,
+    /// Convert the encrypted keystore of the node into a clear text one
+    Decrypt,
+    /// Write a verified copy of the keystore to a path on the node's host. The
+    /// copy is in the same format as the keystore, encrypted or not
+    Backup {
+        /// Destination of the copy
+        path: PathBuf,
+    },
+}
+
+fn prompt_passphrase(prompt: &str) -> anyhow::Result<String> {
+    print!("{prompt}: ");
+    std::io::stdout().flush()?;
+    Ok(read_password()?)
+}
+
+fn prompt_new_passphrase() -> anyhow::Result<String> {
+    let passphrase = prompt_passphrase("Enter the new keystore passphrase")?;
+    anyhow::ensure!(!passphrase.is_empty(), "Passphrase cannot be empty");
+    anyhow::ensure!(
+        passphrase == prompt_passphrase("Confirm passphrase")?,
+        "Passphrases do not match"
+    );
+    Ok(passphrase)
+}
+
+impl KeystoreCommands {
+    pub async fn run(&self, config: Config) -> anyhow::Result<()> {
+        match self {
+            Self::ChangePassphrase => {
+                let passphrase = prompt_passphrase("Enter the current keystore passphrase")?;
+                let new_passphrase = prompt_new_passphrase()?;
+                wallet_change_passphrase((passphrase, new_passphrase), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                println!("Keystore passphrase changed");
+                Ok(())
+            }
+            Self::Encrypt => {
+                let passphrase = prompt_new_passphrase()?;
+                wallet_encrypt_keystore((passphrase,), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                println!("Keystore encrypted. Set `encrypt_keystore = true` in the configuration of the node to keep using it after a restart.");
+                Ok(())
+            }
+            Self::Decrypt => {
+                let passphrase = prompt_passphrase("Enter the current keystore passphrase")?;
+                wallet_decrypt_keystore((passphrase,), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                println!("Keystore decrypted. Set `encrypt_keystore = false` in the configuration of the node to keep using it after a restart.");
+                Ok(())
+            }
+            Self::Backup { path } => {
+                // The path is resolved by the node, make it independent of its working
+                // directory
+                let path = std::env::current_dir()?.join(path);
+                wallet_backup_keystore((path.display().to_string(),), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                println!("Keystore backed up to {}", path.display());
+                Ok(())
+            }
+        }
+    }
+}
@@ -12,7 +12,9 @@ mod chain_cmd;
 mod config_cmd;
 mod db_cmd;
 mod fetch_params_cmd;
+mod keystore_cmd;
 mod mpool_cmd;
+mod msig_cmd;
 mod net_cmd;
 mod send_cmd;
 mod shutdown_cmd;
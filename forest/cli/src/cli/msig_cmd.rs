@@ -0,0 +1,248 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::str::FromStr;
+
+use anyhow::Context;
+use clap::Subcommand;
+use forest_blocks::{tipset_keys_json::TipsetKeysJson, TipsetKeys};
+use forest_json::address::json::{vec::AddressJsonVec, AddressJson};
+use forest_rpc_client::{msig_ops::*, wallet_default_address};
+use forest_shim::{address::Address, econ::TokenAmount};
+use fvm_shared::clock::ChainEpoch;
+use num::BigInt;
+
+use super::{handle_rpc_err, Config};
+
+#[derive(Debug, Subcommand)]
+pub enum MsigCommands {
+    /// Create a new multisig wallet
+    Create {
+        /// Number of approvals required to execute a transaction
+        #[arg(long)]
+        required: Option<u64>,
+        /// Number of epochs over which the initial balance vests
+        #[arg(long, default_value_t = 0)]
+        duration: ChainEpoch,
+        /// Initial balance of the multisig wallet in attoFIL
+        #[arg(long, default_value_t = BigInt::from(0))]
+        value: BigInt,
+        /// Account to send the create message from (otherwise the default one
+        /// will be used)
+        #[arg(long)]
+        from: Option<String>,
+        /// Addresses of the signers
+        #[arg(required = true)]
+        signers: Vec<String>,
+    },
+    /// Propose a multisig transaction
+    Propose {
+        /// Address of the multisig wallet
+        multisig: String,
+        /// Destination of the proposed transaction
+        destination: String,
+        /// Amount to transfer in attoFIL
+        value: BigInt,
+        /// Method to call on the destination
+        #[arg(long, default_value_t = 0)]
+        method: u64,
+        /// Hex encoded parameters of the method
+        #[arg(long)]
+        params: Option<String>,
+        /// Signer proposing the transaction (otherwise the default one will be
+        /// used)
+        #[arg(long)]
+        from: Option<String>,
+    },
+    /// Approve a pending multisig transaction
+    Approve {
+        /// Address of the multisig wallet
+        multisig: String,
+        /// Identifier of the pending transaction
+        txn_id: u64,
+        /// Signer approving the transaction (otherwise the default one will be
+        /// used)
+        #[arg(long)]
+        from: Option<String>,
+    },
+    /// Cancel a pending multisig transaction
+    Cancel {
+        /// Address of the multisig wallet
+        multisig: String,
+        /// Identifier of the pending transaction
+        txn_id: u64,
+        /// Signer that proposed the transaction (otherwise the default one will
+        /// be used)
+        #[arg(long)]
+        from: Option<String>,
+    },
+    /// Show signers, threshold, vesting schedule and pending transactions of a
+    /// multisig wallet
+    Inspect {
+        /// Address of the multisig wallet
+        multisig: String,
+    },
+}
+
+fn parse_address(address: &str) -> anyhow::Result<AddressJson> {
+    Ok(AddressJson(
+        Address::from_str(address).with_context(|| format!("Invalid address: {address}"))?,
+    ))
+}
+
+async fn from_or_default(from: &Option<String>, config: &Config) -> anyhow::Result<AddressJson> {
+    match from {
+        Some(from) => parse_address(from),
+        None => parse_address(
+            &wallet_default_address((), &config.client.rpc_token)
+                .await
+                .map_err(handle_rpc_err)?
+                .context("No default wallet address selected. Please set a default address.")?,
+        ),
+    }
+}
+
+impl MsigCommands {
+    pub async fn run(&self, config: Config) -> anyhow::Result<()> {
+        match self {
+            Self::Create {
+                required,
+                duration,
+                value,
+                from,
+                signers,
+            } => {
+                let signers = signers
+                    .iter()
+                    .map(|signer| parse_address(signer).map(|addr| addr.0))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let required = required.unwrap_or(signers.len() as u64);
+                let from = from_or_default(from, &config).await?;
+                let cid = msig_create(
+                    (
+                        required,
+                        AddressJsonVec(signers),
+                        *duration,
+                        value.to_string(),
+                        from,
+                        "0".to_string(),
+                    ),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+                println!("sent create in message: {}", cid.0);
+                Ok(())
+            }
+            Self::Propose {
+                multisig,
+                destination,
+                value,
+                method,
+                params,
+                from,
+            } => {
+                let params = match params {
+                    Some(params) => hex::decode(params).context("Params have to be hex encoded")?,
+                    None => Vec::new(),
+                };
+                let from = from_or_default(from, &config).await?;
+                let cid = msig_propose(
+                    (
+                        parse_address(multisig)?,
+                        parse_address(destination)?,
+                        value.to_string(),
+                        from,
+                        *method,
+                        params,
+                    ),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+                println!("sent proposal in message: {}", cid.0);
+                Ok(())
+            }
+            Self::Approve {
+                multisig,
+                txn_id,
+                from,
+            } => {
+                let from = from_or_default(from, &config).await?;
+                let cid = msig_approve(
+                    (parse_address(multisig)?, *txn_id, from),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+                println!("sent approval in message: {}", cid.0);
+                Ok(())
+            }
+            Self::Cancel {
+                multisig,
+                txn_id,
+                from,
+            } => {
+                let from = from_or_default(from, &config).await?;
+                let cid = msig_cancel(
+                    (parse_address(multisig)?, *txn_id, from),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+                println!("sent cancel in message: {}", cid.0);
+                Ok(())
+            }
+            Self::Inspect { multisig } => {
+                let info = msig_inspect(
+                    (
+                        parse_address(multisig)?,
+                        TipsetKeysJson(TipsetKeys::default()),
+                    ),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+
+                println!("Balance: {} FIL", info.balance);
+                let spendable = if info.balance > info.locked {
+                    info.balance.clone() - &info.locked
+                } else {
+                    TokenAmount::default()
+                };
+                println!("Spendable: {spendable} FIL");
+                if info.vesting.unlock_duration > 0 {
+                    println!("Vesting:");
+                    println!("  Initial balance: {} FIL", info.vesting.initial_balance);
+                    println!("  Start epoch: {}", info.vesting.start_epoch);
+                    println!("  Unlock duration: {}", info.vesting.unlock_duration);
+                    println!("  Locked: {} FIL", info.locked);
+                }
+                println!("Threshold: {} / {}", info.threshold, info.signers.len());
+                println!("Signers:");
+                for signer in &info.signers {
+                    println!("  {signer}");
+                }
+                println!("Transactions: {}", info.pending.len());
+                if !info.pending.is_empty() {
+                    println!(
+                        "{:<6} {:<10} {:<42} {:<24} {:<8} Params",
+                        "ID", "Approvals", "To", "Value (FIL)", "Method"
+                    );
+                    for txn in &info.pending {
+                        println!(
+                            "{:<6} {:<10} {:<42} {:<24} {:<8} {}",
+                            txn.id,
+                            txn.approved.len(),
+                            txn.to.to_string(),
+                            txn.value.to_string(),
+                            txn.method,
+                            txn.params
+                        );
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
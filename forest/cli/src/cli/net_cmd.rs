@@ -2,20 +2,29 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use ahash::HashSet;
+use chrono::NaiveDateTime;
 use clap::Subcommand;
-use forest_libp2p::{Multiaddr, Protocol};
+use forest_cli_shared::cli::to_size_string;
+use forest_libp2p::{BandwidthStats, Multiaddr, Protocol};
 use forest_rpc_api::data_types::AddrInfo;
 use forest_rpc_client::net_ops::*;
+use num::BigInt;
 
 use super::{handle_rpc_err, print_stdout, Config};
 use crate::cli::cli_error_and_die;
 
 #[derive(Debug, Subcommand)]
 pub enum NetCommands {
-    /// Lists `libp2p` swarm listener addresses
+    /// Lists `libp2p` swarm listener addresses, and the reachability of the
+    /// node detected by `AutoNAT`
     Listen,
     /// Lists `libp2p` swarm peers
-    Peers,
+    Peers {
+        /// Lists every peer known to the node, connected or not, with its
+        /// request stats, reputation and ban expiration
+        #[arg(short, long)]
+        verbose: bool,
+    },
     /// Connects to a peer by its peer ID and multi-addresses
     Connect {
         /// Multi-address (with `/p2p/` protocol)
@@ -26,6 +35,52 @@ pub enum NetCommands {
         /// Peer ID to disconnect from
         id: String,
     },
+    /// Lists the `GossipSub` score of peers, lowest first
+    Scores,
+    /// Bans a peer by its peer ID, disconnecting from it
+    Ban {
+        /// Peer ID to ban
+        id: String,
+        /// Ban duration in seconds, the ban is permanent without one
+        #[arg(short, long)]
+        duration: Option<u64>,
+    },
+    /// Lifts the ban of a peer by its peer ID
+    Unban {
+        /// Peer ID to unban
+        id: String,
+    },
+    /// Lists the banned peers, with the ban reason and expiration
+    Bans,
+    /// Prints the bandwidth used by the node, in total and per second
+    Bandwidth {
+        /// Breaks the bandwidth down by protocol
+        #[arg(long, conflicts_with = "by_peer")]
+        by_protocol: bool,
+        /// Breaks the bandwidth down by connected peer
+        #[arg(long)]
+        by_peer: bool,
+    },
+    /// Looks a peer up in the DHT and prints its addresses
+    FindPeer {
+        /// Peer ID to look up
+        id: String,
+    },
+    /// Prints the agent version, protocols, addresses and latency of a
+    /// connected peer
+    PeerInfo {
+        /// Peer ID of the connected peer
+        id: String,
+    },
+    /// Prints the agent version of a connected peer
+    AgentVersion {
+        /// Peer ID of the connected peer
+        id: String,
+    },
+    /// Lists the peers of the Kademlia routing table, by bucket
+    Dht,
+    /// Prints the statistics of the active and recent bitswap sessions
+    Bitswap,
 }
 
 impl NetCommands {
@@ -41,9 +96,56 @@ impl NetCommands {
                     .map(|addr| format!("{}/p2p/{}", addr, info.id))
                     .collect();
                 print_stdout(addresses.join("\n"));
+                let nat_info = net_auto_nat_status((), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                print_stdout(match nat_info.public_addr {
+                    Some(addr) => format!("Reachability: {:?} ({addr})", nat_info.reachability),
+                    None => format!("Reachability: {:?}", nat_info.reachability),
+                });
+                Ok(())
+            }
+            Self::Peers { verbose: true } => {
+                let connected: HashSet<String> = net_peers((), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?
+                    .into_iter()
+                    .map(|info| info.id)
+                    .collect();
+                let records = net_known_peers((), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                let output: Vec<String> = records
+                    .into_iter()
+                    .map(|record| {
+                        let status = if record.bad {
+                            "bad"
+                        } else if connected.contains(&record.peer_id) {
+                            "connected"
+                        } else {
+                            "known"
+                        };
+                        let addresses: Vec<String> =
+                            record.addrs.iter().map(|addr| addr.to_string()).collect();
+                        format!(
+                            "{}, {status}, successes: {}, failures: {}, latency: {}ms, banned until: {}, last seen: {}, [{}]",
+                            record.peer_id,
+                            record.successes,
+                            record.failures,
+                            record.average_time_ms,
+                            match record.ban_reason {
+                                Some(_) => format_ban_expiration(record.banned_until),
+                                None => "-".to_owned(),
+                            },
+                            format_time(record.last_seen),
+                            addresses.join(", ")
+                        )
+                    })
+                    .collect();
+                print_stdout(output.join("\n"));
                 Ok(())
             }
-            Self::Peers => {
+            Self::Peers { verbose: false } => {
                 let addrs = net_peers((), &config.client.rpc_token)
                     .await
                     .map_err(handle_rpc_err)?;
@@ -111,6 +213,198 @@ impl NetCommands {
                 println!("disconnect {id}: success");
                 Ok(())
             }
+            Self::Scores => {
+                let mut scores = net_pubsub_scores((), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                scores.sort_by(|a, b| a.score.score.total_cmp(&b.score.score));
+                let output: Vec<String> = scores
+                    .into_iter()
+                    .map(|score| format!("{}, {}", score.id, score.score.score))
+                    .collect();
+                print_stdout(output.join("\n"));
+                Ok(())
+            }
+            Self::Ban { id, duration } => {
+                net_ban((id.to_owned(), *duration), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                println!("ban {id}: success");
+                Ok(())
+            }
+            Self::Unban { id } => {
+                let unbanned = net_unban((id.to_owned(),), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                if unbanned {
+                    println!("unban {id}: success");
+                } else {
+                    println!("unban {id}: peer is not banned");
+                }
+                Ok(())
+            }
+            Self::Bans => {
+                let bans = net_bans((), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                let output: Vec<String> = bans
+                    .into_iter()
+                    .map(|ban| {
+                        format!(
+                            "{}, banned until: {}, reason: {}",
+                            ban.peer_id,
+                            format_ban_expiration(ban.banned_until),
+                            ban.reason
+                        )
+                    })
+                    .collect();
+                print_stdout(output.join("\n"));
+                Ok(())
+            }
+            Self::Bandwidth {
+                by_protocol,
+                by_peer,
+            } => {
+                let report = net_bandwidth_stats((), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                let mut segments: Vec<(String, BandwidthStats)> = if *by_protocol {
+                    report.by_protocol.into_iter().collect()
+                } else if *by_peer {
+                    report.by_peer.into_iter().collect()
+                } else {
+                    vec![("Total".to_owned(), report.total)]
+                };
+                segments
+                    .sort_by_key(|(_, stats)| std::cmp::Reverse(stats.total_in + stats.total_out));
+                let width = segments
+                    .iter()
+                    .map(|(segment, _)| segment.len())
+                    .max()
+                    .unwrap_or_default()
+                    .max("Segment".len());
+                let mut output = vec![format!(
+                    "{:width$}  {:>12}  {:>12}  {:>14}  {:>14}",
+                    "Segment", "TotalIn", "TotalOut", "RateIn", "RateOut"
+                )];
+                for (segment, stats) in segments {
+                    output.push(format!(
+                        "{segment:width$}  {:>12}  {:>12}  {:>14}  {:>14}",
+                        format_bytes(stats.total_in)?,
+                        format_bytes(stats.total_out)?,
+                        format!("{}/s", format_bytes(stats.rate_in as u64)?),
+                        format!("{}/s", format_bytes(stats.rate_out as u64)?),
+                    ));
+                }
+                print_stdout(output.join("\n"));
+                Ok(())
+            }
+            Self::FindPeer { id } => {
+                let info = net_find_peer((id.to_owned(),), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                let addresses: Vec<String> = info
+                    .addrs
+                    .iter()
+                    .map(|addr| format!("{}/p2p/{}", addr, info.id))
+                    .collect();
+                print_stdout(addresses.join("\n"));
+                Ok(())
+            }
+            Self::PeerInfo { id } => {
+                let info = net_peer_info((id.to_owned(),), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                let addresses: Vec<String> =
+                    info.addrs.iter().map(|addr| addr.to_string()).collect();
+                let output = [
+                    format!("Peer ID: {}", info.peer_id),
+                    format!("Agent: {}", info.agent),
+                    format!("Protocol version: {}", info.protocol_version),
+                    format!(
+                        "Latency: {}",
+                        info.latency_ms
+                            .map(|ms| format!("{ms}ms"))
+                            .unwrap_or_else(|| "-".to_owned())
+                    ),
+                    format!("Addresses: [{}]", addresses.join(", ")),
+                    format!("Protocols: [{}]", info.protocols.join(", ")),
+                ];
+                print_stdout(output.join("\n"));
+                Ok(())
+            }
+            Self::AgentVersion { id } => {
+                let agent_version = net_agent_version((id.to_owned(),), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                print_stdout(agent_version);
+                Ok(())
+            }
+            Self::Dht => {
+                let buckets = net_dht_routing_table((), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                let mut output = vec![];
+                for bucket in buckets {
+                    output.push(format!(
+                        "Bucket {} ({} peers)",
+                        bucket.index,
+                        bucket.peers.len()
+                    ));
+                    for peer in bucket.peers {
+                        let addresses: Vec<String> =
+                            peer.addrs.iter().map(|addr| addr.to_string()).collect();
+                        output.push(format!(
+                            "  {}, {}, [{}]",
+                            peer.peer_id,
+                            if peer.connected {
+                                "connected"
+                            } else {
+                                "disconnected"
+                            },
+                            addresses.join(", ")
+                        ));
+                    }
+                }
+                print_stdout(output.join("\n"));
+                Ok(())
+            }
+            Self::Bitswap => {
+                let sessions = net_bitswap_sessions((), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                let mut output = vec![];
+                for s in sessions {
+                    output.push(format!(
+                        "Session {}: {} wanted, {} received, {} failed, idle for {}s",
+                        s.id, s.wanted, s.received, s.failed, s.idle_secs
+                    ));
+                    output.push(format!(
+                        "  {} peers, {} provider queries, {}ms average fetch time",
+                        s.peers, s.provider_queries, s.average_fetch_time_ms
+                    ));
+                }
+                print_stdout(output.join("\n"));
+                Ok(())
+            }
         }
     }
 }
+
+fn format_bytes(bytes: u64) -> anyhow::Result<String> {
+    to_size_string(&BigInt::from(bytes))
+}
+
+fn format_time(secs: Option<u64>) -> String {
+    secs.and_then(|secs| NaiveDateTime::from_timestamp_opt(secs as i64, 0))
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "-".to_owned())
+}
+
+/// Formats the expiration of a ban, permanent without one.
+fn format_ban_expiration(banned_until: Option<u64>) -> String {
+    match banned_until {
+        Some(_) => format_time(banned_until),
+        None => "forever".to_owned(),
+    }
+}
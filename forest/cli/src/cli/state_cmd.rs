@@ -1,11 +1,14 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use cid::Cid;
 use clap::Subcommand;
+use forest_json::cid::CidJson;
+use forest_rpc_client::state_ops::*;
 use fvm_shared::{clock::ChainEpoch, econ::TokenAmount};
 use serde_tuple::{self, Deserialize_tuple, Serialize_tuple};
 
-use super::Config;
+use super::{print_rpc_res_pretty, Config};
 
 #[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
 struct VestingSchedule {
@@ -19,11 +22,23 @@ struct VestingScheduleEntry {
 }
 
 #[derive(Debug, Subcommand)]
-pub enum StateCommands {}
+pub enum StateCommands {
+    /// Looks up the tipset a message was executed in and its receipt
+    SearchMsg {
+        /// CID of the message
+        cid: String,
+    },
+}
 
 impl StateCommands {
-    pub fn run(&self, _config: Config) -> anyhow::Result<()> {
-        // match self {}
-        Ok(())
+    pub async fn run(&self, config: Config) -> anyhow::Result<()> {
+        match self {
+            Self::SearchMsg { cid } => {
+                let cid: Cid = cid.parse()?;
+                print_rpc_res_pretty(
+                    state_search_msg((CidJson(cid),), &config.client.rpc_token).await,
+                )
+            }
+        }
     }
 }
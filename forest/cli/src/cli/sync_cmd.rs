@@ -38,6 +38,22 @@ pub enum SyncCommands {
         #[arg(short)]
         cid: String,
     },
+    /// Unmark a given block as bad, so that it is validated again when synced
+    UnmarkBad {
+        /// The block CID to unmark
+        #[arg(short, required_unless_present = "all")]
+        cid: Option<String>,
+        /// Unmark all the bad blocks
+        #[arg(long, conflicts_with = "cid")]
+        all: bool,
+    },
+    /// List the consensus faults of miners detected by the node
+    Faults,
+    /// Validate a given block again and report every check it fails
+    Explain {
+        /// The block CID to validate
+        cid: String,
+    },
 }
 
 impl SyncCommands {
@@ -150,6 +166,68 @@ impl SyncCommands {
                 println!("OK");
                 Ok(())
             }
+            Self::UnmarkBad { cid, all } => {
+                if *all {
+                    sync_unmark_all_bad((), &config.client.rpc_token)
+                        .await
+                        .map_err(handle_rpc_err)?;
+                } else if let Some(cid) = cid {
+                    let cid: Cid = cid.parse()?;
+                    sync_unmark_bad((CidJson(cid),), &config.client.rpc_token)
+                        .await
+                        .map_err(handle_rpc_err)?;
+                }
+                println!("OK");
+                Ok(())
+            }
+            Self::Faults => {
+                let faults = sync_consensus_faults((), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                if faults.is_empty() {
+                    println!("No consensus fault detected");
+                }
+                for fault in faults {
+                    println!(
+                        "{:?}\tminer: {}\theight: {}\tblocks: {}, {}{}",
+                        fault.fault_type,
+                        fault.miner,
+                        fault.height,
+                        fault.block1.0,
+                        fault.block2.0,
+                        fault
+                            .block_extra
+                            .map(|cid| format!(", {}", cid.0))
+                            .unwrap_or_default()
+                    );
+                }
+                Ok(())
+            }
+            Self::Explain { cid } => {
+                let cid: Cid = cid.parse()?;
+                let explanation = sync_explain_block((CidJson(cid),), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+
+                match explanation.bad_block {
+                    Some(record) => {
+                        println!("Block \"{cid}\" is marked bad: {}", record.reason);
+                        for check in record.failed_checks {
+                            println!("\t{}: {}", check.check, check.error);
+                        }
+                    }
+                    None => println!("Block \"{cid}\" is not marked as a bad block"),
+                }
+                if explanation.failed_checks.is_empty() {
+                    println!("Block \"{cid}\" passes validation");
+                } else {
+                    println!("Block \"{cid}\" fails validation:");
+                    for check in explanation.failed_checks {
+                        println!("\t{}: {}", check.check, check.error);
+                    }
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -13,7 +13,10 @@ use forest_json::{
     address::json::AddressJson,
     signature::json::{signature_type::SignatureTypeJson, SignatureJson},
 };
-use forest_key_management::json::KeyInfoJson;
+use forest_key_management::{
+    derive_key, generate_mnemonic, json::KeyInfoJson, mnemonic_to_seed, DerivationPath,
+    Eip155Transaction, EthAddress,
+};
 use forest_rpc_client::wallet_ops::*;
 use forest_shim::{
     address::{Address, Protocol},
@@ -21,15 +24,18 @@ use forest_shim::{
 };
 use forest_utils::io::read_file_to_string;
 use fvm_shared::econ::TokenAmount;
-use num::BigInt;
+use num::{BigInt, BigUint};
 use rpassword::read_password;
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
 
-use super::{handle_rpc_err, Config};
+use super::{handle_rpc_err, keystore_cmd::KeystoreCommands, msig_cmd::MsigCommands, Config};
 
 const NUM_SIGNIFICANT_DIGITS: u32 = 4;
 
+/// Chain ID of the Filecoin mainnet in the Ethereum JSON-RPC API.
+const MAINNET_CHAIN_ID: u64 = 314;
+
 #[allow(clippy::enum_variant_names)]
 enum FormattingMode {
     /// mode to show data in `FIL` units
@@ -54,9 +60,23 @@ enum FormattingMode {
 pub enum WalletCommands {
     /// Create a new wallet
     New {
-        /// The signature type to use. One of SECP256k1, or BLS
+        /// The signature type to use. One of SECP256k1, BLS or Delegated.
+        /// Delegated keys are SECP256k1 keys controlling an `f410` address,
+        /// usable as an Ethereum `0x` address
         #[arg(default_value = "secp256k1")]
         signature_type: String,
+        /// Derive the first key of the given type from a BIP-39 mnemonic
+        /// instead of generating a random one. A new mnemonic is generated if
+        /// none is entered.
+        #[arg(long)]
+        from_mnemonic: bool,
+    },
+    /// Derive a key from a BIP-39 mnemonic and import it in the wallet
+    Derive {
+        /// The derivation path of the key, `m/44'/461'/0'/0/N` for SECP256k1
+        /// keys or `m/12381/461/0/N` for BLS keys
+        #[arg(long)]
+        path: String,
     },
     /// Get account balance
     Balance {
@@ -113,6 +133,35 @@ pub enum WalletCommands {
         #[arg(short)]
         address: String,
     },
+    /// Sign a legacy Ethereum transaction with EIP-155 replay protection and
+    /// print the raw signed transaction
+    SignEthTx {
+        /// The `f410` or `0x` address of the delegated key signing the
+        /// transaction
+        #[arg(long)]
+        from: String,
+        /// The `0x` address of the recipient. Omit it to create a contract
+        #[arg(long)]
+        to: Option<String>,
+        /// The amount to transfer, in `attoFIL`
+        #[arg(long, default_value = "0")]
+        value: BigUint,
+        /// The nonce of the sender
+        #[arg(long)]
+        nonce: u64,
+        /// The gas price, in `attoFIL`
+        #[arg(long)]
+        gas_price: BigUint,
+        /// The gas limit
+        #[arg(long)]
+        gas_limit: u64,
+        /// The hex encoded input data
+        #[arg(long, default_value = "")]
+        data: String,
+        /// The chain ID of the network
+        #[arg(long, default_value_t = MAINNET_CHAIN_ID)]
+        chain_id: u64,
+    },
     /// Verify the signature of a message. Returns true if the signature matches
     /// the message and address
     Verify {
@@ -126,17 +175,72 @@ pub enum WalletCommands {
         #[arg(short)]
         signature: String,
     },
+    /// Interact with a multisig wallet
+    #[command(subcommand)]
+    Msig(MsigCommands),
+    /// Manage the encryption and backups of the node's keystore
+    #[command(subcommand)]
+    Keystore(KeystoreCommands),
+}
+
+/// Prompt for a BIP-39 mnemonic, derive the key at the given path and import
+/// it in the wallet. When `allow_generate` is set, a new mnemonic is generated
+/// if none is entered.
+async fn import_derived_key(
+    path: &DerivationPath,
+    allow_generate: bool,
+    config: &Config,
+) -> anyhow::Result<()> {
+    if allow_generate {
+        println!("Enter the mnemonic (leave empty to generate a new one): ");
+    } else {
+        println!("Enter the mnemonic: ");
+    }
+    let mut mnemonic = read_password()?.trim().to_string();
+    if mnemonic.is_empty() && allow_generate {
+        mnemonic = generate_mnemonic()?;
+        println!("Generated mnemonic, write it down and keep it safe:\n{mnemonic}");
+    }
+    println!("Enter the mnemonic passphrase (optional): ");
+    let passphrase = read_password()?;
+
+    let seed = mnemonic_to_seed(&mnemonic, &passphrase)?;
+    let key_info = derive_key(&seed, path)?;
+    let key = wallet_import(vec![KeyInfoJson(key_info)], &config.client.rpc_token)
+        .await
+        .map_err(handle_rpc_err)?;
+
+    println!("{key} ({path})");
+    Ok(())
+}
+
+/// Parse a Filecoin address, or an Ethereum `0x` address into its `f410` form
+fn parse_address(address: &str) -> anyhow::Result<Address> {
+    if address.starts_with("0x") {
+        Ok(EthAddress::from_str(address)?.to_filecoin_address()?)
+    } else {
+        Address::from_str(address).with_context(|| format!("Invalid address: {address}"))
+    }
 }
 
 impl WalletCommands {
     pub async fn run(&self, config: Config) -> anyhow::Result<()> {
         match self {
-            Self::New { signature_type } => {
+            Self::New {
+                signature_type,
+                from_mnemonic,
+            } => {
                 let signature_type = match signature_type.to_lowercase().as_str() {
                     "secp256k1" => SignatureType::Secp256k1,
+                    "delegated" => SignatureType::Delegated,
                     _ => SignatureType::BLS,
                 };
 
+                if *from_mnemonic {
+                    let path = DerivationPath::default_for(signature_type, 0)?;
+                    return import_derived_key(&path, true, &config).await;
+                }
+
                 let signature_type_json = SignatureTypeJson(signature_type);
 
                 let response = wallet_new((signature_type_json,), &config.client.rpc_token)
@@ -145,7 +249,12 @@ impl WalletCommands {
                 println!("{response}");
                 Ok(())
             }
+            Self::Derive { path } => {
+                let path = DerivationPath::from_str(path)?;
+                import_derived_key(&path, false, &config).await
+            }
             Self::Balance { address } => {
+                let address = parse_address(address)?;
                 let response = wallet_balance((address.to_string(),), &config.client.rpc_token)
                     .await
                     .map_err(handle_rpc_err)?;
@@ -161,6 +270,7 @@ impl WalletCommands {
                 Ok(())
             }
             Self::Export { address } => {
+                let address = parse_address(address)?;
                 let response = wallet_export((address.to_string(),), &config.client.rpc_token)
                     .await
                     .map_err(handle_rpc_err)?;
@@ -170,6 +280,7 @@ impl WalletCommands {
                 Ok(())
             }
             Self::Has { key } => {
+                let key = parse_address(key)?;
                 let response = wallet_has((key.to_string(),), &config.client.rpc_token)
                     .await
                     .map_err(handle_rpc_err)?;
@@ -213,11 +324,16 @@ impl WalletCommands {
                     .await
                     .map_err(handle_rpc_err)?;
 
-                let (title_address, title_default_mark, title_balance) =
-                    ("Address", "Default", "Balance");
-                println!("{title_address:41} {title_default_mark:7} {title_balance}");
+                let (title_address, title_eth_address, title_default_mark, title_balance) =
+                    ("Address", "Eth Address", "Default", "Balance");
+                println!(
+                    "{title_address:44} {title_eth_address:42} {title_default_mark:7} {title_balance}"
+                );
 
                 for address in response {
+                    let eth_addr = EthAddress::from_filecoin_address(&address.0)
+                        .map(|eth_addr| eth_addr.to_string())
+                        .unwrap_or_default();
                     let addr = address.0.to_string();
                     let default_address_mark = if default.as_ref() == Some(&addr) {
                         "X"
@@ -236,13 +352,14 @@ impl WalletCommands {
                         bool_pair_to_mode(*exact_balance, *fixed_unit),
                     )?;
 
-                    println!("{addr:41}  {default_address_mark:7}  {balance_string}",);
+                    println!(
+                        "{addr:44}  {eth_addr:42}  {default_address_mark:7}  {balance_string}",
+                    );
                 }
                 Ok(())
             }
             Self::SetDefault { key } => {
-                let key =
-                    Address::from_str(key).with_context(|| format!("Invalid address: {key}"))?;
+                let key = parse_address(key)?;
 
                 let key_json = AddressJson(key);
                 wallet_set_default((key_json,), &config.client.rpc_token)
@@ -251,8 +368,7 @@ impl WalletCommands {
                 Ok(())
             }
             Self::Sign { address, message } => {
-                let address = Address::from_str(address)
-                    .with_context(|| format!("Invalid address: {address}"))?;
+                let address = parse_address(address)?;
 
                 let message = hex::decode(message).context("Message has to be a hex string")?;
                 let message = BASE64_STANDARD.encode(message);
@@ -266,6 +382,42 @@ impl WalletCommands {
                 println!("{}", hex::encode(response.0.bytes()));
                 Ok(())
             }
+            Self::SignEthTx {
+                from,
+                to,
+                value,
+                nonce,
+                gas_price,
+                gas_limit,
+                data,
+                chain_id,
+            } => {
+                let from = parse_address(from)?;
+                anyhow::ensure!(
+                    EthAddress::from_filecoin_address(&from).is_some(),
+                    "{from} is not the address of a delegated key"
+                );
+                let tx = Eip155Transaction {
+                    chain_id: *chain_id,
+                    nonce: *nonce,
+                    gas_price: gas_price.clone(),
+                    gas_limit: *gas_limit,
+                    to: to.as_deref().map(EthAddress::from_str).transpose()?,
+                    value: value.clone(),
+                    data: hex::decode(data.trim_start_matches("0x"))
+                        .context("Data has to be a hex string")?,
+                };
+                let message = BASE64_STANDARD.encode(tx.signing_payload());
+
+                let response = wallet_sign(
+                    (AddressJson(from), message.into_bytes()),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+                println!("0x{}", hex::encode(tx.encode_signed(&response.0)?));
+                Ok(())
+            }
             Self::Verify {
                 message,
                 address,
@@ -273,12 +425,12 @@ impl WalletCommands {
             } => {
                 let sig_bytes =
                     hex::decode(signature).context("Signature has to be a hex string")?;
-                let address = Address::from_str(address)
-                    .with_context(|| format!("Invalid address: {address}"))?;
+                let address = parse_address(address)?;
                 let signature = match address.protocol() {
                     Protocol::Secp256k1 => Signature::new_secp256k1(sig_bytes),
                     Protocol::BLS => Signature::new_bls(sig_bytes),
-                    _ => anyhow::bail!("Invalid signature (must be bls or secp256k1)"),
+                    Protocol::Delegated => Signature::new(SignatureType::Delegated, sig_bytes),
+                    _ => anyhow::bail!("Invalid signature (must be bls, secp256k1 or delegated)"),
                 };
                 let msg = hex::decode(message).context("Message has to be a hex string")?;
 
@@ -292,6 +444,8 @@ impl WalletCommands {
                 println!("{response}");
                 Ok(())
             }
+            Self::Msig(cmd) => cmd.run(config).await,
+            Self::Keystore(cmd) => cmd.run(config).await,
         }
     }
 }
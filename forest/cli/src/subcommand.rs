@@ -5,7 +5,7 @@ use super::cli::{Config, Subcommand};
 
 /// Process CLI sub-command
 pub(super) async fn process(command: Subcommand, config: Config) -> anyhow::Result<()> {
-    if config.chain.name == "calibnet" {
+    if matches!(config.chain.name.as_str(), "calibnet" | "devnet") {
         forest_shim::address::set_current_network(forest_shim::address::Network::Testnet);
     }
     // Run command
@@ -17,7 +17,7 @@ pub(super) async fn process(command: Subcommand, config: Config) -> anyhow::Resu
         Subcommand::Wallet(cmd) => cmd.run(config).await,
         Subcommand::Sync(cmd) => cmd.run(config).await,
         Subcommand::Mpool(cmd) => cmd.run(config),
-        Subcommand::State(cmd) => cmd.run(config),
+        Subcommand::State(cmd) => cmd.run(config).await,
         Subcommand::Config(cmd) => cmd.run(&config, &mut std::io::stdout()),
         Subcommand::Send(cmd) => cmd.run(config).await,
         Subcommand::DB(cmd) => cmd.run(&config).await,
@@ -1,14 +1,21 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::{io::prelude::*, net::TcpListener, path::PathBuf, sync::Arc, time, time::Duration};
+use std::{
+    io::prelude::*,
+    net::TcpListener,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time,
+    time::Duration,
+};
 
 use anyhow::Context;
 use dialoguer::{theme::ColorfulTheme, Confirm};
 use forest_auth::{create_token, generate_priv_key, ADMIN, JWT_IDENTIFIER};
 use forest_blocks::Tipset;
-use forest_chain::ChainStore;
-use forest_chain_sync::{consensus::SyncGossipSubmitter, ChainMuxer};
+use forest_chain::{event_index_loop, message_index_loop, ChainStore, EventIndex, MessageIndex};
+use forest_chain_sync::{consensus::SyncGossipSubmitter, BadBlockCache, ChainMuxer, Slasher};
 use forest_cli_shared::{
     chain_path,
     cli::{
@@ -17,17 +24,21 @@ use forest_cli_shared::{
     },
 };
 use forest_db::{
-    db_engine::{db_root, open_proxy_db},
+    db_engine::{db_root, open_index_db, open_proxy_db},
     rolling::{DbGarbageCollector, RollingDB},
-    Store,
+    MemoryDB, Store,
+};
+use forest_genesis::{
+    build_genesis, export_genesis, get_network_name_from_genesis, import_chain,
+    read_genesis_header, GenesisTemplate,
 };
-use forest_genesis::{get_network_name_from_genesis, import_chain, read_genesis_header};
 use forest_key_management::{
-    KeyStore, KeyStoreConfig, ENCRYPTED_KEYSTORE_NAME, FOREST_KEYSTORE_PHRASE_ENV,
+    KeyStore, KeyStoreConfig, RemoteSigner, Signer, ENCRYPTED_KEYSTORE_NAME,
+    FOREST_KEYSTORE_PHRASE_ENV,
 };
 use forest_libp2p::{get_keypair, Libp2pConfig, Libp2pService, PeerId, PeerManager};
 use forest_message_pool::{MessagePool, MpoolConfig, MpoolRpcProvider};
-use forest_rpc::start_rpc;
+use forest_rpc::{report_consensus_faults, start_rpc};
 use forest_rpc_api::data_types::RPCState;
 use forest_shim::version::NetworkVersion;
 use forest_state_manager::StateManager;
@@ -69,9 +80,15 @@ fn unblock_parent_process() -> anyhow::Result<()> {
 
 /// Starts daemon process
 pub(super) async fn start(opts: CliOpts, config: Config) -> anyhow::Result<RollingDB> {
-    if config.chain.name == "calibnet" {
+    if matches!(config.chain.name.as_str(), "calibnet" | "devnet") {
         forest_shim::address::set_current_network(forest_shim::address::Network::Testnet);
     }
+    #[cfg(not(feature = "forest_deleg_cns"))]
+    if config.chain.name == "devnet" {
+        anyhow::bail!(
+            "A devnet runs on delegated consensus; build Forest with the `forest_deleg_cns` feature"
+        );
+    }
 
     set_sigint_handler();
 
@@ -120,8 +137,14 @@ pub(super) async fn start(opts: CliOpts, config: Config) -> anyhow::Result<Rolli
 
     let keystore = Arc::new(RwLock::new(keystore));
 
+    let remote_signer = config.client.remote_signer.as_ref().map(|socket_path| {
+        info!("Using remote signer at {}", socket_path.display());
+        Arc::new(RemoteSigner::new(socket_path)) as Arc<dyn Signer>
+    });
+
     let chain_data_path = chain_path(&config);
     let db = open_proxy_db(db_root(&chain_data_path), config.db_config().clone())?;
+    let index_db = open_index_db(&chain_data_path, config.db_config())?;
 
     let mut services = JoinSet::new();
 
@@ -154,20 +177,34 @@ pub(super) async fn start(opts: CliOpts, config: Config) -> anyhow::Result<Rolli
     // Read Genesis file
     // * When snapshot command implemented, this genesis does not need to be
     //   initialized
-    let genesis_header = read_genesis_header(
-        config.client.genesis_file.as_ref(),
-        config.chain.genesis_bytes(),
-        &db,
-    )
-    .await?;
+    let genesis_file = match &config.client.genesis_file {
+        None if config.chain.name == "devnet" => {
+            Some(devnet_genesis(&config, &chain_data_path).await?)
+        }
+        genesis_file => genesis_file.clone(),
+    };
+    let genesis_header =
+        read_genesis_header(genesis_file.as_ref(), config.chain.genesis_bytes(), &db).await?;
 
     // Initialize ChainStore
-    let chain_store = Arc::new(ChainStore::new(
+    let mut chain_store = ChainStore::new(
         db.clone(),
         config.chain.clone(),
         &genesis_header,
         chain_data_path.as_path(),
-    )?);
+    )?
+    .with_message_index(MessageIndex::new(index_db.clone()))
+    .with_event_index(EventIndex::new(
+        index_db.clone(),
+        config.sync.event_retention,
+    ));
+    if let Some(max_reorg_depth) = config.sync.max_reorg_depth {
+        chain_store = chain_store.with_max_reorg_depth(max_reorg_depth);
+    }
+    if let Some(checkpoint) = config.sync.trusted_checkpoint()? {
+        chain_store = chain_store.with_checkpoint_epoch(checkpoint.epoch);
+    }
+    let chain_store = Arc::new(chain_store);
 
     chain_store.set_genesis(&genesis_header)?;
     let db_garbage_collector = {
@@ -188,6 +225,9 @@ pub(super) async fn start(opts: CliOpts, config: Config) -> anyhow::Result<Rolli
         async move { db_garbage_collector.collect_loop_event().await }
     });
 
+    services.spawn(message_index_loop(chain_store.clone()));
+    services.spawn(event_index_loop(chain_store.clone()));
+
     let publisher = chain_store.publisher();
 
     // Reward calculation is needed by the VM to calculate state, which can happen
@@ -201,7 +241,9 @@ pub(super) async fn start(opts: CliOpts, config: Config) -> anyhow::Result<Rolli
         Arc::clone(&chain_store),
         Arc::clone(&config.chain),
         reward_calc,
-    )?;
+    )?
+    .with_tipset_state_store(db.clone())
+    .with_beacon_entry_store(index_db.clone());
 
     let state_manager = Arc::new(sm);
 
@@ -246,8 +288,15 @@ pub(super) async fn start(opts: CliOpts, config: Config) -> anyhow::Result<Rolli
         false
     };
 
-    let peer_manager = Arc::new(PeerManager::default());
+    let peer_manager = Arc::new(PeerManager::new(
+        Duration::from_secs(config.network.ban_duration_secs),
+        config.network.protected_peer_ids(),
+    ));
+    if let Err(e) = peer_manager.load_peers(&db).await {
+        warn!("Failed to load known peers: {e}");
+    }
     services.spawn(peer_manager.clone().peer_operation_event_loop_task());
+    services.spawn(peer_manager.clone().save_peers_task(db.clone()));
     let genesis_cid = *genesis_header.cid();
     // Libp2p service setup
     let p2p_service = Libp2pService::new(
@@ -289,10 +338,10 @@ pub(super) async fn start(opts: CliOpts, config: Config) -> anyhow::Result<Rolli
 
     // Initialize ChainMuxer
     let chain_muxer_tipset_sink = tipset_sink.clone();
-    let chain_muxer = ChainMuxer::new(
+    let mut chain_muxer = ChainMuxer::new(
         Arc::new(consensus),
         Arc::clone(&state_manager),
-        peer_manager,
+        peer_manager.clone(),
         mpool.clone(),
         network_send.clone(),
         network_rx,
@@ -301,7 +350,13 @@ pub(super) async fn start(opts: CliOpts, config: Config) -> anyhow::Result<Rolli
         tipset_stream,
         config.sync.clone(),
     )?;
-    let bad_blocks = chain_muxer.bad_blocks_cloned();
+    let slasher = config.slasher.enabled.then(|| Arc::new(Slasher::default()));
+    if let Some(slasher) = &slasher {
+        chain_muxer = chain_muxer.with_slasher(slasher.clone());
+    }
+    let bad_blocks = Arc::new(BadBlockCache::load(chain_data_path.join("BAD_BLOCKS"))?);
+    chain_muxer = chain_muxer.with_bad_blocks(bad_blocks.clone());
+    let block_explainer = chain_muxer.block_explainer();
     let sync_state = chain_muxer.sync_state_cloned();
     services.spawn(async { Err(anyhow::anyhow!("{}", chain_muxer.await)) });
 
@@ -318,24 +373,34 @@ pub(super) async fn start(opts: CliOpts, config: Config) -> anyhow::Result<Rolli
         let rpc_chain_store = Arc::clone(&chain_store);
 
         let gc_event_tx = db_garbage_collector.get_tx();
+        let rpc_state = Arc::new(RPCState {
+            state_manager: Arc::clone(&rpc_state_manager),
+            keystore: keystore_rpc,
+            remote_signer,
+            mpool,
+            bad_blocks: bad_blocks.clone(),
+            sync_state,
+            network_send,
+            network_name,
+            // TODO: the RPCState can fetch this itself from the StateManager
+            beacon: rpc_state_manager.beacon_schedule(),
+            chain_store: rpc_chain_store,
+            new_mined_block_tx: tipset_sink,
+            gc_event_tx,
+            slasher,
+            block_explainer: Some(block_explainer),
+        });
+        if let Some(reporter) = &config.slasher.reporter {
+            let reporter = reporter
+                .parse()
+                .context(format!("invalid slasher reporter address {reporter}"))?;
+            services.spawn(report_consensus_faults(rpc_state.clone(), reporter));
+        }
         services.spawn(async move {
             info!("JSON-RPC endpoint started at {}", config.client.rpc_address);
             // XXX: The JSON error message are a nightmare to print.
             start_rpc::<_, _, cns::FullConsensus>(
-                Arc::new(RPCState {
-                    state_manager: Arc::clone(&rpc_state_manager),
-                    keystore: keystore_rpc,
-                    mpool,
-                    bad_blocks,
-                    sync_state,
-                    network_send,
-                    network_name,
-                    // TODO: the RPCState can fetch this itself from the StateManager
-                    beacon: rpc_state_manager.beacon_schedule(),
-                    chain_store: rpc_chain_store,
-                    new_mined_block_tx: tipset_sink,
-                    gc_event_tx,
-                }),
+                rpc_state,
                 rpc_listen,
                 FOREST_VERSION_STRING.as_str(),
                 shutdown_send,
@@ -344,6 +409,9 @@ pub(super) async fn start(opts: CliOpts, config: Config) -> anyhow::Result<Rolli
             .map_err(|err| anyhow::anyhow!("{:?}", serde_json::to_string(&err)))
         });
     } else {
+        if config.slasher.reporter.is_some() {
+            warn!("Consensus faults are only reported with the RPC enabled");
+        }
         debug!("RPC disabled.");
     };
     if opts.detach {
@@ -366,19 +434,23 @@ pub(super) async fn start(opts: CliOpts, config: Config) -> anyhow::Result<Rolli
         ret = sync_from_snapshot(&config, &state_manager).fuse() => {
             if let Err(err) = ret {
                 services.shutdown().await;
+                save_on_shutdown(&peer_manager, &db, &bad_blocks).await;
                 return Err(err);
             }
         },
         _ = tokio::signal::ctrl_c() => {
             services.shutdown().await;
+            save_on_shutdown(&peer_manager, &db, &bad_blocks).await;
             return Ok(db);
         },
         _ = terminate.recv() => {
             services.shutdown().await;
+            save_on_shutdown(&peer_manager, &db, &bad_blocks).await;
             return Ok(db);
         },
         _ = shutdown_recv.recv() => {
             services.shutdown().await;
+            save_on_shutdown(&peer_manager, &db, &bad_blocks).await;
             return Ok(db);
         },
     }
@@ -393,6 +465,7 @@ pub(super) async fn start(opts: CliOpts, config: Config) -> anyhow::Result<Rolli
     if opts.halt_after_import {
         // Cancel all async services
         services.shutdown().await;
+        save_on_shutdown(&peer_manager, &db, &bad_blocks).await;
         return Ok(db);
     }
 
@@ -408,10 +481,23 @@ pub(super) async fn start(opts: CliOpts, config: Config) -> anyhow::Result<Rolli
     }
 
     services.shutdown().await;
+    save_on_shutdown(&peer_manager, &db, &bad_blocks).await;
 
     Ok(db)
 }
 
+/// Saves the known peers and the bad block records a last time, as the
+/// periodic saving task is cancelled and the records are only written
+/// periodically.
+async fn save_on_shutdown(peer_manager: &PeerManager, db: &impl Store, bad_blocks: &BadBlockCache) {
+    if let Err(e) = peer_manager.save_peers(db).await {
+        warn!("Failed to save known peers: {e}");
+    }
+    if let Err(e) = bad_blocks.flush() {
+        warn!("Failed to save bad block records: {e}");
+    }
+}
+
 /// Generates, prints and optionally writes to a file the administrator JWT
 /// token.
 fn handle_admin_token(opts: &CliOpts, config: &Config, keystore: &KeyStore) -> anyhow::Result<()> {
@@ -544,6 +630,33 @@ where
     Ok(())
 }
 
+/// Returns the path of the genesis of a devnet. The genesis is built from the
+/// template on the first start, and kept in the chain data directory from where
+/// it can be shared with the other nodes joining the devnet.
+async fn devnet_genesis(config: &Config, chain_data_path: &Path) -> anyhow::Result<String> {
+    let genesis_path = chain_data_path.join("genesis.car");
+    if !genesis_path.exists() {
+        let template_path = config
+            .client
+            .genesis_template
+            .as_ref()
+            .context("A devnet needs a genesis; use either --genesis or --genesis-template")?;
+        let template = GenesisTemplate::load(template_path)?;
+        let db = MemoryDB::default();
+        let genesis = build_genesis(&template, config.chain.clone(), &db).await?;
+        // Renamed once complete, not to start from a partial genesis later.
+        let tmp_path = genesis_path.with_extension("car.tmp");
+        export_genesis(&db, &genesis, tokio::fs::File::create(&tmp_path).await?).await?;
+        std::fs::rename(&tmp_path, &genesis_path)?;
+        info!(
+            "Built the devnet genesis {} into {}",
+            genesis.cid(),
+            genesis_path.display()
+        );
+    }
+    Ok(genesis_path.display().to_string())
+}
+
 fn get_actual_chain_name(internal_network_name: &str) -> &str {
     match internal_network_name {
         "testnetnet" => "mainnet",
@@ -0,0 +1,162 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+#![cfg(all(feature = "slow_tests", feature = "forest_deleg_cns"))]
+pub mod common;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Context, Result};
+use forest_utils::net::{https_client, hyper};
+
+use crate::common::{cli, CommonArgs, CommonEnv};
+
+/// Bundle of the builtin actors of network version 18, built for devnets so
+/// that its miners accept 2 KiB sectors.
+const ACTORS_BUNDLE_URL: &str =
+    "https://github.com/filecoin-project/builtin-actors/releases/download/v10.0.0/builtin-actors-devnet.car";
+
+const OWNER: &str =
+    "t3vvrnnenu67ux2nk7tdiop53lvn3ruayaa4n5bcimewef76ocgchx2ivd2k7hoaxmzme7dfc53vi7bofzz2eq";
+
+#[tokio::test]
+async fn devnet_starts_from_built_genesis() -> Result<()> {
+    let bundle = actors_bundle().await?;
+    let temp_dir = tempfile::tempdir()?;
+    let template = temp_dir.path().join("devnet.json");
+    std::fs::write(
+        &template,
+        format!(
+            r#"{{
+  "NetworkName": "devnet",
+  "NetworkVersion": 18,
+  "ActorsBundle": "{}",
+  "Accounts": [
+    {{
+      "Type": "account",
+      "Balance": "50000000000000000000000000",
+      "Meta": {{ "Owner": "{OWNER}" }}
+    }}
+  ],
+  "Miners": [
+    {{
+      "ID": "t01000",
+      "Owner": "{OWNER}",
+      "Worker": "{OWNER}",
+      "PeerId": "12D3KooWEGdCsa4DAP8HG4r33ymxXFcXtui192zJnY48Hjzr4KKF",
+      "SectorSize": 2048
+    }}
+  ]
+}}"#,
+            bundle.display()
+        ),
+    )?;
+
+    // The first node builds the genesis and exports it into its chain data.
+    let proposer_dir = temp_dir.path().join("proposer");
+    let proposer_config = create_devnet_config(&proposer_dir)?;
+    cli()?
+        .common_env()
+        .common_args()
+        .arg("--config")
+        .arg(&proposer_config)
+        .arg("--chain")
+        .arg("devnet")
+        .arg("--encrypt-keystore")
+        .arg("false")
+        .arg("--genesis-template")
+        .arg(&template)
+        .assert()
+        .success();
+    let genesis = proposer_dir.join("devnet").join("genesis.car");
+    ensure!(genesis.exists(), "Genesis not exported to {genesis:?}");
+
+    // On later starts, the exported genesis is used instead of building it
+    // again.
+    let modified = std::fs::metadata(&genesis)?.modified()?;
+    cli()?
+        .common_env()
+        .common_args()
+        .arg("--config")
+        .arg(&proposer_config)
+        .arg("--chain")
+        .arg("devnet")
+        .arg("--encrypt-keystore")
+        .arg("false")
+        .arg("--genesis-template")
+        .arg(&template)
+        .assert()
+        .success();
+    ensure!(std::fs::metadata(&genesis)?.modified()? == modified);
+
+    // Other nodes join the devnet with the exported genesis.
+    let delegator_dir = temp_dir.path().join("delegator");
+    cli()?
+        .common_env()
+        .common_args()
+        .arg("--config")
+        .arg(create_devnet_config(&delegator_dir)?)
+        .arg("--chain")
+        .arg("devnet")
+        .arg("--encrypt-keystore")
+        .arg("false")
+        .arg("--genesis")
+        .arg(&genesis)
+        .assert()
+        .success();
+
+    Ok(())
+}
+
+fn create_devnet_config(data_dir: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(data_dir)?;
+    let config_file = data_dir.join("config.toml");
+    std::fs::write(
+        &config_file,
+        format!(
+            r#"
+[client]
+data_dir = "{}"
+"#,
+            data_dir.display()
+        ),
+    )?;
+    Ok(config_file)
+}
+
+/// Downloads the actors bundle once, to the same location for all test runs.
+async fn actors_bundle() -> Result<PathBuf> {
+    let path = Path::new("/tmp/forest-test-actors/builtin-actors-devnet-v10.0.0.car");
+    if path.exists() {
+        return Ok(path.to_owned());
+    }
+
+    let client = https_client();
+    let mut url: hyper::Uri = ACTORS_BUNDLE_URL.parse()?;
+    // GitHub redirects release downloads to its storage.
+    let response = loop {
+        let response = client.get(url.clone()).await?;
+        if !response.status().is_redirection() {
+            break response;
+        }
+        url = response
+            .headers()
+            .get(hyper::header::LOCATION)
+            .context("Redirect without a location")?
+            .to_str()?
+            .parse()?;
+    };
+    ensure!(
+        response.status().is_success(),
+        "Failed to download {ACTORS_BUNDLE_URL}: {}",
+        response.status()
+    );
+    let bundle = hyper::body::to_bytes(response.into_body()).await?;
+
+    // Renamed once complete, not to use a partial download later.
+    std::fs::create_dir_all(path.parent().context("Bundle path without a parent")?)?;
+    let tmp_path = path.with_extension("car.tmp");
+    std::fs::write(&tmp_path, bundle)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(path.to_owned())
+}
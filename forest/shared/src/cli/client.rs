@@ -20,6 +20,8 @@ use serde_with::{serde_as, DurationSeconds};
 pub struct Client {
     pub data_dir: PathBuf,
     pub genesis_file: Option<String>,
+    /// Template to build the genesis of a devnet from, on its first start.
+    pub genesis_template: Option<PathBuf>,
     pub enable_rpc: bool,
     pub rpc_port: u16,
     pub rpc_token: Option<String>,
@@ -32,6 +34,9 @@ pub struct Client {
     /// Will use the CIDs in the header of the file to index the chain.
     pub skip_load: bool,
     pub encrypt_keystore: bool,
+    /// Unix socket of an external wallet process signing on behalf of the
+    /// node, for keys that are not in the local key-store
+    pub remote_signer: Option<PathBuf>,
     /// Metrics bind, e.g. 127.0.0.1:6116
     pub metrics_address: SocketAddr,
     /// RPC bind, e.g. 127.0.0.1:1234
@@ -49,6 +54,7 @@ impl Default for Client {
         Self {
             data_dir: dir.data_dir().to_path_buf(),
             genesis_file: None,
+            genesis_template: None,
             enable_rpc: true,
             rpc_port: DEFAULT_PORT,
             rpc_token: None,
@@ -57,6 +63,7 @@ impl Default for Client {
             snapshot_height: None,
             skip_load: false,
             encrypt_keystore: true,
+            remote_signer: None,
             metrics_address: FromStr::from_str("0.0.0.0:6116").unwrap(),
             rpc_address: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), DEFAULT_PORT),
             token_exp: Duration::seconds(5184000), // 60 Days = 5184000 Seconds
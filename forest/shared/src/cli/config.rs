@@ -4,7 +4,7 @@
 use core::time::Duration;
 use std::{path::PathBuf, sync::Arc};
 
-use forest_chain_sync::SyncConfig;
+use forest_chain_sync::{SlasherConfig, SyncConfig};
 use forest_db::db_engine::DbConfig;
 use forest_libp2p::Libp2pConfig;
 use forest_networks::ChainConfig;
@@ -184,6 +184,7 @@ pub struct Config {
     pub log: LogConfig,
     pub snapshot_fetch: SnapshotFetchConfig,
     pub tokio: TokioConfig,
+    pub slasher: SlasherConfig,
 }
 
 impl Config {
@@ -237,6 +238,7 @@ mod test {
                 log: Default::default(),
                 snapshot_fetch: Default::default(),
                 tokio: Default::default(),
+                slasher: Default::default(),
             }
         }
     }
@@ -247,6 +249,7 @@ mod test {
                 client: Client {
                     data_dir: PathBuf::arbitrary(g),
                     genesis_file: Option::arbitrary(g),
+                    genesis_template: Option::arbitrary(g),
                     enable_rpc: bool::arbitrary(g),
                     rpc_port: u16::arbitrary(g),
                     rpc_token: Option::arbitrary(g),
@@ -255,6 +258,7 @@ mod test {
                     snapshot_path: Option::arbitrary(g),
                     skip_load: bool::arbitrary(g),
                     encrypt_keystore: bool::arbitrary(g),
+                    remote_signer: Option::arbitrary(g),
                     metrics_address: SocketAddr::arbitrary(g),
                     rpc_address: SocketAddr::arbitrary(g),
                     token_exp: Duration::milliseconds(i64::arbitrary(g)),
@@ -284,10 +288,34 @@ mod test {
                     mdns: bool::arbitrary(g),
                     kademlia: bool::arbitrary(g),
                     target_peer_count: u32::arbitrary(g),
+                    transports: forest_libp2p::Libp2pTransport::ALL
+                        .into_iter()
+                        .filter(|_| bool::arbitrary(g))
+                        .collect(),
+                    gossipsub_peer_scoring: bool::arbitrary(g),
+                    direct_peers: vec![Ipv4Addr::arbitrary(g).into(); u8::arbitrary(g) as usize],
+                    ban_duration_secs: u64::arbitrary(g),
+                    allow_list: Vec::arbitrary(g),
+                    deny_list: Vec::arbitrary(g),
+                    protected_peers: Vec::arbitrary(g),
+                    max_connections_per_ip: Option::arbitrary(g),
+                    serving_limits: forest_libp2p::ServingLimits {
+                        peer_requests_per_sec: u64::arbitrary(g),
+                        peer_bytes_per_sec: u64::arbitrary(g),
+                        global_bytes_per_sec: u64::arbitrary(g),
+                    },
+                    nat_traversal: bool::arbitrary(g),
+                    relays: vec![Ipv4Addr::arbitrary(g).into(); u8::arbitrary(g) as usize],
+                    upnp: bool::arbitrary(g),
                 },
                 sync: SyncConfig {
                     req_window: i64::arbitrary(g),
                     tipset_sample_size: u32::arbitrary(g) as _,
+                    checkpoint_file: Option::<String>::arbitrary(g).map(Into::into),
+                    checkpoint_signer: Option::arbitrary(g),
+                    checkpoint: None,
+                    event_retention: i64::arbitrary(g),
+                    max_reorg_depth: Option::arbitrary(g),
                 },
             }
         }
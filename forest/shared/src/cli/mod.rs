@@ -54,6 +54,13 @@ pub struct CliOpts {
     /// The genesis CAR file
     #[arg(short, long)]
     pub genesis: Option<String>,
+    /// A JSON template to build the genesis of a devnet from, in the format of
+    /// `lotus-seed genesis new`
+    #[arg(long)]
+    pub genesis_template: Option<PathBuf>,
+    /// Interval in seconds between the blocks of a devnet
+    #[arg(long)]
+    pub block_delay: Option<u64>,
     /// Allow RPC to be active or not (default: true)
     #[arg(short, long)]
     pub rpc: Option<bool>,
@@ -103,6 +110,9 @@ pub struct CliOpts {
     /// Encrypt the key-store (default: true)
     #[arg(long)]
     pub encrypt_keystore: Option<bool>,
+    /// Unix socket of a remote signer holding the wallet keys
+    #[arg(long)]
+    pub remote_signer: Option<PathBuf>,
     /// Choose network chain to sync to
     #[arg(long, default_value = "mainnet")]
     pub chain: String,
@@ -146,24 +156,50 @@ pub struct CliOpts {
 impl CliOpts {
     pub fn to_config(&self) -> Result<(Config, Option<ConfigPath>), anyhow::Error> {
         let path = find_config_path(self);
-        let mut cfg: Config = match &path {
+        let (mut cfg, sample_size_configured): (Config, bool) = match &path {
             Some(path) => {
                 // Read from config file
                 let toml = read_file_to_string(path.to_path_buf())?;
+                let sample_size_configured = toml::from_str::<toml::Value>(&toml)?
+                    .get("sync")
+                    .and_then(|sync| sync.get("tipset_sample_size"))
+                    .is_some();
                 // Parse and return the configuration file
-                read_toml(&toml)?
+                (read_toml(&toml)?, sample_size_configured)
             }
-            None => Config::default(),
+            None => (Config::default(), false),
         };
 
-        if self.chain == "calibnet" {
+        match self.chain.as_str() {
             // override the chain configuration
-            cfg.chain = Arc::new(ChainConfig::calibnet());
+            "calibnet" => cfg.chain = Arc::new(ChainConfig::calibnet()),
+            // keep a devnet configured in the file, e.g. with other heights
+            "devnet" if cfg.chain.name != "devnet" => {
+                cfg.chain = Arc::new(ChainConfig::devnet());
+            }
+            _ => {}
+        }
+        // A devnet may consist of a single node, which can't sample the heads
+        // of its peers, so it doesn't sample unless configured to.
+        if cfg.chain.name == "devnet" && !sample_size_configured {
+            cfg.sync.tipset_sample_size = 0;
+        }
+        if let Some(block_delay) = self.block_delay {
+            anyhow::ensure!(
+                cfg.chain.name == "devnet",
+                "The block delay can only be set on a devnet"
+            );
+            Arc::get_mut(&mut cfg.chain)
+                .expect("chain configuration is not shared yet")
+                .block_delay_secs = block_delay;
         }
 
         if let Some(genesis_file) = &self.genesis {
             cfg.client.genesis_file = Some(genesis_file.to_owned());
         }
+        if let Some(genesis_template) = &self.genesis_template {
+            cfg.client.genesis_template = Some(genesis_template.clone());
+        }
         if self.rpc.unwrap_or(cfg.client.enable_rpc) {
             cfg.client.enable_rpc = true;
             if let Some(rpc_address) = self.rpc_address {
@@ -218,6 +254,9 @@ impl CliOpts {
         if let Some(encrypt_keystore) = self.encrypt_keystore {
             cfg.client.encrypt_keystore = encrypt_keystore;
         }
+        if let Some(remote_signer) = &self.remote_signer {
+            cfg.client.remote_signer = Some(remote_signer.clone());
+        }
 
         Ok((cfg, path))
     }
@@ -347,6 +386,44 @@ mod tests {
 
     use super::*;
 
+    fn devnet_config(toml: &str, args: &[&str]) -> Config {
+        let mut config_file = tempfile::Builder::new().tempfile().unwrap();
+        std::io::Write::write_all(&mut config_file, toml.as_bytes()).unwrap();
+        let config_path = config_file.path().display().to_string();
+        let opts = CliOpts::parse_from(
+            ["forest", "--config", config_path.as_str()]
+                .iter()
+                .chain(args),
+        );
+        opts.to_config().unwrap().0
+    }
+
+    #[test]
+    fn devnet_does_not_sample_heads_by_default() {
+        // chosen on the command line
+        let config = devnet_config("", &["--chain", "devnet"]);
+        assert_eq!(config.sync.tipset_sample_size, 0);
+        // configured in the file
+        let config = devnet_config("[chain]\nname = \"devnet\"\n", &[]);
+        assert_eq!(config.sync.tipset_sample_size, 0);
+        let config = devnet_config("[chain]\nname = \"devnet\"\n", &["--chain", "devnet"]);
+        assert_eq!(config.sync.tipset_sample_size, 0);
+        // other chains still sample
+        let config = devnet_config("", &["--chain", "calibnet"]);
+        assert_eq!(
+            config.sync.tipset_sample_size,
+            Config::default().sync.tipset_sample_size
+        );
+    }
+
+    #[test]
+    fn devnet_samples_heads_when_configured() {
+        let config = devnet_config("[sync]\ntipset_sample_size = 1\n", &["--chain", "devnet"]);
+        assert_eq!(config.sync.tipset_sample_size, 1);
+        let config = devnet_config("", &["--chain", "devnet", "--tipset-sample-size", "2"]);
+        assert_eq!(config.sync.tipset_sample_size, 2);
+    }
+
     #[test]
     fn to_size_string_valid_input() {
         let cases = [
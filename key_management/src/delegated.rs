@@ -0,0 +1,372 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Delegated keys: `secp256k1` keys controlling `f410` addresses, which are
+//! the Filecoin form of Ethereum addresses managed by the Ethereum Address
+//! Manager actor.
+
+use std::str::FromStr;
+
+use forest_shim::{
+    address::{Address, Payload},
+    crypto::{Signature, SignatureType},
+};
+use num_bigint::BigUint;
+use sha3::{Digest, Keccak256};
+
+use super::{errors::Error, wallet_helpers};
+
+/// Actor ID of the Ethereum Address Manager, namespace of `f410` addresses.
+pub const EAM_NAMESPACE: u64 = 10;
+
+/// Length of an Ethereum address.
+pub const ETH_ADDRESS_LEN: usize = 20;
+
+/// Return the Keccak-256 hash of some data
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// An Ethereum address, i.e. the last 20 bytes of the Keccak-256 hash of an
+/// uncompressed `secp256k1` public key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EthAddress(pub [u8; ETH_ADDRESS_LEN]);
+
+impl EthAddress {
+    /// Return the Ethereum address of an uncompressed `secp256k1` public key
+    pub fn from_public_key(public_key: &[u8]) -> Result<Self, Error> {
+        if public_key.len() != 65 || public_key[0] != 0x04 {
+            return Err(Error::Other(
+                "Expected an uncompressed secp256k1 public key".to_string(),
+            ));
+        }
+        let hash = keccak256(&public_key[1..]);
+        let mut addr = [0; ETH_ADDRESS_LEN];
+        addr.copy_from_slice(&hash[32 - ETH_ADDRESS_LEN..]);
+        Ok(Self(addr))
+    }
+
+    /// Return the Ethereum address of an `f410` address, if it is one
+    pub fn from_filecoin_address(addr: &Address) -> Option<Self> {
+        match addr.into_payload() {
+            Payload::Delegated(delegated)
+                if delegated.namespace() == EAM_NAMESPACE
+                    && delegated.subaddress().len() == ETH_ADDRESS_LEN =>
+            {
+                let mut eth_addr = [0; ETH_ADDRESS_LEN];
+                eth_addr.copy_from_slice(delegated.subaddress());
+                Some(Self(eth_addr))
+            }
+            _ => None,
+        }
+    }
+
+    /// Return the `f410` address of this Ethereum address
+    pub fn to_filecoin_address(&self) -> Result<Address, Error> {
+        Address::new_delegated(EAM_NAMESPACE, &self.0).map_err(|e| Error::Other(e.to_string()))
+    }
+}
+
+impl FromStr for EthAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::Other(format!("Invalid Ethereum address: {s}"));
+        let hex_addr = s.strip_prefix("0x").ok_or_else(invalid)?;
+        let bytes = hex::decode(hex_addr).map_err(|_| invalid())?;
+        Ok(Self(bytes.try_into().map_err(|_| invalid())?))
+    }
+}
+
+impl std::fmt::Display for EthAddress {
+    /// Format the address with the mixed-case checksum of EIP-55
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hex_addr = hex::encode(self.0);
+        let hash = keccak256(hex_addr.as_bytes());
+        write!(f, "0x")?;
+        for (i, c) in hex_addr.chars().enumerate() {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if nibble >= 8 {
+                write!(f, "{}", c.to_ascii_uppercase())?;
+            } else {
+                write!(f, "{c}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A legacy Ethereum transaction, signed with the replay protection of
+/// EIP-155.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Eip155Transaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub gas_price: BigUint,
+    pub gas_limit: u64,
+    /// Recipient of the transaction, `None` for contract creations
+    pub to: Option<EthAddress>,
+    pub value: BigUint,
+    pub data: Vec<u8>,
+}
+
+impl Eip155Transaction {
+    fn rlp_fields(&self) -> Vec<Vec<u8>> {
+        vec![
+            rlp::encode_uint(&BigUint::from(self.nonce)),
+            rlp::encode_uint(&self.gas_price),
+            rlp::encode_uint(&BigUint::from(self.gas_limit)),
+            rlp::encode_bytes(self.to.as_ref().map(|to| &to.0[..]).unwrap_or_default()),
+            rlp::encode_uint(&self.value),
+            rlp::encode_bytes(&self.data),
+        ]
+    }
+
+    /// Return the payload to sign: the RLP encoding of the transaction
+    /// followed by the chain ID and two empty fields
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut fields = self.rlp_fields();
+        fields.push(rlp::encode_uint(&BigUint::from(self.chain_id)));
+        fields.push(rlp::encode_bytes(&[]));
+        fields.push(rlp::encode_bytes(&[]));
+        rlp::encode_list(&fields)
+    }
+
+    /// Return the raw signed transaction given a delegated signature of
+    /// [`Eip155Transaction::signing_payload`]
+    pub fn encode_signed(&self, signature: &Signature) -> Result<Vec<u8>, Error> {
+        let bytes = signature.bytes();
+        if signature.signature_type() != SignatureType::Delegated || bytes.len() != 65 {
+            return Err(Error::Other(
+                "Expected a 65 bytes delegated signature".to_string(),
+            ));
+        }
+        let v = u64::from(bytes[64]) + 35 + 2 * self.chain_id;
+        let mut fields = self.rlp_fields();
+        fields.push(rlp::encode_uint(&BigUint::from(v)));
+        fields.push(rlp::encode_uint(&BigUint::from_bytes_be(&bytes[..32])));
+        fields.push(rlp::encode_uint(&BigUint::from_bytes_be(&bytes[32..64])));
+        Ok(rlp::encode_list(&fields))
+    }
+
+    /// Sign the transaction with a delegated private key and return the raw
+    /// signed transaction
+    pub fn sign(&self, private_key: &[u8]) -> Result<Vec<u8>, Error> {
+        let signature = wallet_helpers::sign(
+            SignatureType::Delegated,
+            private_key,
+            &self.signing_payload(),
+        )?;
+        self.encode_signed(&signature)
+    }
+}
+
+/// An EIP-1559 Ethereum transaction, the form in which messages signed with
+/// delegated keys are signed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Eip1559Transaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: BigUint,
+    pub max_fee_per_gas: BigUint,
+    pub gas_limit: u64,
+    /// Recipient of the transaction, `None` for contract creations
+    pub to: Option<EthAddress>,
+    pub value: BigUint,
+    pub data: Vec<u8>,
+}
+
+impl Eip1559Transaction {
+    /// Type of the transaction envelope of EIP-2718
+    const TYPE: u8 = 2;
+
+    fn rlp_fields(&self) -> Vec<Vec<u8>> {
+        vec![
+            rlp::encode_uint(&BigUint::from(self.chain_id)),
+            rlp::encode_uint(&BigUint::from(self.nonce)),
+            rlp::encode_uint(&self.max_priority_fee_per_gas),
+            rlp::encode_uint(&self.max_fee_per_gas),
+            rlp::encode_uint(&BigUint::from(self.gas_limit)),
+            rlp::encode_bytes(self.to.as_ref().map(|to| &to.0[..]).unwrap_or_default()),
+            rlp::encode_uint(&self.value),
+            rlp::encode_bytes(&self.data),
+            // Empty access list
+            rlp::encode_list(&[]),
+        ]
+    }
+
+    /// Return the payload to sign: the transaction type followed by the RLP
+    /// encoding of the transaction
+    pub fn signing_payload(&self) -> Vec<u8> {
+        [&[Self::TYPE][..], &rlp::encode_list(&self.rlp_fields())].concat()
+    }
+
+    /// Return the raw signed transaction given a delegated signature of
+    /// [`Eip1559Transaction::signing_payload`]
+    pub fn encode_signed(&self, signature: &Signature) -> Result<Vec<u8>, Error> {
+        let bytes = signature.bytes();
+        if signature.signature_type() != SignatureType::Delegated || bytes.len() != 65 {
+            return Err(Error::Other(
+                "Expected a 65 bytes delegated signature".to_string(),
+            ));
+        }
+        let mut fields = self.rlp_fields();
+        fields.push(rlp::encode_uint(&BigUint::from(bytes[64])));
+        fields.push(rlp::encode_uint(&BigUint::from_bytes_be(&bytes[..32])));
+        fields.push(rlp::encode_uint(&BigUint::from_bytes_be(&bytes[32..64])));
+        Ok([&[Self::TYPE][..], &rlp::encode_list(&fields)].concat())
+    }
+
+    /// Return the hash of the signed transaction, which identifies it on
+    /// Ethereum
+    pub fn signed_hash(&self, signature: &Signature) -> Result<[u8; 32], Error> {
+        Ok(keccak256(&self.encode_signed(signature)?))
+    }
+}
+
+/// Minimal RLP encoder, enough for legacy and EIP-1559 transactions.
+mod rlp {
+    use num_bigint::BigUint;
+
+    fn encode_header(offset: u8, len: usize, out: &mut Vec<u8>) {
+        if len < 56 {
+            out.push(offset + len as u8);
+        } else {
+            let len_bytes = len.to_be_bytes();
+            let len_bytes = &len_bytes[len_bytes.iter().take_while(|b| **b == 0).count()..];
+            out.push(offset + 55 + len_bytes.len() as u8);
+            out.extend_from_slice(len_bytes);
+        }
+    }
+
+    pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+        if bytes.len() == 1 && bytes[0] < 0x80 {
+            return bytes.to_vec();
+        }
+        let mut out = Vec::with_capacity(bytes.len() + 9);
+        encode_header(0x80, bytes.len(), &mut out);
+        out.extend_from_slice(bytes);
+        out
+    }
+
+    pub fn encode_uint(value: &BigUint) -> Vec<u8> {
+        if *value == BigUint::default() {
+            encode_bytes(&[])
+        } else {
+            encode_bytes(&value.to_bytes_be())
+        }
+    }
+
+    pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+        let payload = items.concat();
+        let mut out = Vec::with_capacity(payload.len() + 9);
+        encode_header(0xc0, payload.len(), &mut out);
+        out.extend(payload);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_key, Key, KeyInfo};
+
+    #[test]
+    fn eip55_checksum() {
+        for addr in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            assert_eq!(addr.parse::<EthAddress>().unwrap().to_string(), addr);
+        }
+        assert!("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse::<EthAddress>()
+            .is_err());
+        assert!("0x5aAeb6".parse::<EthAddress>().is_err());
+    }
+
+    #[test]
+    fn delegated_key_addresses() {
+        let key = generate_key(SignatureType::Delegated).unwrap();
+        let eth_addr = EthAddress::from_filecoin_address(&key.address).unwrap();
+        assert_eq!(
+            eth_addr,
+            EthAddress::from_public_key(&key.public_key).unwrap()
+        );
+        assert_eq!(eth_addr.to_filecoin_address().unwrap(), key.address);
+        assert!(key.address.to_string().starts_with("f410f"));
+    }
+
+    #[test]
+    fn eip155_example() {
+        // Example transaction of EIP-155
+        let private_key = [0x46; 32].to_vec();
+        let key = Key::try_from(KeyInfo::new(SignatureType::Delegated, private_key)).unwrap();
+        assert_eq!(
+            EthAddress::from_filecoin_address(&key.address)
+                .unwrap()
+                .to_string(),
+            "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F"
+        );
+
+        let tx = Eip155Transaction {
+            chain_id: 1,
+            nonce: 9,
+            gas_price: BigUint::from(20_000_000_000u64),
+            gas_limit: 21000,
+            to: Some(
+                "0x3535353535353535353535353535353535353535"
+                    .parse()
+                    .unwrap(),
+            ),
+            value: BigUint::from(1_000_000_000_000_000_000u64),
+            data: vec![],
+        };
+        assert_eq!(
+            hex::encode(tx.signing_payload()),
+            "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080"
+        );
+        assert_eq!(
+            hex::encode(tx.sign(key.key_info.private_key()).unwrap()),
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+    }
+
+    #[test]
+    fn eip1559_encoding() {
+        let tx = Eip1559Transaction {
+            chain_id: 1,
+            nonce: 0,
+            max_priority_fee_per_gas: BigUint::from(1u64),
+            max_fee_per_gas: BigUint::from(2u64),
+            gas_limit: 21000,
+            to: Some(
+                "0x3535353535353535353535353535353535353535"
+                    .parse()
+                    .unwrap(),
+            ),
+            value: BigUint::default(),
+            data: vec![],
+        };
+        assert_eq!(
+            hex::encode(tx.signing_payload()),
+            "02df01800102825208943535353535353535353535353535353535353535808080c0"
+        );
+
+        let key = generate_key(SignatureType::Delegated).unwrap();
+        let signature = wallet_helpers::sign(
+            SignatureType::Delegated,
+            key.key_info.private_key(),
+            &tx.signing_payload(),
+        )
+        .unwrap();
+        let signed = tx.encode_signed(&signature).unwrap();
+        assert_eq!(signed[0], 2);
+        assert_eq!(tx.signed_hash(&signature).unwrap(), keccak256(&signed));
+        assert!(tx
+            .encode_signed(&Signature::new_secp256k1(signature.bytes().to_vec()))
+            .is_err());
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Deterministic wallets: BIP-39 mnemonics and hierarchical key derivation.
+//! `secp256k1` keys are derived with BIP-32 along `m/44'/461'/...` paths
+//! (461 being the Filecoin coin type) and BLS keys with EIP-2333 along
+//! `m/12381/461/...` paths.
+
+use std::{fmt, str::FromStr};
+
+use bip39::Mnemonic;
+use forest_shim::crypto::SignatureType;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use libsecp256k1::{PublicKey as SecpPublic, SecretKey as SecpPrivate};
+use num_bigint::BigUint;
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256, Sha512};
+
+use super::{errors::Error, KeyInfo};
+
+/// Filecoin coin type, as registered in SLIP-44.
+pub const FILECOIN_COIN_TYPE: u32 = 461;
+
+/// BIP-44 purpose of `secp256k1` derivation paths.
+const BIP44_PURPOSE: u32 = 44;
+
+/// EIP-2334 purpose of BLS derivation paths.
+const EIP2334_PURPOSE: u32 = 12381;
+
+/// Flag of hardened BIP-32 child indices.
+const HARDENED: u32 = 0x8000_0000;
+
+/// Number of words of generated mnemonics.
+const MNEMONIC_WORDS: usize = 24;
+
+/// A derivation path, e.g. `m/44'/461'/0'/0/0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// Return the default path of the `index`-th key of the given type:
+    /// `m/44'/461'/0'/0/<index>` for `secp256k1` and `m/12381/461/0/<index>`
+    /// for BLS
+    pub fn default_for(sig_type: SignatureType, index: u32) -> Result<Self, Error> {
+        match sig_type {
+            SignatureType::Secp256k1 => Ok(Self(vec![
+                BIP44_PURPOSE | HARDENED,
+                FILECOIN_COIN_TYPE | HARDENED,
+                HARDENED,
+                0,
+                index,
+            ])),
+            SignatureType::BLS => Ok(Self(vec![EIP2334_PURPOSE, FILECOIN_COIN_TYPE, 0, index])),
+            SignatureType::Delegated => Err(Error::Other(
+                "Key derivation is not supported for delegated keys".to_string(),
+            )),
+        }
+    }
+
+    /// Return the type of the keys derived along this path, according to its
+    /// purpose
+    pub fn signature_type(&self) -> Result<SignatureType, Error> {
+        match self.0.first() {
+            Some(&purpose) if purpose == BIP44_PURPOSE | HARDENED => Ok(SignatureType::Secp256k1),
+            Some(&EIP2334_PURPOSE) => Ok(SignatureType::BLS),
+            _ => Err(Error::Other(format!(
+                "Unsupported derivation path {self}, expected m/{BIP44_PURPOSE}'/... or m/{EIP2334_PURPOSE}/..."
+            ))),
+        }
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::Other(format!("Invalid derivation path: {s}"));
+        let mut segments = s.split('/');
+        if segments.next() != Some("m") {
+            return Err(invalid());
+        }
+        segments
+            .map(|segment| {
+                let (index, hardened) = match segment.strip_suffix('\'') {
+                    Some(index) => (index, true),
+                    None => (segment, false),
+                };
+                let index: u32 = index.parse().map_err(|_| invalid())?;
+                if index & HARDENED != 0 {
+                    return Err(invalid());
+                }
+                Ok(if hardened { index | HARDENED } else { index })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            if index & HARDENED != 0 {
+                write!(f, "/{}'", index & !HARDENED)?;
+            } else {
+                write!(f, "/{index}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Generate a new random 24 words mnemonic
+pub fn generate_mnemonic() -> Result<String, Error> {
+    let mut entropy = [0u8; MNEMONIC_WORDS / 3 * 4];
+    OsRng.fill_bytes(&mut entropy);
+    let mnemonic = Mnemonic::from_entropy(&entropy).map_err(|e| Error::Other(e.to_string()))?;
+    Ok(mnemonic.to_string())
+}
+
+/// Return the BIP-39 seed of a mnemonic, protected by an optional passphrase
+pub fn mnemonic_to_seed(mnemonic: &str, passphrase: &str) -> Result<[u8; 64], Error> {
+    let mnemonic = Mnemonic::parse_normalized(mnemonic)
+        .map_err(|e| Error::Other(format!("Invalid mnemonic: {e}")))?;
+    Ok(mnemonic.to_seed(passphrase))
+}
+
+/// Derive the key at the given path from a seed. The key type is given by the
+/// purpose of the path.
+pub fn derive_key(seed: &[u8], path: &DerivationPath) -> Result<KeyInfo, Error> {
+    let sig_type = path.signature_type()?;
+    let private_key = match sig_type {
+        SignatureType::Secp256k1 => derive_secp256k1(seed, path)?.serialize().to_vec(),
+        SignatureType::BLS => {
+            // BLS private keys are stored little-endian
+            let mut private_key = derive_bls(seed, path)?.to_bytes_le();
+            private_key.resize(32, 0);
+            private_key
+        }
+        SignatureType::Delegated => unreachable!("derivation paths only map to secp256k1 or BLS"),
+    };
+    Ok(KeyInfo::new(sig_type, private_key))
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC takes keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// BIP-32 derivation of a `secp256k1` private key
+fn derive_secp256k1(seed: &[u8], path: &DerivationPath) -> Result<SecpPrivate, Error> {
+    let invalid = |_| Error::Other("Derived an invalid secp256k1 key, use another index".into());
+
+    let master = hmac_sha512(b"Bitcoin seed", seed);
+    let mut key = SecpPrivate::parse_slice(&master[..32]).map_err(invalid)?;
+    let mut chain_code: [u8; 32] = master[32..].try_into().expect("slice of 32 bytes");
+
+    for index in &path.0 {
+        let mut data = Vec::with_capacity(37);
+        if index & HARDENED != 0 {
+            data.push(0);
+            data.extend_from_slice(&key.serialize());
+        } else {
+            data.extend_from_slice(&SecpPublic::from_secret_key(&key).serialize_compressed());
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+
+        let child = hmac_sha512(&chain_code, &data);
+        let tweak = SecpPrivate::parse_slice(&child[..32]).map_err(invalid)?;
+        key.tweak_add_assign(&tweak).map_err(invalid)?;
+        chain_code.copy_from_slice(&child[32..]);
+    }
+    Ok(key)
+}
+
+/// Order of the BLS12-381 scalar field.
+fn bls_curve_order() -> BigUint {
+    BigUint::parse_bytes(
+        b"73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001",
+        16,
+    )
+    .expect("valid hexadecimal constant")
+}
+
+/// EIP-2333 derivation of a BLS private key
+fn derive_bls(seed: &[u8], path: &DerivationPath) -> Result<BigUint, Error> {
+    if path.0.iter().any(|index| index & HARDENED != 0) {
+        return Err(Error::Other(format!(
+            "BLS derivation path {path} cannot contain hardened indices"
+        )));
+    }
+    if seed.len() < 32 {
+        return Err(Error::Other("Seed must be at least 32 bytes".into()));
+    }
+    let mut key = hkdf_mod_r(seed);
+    for index in &path.0 {
+        key = hkdf_mod_r(&parent_sk_to_lamport_pk(&key, *index));
+    }
+    Ok(key)
+}
+
+fn hkdf_mod_r(ikm: &[u8]) -> BigUint {
+    const L: usize = 48;
+    let r = bls_curve_order();
+    let mut salt = Sha256::digest(b"BLS-SIG-KEYGEN-SALT-").to_vec();
+    let mut ikm = ikm.to_vec();
+    ikm.push(0);
+    loop {
+        let mut okm = [0u8; L];
+        Hkdf::<Sha256>::new(Some(&salt), &ikm)
+            .expand(&(L as u16).to_be_bytes(), &mut okm)
+            .expect("valid HKDF output length");
+        let key = BigUint::from_bytes_be(&okm) % &r;
+        if key != BigUint::default() {
+            return key;
+        }
+        salt = Sha256::digest(&salt).to_vec();
+    }
+}
+
+fn ikm_to_lamport_sk(ikm: &[u8], salt: &[u8]) -> Vec<u8> {
+    let mut okm = vec![0u8; 32 * 255];
+    Hkdf::<Sha256>::new(Some(salt), ikm)
+        .expand(&[], &mut okm)
+        .expect("valid HKDF output length");
+    okm
+}
+
+fn parent_sk_to_lamport_pk(parent_sk: &BigUint, index: u32) -> [u8; 32] {
+    let salt = index.to_be_bytes();
+    let mut ikm = parent_sk.to_bytes_be();
+    while ikm.len() < 32 {
+        ikm.insert(0, 0);
+    }
+    let not_ikm: Vec<u8> = ikm.iter().map(|byte| !byte).collect();
+
+    let mut lamport_pk = Sha256::new();
+    for lamport_sk in [
+        ikm_to_lamport_sk(&ikm, &salt),
+        ikm_to_lamport_sk(&not_ikm, &salt),
+    ] {
+        for chunk in lamport_sk.chunks(32) {
+            lamport_pk.update(Sha256::digest(chunk));
+        }
+    }
+    lamport_pk.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{wallet_helpers, Key};
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn mnemonic_seed() {
+        // BIP-39 test vector
+        let seed = mnemonic_to_seed(MNEMONIC, "TREZOR").unwrap();
+        assert_eq!(hex::encode(seed), "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04");
+        assert!(mnemonic_to_seed("abandon abandon", "").is_err());
+
+        let generated = generate_mnemonic().unwrap();
+        assert_eq!(generated.split(' ').count(), MNEMONIC_WORDS);
+        assert!(mnemonic_to_seed(&generated, "").is_ok());
+    }
+
+    #[test]
+    fn derivation_path_round_trip() {
+        let path: DerivationPath = "m/44'/461'/0'/0/7".parse().unwrap();
+        assert_eq!(
+            path,
+            DerivationPath::default_for(SignatureType::Secp256k1, 7).unwrap()
+        );
+        assert_eq!(path.to_string(), "m/44'/461'/0'/0/7");
+        assert_eq!(path.signature_type().unwrap(), SignatureType::Secp256k1);
+
+        let path: DerivationPath = "m/12381/461/0/3".parse().unwrap();
+        assert_eq!(
+            path,
+            DerivationPath::default_for(SignatureType::BLS, 3).unwrap()
+        );
+        assert_eq!(path.signature_type().unwrap(), SignatureType::BLS);
+
+        for invalid in ["", "44'/461'", "m/x", "m/2147483648", "m/1''"] {
+            assert!(invalid.parse::<DerivationPath>().is_err(), "{invalid}");
+        }
+        assert!("m/0/1"
+            .parse::<DerivationPath>()
+            .unwrap()
+            .signature_type()
+            .is_err());
+    }
+
+    #[test]
+    fn bip32_test_vector() {
+        // BIP-32 test vector 1
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let path = "m/0'/1/2'/2/1000000000".parse().unwrap();
+        assert_eq!(
+            hex::encode(derive_secp256k1(&seed, &path).unwrap().serialize()),
+            "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8"
+        );
+    }
+
+    #[test]
+    fn eip2333_test_vector() {
+        // EIP-2333 test case 0
+        let seed = mnemonic_to_seed(MNEMONIC, "TREZOR").unwrap();
+        assert_eq!(
+            derive_bls(&seed, &"m".parse().unwrap())
+                .unwrap()
+                .to_string(),
+            "6083874454709270928345386274498605044986640685124978867557563392430687146096"
+        );
+        assert_eq!(
+            derive_bls(&seed, &"m/0".parse().unwrap())
+                .unwrap()
+                .to_string(),
+            "20397789859736650942317412262472558107875392172444076792671091975210932703118"
+        );
+    }
+
+    #[test]
+    fn derived_keys_are_usable() {
+        let seed = mnemonic_to_seed(MNEMONIC, "").unwrap();
+        for sig_type in [SignatureType::Secp256k1, SignatureType::BLS] {
+            let path = DerivationPath::default_for(sig_type, 0).unwrap();
+            let key = Key::try_from(derive_key(&seed, &path).unwrap()).unwrap();
+            assert_eq!(*key.key_info.key_type(), sig_type);
+            // Derivation is deterministic
+            assert_eq!(derive_key(&seed, &path).unwrap(), key.key_info);
+            let sig = wallet_helpers::sign(sig_type, key.key_info.private_key(), b"msg").unwrap();
+            assert!(sig.verify(b"msg", &key.address.into()).is_ok());
+        }
+    }
+}
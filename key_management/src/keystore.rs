@@ -4,7 +4,7 @@
 use std::{
     fmt::Display,
     fs::{self, create_dir, File},
-    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    io::{BufReader, ErrorKind, Read, Write},
     path::{Path, PathBuf},
 };
 
@@ -241,6 +241,21 @@ impl KeyStore {
 
                 let file_path = location.join(Path::new(ENCRYPTED_KEYSTORE_NAME));
 
+                // Don't start with an empty encrypted keystore next to the keys of a
+                // clear text one, they are only converted on request
+                let encrypted_is_empty = fs::metadata(&file_path)
+                    .map(|metadata| metadata.len() == 0)
+                    .unwrap_or(true);
+                if encrypted_is_empty && location.join(KEYSTORE_NAME).exists() {
+                    let keystore = KeyStore::new(KeyStoreConfig::Persistent(location.clone()))?;
+                    if !keystore.key_info.is_empty() {
+                        return Err(Error::Other(format!(
+                            "Found a clear text keystore at {:?} but encryption is enabled. Start the node with `--encrypt-keystore false` and run `forest-cli wallet keystore encrypt` to convert it",
+                            location.join(KEYSTORE_NAME)
+                        )));
+                    }
+                }
+
                 if !file_path.exists() {
                     File::create(file_path.clone())?;
                 }
@@ -331,63 +346,212 @@ impl KeyStore {
 
     pub fn flush(&self) -> anyhow::Result<()> {
         match &self.persistence {
-            Some(persistent_keystore) => {
-                let dir = persistent_keystore
-                    .file_path
-                    .parent()
-                    .ok_or_else(|| Error::Other("Invalid Path".to_string()))?;
-                fs::create_dir_all(dir)?;
-                let file = File::create(&persistent_keystore.file_path)?;
-
-                // Restrict permissions on files containing private keys
-                #[cfg(unix)]
-                forest_utils::io::set_user_perm(&file)?;
-
-                let mut writer = BufWriter::new(file);
-
-                match &self.encryption {
-                    Some(encrypted_keystore) => {
-                        // Flush For EncryptedKeyStore
-                        let data = serde_ipld_dagcbor::to_vec(&self.key_info).map_err(|e| {
-                            Error::Other(format!("failed to serialize and write key info: {e}"))
-                        })?;
-
-                        let encrypted_data =
-                            EncryptedKeyStore::encrypt(&encrypted_keystore.encryption_key, &data)?;
-                        let mut salt_vec = encrypted_keystore.salt.to_vec();
-                        salt_vec.extend(encrypted_data);
-                        writer.write_all(&salt_vec)?;
-
-                        Ok(())
-                    }
-                    None => {
-                        let mut key_info: HashMap<String, PersistentKeyInfo> = HashMap::new();
-                        for (key, value) in self.key_info.iter() {
-                            key_info.insert(
-                                key.to_string(),
-                                PersistentKeyInfo {
-                                    private_key: BASE64_STANDARD.encode(value.private_key.clone()),
-                                    key_type: value.key_type,
-                                },
-                            );
-                        }
+            Some(persistent_keystore) => self.write_to(&persistent_keystore.file_path),
+            None => {
+                // NoOp for MemKeyStore
+                Ok(())
+            }
+        }
+    }
 
-                        // Flush for PersistentKeyStore
-                        serde_json::to_writer_pretty(writer, &key_info).map_err(|e| {
-                            Error::Other(format!("failed to serialize and write key info: {e}"))
-                        })?;
+    /// Return whether or not the `KeyStore` is encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption.is_some()
+    }
 
-                        Ok(())
-                    }
+    /// Write a copy of the `KeyStore`, in the same format as the keystore file,
+    /// to the given path. The copy is only put in place once it has been read
+    /// back and verified.
+    pub fn backup(&self, path: &Path) -> anyhow::Result<()> {
+        self.write_to(path)
+    }
+
+    /// Re-encrypt the `KeyStore` with a key derived from a new passphrase and
+    /// a fresh salt
+    pub fn change_passphrase(
+        &mut self,
+        passphrase: &str,
+        new_passphrase: &str,
+    ) -> anyhow::Result<()> {
+        self.check_passphrase(passphrase)?;
+        let (salt, encryption_key) = EncryptedKeyStore::derive_key(new_passphrase, None)?;
+        let previous = self.encryption.replace(EncryptedKeyStore {
+            salt,
+            encryption_key,
+        });
+        if let Err(e) = self.flush() {
+            self.encryption = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Convert a clear text persistent `KeyStore` into an encrypted one. The
+    /// clear text file is removed once the encrypted one has been written.
+    pub fn encrypt(&mut self, passphrase: &str) -> anyhow::Result<()> {
+        anyhow::ensure!(!self.is_encrypted(), "Keystore is already encrypted");
+        let (salt, encryption_key) = EncryptedKeyStore::derive_key(passphrase, None)?;
+        self.convert(
+            ENCRYPTED_KEYSTORE_NAME,
+            Some(EncryptedKeyStore {
+                salt,
+                encryption_key,
+            }),
+        )
+    }
+
+    /// Convert an encrypted persistent `KeyStore` into a clear text one. The
+    /// encrypted file is removed once the clear text one has been written.
+    pub fn decrypt(&mut self, passphrase: &str) -> anyhow::Result<()> {
+        self.check_passphrase(passphrase)?;
+        self.convert(KEYSTORE_NAME, None)
+    }
+
+    fn convert(
+        &mut self,
+        file_name: &str,
+        encryption: Option<EncryptedKeyStore>,
+    ) -> anyhow::Result<()> {
+        let old_path = match &self.persistence {
+            Some(persistent_keystore) => persistent_keystore.file_path.clone(),
+            None => anyhow::bail!("In-memory keystore cannot be converted"),
+        };
+        let converted = Self {
+            key_info: self.key_info.clone(),
+            persistence: Some(PersistentKeyStore {
+                file_path: old_path.with_file_name(file_name),
+            }),
+            encryption,
+        };
+        converted.flush()?;
+        if let Err(e) = fs::remove_file(&old_path) {
+            if e.kind() != ErrorKind::NotFound {
+                return Err(e.into());
+            }
+        }
+        *self = converted;
+        Ok(())
+    }
+
+    fn check_passphrase(&self, passphrase: &str) -> anyhow::Result<()> {
+        let encrypted_keystore = self
+            .encryption
+            .as_ref()
+            .ok_or(EncryptedKeyStoreError::ConfigurationError)?;
+        let (_, encryption_key) =
+            EncryptedKeyStore::derive_key(passphrase, Some(encrypted_keystore.salt))?;
+        anyhow::ensure!(
+            encryption_key == encrypted_keystore.encryption_key,
+            "Incorrect passphrase"
+        );
+        Ok(())
+    }
+
+    /// Serialize the keys as they are stored in the keystore file: CBOR
+    /// encrypted with the keystore key and prepended with its salt, or clear
+    /// text JSON
+    fn encode(&self) -> anyhow::Result<Vec<u8>> {
+        match &self.encryption {
+            Some(encrypted_keystore) => {
+                let data = serde_ipld_dagcbor::to_vec(&self.key_info).map_err(|e| {
+                    Error::Other(format!("failed to serialize and write key info: {e}"))
+                })?;
+
+                let encrypted_data =
+                    EncryptedKeyStore::encrypt(&encrypted_keystore.encryption_key, &data)?;
+                let mut salt_vec = encrypted_keystore.salt.to_vec();
+                salt_vec.extend(encrypted_data);
+                Ok(salt_vec)
+            }
+            None => {
+                let mut key_info: HashMap<String, PersistentKeyInfo> = HashMap::new();
+                for (key, value) in self.key_info.iter() {
+                    key_info.insert(
+                        key.to_string(),
+                        PersistentKeyInfo {
+                            private_key: BASE64_STANDARD.encode(value.private_key.clone()),
+                            key_type: value.key_type,
+                        },
+                    );
                 }
+
+                serde_json::to_vec_pretty(&key_info).map_err(|e| {
+                    Error::Other(format!("failed to serialize and write key info: {e}")).into()
+                })
+            }
+        }
+    }
+
+    /// Deserialize keys written by [`KeyStore::encode`]
+    fn decode(&self, data: &[u8]) -> anyhow::Result<HashMap<String, KeyInfo>> {
+        match &self.encryption {
+            Some(encrypted_keystore) => {
+                anyhow::ensure!(
+                    data.len() > RECOMMENDED_SALT_LEN
+                        && data[..RECOMMENDED_SALT_LEN] == encrypted_keystore.salt,
+                    "Keystore salt mismatch"
+                );
+                let decrypted_data = EncryptedKeyStore::decrypt(
+                    &encrypted_keystore.encryption_key,
+                    &data[RECOMMENDED_SALT_LEN..],
+                )?;
+                serde_ipld_dagcbor::from_slice(&decrypted_data).map_err(map_err_to_anyhow)
             }
             None => {
-                // NoOp for MemKeyStore
-                Ok(())
+                let persisted_key_info: HashMap<String, PersistentKeyInfo> =
+                    serde_json::from_slice(data)?;
+                let mut key_info = HashMap::new();
+                for (key, value) in persisted_key_info.into_iter() {
+                    key_info.insert(
+                        key,
+                        KeyInfo {
+                            private_key: BASE64_STANDARD.decode(value.private_key)?,
+                            key_type: value.key_type,
+                        },
+                    );
+                }
+                Ok(key_info)
             }
         }
     }
 
+    /// Atomically write the keystore file to the given path. The content is
+    /// written to a temporary file first, read back and checked against the
+    /// keys in memory, and only then moved over the destination.
+    fn write_to(&self, path: &Path) -> anyhow::Result<()> {
+        let dir = path
+            .parent()
+            .ok_or_else(|| Error::Other("Invalid Path".to_string()))?;
+        fs::create_dir_all(dir)?;
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| Error::Other("Invalid Path".to_string()))?;
+        let mut tmp_file_name = file_name.to_owned();
+        tmp_file_name.push(".tmp");
+        let tmp_path = path.with_file_name(tmp_file_name);
+
+        let data = self.encode()?;
+        {
+            let mut file = File::create(&tmp_path)?;
+
+            // Restrict permissions on files containing private keys
+            #[cfg(unix)]
+            forest_utils::io::set_user_perm(&file)?;
+
+            file.write_all(&data)?;
+            file.sync_all()?;
+        }
+
+        let written = fs::read(&tmp_path)?;
+        if !matches!(self.decode(&written), Ok(key_info) if key_info == self.key_info) {
+            fs::remove_file(&tmp_path)?;
+            anyhow::bail!("Integrity check of the keystore written to {tmp_path:?} failed");
+        }
+        fs::rename(&tmp_path, path)?;
+
+        Ok(())
+    }
+
     /// Return all of the keys that are stored in the `KeyStore`
     pub fn list(&self) -> Vec<String> {
         self.key_info.keys().cloned().collect()
@@ -606,6 +770,103 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_change_passphrase() -> Result<()> {
+        let keystore_location = tempfile::tempdir()?.into_path();
+        let mut ks = KeyStore::new(KeyStoreConfig::Encrypted(
+            keystore_location.clone(),
+            PASSPHRASE.to_string(),
+        ))?;
+        let key = wallet::generate_key(SignatureType::Secp256k1)?;
+        ks.put(format!("wallet-{}", key.address), key.key_info)?;
+
+        ensure!(ks.change_passphrase("wrong", "new passphrase").is_err());
+        ks.change_passphrase(PASSPHRASE, "new passphrase")?;
+
+        ensure!(KeyStore::new(KeyStoreConfig::Encrypted(
+            keystore_location.clone(),
+            PASSPHRASE.to_string(),
+        ))
+        .is_err());
+        let ks_read = KeyStore::new(KeyStoreConfig::Encrypted(
+            keystore_location,
+            "new passphrase".to_string(),
+        ))?;
+        ensure!(ks == ks_read);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_keystore() -> Result<()> {
+        let keystore_location = tempfile::tempdir()?.into_path();
+        let mut ks = KeyStore::new(KeyStoreConfig::Persistent(keystore_location.clone()))?;
+        let key = wallet::generate_key(SignatureType::BLS)?;
+        ks.put(format!("wallet-{}", key.address), key.key_info)?;
+
+        ks.encrypt(PASSPHRASE)?;
+        ensure!(ks.is_encrypted());
+        ensure!(!keystore_location.join(KEYSTORE_NAME).exists());
+        let ks_read = KeyStore::new(KeyStoreConfig::Encrypted(
+            keystore_location.clone(),
+            PASSPHRASE.to_string(),
+        ))?;
+        ensure!(ks == ks_read);
+
+        ensure!(ks.decrypt("wrong").is_err());
+        ks.decrypt(PASSPHRASE)?;
+        ensure!(!ks.is_encrypted());
+        ensure!(!keystore_location.join(ENCRYPTED_KEYSTORE_NAME).exists());
+        let ks_read = KeyStore::new(KeyStoreConfig::Persistent(keystore_location))?;
+        ensure!(ks == ks_read);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clear_text_keystore_is_not_migrated() -> Result<()> {
+        let keystore_location = tempfile::tempdir()?.into_path();
+        let mut ks = KeyStore::new(KeyStoreConfig::Persistent(keystore_location.clone()))?;
+        let key = wallet::generate_key(SignatureType::Secp256k1)?;
+        let addr = format!("wallet-{}", key.address);
+        ks.put(addr.clone(), key.key_info.clone())?;
+
+        let err = KeyStore::new(KeyStoreConfig::Encrypted(
+            keystore_location.clone(),
+            PASSPHRASE.to_string(),
+        ))
+        .unwrap_err();
+        ensure!(err
+            .to_string()
+            .contains("forest-cli wallet keystore encrypt"));
+        ensure!(!keystore_location.join(ENCRYPTED_KEYSTORE_NAME).exists());
+        let ks_read = KeyStore::new(KeyStoreConfig::Persistent(keystore_location))?;
+        ensure!(ks_read.get(&addr)? == key.key_info);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_backup_keystore() -> Result<()> {
+        let keystore_location = tempfile::tempdir()?.into_path();
+        let mut ks = KeyStore::new(KeyStoreConfig::Encrypted(
+            keystore_location.clone(),
+            PASSPHRASE.to_string(),
+        ))?;
+        let key = wallet::generate_key(SignatureType::BLS)?;
+        ks.put(format!("wallet-{}", key.address), key.key_info)?;
+
+        let backup_location = tempfile::tempdir()?.into_path();
+        ks.backup(&backup_location.join(ENCRYPTED_KEYSTORE_NAME))?;
+        let ks_backup = KeyStore::new(KeyStoreConfig::Encrypted(
+            backup_location,
+            PASSPHRASE.to_string(),
+        ))?;
+        ensure!(ks.list() == ks_backup.list());
+
+        Ok(())
+    }
+
     impl quickcheck::Arbitrary for KeyInfo {
         fn arbitrary(g: &mut quickcheck::Gen) -> Self {
             let sigtype = g
@@ -1,12 +1,18 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+mod delegated;
 mod errors;
+mod hd_wallet;
 mod keystore;
+mod signer;
 mod wallet;
 mod wallet_helpers;
 
+pub use delegated::*;
 pub use errors::*;
+pub use hd_wallet::*;
 pub use keystore::*;
+pub use signer::*;
 pub use wallet::*;
 pub use wallet_helpers::*;
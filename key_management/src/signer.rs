@@ -0,0 +1,253 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use forest_json::signature::json::SignatureJson;
+use forest_shim::{address::Address, crypto::Signature};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{errors::Error, list_addrs, try_find, wallet_helpers, KeyStore};
+
+/// A backend able to produce signatures on behalf of a set of addresses.
+pub trait Signer: Send + Sync {
+    /// Return the addresses this signer holds keys for
+    fn list_addrs(&self) -> Result<Vec<Address>, Error>;
+
+    /// Return whether or not the signer holds the key of the given address
+    fn has_key(&self, addr: &Address) -> Result<bool, Error>;
+
+    /// Return the resultant `Signature` after signing a given message
+    fn sign(&self, addr: &Address, msg: &[u8]) -> Result<Signature, Error>;
+}
+
+impl Signer for KeyStore {
+    fn list_addrs(&self) -> Result<Vec<Address>, Error> {
+        list_addrs(self)
+    }
+
+    fn has_key(&self, addr: &Address) -> Result<bool, Error> {
+        Ok(try_find(addr, self).is_ok())
+    }
+
+    fn sign(&self, addr: &Address, msg: &[u8]) -> Result<Signature, Error> {
+        let key_info = try_find(addr, self).map_err(|_| Error::KeyNotExists)?;
+        wallet_helpers::sign(*key_info.key_type(), key_info.private_key(), msg)
+    }
+}
+
+pub const REMOTE_SIGNER_LIST: &str = "Filecoin.WalletList";
+pub const REMOTE_SIGNER_HAS: &str = "Filecoin.WalletHas";
+pub const REMOTE_SIGNER_SIGN: &str = "Filecoin.WalletSign";
+
+/// Default time to wait for the remote signer to answer a request.
+const REMOTE_SIGNER_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize)]
+struct RemoteRequest<'a, P> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    params: P,
+}
+
+#[derive(Deserialize)]
+struct RemoteResponse {
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<RemoteError>,
+}
+
+#[derive(Deserialize)]
+struct RemoteError {
+    code: i64,
+    message: String,
+}
+
+/// Signer forwarding requests to an external wallet process, so that private
+/// keys never have to be loaded by the node.
+///
+/// Every request opens a new connection to the Unix socket of the remote
+/// signer and sends a single newline-delimited JSON-RPC 2.0 request, using the
+/// same method names and parameters as the `Filecoin.Wallet*` API:
+/// - `Filecoin.WalletList` takes no parameters and returns a list of addresses
+/// - `Filecoin.WalletHas` takes an address and returns a boolean
+/// - `Filecoin.WalletSign` takes an address and a base64 encoded message and
+///   returns a signature
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    socket_path: PathBuf,
+    timeout: Duration,
+}
+
+impl RemoteSigner {
+    /// Return a signer connecting to the Unix socket at the given path
+    pub fn new(socket_path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: socket_path.into(),
+            timeout: REMOTE_SIGNER_TIMEOUT,
+        }
+    }
+
+    /// Set the time to wait for the remote signer to answer a request
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Return the path of the Unix socket of the remote signer
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    fn call<P: Serialize, R: DeserializeOwned>(&self, method: &str, params: P) -> Result<R, Error> {
+        let mut stream = UnixStream::connect(&self.socket_path).map_err(|e| {
+            Error::Other(format!(
+                "Could not connect to remote signer at {}: {e}",
+                self.socket_path.display()
+            ))
+        })?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        let mut request = serde_json::to_vec(&RemoteRequest {
+            jsonrpc: "2.0",
+            id: 1,
+            method,
+            params,
+        })
+        .map_err(|e| Error::Other(e.to_string()))?;
+        request.push(b'\n');
+        stream.write_all(&request)?;
+
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line)?;
+        let response: RemoteResponse = serde_json::from_str(&line)
+            .map_err(|e| Error::Other(format!("Invalid response from remote signer: {e}")))?;
+        if let Some(RemoteError { code, message }) = response.error {
+            return Err(Error::Other(format!(
+                "Remote signer error {code}: {message}"
+            )));
+        }
+        serde_json::from_value(response.result.unwrap_or(Value::Null))
+            .map_err(|e| Error::Other(format!("Invalid response from remote signer: {e}")))
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn list_addrs(&self) -> Result<Vec<Address>, Error> {
+        let addrs: Vec<String> = self.call(REMOTE_SIGNER_LIST, [(); 0])?;
+        addrs
+            .iter()
+            .map(|addr| {
+                Address::from_str(addr).map_err(|e| {
+                    Error::Other(format!("Invalid address {addr} from remote signer: {e}"))
+                })
+            })
+            .collect()
+    }
+
+    fn has_key(&self, addr: &Address) -> Result<bool, Error> {
+        self.call(REMOTE_SIGNER_HAS, (addr.to_string(),))
+    }
+
+    fn sign(&self, addr: &Address, msg: &[u8]) -> Result<Signature, Error> {
+        let SignatureJson(sig) = self.call(
+            REMOTE_SIGNER_SIGN,
+            (addr.to_string(), BASE64_STANDARD.encode(msg)),
+        )?;
+        Ok(sig)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::net::UnixListener, thread};
+
+    use forest_json::signature::json::SignatureJsonRef;
+    use forest_shim::crypto::SignatureType;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{generate_key, import, KeyStoreConfig};
+
+    /// Serve a single request from the socket using the given key store, as an
+    /// external wallet process would.
+    fn serve_one(listener: &UnixListener, keystore: &KeyStore) {
+        let (stream, _) = listener.accept().unwrap();
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        let request: Value = serde_json::from_str(&line).unwrap();
+        let params = &request["params"];
+        let address = |i: usize| Address::from_str(params[i].as_str().unwrap()).unwrap();
+        let response = match request["method"].as_str().unwrap() {
+            REMOTE_SIGNER_LIST => json!({
+                "result": list_addrs(keystore)
+                    .unwrap()
+                    .iter()
+                    .map(|addr| addr.to_string())
+                    .collect::<Vec<_>>()
+            }),
+            REMOTE_SIGNER_HAS => {
+                json!({ "result": Signer::has_key(keystore, &address(0)).unwrap() })
+            }
+            REMOTE_SIGNER_SIGN => {
+                let msg = BASE64_STANDARD.decode(params[1].as_str().unwrap()).unwrap();
+                match Signer::sign(keystore, &address(0), &msg) {
+                    Ok(sig) => json!({ "result": SignatureJsonRef(&sig) }),
+                    Err(e) => json!({ "error": { "code": 1, "message": e.to_string() } }),
+                }
+            }
+            method => panic!("unexpected method {method}"),
+        };
+        let mut stream = stream;
+        writeln!(stream, "{response}").unwrap();
+    }
+
+    #[test]
+    fn remote_signer_forwards_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("signer.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        let mut keystore = KeyStore::new(KeyStoreConfig::Memory).unwrap();
+        let key = generate_key(SignatureType::Secp256k1).unwrap();
+        let addr = import(key.key_info, &mut keystore).unwrap();
+        let unknown = generate_key(SignatureType::BLS).unwrap().address;
+        let msg = b"hardened host";
+        let expected = Signer::sign(&keystore, &addr, msg).unwrap();
+
+        let server = thread::spawn(move || {
+            for _ in 0..5 {
+                serve_one(&listener, &keystore);
+            }
+        });
+
+        let signer = RemoteSigner::new(&socket_path).with_timeout(Duration::from_secs(5));
+        assert_eq!(signer.list_addrs().unwrap(), vec![addr]);
+        assert!(signer.has_key(&addr).unwrap());
+        assert!(!signer.has_key(&unknown).unwrap());
+        assert_eq!(signer.sign(&addr, msg).unwrap(), expected);
+        assert!(signer.sign(&unknown, msg).is_err());
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn remote_signer_unavailable() {
+        let dir = tempfile::tempdir().unwrap();
+        let signer = RemoteSigner::new(dir.path().join("missing.sock"));
+        let addr = generate_key(SignatureType::Secp256k1).unwrap().address;
+        assert!(signer.has_key(&addr).is_err());
+        assert!(signer.sign(&addr, b"msg").is_err());
+    }
+}
@@ -10,7 +10,7 @@ use forest_utils::encoding::blake2b_256;
 use libsecp256k1::{Message as SecpMessage, PublicKey as SecpPublic, SecretKey as SecpPrivate};
 use rand::rngs::OsRng;
 
-use super::errors::Error;
+use super::{errors::Error, keccak256, EthAddress};
 
 /// Return the public key for a given private key and `SignatureType`
 pub fn to_public(sig_type: SignatureType, private_key: &[u8]) -> Result<Vec<u8>, Error> {
@@ -19,15 +19,12 @@ pub fn to_public(sig_type: SignatureType, private_key: &[u8]) -> Result<Vec<u8>,
             .map_err(|err| Error::Other(err.to_string()))?
             .public_key()
             .as_bytes()),
-        SignatureType::Secp256k1 => {
+        SignatureType::Secp256k1 | SignatureType::Delegated => {
             let private_key = SecpPrivate::parse_slice(private_key)
                 .map_err(|err| Error::Other(err.to_string()))?;
             let public_key = SecpPublic::from_secret_key(&private_key);
             Ok(public_key.serialize().to_vec())
         }
-        SignatureType::Delegated => {
-            unimplemented!()
-        }
     }
 }
 
@@ -44,9 +41,7 @@ pub fn new_address(sig_type: SignatureType, public_key: &[u8]) -> Result<Address
                 Address::new_secp256k1(public_key).map_err(|err| Error::Other(err.to_string()))?;
             Ok(addr)
         }
-        SignatureType::Delegated => {
-            unimplemented!()
-        }
+        SignatureType::Delegated => EthAddress::from_public_key(public_key)?.to_filecoin_address(),
     }
 }
 
@@ -76,7 +71,16 @@ pub fn sign(sig_type: SignatureType, private_key: &[u8], msg: &[u8]) -> Result<S
             Ok(crypto_sig)
         }
         SignatureType::Delegated => {
-            unimplemented!()
+            // Delegated signatures are `secp256k1` signatures of the Keccak-256 hash of the
+            // message, as done by Ethereum
+            let priv_key = SecpPrivate::parse_slice(private_key)
+                .map_err(|err| Error::Other(err.to_string()))?;
+            let message = SecpMessage::parse(&keccak256(msg));
+            let (sig, recovery_id) = libsecp256k1::sign(&message, &priv_key);
+            let mut new_bytes = [0; 65];
+            new_bytes[..64].copy_from_slice(&sig.serialize());
+            new_bytes[64] = recovery_id.serialize();
+            Ok(Signature::new(SignatureType::Delegated, new_bytes.to_vec()))
         }
     }
 }
@@ -89,12 +93,9 @@ pub fn generate(sig_type: SignatureType) -> Result<Vec<u8>, Error> {
             let key = BlsPrivate::generate(rng);
             Ok(key.as_bytes())
         }
-        SignatureType::Secp256k1 => {
+        SignatureType::Secp256k1 | SignatureType::Delegated => {
             let key = SecpPrivate::random(rng);
             Ok(key.serialize().to_vec())
         }
-        SignatureType::Delegated => {
-            unimplemented!()
-        }
     }
 }
@@ -0,0 +1,88 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::{Height, HeightInfo};
+
+/// Default interval between blocks of a local devnet, in seconds.
+pub const DEFAULT_BLOCK_DELAY_SECS: u64 = 4;
+
+/// Height epochs. A devnet starts from the newest network version, without
+/// running any migrations.
+pub const HEIGHT_INFOS: [HeightInfo; 19] = [
+    HeightInfo {
+        height: Height::Breeze,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Smoke,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Ignition,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::ActorsV2,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Tape,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Liftoff,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Kumquat,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Calico,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Persian,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Orange,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Trust,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Norwegian,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Turbo,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Hyperdrive,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Chocolate,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::OhSnap,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Skyr,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Shark,
+        epoch: -1,
+    },
+    HeightInfo {
+        height: Height::Hygge,
+        epoch: -1,
+    },
+];
@@ -10,6 +10,7 @@ use fvm_shared::clock::{ChainEpoch, EPOCH_DURATION_SECONDS};
 use serde::{Deserialize, Serialize};
 
 pub mod calibnet;
+pub mod devnet;
 mod drand;
 pub mod mainnet;
 
@@ -18,6 +19,8 @@ pub mod mainnet;
 const MAINNET_ETH_CHAIN_ID: u64 = 314;
 // https://github.com/ethereum-lists/chains/blob/4731f6713c6fc2bf2ae727388642954a6545b3a9/_data/chains/eip155-314159.json
 const CALIBNET_ETH_CHAIN_ID: u64 = 314159;
+// Same as the Lotus devnets, see https://github.com/filecoin-project/lotus/blob/v1.20.0/build/params_2k.go
+const DEVNET_ETH_CHAIN_ID: u64 = 31415926;
 
 /// Newest network version for all networks
 pub const NEWEST_NETWORK_VERSION: NetworkVersion = NetworkVersion::V17;
@@ -132,6 +135,22 @@ impl ChainConfig {
         }
     }
 
+    /// Configuration of a local development network, where a single miner
+    /// proposes all blocks. There is no default genesis; it has to be built
+    /// from a template.
+    pub fn devnet() -> Self {
+        use devnet::*;
+        Self {
+            name: "devnet".to_string(),
+            genesis_cid: None,
+            bootstrap_peers: vec![],
+            block_delay_secs: DEFAULT_BLOCK_DELAY_SECS,
+            height_infos: HEIGHT_INFOS.to_vec(),
+            policy: Policy::mainnet(),
+            eth_chain_id: DEVNET_ETH_CHAIN_ID,
+        }
+    }
+
     pub fn network_version(&self, epoch: ChainEpoch) -> NetworkVersion {
         let height = sort_by_epoch(&self.height_infos)
             .iter()
@@ -86,6 +86,30 @@ impl<BS: Store> Store for &BS {
     }
 }
 
+/// Implements [`KeyValueStore`](forest_utils::db::KeyValueStore) for a
+/// [`Store`].
+macro_rules! impl_key_value_store {
+    ($store:ty) => {
+        impl forest_utils::db::KeyValueStore for $store {
+            fn read_value(&self, key: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+                Ok(Store::read(self, key)?)
+            }
+
+            fn write_values(&self, values: Vec<(Vec<u8>, Vec<u8>)>) -> anyhow::Result<()> {
+                Ok(Store::bulk_write(self, values)?)
+            }
+        }
+    };
+}
+
+impl_key_value_store!(MemoryDB);
+#[cfg(feature = "rocksdb")]
+impl_key_value_store!(rocks::RocksDb);
+#[cfg(feature = "paritydb")]
+impl_key_value_store!(parity_db::ParityDb);
+#[cfg(any(feature = "paritydb", feature = "rocksdb"))]
+impl_key_value_store!(rolling::RollingDB);
+
 /// Traits for collecting DB stats
 pub trait DBStatistics {
     fn get_statistics(&self) -> Option<String> {
@@ -112,6 +136,7 @@ pub mod db_engine {
     const DIR_NAME: &str = "rocksdb";
     #[cfg(feature = "paritydb")]
     const DIR_NAME: &str = "paritydb";
+    const INDEX_DIR_NAME: &str = "index";
 
     pub fn db_root(chain_data_root: &Path) -> PathBuf {
         chain_data_root.join(DIR_NAME)
@@ -124,4 +149,15 @@ pub mod db_engine {
     pub fn open_proxy_db(db_root: PathBuf, db_config: DbConfig) -> anyhow::Result<RollingDB> {
         RollingDB::load_or_create(db_root, db_config)
     }
+
+    /// Opens the database of the indexes built from the chain, which also
+    /// keeps the verified beacon entries. It is kept out of the [`RollingDB`]
+    /// so that garbage collections do not drop them.
+    pub fn open_index_db(chain_data_root: &Path, config: &DbConfig) -> anyhow::Result<Db> {
+        open_db(&index_db_root(chain_data_root), config)
+    }
+
+    pub fn index_db_root(chain_data_root: &Path) -> PathBuf {
+        chain_data_root.join(INDEX_DIR_NAME).join(DIR_NAME)
+    }
 }
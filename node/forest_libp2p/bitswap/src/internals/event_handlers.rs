@@ -19,6 +19,7 @@ pub fn handle_event_impl<S: BitswapStoreRead>(
     bitswap: &mut BitswapBehaviour,
     store: &S,
     event: BitswapBehaviourEvent,
+    limiter: &impl ServingLimiter,
 ) -> anyhow::Result<()> {
     if let BitswapBehaviourEvent::Message { peer, message } = event {
         match message {
@@ -30,10 +31,29 @@ pub fn handle_event_impl<S: BitswapStoreRead>(
                 // Close inbound stream immediately since `go-bitswap` does not read this
                 // stream. responses will be sent over a new outbound request
                 _ = bitswap.inner_mut().send_response(channel, ());
+                // A message is charged as one request, whatever the number of entries in
+                // its want-list
+                let throttled = request
+                    .iter()
+                    .any(|m| matches!(m, BitswapMessage::Request(r) if !r.cancel))
+                    && !limiter.try_acquire(&peer);
                 for message in request {
                     match message {
                         BitswapMessage::Request(request) => {
+                            if !request.cancel && throttled {
+                                metrics::message_counter_inbound_request_throttled().inc();
+                                if request.send_dont_have {
+                                    bitswap.send_response(
+                                        &peer,
+                                        (request.cid, BitswapResponse::Have(false)),
+                                    );
+                                }
+                                continue;
+                            }
                             if let Some(response) = handle_inbound_request(store, &request) {
+                                if let BitswapResponse::Block(data) = &response {
+                                    limiter.charge(&peer, data.len());
+                                }
                                 bitswap.send_response(&peer, (request.cid, response));
                             }
                         }
@@ -15,6 +15,9 @@ use internals::*;
 mod behaviour;
 pub use behaviour::*;
 
+mod limiter;
+pub use limiter::*;
+
 mod message;
 pub use message::*;
 
@@ -23,6 +26,9 @@ pub use metrics::register_metrics;
 
 pub mod request_manager;
 
+mod session;
+pub use session::{BitswapSessionId, BitswapSessionStats};
+
 mod store;
 pub use store::*;
 
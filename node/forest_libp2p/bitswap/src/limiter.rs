@@ -0,0 +1,25 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use libp2p::PeerId;
+
+/// Limits the inbound requests served to peers.
+pub trait ServingLimiter {
+    /// Returns whether a request of the peer can be served.
+    fn try_acquire(&self, peer: &PeerId) -> bool;
+
+    /// Accounts for the bytes served to the peer.
+    fn charge(&self, peer: &PeerId, bytes: usize);
+}
+
+/// Serves every request.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Unlimited;
+
+impl ServingLimiter for Unlimited {
+    fn try_acquire(&self, _peer: &PeerId) -> bool {
+        true
+    }
+
+    fn charge(&self, _peer: &PeerId, _bytes: usize) {}
+}
@@ -64,6 +64,10 @@ pub(crate) fn message_counter_get_block_failure() -> GenericCounter<AtomicU64> {
     MESSAGE_COUNTER.with_label_values(&["get_block_failure"])
 }
 
+pub(crate) fn message_counter_provider_query() -> GenericCounter<AtomicU64> {
+    MESSAGE_COUNTER.with_label_values(&["provider_query"])
+}
+
 pub(crate) fn message_counter_inbound_request_have() -> GenericCounter<AtomicU64> {
     MESSAGE_COUNTER.with_label_values(&["inbound_request_have"])
 }
@@ -72,6 +76,10 @@ pub(crate) fn message_counter_inbound_request_block() -> GenericCounter<AtomicU6
     MESSAGE_COUNTER.with_label_values(&["inbound_request_block"])
 }
 
+pub(crate) fn message_counter_inbound_request_throttled() -> GenericCounter<AtomicU64> {
+    MESSAGE_COUNTER.with_label_values(&["inbound_request_throttled"])
+}
+
 pub(crate) fn message_counter_outbound_request_cancel() -> GenericCounter<AtomicU64> {
     MESSAGE_COUNTER.with_label_values(&["outbound_request_cancel"])
 }
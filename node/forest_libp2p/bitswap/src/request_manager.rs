@@ -5,6 +5,7 @@
 //! usage
 
 use std::{
+    collections::VecDeque,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -15,14 +16,22 @@ use libipld::{Block, Cid};
 use libp2p::PeerId;
 use parking_lot::RwLock;
 
-use crate::{event_handlers::*, *};
+use crate::{event_handlers::*, session::Session, *};
 
 const BITSWAP_BLOCK_REQUEST_INTERVAL: Duration = Duration::from_millis(500);
+/// Maximum number of peers asked for the same block at the same time.
+const MAX_PARALLEL_BLOCK_REQUESTS: usize = 3;
+/// Duration after which a peer that has not sent a block it claimed to have
+/// is considered unresponsive, and another peer is asked instead.
+const BLOCK_RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Duration after which the block is looked up in the DHT when no connected
+/// peer claims to have it.
+const PROVIDER_QUERY_DELAY: Duration = Duration::from_secs(1);
 
 #[derive(Debug, Clone)]
 struct ResponseChannels {
     block_have: flume::Sender<PeerId>,
-    block_received: flume::Sender<Option<Vec<u8>>>,
+    block_received: flume::Sender<(PeerId, Option<Vec<u8>>)>,
 }
 
 /// Request manager implementation that is optimized for `filecoin` network
@@ -31,8 +40,11 @@ struct ResponseChannels {
 pub struct BitswapRequestManager {
     outbound_request_tx: flume::Sender<(PeerId, BitswapRequest)>,
     outbound_request_rx: flume::Receiver<(PeerId, BitswapRequest)>,
+    provider_query_tx: flume::Sender<Cid>,
+    provider_query_rx: flume::Receiver<Cid>,
     peers: RwLock<HashSet<PeerId>>,
     response_channels: RwLock<HashMap<Cid, ResponseChannels>>,
+    sessions: RwLock<HashMap<BitswapSessionId, Session>>,
 }
 
 impl BitswapRequestManager {
@@ -43,16 +55,54 @@ impl BitswapRequestManager {
     pub fn outbound_request_rx(&self) -> &flume::Receiver<(PeerId, BitswapRequest)> {
         &self.outbound_request_rx
     }
+
+    /// A receiver channel of the blocks that no connected peer claims to
+    /// have. Providers of those blocks should be looked up, e.g. in the DHT,
+    /// and handed back with [BitswapRequestManager::on_providers_found].
+    pub fn provider_query_rx(&self) -> &flume::Receiver<Cid> {
+        &self.provider_query_rx
+    }
+
+    /// Asks the providers of a block that is being fetched whether they have
+    /// it.
+    pub fn on_providers_found(&self, cid: Cid, providers: impl IntoIterator<Item = PeerId>) {
+        if !self.response_channels.read().contains_key(&cid) {
+            return;
+        }
+        let have_request = BitswapRequest::new_have(cid).send_dont_have(false);
+        for peer in providers {
+            if let Err(e) = self.outbound_request_tx.send((peer, have_request.clone())) {
+                warn!("{e}");
+            }
+        }
+    }
+
+    /// Returns the statistics of the active and recent sessions.
+    pub fn session_stats(&self) -> Vec<BitswapSessionStats> {
+        let now = Instant::now();
+        let mut sessions = self.sessions.write();
+        sessions.retain(|_, session| !session.is_expired(now));
+        let mut stats: Vec<_> = sessions
+            .iter()
+            .map(|(&id, session)| session.stats(id, now))
+            .collect();
+        stats.sort_by_key(|s| s.id);
+        stats
+    }
 }
 
 impl Default for BitswapRequestManager {
     fn default() -> Self {
         let (outbound_request_tx, outbound_request_rx) = flume::unbounded();
+        let (provider_query_tx, provider_query_rx) = flume::unbounded();
         Self {
             outbound_request_tx,
             outbound_request_rx,
+            provider_query_tx,
+            provider_query_rx,
             peers: RwLock::new(HashSet::new()),
             response_channels: RwLock::new(HashMap::new()),
+            sessions: RwLock::new(HashMap::new()),
         }
     }
 }
@@ -65,7 +115,21 @@ impl BitswapRequestManager {
         store: &S,
         event: BitswapBehaviourEvent,
     ) -> anyhow::Result<()> {
-        handle_event_impl(self, bitswap, store, event)
+        handle_event_impl(self, bitswap, store, event, &Unlimited)
+    }
+
+    /// Hook the `bitswap` network event into the [BitswapRequestManager],
+    /// serving inbound requests within the limits of the given
+    /// [ServingLimiter]. Peers over their limits are told that blocks are
+    /// missing when they asked to be told.
+    pub fn handle_event_with_limiter<S: BitswapStoreRead>(
+        self: &Arc<Self>,
+        bitswap: &mut BitswapBehaviour,
+        store: &S,
+        event: BitswapBehaviourEvent,
+        limiter: &impl ServingLimiter,
+    ) -> anyhow::Result<()> {
+        handle_event_impl(self, bitswap, store, event, limiter)
     }
 
     /// Gets a block, writing it to the given block store that implements
@@ -78,6 +142,24 @@ impl BitswapRequestManager {
         cid: Cid,
         timeout: Duration,
         responder: Option<flume::Sender<bool>>,
+    ) {
+        self.get_block_in_session(store, cid, timeout, None, true, responder)
+    }
+
+    /// Same as [BitswapRequestManager::get_block], within a session. The
+    /// peers that sent the previous blocks of the session are asked for the
+    /// block first. The providers of a block no connected peer has are only
+    /// looked up if `find_providers` is set, as it is pointless for blocks that
+    /// are not announced in the DHT, such as Filecoin messages.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn get_block_in_session(
+        self: Arc<Self>,
+        store: Arc<impl BitswapStoreReadWrite>,
+        cid: Cid,
+        timeout: Duration,
+        session: Option<BitswapSessionId>,
+        find_providers: bool,
+        responder: Option<flume::Sender<bool>>,
     ) {
         let start = Instant::now();
         let timer = metrics::GET_BLOCK_TIME.start_timer();
@@ -86,16 +168,28 @@ impl BitswapRequestManager {
             let mut success = store.contains(&cid).unwrap_or_default();
             if !success {
                 let deadline = start.checked_add(timeout).expect("Infallible");
-                success =
-                    task::spawn_blocking(move || self.get_block_sync(store_cloned, cid, deadline))
-                        .await
-                        .unwrap_or_default();
+                let this = self.clone();
+                let (fetched, peer) = task::spawn_blocking(move || {
+                    this.get_block_sync(store_cloned, cid, deadline, session, find_providers)
+                })
+                .await
+                .unwrap_or_default();
+                success = fetched;
                 // Spin check db when `get_block_sync` fails fast,
                 // which means there is other task actually processing the same `cid`
                 while !success && Instant::now() < deadline {
                     task::sleep(BITSWAP_BLOCK_REQUEST_INTERVAL).await;
                     success = store.contains(&cid).unwrap_or_default();
                 }
+                if let Some(id) = session {
+                    if let Some(session) = self.sessions.write().get_mut(&id) {
+                        if success {
+                            session.on_received(&cid, peer, start.elapsed());
+                        } else {
+                            session.on_failed(&cid);
+                        }
+                    }
+                }
             }
 
             if success {
@@ -114,15 +208,35 @@ impl BitswapRequestManager {
         });
     }
 
+    /// Adds the block to the want-list of the session, returning the peers of
+    /// the session.
+    fn session_want(&self, id: BitswapSessionId, cid: Cid) -> Vec<PeerId> {
+        let now = Instant::now();
+        let mut sessions = self.sessions.write();
+        sessions.retain(|_, session| !session.is_expired(now));
+        let peers = self.peers.read();
+        sessions
+            .entry(id)
+            .or_insert_with(|| Session::new(now))
+            .want(cid, now)
+            .into_iter()
+            .filter(|peer| peers.contains(peer))
+            .collect()
+    }
+
+    /// Fetches a block, returning whether it has been saved and the peer that
+    /// sent it.
     fn get_block_sync(
         &self,
         store: Arc<impl BitswapStoreReadWrite>,
         cid: Cid,
         deadline: Instant,
-    ) -> bool {
+        session: Option<BitswapSessionId>,
+        find_providers: bool,
+    ) -> (bool, Option<PeerId>) {
         // Fail fast here when the given `cid` is being processed by other tasks
         if self.response_channels.read().contains_key(&cid) {
-            return false;
+            return (false, None);
         }
 
         let (block_have_tx, block_have_rx) = flume::unbounded();
@@ -135,37 +249,95 @@ impl BitswapRequestManager {
             self.response_channels.write().insert(cid, channels);
         }
 
+        let start = Instant::now();
+        let block_request = BitswapRequest::new_block(cid).send_dont_have(false);
+        // Peers asked for the block, with the time they were asked at
+        let mut in_flight = HashMap::new();
+        // Peers that claim to have the block, waiting for a free slot
+        let mut candidates = VecDeque::new();
+        let mut tried = HashSet::new();
+
+        // The peers of the session likely have the block, ask them directly
+        let session_peers = match session {
+            Some(id) => self.session_want(id, cid),
+            None => vec![],
+        };
+        for peer in session_peers.into_iter().take(MAX_PARALLEL_BLOCK_REQUESTS) {
+            _ = self.outbound_request_tx.send((peer, block_request.clone()));
+            tried.insert(peer);
+            in_flight.insert(peer, start);
+        }
+
         let have_request = BitswapRequest::new_have(cid).send_dont_have(false);
-        for &peer in self.peers.read().iter() {
-            if let Err(e) = self.outbound_request_tx.send((peer, have_request.clone())) {
+        for peer in self.peers.read().iter() {
+            if tried.contains(peer) {
+                continue;
+            }
+            if let Err(e) = self.outbound_request_tx.send((*peer, have_request.clone())) {
                 warn!("{e}");
             }
         }
 
         let mut success = false;
         let mut block_data = None;
-        let block_request = BitswapRequest::new_block(cid).send_dont_have(false);
-        while !success && Instant::now() < deadline {
-            match block_have_rx.try_recv() {
-                Ok(peer) => {
-                    _ = self.outbound_request_tx.send((peer, block_request.clone()));
+        let mut sender = None;
+        let mut provider_query_sent = false;
+        'outer: while !success && Instant::now() < deadline {
+            loop {
+                match block_have_rx.try_recv() {
+                    Ok(peer) => {
+                        if !tried.contains(&peer) && !candidates.contains(&peer) {
+                            candidates.push_back(peer);
+                        }
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => break 'outer,
                 }
-                Err(TryRecvError::Empty) => {}
-                Err(TryRecvError::Disconnected) => {
-                    break;
+            }
+
+            // Unresponsive peers free their slot for the next candidate
+            let now = Instant::now();
+            in_flight.retain(|_, asked_at| now.duration_since(*asked_at) < BLOCK_RESPONSE_TIMEOUT);
+            while in_flight.len() < MAX_PARALLEL_BLOCK_REQUESTS {
+                match candidates.pop_front() {
+                    Some(peer) => {
+                        _ = self.outbound_request_tx.send((peer, block_request.clone()));
+                        tried.insert(peer);
+                        in_flight.insert(peer, now);
+                    }
+                    None => break,
                 }
             }
 
-            if let Ok(data) = block_saved_rx.recv_timeout(BITSWAP_BLOCK_REQUEST_INTERVAL) {
+            if find_providers
+                && !provider_query_sent
+                && in_flight.is_empty()
+                && now.duration_since(start) >= PROVIDER_QUERY_DELAY
+            {
+                provider_query_sent = true;
+                metrics::message_counter_provider_query().inc();
+                if let Some(id) = session {
+                    if let Some(session) = self.sessions.write().get_mut(&id) {
+                        session.on_provider_query();
+                    }
+                }
+                if let Err(e) = self.provider_query_tx.send(cid) {
+                    warn!("{e}");
+                }
+            }
+
+            if let Ok((peer, data)) = block_saved_rx.recv_timeout(BITSWAP_BLOCK_REQUEST_INTERVAL) {
                 success = true;
                 block_data = data;
+                sender = Some(peer);
             }
         }
 
         if !success {
-            if let Ok(data) = block_saved_rx.recv_deadline(deadline) {
+            if let Ok((peer, data)) = block_saved_rx.recv_deadline(deadline) {
                 success = true;
                 block_data = data;
+                sender = Some(peer);
             }
         }
 
@@ -199,7 +371,7 @@ impl BitswapRequestManager {
             metrics::response_channel_container_capacity().set(response_channels.capacity() as _);
         }
 
-        success
+        (success, sender)
     }
 
     pub(crate) fn on_inbound_response_event<S: BitswapStoreRead>(
@@ -215,15 +387,15 @@ impl BitswapRequestManager {
                     _ = chans.block_have.send(peer);
                 }
             }
-            DataBlock(_peer, cid, data) => {
+            DataBlock(peer, cid, data) => {
                 if let Some(chans) = self.response_channels.read().get(&cid) {
                     if let Ok(true) = store.contains(&cid) {
                         // Avoid duplicate writes, still notify the receiver
                         metrics::message_counter_inbound_response_block_already_exists_in_db()
                             .inc();
-                        _ = chans.block_received.send(None);
+                        _ = chans.block_received.send((peer, None));
                     } else {
-                        _ = chans.block_received.send(Some(data));
+                        _ = chans.block_received.send((peer, Some(data)));
                     }
 
                     // <https://github.com/ipfs/go-libipfs/tree/main/bitswap#background>
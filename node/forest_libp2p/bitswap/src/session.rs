@@ -0,0 +1,167 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Sessions group the blocks wanted for the same purpose, e.g. the messages
+//! of a block. Peers that have sent blocks of a session are likely to have
+//! the others, so they are asked for them first.
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use ahash::{HashMap, HashSet};
+use libipld::Cid;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+
+/// Duration for which the stats of idle sessions are kept.
+pub(crate) const SESSION_RETENTION: Duration = Duration::from_secs(10 * 60);
+
+/// Identifier of a bitswap session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BitswapSessionId(u64);
+
+impl BitswapSessionId {
+    /// Returns a new unique session identifier.
+    pub fn generate() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Statistics of a bitswap session.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BitswapSessionStats {
+    #[serde(rename = "ID")]
+    pub id: u64,
+    /// Number of blocks currently wanted.
+    pub wanted: usize,
+    /// Number of blocks received.
+    pub received: u64,
+    /// Number of blocks that could not be fetched before their deadline.
+    pub failed: u64,
+    /// Number of DHT provider queries made for blocks nobody had.
+    pub provider_queries: u64,
+    /// Number of peers that sent blocks.
+    pub peers: usize,
+    /// Average time to fetch a block, in milliseconds.
+    pub average_fetch_time_ms: u64,
+    /// Time since the last block was wanted or received, in seconds.
+    pub idle_secs: u64,
+}
+
+#[derive(Debug)]
+pub(crate) struct Session {
+    want_list: HashSet<Cid>,
+    /// Number of blocks received from each peer.
+    peer_blocks: HashMap<PeerId, u64>,
+    received: u64,
+    failed: u64,
+    provider_queries: u64,
+    total_fetch_time: Duration,
+    last_active: Instant,
+}
+
+impl Session {
+    pub fn new(now: Instant) -> Self {
+        Session {
+            want_list: Default::default(),
+            peer_blocks: Default::default(),
+            received: 0,
+            failed: 0,
+            provider_queries: 0,
+            total_fetch_time: Duration::ZERO,
+            last_active: now,
+        }
+    }
+
+    /// Adds a block to the want-list, returning the peers of the session, the
+    /// ones that sent the most blocks first.
+    pub fn want(&mut self, cid: Cid, now: Instant) -> Vec<PeerId> {
+        self.want_list.insert(cid);
+        self.last_active = now;
+        let mut peers: Vec<_> = self.peer_blocks.iter().collect();
+        peers.sort_by_key(|(_, &blocks)| std::cmp::Reverse(blocks));
+        peers.into_iter().map(|(&peer, _)| peer).collect()
+    }
+
+    pub fn on_provider_query(&mut self) {
+        self.provider_queries += 1;
+    }
+
+    pub fn on_received(&mut self, cid: &Cid, peer: Option<PeerId>, fetch_time: Duration) {
+        self.want_list.remove(cid);
+        self.received += 1;
+        self.total_fetch_time += fetch_time;
+        if let Some(peer) = peer {
+            *self.peer_blocks.entry(peer).or_default() += 1;
+        }
+        self.last_active = Instant::now();
+    }
+
+    pub fn on_failed(&mut self, cid: &Cid) {
+        self.want_list.remove(cid);
+        self.failed += 1;
+        self.last_active = Instant::now();
+    }
+
+    /// Returns true if the session wants no block and has been idle for
+    /// longer than [`SESSION_RETENTION`].
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.want_list.is_empty()
+            && now.saturating_duration_since(self.last_active) > SESSION_RETENTION
+    }
+
+    pub fn stats(&self, id: BitswapSessionId, now: Instant) -> BitswapSessionStats {
+        BitswapSessionStats {
+            id: id.0,
+            wanted: self.want_list.len(),
+            received: self.received,
+            failed: self.failed,
+            provider_queries: self.provider_queries,
+            peers: self.peer_blocks.len(),
+            average_fetch_time_ms: self
+                .total_fetch_time
+                .as_millis()
+                .checked_div(self.received as u128)
+                .unwrap_or_default() as u64,
+            idle_secs: now.saturating_duration_since(self.last_active).as_secs(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libipld::multihash::{Code, MultihashDigest};
+
+    use super::*;
+
+    #[test]
+    fn session_peers_and_stats() {
+        let now = Instant::now();
+        let mut session = Session::new(now);
+        let cid = |i: u8| Cid::new_v0(Code::Sha2_256.digest(&[i])).unwrap();
+        let (a, b) = (PeerId::random(), PeerId::random());
+
+        assert!(session.want(cid(1), now).is_empty());
+        session.on_received(&cid(1), Some(b), Duration::from_millis(100));
+        session.want(cid(2), now);
+        session.on_received(&cid(2), Some(a), Duration::from_millis(300));
+        session.want(cid(3), now);
+        session.on_received(&cid(3), Some(a), Duration::from_millis(200));
+        assert_eq!(session.want(cid(4), now), vec![a, b]);
+        session.on_failed(&cid(4));
+
+        let stats = session.stats(BitswapSessionId(7), now);
+        assert_eq!(stats.id, 7);
+        assert_eq!(stats.wanted, 0);
+        assert_eq!(stats.received, 3);
+        assert_eq!(stats.failed, 1);
+        assert_eq!(stats.peers, 2);
+        assert_eq!(stats.average_fetch_time_ms, 200);
+        assert!(!session.is_expired(Instant::now()));
+        assert!(session.is_expired(Instant::now() + SESSION_RETENTION * 2));
+    }
+}
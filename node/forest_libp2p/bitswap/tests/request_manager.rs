@@ -99,6 +99,69 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn request_manager_session_test() {
+        request_manager_session_test_mpl().await.unwrap();
+    }
+
+    async fn request_manager_session_test_mpl() -> Result<()> {
+        let blocks = [new_random_block()?, new_random_block()?];
+        let block_not_exist = new_random_block()?;
+
+        // 1. Set up a server with the blocks of the session, and one without
+        let mut joinset = JoinSet::new();
+        let (mut client, _, _) = create_swarm().await?;
+        for has_blocks in [true, false] {
+            let (server, server_peer_id, server_peer_addr) = create_swarm().await?;
+            let server_store = TestStore::default();
+            if has_blocks {
+                for block in &blocks {
+                    server_store.insert(block)?;
+                }
+            }
+            joinset.spawn(run_swarm_loop(server, server_store));
+            client.dial(server_peer_addr.with(Protocol::P2p(server_peer_id.into())))?;
+        }
+
+        let client_request_manager = client.behaviour().request_manager();
+        let client_store = TestStore::default();
+        joinset.spawn(run_swarm_loop(client, client_store.clone()));
+        // Wait for 1s to establish connections
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        // 2. Get the blocks, and one that no server has, in a single session
+        // without provider lookups
+        let session = BitswapSessionId::generate();
+        let get_block = |cid, timeout| {
+            let (request_tx, request_rx) = flume::unbounded();
+            client_request_manager.clone().get_block_in_session(
+                client_store.clone(),
+                cid,
+                timeout,
+                Some(session),
+                false,
+                Some(request_tx),
+            );
+            tokio::task::spawn_blocking(move || request_rx.recv_timeout(TIMEOUT))
+        };
+        for block in &blocks {
+            assert!(get_block(*block.cid(), TIMEOUT).await??);
+        }
+        assert!(!get_block(*block_not_exist.cid(), Duration::from_secs(2)).await??);
+
+        // 3. The session only got blocks from the server that has them, and
+        // nobody was looked up in the DHT
+        let stats = client_request_manager.session_stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].received, 2);
+        assert_eq!(stats[0].failed, 1);
+        assert_eq!(stats[0].peers, 1);
+        assert_eq!(stats[0].provider_queries, 0);
+        assert!(client_request_manager.provider_query_rx().is_empty());
+
+        Ok(())
+    }
+
     async fn create_swarm() -> Result<(Swarm<BitswapBehaviour>, PeerId, Multiaddr)> {
         let id_keys = Keypair::generate_ed25519();
         let peer_id = PeerId::from(id_keys.public());
@@ -0,0 +1,482 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Bandwidth accounting per protocol and per peer. Connections are wrapped so
+//! that every substream counts the bytes it reads and writes, and learns its
+//! protocol by following the `multistream-select` negotiation that opens it.
+
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use ahash::HashMap;
+use futures::{ready, AsyncRead, AsyncWrite};
+use libp2p::{
+    core::muxing::{StreamMuxer, StreamMuxerBox, StreamMuxerEvent, SubstreamBox},
+    PeerId,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::metrics;
+
+/// Protocol of the substreams whose negotiation could not be followed.
+const UNKNOWN_PROTOCOL: &str = "unknown";
+const MULTISTREAM_PROTOCOL: &[u8] = b"/multistream/1.0.0";
+const NOT_AVAILABLE: &[u8] = b"na";
+/// Bytes of negotiation after which a substream protocol is deemed unknown.
+const MAX_NEGOTIATION_LEN: usize = 1024;
+
+/// Traffic of a protocol, a peer, or of the whole node.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BandwidthStats {
+    pub total_in: u64,
+    pub total_out: u64,
+    /// Bytes per second received during the last sampling period.
+    pub rate_in: f64,
+    /// Bytes per second sent during the last sampling period.
+    pub rate_out: f64,
+}
+
+/// Traffic of the node, by protocol and by connected peer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BandwidthReport {
+    pub total: BandwidthStats,
+    pub by_protocol: HashMap<String, BandwidthStats>,
+    pub by_peer: HashMap<String, BandwidthStats>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Inbound,
+    Outbound,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Sample {
+    total_in: u64,
+    total_out: u64,
+    rate_in: f64,
+    rate_out: f64,
+}
+
+#[derive(Debug, Default)]
+struct Counter {
+    inbound: AtomicU64,
+    outbound: AtomicU64,
+    sample: Mutex<Sample>,
+}
+
+impl Counter {
+    fn record(&self, direction: Direction, bytes: u64) {
+        match direction {
+            Direction::Inbound => self.inbound.fetch_add(bytes, Ordering::Relaxed),
+            Direction::Outbound => self.outbound.fetch_add(bytes, Ordering::Relaxed),
+        };
+    }
+
+    /// Updates the rates with the traffic since the previous sample, and
+    /// returns that traffic.
+    fn sample(&self, elapsed_secs: f64) -> (u64, u64) {
+        let total_in = self.inbound.load(Ordering::Relaxed);
+        let total_out = self.outbound.load(Ordering::Relaxed);
+        let mut sample = self.sample.lock();
+        let delta_in = total_in.saturating_sub(sample.total_in);
+        let delta_out = total_out.saturating_sub(sample.total_out);
+        *sample = Sample {
+            total_in,
+            total_out,
+            rate_in: delta_in as f64 / elapsed_secs,
+            rate_out: delta_out as f64 / elapsed_secs,
+        };
+        (delta_in, delta_out)
+    }
+
+    fn stats(&self) -> BandwidthStats {
+        let sample = *self.sample.lock();
+        BandwidthStats {
+            total_in: self.inbound.load(Ordering::Relaxed),
+            total_out: self.outbound.load(Ordering::Relaxed),
+            rate_in: sample.rate_in,
+            rate_out: sample.rate_out,
+        }
+    }
+}
+
+/// Sinks the bytes sent and received over the node connections.
+#[derive(Debug)]
+pub struct BandwidthSinks {
+    total: Counter,
+    by_protocol: Mutex<HashMap<String, Arc<Counter>>>,
+    by_peer: Mutex<HashMap<PeerId, Arc<Counter>>>,
+    last_sample: Mutex<Instant>,
+}
+
+impl Default for BandwidthSinks {
+    fn default() -> Self {
+        BandwidthSinks {
+            total: Default::default(),
+            by_protocol: Default::default(),
+            by_peer: Default::default(),
+            last_sample: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+impl BandwidthSinks {
+    fn peer_counter(&self, peer: PeerId) -> Arc<Counter> {
+        self.by_peer.lock().entry(peer).or_default().clone()
+    }
+
+    fn protocol_counter(&self, protocol: &str) -> Arc<Counter> {
+        let mut by_protocol = self.by_protocol.lock();
+        match by_protocol.get(protocol) {
+            Some(counter) => counter.clone(),
+            None => by_protocol.entry(protocol.to_owned()).or_default().clone(),
+        }
+    }
+
+    /// Forgets the traffic of a peer once none of its connections is left.
+    fn release_peer(&self, peer: &PeerId) {
+        let mut by_peer = self.by_peer.lock();
+        if by_peer
+            .get(peer)
+            .map_or(false, |counter| Arc::strong_count(counter) == 1)
+        {
+            by_peer.remove(peer);
+        }
+    }
+
+    /// Refreshes the rates and the Prometheus metrics with the traffic since
+    /// the previous call.
+    pub fn sample(&self) {
+        let elapsed_secs = {
+            let mut last_sample = self.last_sample.lock();
+            let now = Instant::now();
+            let elapsed = now.duration_since(*last_sample);
+            *last_sample = now;
+            elapsed.as_secs_f64().max(f64::EPSILON)
+        };
+        self.total.sample(elapsed_secs);
+        for (protocol, counter) in self.by_protocol.lock().iter() {
+            let (delta_in, delta_out) = counter.sample(elapsed_secs);
+            metrics::BANDWIDTH_BYTES
+                .with_label_values(&[protocol, metrics::values::INBOUND])
+                .inc_by(delta_in);
+            metrics::BANDWIDTH_BYTES
+                .with_label_values(&[protocol, metrics::values::OUTBOUND])
+                .inc_by(delta_out);
+        }
+        for counter in self.by_peer.lock().values() {
+            counter.sample(elapsed_secs);
+        }
+    }
+
+    pub fn report(&self) -> BandwidthReport {
+        BandwidthReport {
+            total: self.total.stats(),
+            by_protocol: self
+                .by_protocol
+                .lock()
+                .iter()
+                .map(|(protocol, counter)| (protocol.clone(), counter.stats()))
+                .collect(),
+            by_peer: self
+                .by_peer
+                .lock()
+                .iter()
+                .map(|(peer, counter)| (peer.to_string(), counter.stats()))
+                .collect(),
+        }
+    }
+}
+
+/// Decodes an unsigned varint, returning it with its length.
+fn decode_uvarint(buf: &[u8]) -> Option<(usize, usize)> {
+    let mut value = 0usize;
+    for (i, byte) in buf.iter().enumerate().take(9) {
+        value |= ((byte & 0x7f) as usize) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Outcome of following the negotiation of a substream.
+#[derive(Debug, PartialEq, Eq)]
+enum Negotiated {
+    Pending,
+    Protocol(String),
+    Unknown,
+}
+
+/// Follows the `multistream-select` messages sent by the listener side of a
+/// substream. The first protocol the listener echoes is the negotiated one.
+fn negotiated_protocol(buf: &[u8]) -> Negotiated {
+    let mut rest = buf;
+    loop {
+        let (len, prefix_len) = match decode_uvarint(rest) {
+            Some((len, _)) if len > MAX_NEGOTIATION_LEN => return Negotiated::Unknown,
+            Some(decoded) => decoded,
+            None if rest.len() >= 9 => return Negotiated::Unknown,
+            None => return Negotiated::Pending,
+        };
+        let message = match rest.get(prefix_len..prefix_len + len) {
+            Some(message) => message,
+            None => return Negotiated::Pending,
+        };
+        rest = &rest[prefix_len + len..];
+        let message = message.strip_suffix(b"\n").unwrap_or(message);
+        if message != MULTISTREAM_PROTOCOL && message != NOT_AVAILABLE {
+            return match std::str::from_utf8(message) {
+                Ok(protocol) if protocol.starts_with('/') => {
+                    Negotiated::Protocol(protocol.to_owned())
+                }
+                _ => Negotiated::Unknown,
+            };
+        }
+    }
+}
+
+enum ProtocolState {
+    /// Negotiation in progress: the listener messages seen so far, and the
+    /// traffic to account for once the protocol is known.
+    Negotiating {
+        listener_bytes: Vec<u8>,
+        inbound: u64,
+        outbound: u64,
+    },
+    Negotiated(Arc<Counter>),
+}
+
+/// Substream counting the bytes it reads and writes.
+pub(crate) struct CountingSubstream {
+    inner: SubstreamBox,
+    sinks: Arc<BandwidthSinks>,
+    peer: Arc<Counter>,
+    /// Direction of the listener messages: inbound on the substreams we open.
+    listener_direction: Direction,
+    protocol: ProtocolState,
+}
+
+impl CountingSubstream {
+    fn record(&mut self, direction: Direction, bytes: &[u8]) {
+        let len = bytes.len() as u64;
+        self.sinks.total.record(direction, len);
+        self.peer.record(direction, len);
+        match &mut self.protocol {
+            ProtocolState::Negotiated(counter) => counter.record(direction, len),
+            ProtocolState::Negotiating {
+                listener_bytes,
+                inbound,
+                outbound,
+            } => {
+                match direction {
+                    Direction::Inbound => *inbound += len,
+                    Direction::Outbound => *outbound += len,
+                }
+                if direction != self.listener_direction {
+                    return;
+                }
+                listener_bytes.extend_from_slice(bytes);
+                let protocol = match negotiated_protocol(listener_bytes) {
+                    Negotiated::Pending if listener_bytes.len() <= MAX_NEGOTIATION_LEN => return,
+                    Negotiated::Protocol(protocol) => protocol,
+                    Negotiated::Pending | Negotiated::Unknown => UNKNOWN_PROTOCOL.to_owned(),
+                };
+                let counter = self.sinks.protocol_counter(&protocol);
+                counter.record(Direction::Inbound, *inbound);
+                counter.record(Direction::Outbound, *outbound);
+                self.protocol = ProtocolState::Negotiated(counter);
+            }
+        }
+    }
+}
+
+impl Drop for CountingSubstream {
+    fn drop(&mut self) {
+        if let ProtocolState::Negotiating {
+            inbound, outbound, ..
+        } = self.protocol
+        {
+            let counter = self.sinks.protocol_counter(UNKNOWN_PROTOCOL);
+            counter.record(Direction::Inbound, inbound);
+            counter.record(Direction::Outbound, outbound);
+        }
+    }
+}
+
+impl AsyncRead for CountingSubstream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.record(Direction::Inbound, &buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl AsyncWrite for CountingSubstream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = ready!(Pin::new(&mut this.inner).poll_write(cx, buf))?;
+        this.record(Direction::Outbound, &buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
+
+/// Connection muxer handing out [`CountingSubstream`]s.
+pub(crate) struct CountingMuxer {
+    inner: StreamMuxerBox,
+    sinks: Arc<BandwidthSinks>,
+    peer_id: PeerId,
+    peer: Option<Arc<Counter>>,
+}
+
+impl CountingMuxer {
+    pub fn new(inner: StreamMuxerBox, sinks: Arc<BandwidthSinks>, peer_id: PeerId) -> Self {
+        let peer = Some(sinks.peer_counter(peer_id));
+        CountingMuxer {
+            inner,
+            sinks,
+            peer_id,
+            peer,
+        }
+    }
+
+    fn wrap(&self, inner: SubstreamBox, listener_direction: Direction) -> CountingSubstream {
+        CountingSubstream {
+            inner,
+            sinks: self.sinks.clone(),
+            peer: self.peer.clone().unwrap_or_default(),
+            listener_direction,
+            protocol: ProtocolState::Negotiating {
+                listener_bytes: vec![],
+                inbound: 0,
+                outbound: 0,
+            },
+        }
+    }
+}
+
+impl Drop for CountingMuxer {
+    fn drop(&mut self) {
+        self.peer = None;
+        self.sinks.release_peer(&self.peer_id);
+    }
+}
+
+impl StreamMuxer for CountingMuxer {
+    type Substream = CountingSubstream;
+    type Error = io::Error;
+
+    fn poll_inbound(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        let inner = ready!(Pin::new(&mut this.inner).poll_inbound(cx))?;
+        // We listen on the substreams opened by the remote
+        Poll::Ready(Ok(this.wrap(inner, Direction::Outbound)))
+    }
+
+    fn poll_outbound(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        let inner = ready!(Pin::new(&mut this.inner).poll_outbound(cx))?;
+        Poll::Ready(Ok(this.wrap(inner, Direction::Inbound)))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<StreamMuxerEvent, Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(content: &[u8]) -> Vec<u8> {
+        let mut message = vec![content.len() as u8 + 1];
+        message.extend_from_slice(content);
+        message.push(b'\n');
+        message
+    }
+
+    #[test]
+    fn follow_negotiation() {
+        let mut listener_bytes = message(MULTISTREAM_PROTOCOL);
+        assert_eq!(negotiated_protocol(&listener_bytes), Negotiated::Pending);
+
+        listener_bytes.extend(message(NOT_AVAILABLE));
+        let echo = message(b"/chain/ipfs/bitswap/1.1.0");
+        listener_bytes.extend_from_slice(&echo[..5]);
+        assert_eq!(negotiated_protocol(&listener_bytes), Negotiated::Pending);
+
+        listener_bytes.extend_from_slice(&echo[5..]);
+        listener_bytes.extend_from_slice(b"payload");
+        assert_eq!(
+            negotiated_protocol(&listener_bytes),
+            Negotiated::Protocol("/chain/ipfs/bitswap/1.1.0".into())
+        );
+
+        assert_eq!(negotiated_protocol(&[0xff; 16]), Negotiated::Unknown);
+        assert_eq!(
+            negotiated_protocol(&message(b"not a protocol")),
+            Negotiated::Unknown
+        );
+    }
+
+    #[test]
+    fn counter_rates() {
+        let counter = Counter::default();
+        counter.record(Direction::Inbound, 300);
+        counter.record(Direction::Outbound, 100);
+        assert_eq!(counter.sample(2.0), (300, 100));
+        counter.record(Direction::Inbound, 50);
+        assert_eq!(counter.sample(1.0), (50, 0));
+        assert_eq!(
+            counter.stats(),
+            BandwidthStats {
+                total_in: 350,
+                total_out: 100,
+                rate_in: 50.0,
+                rate_out: 0.0,
+            }
+        );
+    }
+}
@@ -2,10 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use ahash::{HashMap, HashSet};
+use cid::Cid;
 use forest_libp2p_bitswap::BitswapBehaviour;
 use forest_utils::encoding::blake2b_256;
+use futures::channel::oneshot::Sender as OneShotSender;
 use libp2p::{
+    autonat::{self, NatStatus},
     core::identity::Keypair,
+    dcutr,
     gossipsub::{
         self, IdentTopic as Topic, MessageAuthenticity, MessageId, PublishError, SubscriptionError,
         ValidationMode,
@@ -14,8 +18,8 @@ use libp2p::{
     identity::PeerId,
     kad::QueryId,
     metrics::{Metrics, Recorder},
-    ping,
-    swarm::NetworkBehaviour,
+    ping, relay,
+    swarm::{behaviour::toggle::Toggle, NetworkBehaviour},
     Multiaddr,
 };
 use log::warn;
@@ -23,8 +27,10 @@ use log::warn;
 use crate::{
     chain_exchange::ChainExchangeBehaviour,
     config::Libp2pConfig,
-    discovery::{DiscoveryBehaviour, DiscoveryConfig},
+    discovery::{peer_id_from_multiaddr, DhtBucket, DiscoveryBehaviour, DiscoveryConfig},
+    gater::ConnectionGater,
     gossip_params::{build_peer_score_params, build_peer_score_threshold},
+    graphsync::GraphsyncBehaviour,
     hello::HelloBehaviour,
 };
 
@@ -32,12 +38,17 @@ use crate::{
 /// for a Filecoin node.
 #[derive(NetworkBehaviour)]
 pub(crate) struct ForestBehaviour {
+    gater: ConnectionGater,
+    autonat: Toggle<autonat::Behaviour>,
+    relay_client: Toggle<relay::client::Behaviour>,
+    dcutr: Toggle<dcutr::Behaviour>,
     gossipsub: gossipsub::Behaviour,
     discovery: DiscoveryBehaviour,
     ping: ping::Behaviour,
     identify: identify::Behaviour,
     pub(super) hello: HelloBehaviour,
     pub(super) chain_exchange: ChainExchangeBehaviour,
+    pub(super) graphsync: GraphsyncBehaviour,
     pub(super) bitswap: BitswapBehaviour,
 }
 
@@ -53,7 +64,14 @@ impl Recorder<ForestBehaviourEvent> for Metrics {
 }
 
 impl ForestBehaviour {
-    pub fn new(local_key: &Keypair, config: &Libp2pConfig, network_name: &str) -> Self {
+    /// Creates the behaviour, with the relay client paired with the relay
+    /// transport when NAT traversal is enabled.
+    pub fn new(
+        local_key: &Keypair,
+        config: &Libp2pConfig,
+        network_name: &str,
+        relay_client: Option<relay::client::Behaviour>,
+    ) -> Self {
         let mut gs_config_builder = gossipsub::ConfigBuilder::default();
         gs_config_builder.max_transmit_size(1 << 20);
         gs_config_builder.validation_mode(ValidationMode::Strict);
@@ -69,12 +87,20 @@ impl ForestBehaviour {
         )
         .unwrap();
 
-        gossipsub
-            .with_peer_score(
-                build_peer_score_params(network_name),
-                build_peer_score_threshold(),
-            )
-            .unwrap();
+        if config.gossipsub_peer_scoring {
+            gossipsub
+                .with_peer_score(
+                    build_peer_score_params(network_name),
+                    build_peer_score_threshold(),
+                )
+                .unwrap();
+        }
+        for multiaddr in &config.direct_peers {
+            match peer_id_from_multiaddr(multiaddr) {
+                Some(peer_id) => gossipsub.add_explicit_peer(&peer_id),
+                None => warn!("Direct peer address {multiaddr} has no /p2p/ protocol"),
+            }
+        }
 
         let bitswap = BitswapBehaviour::new(
             &[
@@ -94,9 +120,21 @@ impl ForestBehaviour {
             .with_mdns(config.mdns)
             .with_kademlia(config.kademlia)
             .with_user_defined(config.bootstrap_peers.clone())
+            .with_user_defined(config.direct_peers.clone())
             .target_peer_count(config.target_peer_count as u64);
 
+        let local_peer_id = local_key.public().to_peer_id();
+        let nat_traversal = config.nat_traversal;
+
         ForestBehaviour {
+            gater: ConnectionGater::new(config),
+            autonat: nat_traversal
+                .then(|| autonat::Behaviour::new(local_peer_id, Default::default()))
+                .into(),
+            relay_client: relay_client.into(),
+            dcutr: nat_traversal
+                .then(|| dcutr::Behaviour::new(local_peer_id))
+                .into(),
             gossipsub,
             discovery: discovery_config.finish(),
             ping: Default::default(),
@@ -107,6 +145,7 @@ impl ForestBehaviour {
             bitswap,
             hello: HelloBehaviour::default(),
             chain_exchange: ChainExchangeBehaviour::default(),
+            graphsync: GraphsyncBehaviour::default(),
         }
     }
 
@@ -115,6 +154,11 @@ impl ForestBehaviour {
         self.discovery.bootstrap()
     }
 
+    /// Adds a known address of a peer, to be used for discovery
+    pub fn add_known_address(&mut self, peer_id: &PeerId, addr: Multiaddr) {
+        self.discovery.add_address(peer_id, addr)
+    }
+
     /// Publish data over the gossip network.
     pub fn publish(
         &mut self,
@@ -129,6 +173,27 @@ impl ForestBehaviour {
         self.gossipsub.subscribe(topic)
     }
 
+    /// Sets the application specific `GossipSub` score of a connected peer.
+    /// Returns `false` if peer scoring is disabled or the peer is unknown.
+    pub fn set_pubsub_app_score(&mut self, peer_id: &PeerId, score: f64) -> bool {
+        self.gossipsub.set_application_score(peer_id, score)
+    }
+
+    /// Returns the current `GossipSub` score of every known peer, or an empty
+    /// list if peer scoring is disabled.
+    pub fn pubsub_scores(&self) -> Vec<(PeerId, f64)> {
+        self.gossipsub
+            .all_peers()
+            .filter_map(|(peer_id, _)| Some((*peer_id, self.gossipsub.peer_score(peer_id)?)))
+            .collect()
+    }
+
+    /// Returns the reachability of the node detected by `AutoNAT`, `None` if
+    /// NAT traversal is disabled.
+    pub fn nat_status(&self) -> Option<NatStatus> {
+        self.autonat.as_ref().map(|autonat| autonat.nat_status())
+    }
+
     /// Returns a set of peer ids
     pub fn peers(&self) -> &HashSet<PeerId> {
         self.discovery.peers()
@@ -138,4 +203,19 @@ impl ForestBehaviour {
     pub fn peer_addresses(&mut self) -> &HashMap<PeerId, HashSet<Multiaddr>> {
         self.discovery.peer_addresses()
     }
+
+    /// Looks a peer up in the DHT, sending its addresses once found.
+    pub fn find_peer(&mut self, peer_id: PeerId, response_channel: OneShotSender<Vec<Multiaddr>>) {
+        self.discovery.find_peer(peer_id, response_channel)
+    }
+
+    /// Returns the non-empty buckets of the Kademlia routing table.
+    pub fn routing_table(&mut self) -> Vec<DhtBucket> {
+        self.discovery.routing_table()
+    }
+
+    /// Looks the providers of a block up in the DHT.
+    pub fn find_providers(&mut self, cid: Cid) {
+        self.discovery.find_providers(cid)
+    }
 }
@@ -1,16 +1,36 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use libp2p::Multiaddr;
+use libp2p::{Multiaddr, PeerId};
+use log::warn;
 use serde::{Deserialize, Serialize};
 
+use crate::DEFAULT_BAN_DURATION;
+
+/// Transports the libp2p node can listen and dial over.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Libp2pTransport {
+    /// QUIC over UDP, with `quic-v1` addresses
+    Quic,
+    /// TCP, with DNS resolution
+    Tcp,
+    /// WebSocket over TCP, with DNS resolution
+    WebSocket,
+}
+
+impl Libp2pTransport {
+    /// All supported transports, in order of preference when dialing
+    pub const ALL: [Libp2pTransport; 3] = [Self::Quic, Self::Tcp, Self::WebSocket];
+}
+
 /// Libp2p configuration for the Forest node.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(default)]
 pub struct Libp2pConfig {
-    /// Local addresses. Tcp and websocket with dns are supported. By making it
-    /// empty, the libp2p node will not be capable of working as a dialee but
-    /// can still work as a dialer
+    /// Local addresses. QUIC, Tcp and websocket with dns are supported. By
+    /// making it empty, the libp2p node will not be capable of working as a
+    /// dialee but can still work as a dialer
     pub listening_multiaddrs: Vec<Multiaddr>,
     /// Bootstrap peer list.
     pub bootstrap_peers: Vec<Multiaddr>,
@@ -20,16 +40,95 @@ pub struct Libp2pConfig {
     pub kademlia: bool,
     /// Target peer count.
     pub target_peer_count: u32,
+    /// Enabled transports. Peers advertising both QUIC and TCP addresses are
+    /// dialed over both, the first connection to succeed is kept.
+    pub transports: Vec<Libp2pTransport>,
+    /// `GossipSub` peer scoring enabled. Peers with a negative score are
+    /// pruned from the mesh, and ignored below the graylist threshold.
+    pub gossipsub_peer_scoring: bool,
+    /// Peers (with `/p2p/` protocol) that `GossipSub` always forwards messages
+    /// to and accepts messages from, regardless of their score.
+    pub direct_peers: Vec<Multiaddr>,
+    /// Duration, in seconds, of the bans of misbehaving peers.
+    pub ban_duration_secs: u64,
+    /// Peer IDs, IP addresses or CIDR ranges that are always accepted,
+    /// regardless of the deny list and of the connection limits.
+    pub allow_list: Vec<String>,
+    /// Peer IDs, IP addresses or CIDR ranges that are never connected to.
+    pub deny_list: Vec<String>,
+    /// Peer IDs that are never banned, and are exempt from the connection
+    /// limits.
+    pub protected_peers: Vec<String>,
+    /// Maximum number of connections from a single IP address.
+    pub max_connections_per_ip: Option<u32>,
+    /// Limits of the chain exchange and bitswap requests served to peers.
+    pub serving_limits: ServingLimits,
+    /// NAT traversal enabled: reachability detection with `AutoNAT`, relayed
+    /// connections with a circuit relay v2 client, and direct connections
+    /// upgraded from relayed ones with `DCUtR` hole punching.
+    pub nat_traversal: bool,
+    /// Circuit relay v2 servers (with `/p2p/` protocol) to obtain relayed
+    /// addresses from, when `AutoNAT` finds the node to be unreachable.
+    pub relays: Vec<Multiaddr>,
+    /// UPnP mapping of the listening ports on the local gateway.
+    pub upnp: bool,
 }
 
 impl Default for Libp2pConfig {
     fn default() -> Self {
         Self {
-            listening_multiaddrs: vec!["/ip4/0.0.0.0/tcp/0".parse().expect("Infallible")],
+            listening_multiaddrs: vec![
+                "/ip4/0.0.0.0/tcp/0".parse().expect("Infallible"),
+                "/ip4/0.0.0.0/udp/0/quic-v1".parse().expect("Infallible"),
+            ],
             bootstrap_peers: vec![],
             mdns: false,
             kademlia: true,
             target_peer_count: 75,
+            transports: Libp2pTransport::ALL.to_vec(),
+            gossipsub_peer_scoring: true,
+            direct_peers: vec![],
+            ban_duration_secs: DEFAULT_BAN_DURATION.as_secs(),
+            allow_list: vec![],
+            deny_list: vec![],
+            protected_peers: vec![],
+            max_connections_per_ip: Some(8),
+            serving_limits: Default::default(),
+            nat_traversal: false,
+            relays: vec![],
+            upnp: false,
         }
     }
 }
+
+/// Rate limits of the chain exchange and bitswap requests served to peers,
+/// with bursts of up to 10 seconds worth of requests. A limit of 0 disables
+/// it, and all are disabled by default. A bitswap message counts as a single
+/// request whatever the size of its want-list. Chain exchange requests over
+/// the limits are answered with `GoAway`.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ServingLimits {
+    /// Requests served per second to each peer.
+    pub peer_requests_per_sec: u64,
+    /// Bytes served per second to each peer.
+    pub peer_bytes_per_sec: u64,
+    /// Bytes served per second to all peers.
+    pub global_bytes_per_sec: u64,
+}
+
+impl Libp2pConfig {
+    /// Parsed protected peers, skipping the invalid ones.
+    pub fn protected_peer_ids(&self) -> Vec<PeerId> {
+        self.protected_peers
+            .iter()
+            .filter_map(|peer| match peer.parse() {
+                Ok(peer_id) => Some(peer_id),
+                Err(e) => {
+                    warn!("Ignoring invalid protected peer {peer}: {e}");
+                    None
+                }
+            })
+            .collect()
+    }
+}
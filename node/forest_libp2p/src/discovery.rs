@@ -10,10 +10,15 @@ use std::{
 };
 
 use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
+use cid::Cid;
+use futures::channel::oneshot::Sender as OneShotSender;
 use libp2p::{
     core::Multiaddr,
     identity::{PeerId, PublicKey},
-    kad::{record::store::MemoryStore, Kademlia, KademliaConfig, KademliaEvent, QueryId},
+    kad::{
+        record::{store::MemoryStore, Key},
+        GetProvidersOk, Kademlia, KademliaConfig, KademliaEvent, QueryId, QueryResult,
+    },
     mdns::{tokio::Behaviour as Mdns, Event as MdnsEvent},
     multiaddr::Protocol,
     swarm::{
@@ -22,8 +27,16 @@ use libp2p::{
     },
 };
 use log::{debug, error, trace, warn};
+use serde::{Deserialize, Serialize};
 use tokio::time::Interval;
 
+/// Returns the DHT key of the providers of a block. Providers are recorded
+/// under the multihash of blocks, whatever the version and codec of the [Cid]
+/// they are known by.
+fn provider_key(cid: &Cid) -> Key {
+    Key::new(&cid.hash().to_bytes())
+}
+
 /// Event generated by the `DiscoveryBehaviour`.
 #[derive(Debug)]
 pub enum DiscoveryEvent {
@@ -34,6 +47,37 @@ pub enum DiscoveryEvent {
     /// Event that notifies that we disconnected with the node with the given
     /// peer id.
     PeerDisconnected(PeerId),
+
+    /// Event that notifies that providers of the block with the given [Cid]
+    /// were found in the DHT.
+    ProvidersFound(Cid, Vec<PeerId>),
+}
+
+/// Peers of a bucket of the Kademlia routing table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DhtBucket {
+    /// Index of the bucket, the base-2 logarithm of the distance of its peers
+    /// to the node.
+    pub index: u32,
+    pub peers: Vec<DhtPeer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DhtPeer {
+    #[serde(rename = "ID")]
+    pub peer_id: String,
+    pub addrs: Vec<Multiaddr>,
+    pub connected: bool,
+}
+
+/// Returns the peer ID of a multi-address ending with the `/p2p/` protocol.
+pub(crate) fn peer_id_from_multiaddr(multiaddr: &Multiaddr) -> Option<PeerId> {
+    match multiaddr.iter().last() {
+        Some(Protocol::P2p(mh)) => PeerId::from_multihash(mh).ok(),
+        _ => None,
+    }
 }
 
 /// `DiscoveryBehaviour` configuration.
@@ -76,11 +120,10 @@ impl<'a> DiscoveryConfig<'a> {
     {
         self.user_defined
             .extend(user_defined.into_iter().filter_map(|multiaddr| {
-                let mut addr = multiaddr.clone();
-                if let Some(Protocol::P2p(mh)) = addr.pop() {
-                    if let Ok(peer_id) = PeerId::from_multihash(mh) {
-                        return Some((peer_id, addr));
-                    }
+                if let Some(peer_id) = peer_id_from_multiaddr(&multiaddr) {
+                    let mut addr = multiaddr;
+                    addr.pop();
+                    return Some((peer_id, addr));
                 }
                 warn!("Could not parse bootstrap addr {}", multiaddr);
                 None
@@ -156,6 +199,8 @@ impl<'a> DiscoveryConfig<'a> {
             peers,
             peer_addresses,
             target_peer_count,
+            find_peer_queries: HashMap::new(),
+            provider_queries: HashMap::new(),
         }
     }
 }
@@ -185,6 +230,10 @@ pub struct DiscoveryBehaviour {
     peer_addresses: HashMap<PeerId, HashSet<Multiaddr>>,
     /// Number of connected peers to pause discovery on.
     target_peer_count: u64,
+    /// Peers looked up in the DHT, by Kademlia query.
+    find_peer_queries: HashMap<QueryId, (PeerId, OneShotSender<Vec<Multiaddr>>)>,
+    /// Providers of blocks looked up in the DHT, by Kademlia query.
+    provider_queries: HashMap<QueryId, Cid>,
 }
 
 impl DiscoveryBehaviour {
@@ -198,6 +247,86 @@ impl DiscoveryBehaviour {
         &self.peer_addresses
     }
 
+    /// Adds a known address of a peer to the Kademlia routing table, to be
+    /// used when bootstrapping.
+    pub fn add_address(&mut self, peer_id: &PeerId, addr: Multiaddr) {
+        if let Some(active_kad) = self.kademlia.as_mut() {
+            active_kad.add_address(peer_id, addr);
+        }
+    }
+
+    /// Looks a peer up in the DHT, sending its addresses once found, or an
+    /// empty list if it could not be found.
+    pub fn find_peer(&mut self, peer_id: PeerId, response_channel: OneShotSender<Vec<Multiaddr>>) {
+        if self.peer_addresses.contains_key(&peer_id) {
+            let _ = response_channel.send(self.known_addresses(&peer_id));
+            return;
+        }
+        match self.kademlia.as_mut() {
+            Some(kad) => {
+                let query_id = kad.get_closest_peers(peer_id);
+                self.find_peer_queries
+                    .insert(query_id, (peer_id, response_channel));
+            }
+            None => {
+                let _ = response_channel.send(vec![]);
+            }
+        }
+    }
+
+    /// Looks the providers of a block up in the DHT, they are reported with
+    /// [`DiscoveryEvent::ProvidersFound`] as they are found.
+    pub fn find_providers(&mut self, cid: Cid) {
+        if let Some(kad) = self.kademlia.as_mut() {
+            let query_id = kad.get_providers(provider_key(&cid));
+            self.provider_queries.insert(query_id, cid);
+        }
+    }
+
+    /// Returns the addresses of a peer, from its connections or else from the
+    /// Kademlia routing table.
+    fn known_addresses(&mut self, peer_id: &PeerId) -> Vec<Multiaddr> {
+        if let Some(addrs) = self.peer_addresses.get(peer_id) {
+            return addrs.iter().cloned().collect();
+        }
+        self.kademlia
+            .as_mut()
+            .and_then(|kad| {
+                let bucket = kad.kbucket(*peer_id)?;
+                let addrs = bucket
+                    .iter()
+                    .find(|entry| entry.node.key.preimage() == peer_id)
+                    .map(|entry| entry.node.value.iter().cloned().collect());
+                addrs
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the non-empty buckets of the Kademlia routing table.
+    pub fn routing_table(&mut self) -> Vec<DhtBucket> {
+        let peers = &self.peers;
+        match self.kademlia.as_mut() {
+            Some(kad) => kad
+                .kbuckets()
+                .map(|bucket| DhtBucket {
+                    index: bucket.range().0.ilog2().unwrap_or_default(),
+                    peers: bucket
+                        .iter()
+                        .map(|entry| {
+                            let peer_id = entry.node.key.preimage();
+                            DhtPeer {
+                                peer_id: peer_id.to_string(),
+                                addrs: entry.node.value.iter().cloned().collect(),
+                                connected: peers.contains(peer_id),
+                            }
+                        })
+                        .collect(),
+                })
+                .collect(),
+            None => vec![],
+        }
+    }
+
     /// Bootstrap Kademlia network
     pub fn bootstrap(&mut self) -> Result<QueryId, String> {
         if let Some(active_kad) = self.kademlia.as_mut() {
@@ -357,6 +486,41 @@ impl NetworkBehaviour for DiscoveryBehaviour {
                     KademliaEvent::PendingRoutablePeer { .. } => {
                         // Intentionally ignore
                     }
+                    KademliaEvent::OutboundQueryProgressed {
+                        id,
+                        result: QueryResult::GetClosestPeers(_),
+                        step,
+                        ..
+                    } if step.last => {
+                        if let Some((peer_id, response_channel)) =
+                            self.find_peer_queries.remove(&id)
+                        {
+                            let _ = response_channel.send(self.known_addresses(&peer_id));
+                        }
+                    }
+                    KademliaEvent::OutboundQueryProgressed {
+                        id,
+                        result: QueryResult::GetProviders(result),
+                        step,
+                        ..
+                    } => {
+                        let cid = if step.last {
+                            self.provider_queries.remove(&id)
+                        } else {
+                            self.provider_queries.get(&id).copied()
+                        };
+                        match (cid, result) {
+                            (Some(cid), Ok(GetProvidersOk::FoundProviders { providers, .. })) => {
+                                self.pending_events
+                                    .push_back(DiscoveryEvent::ProvidersFound(
+                                        cid,
+                                        providers.into_iter().collect(),
+                                    ));
+                            }
+                            (_, Err(e)) => debug!("Provider lookup failed: {e}"),
+                            _ => {}
+                        }
+                    }
                     other => {
                         trace!("Libp2p => Unhandled Kademlia event: {:?}", other)
                     }
@@ -443,3 +607,33 @@ impl NetworkBehaviour for DiscoveryBehaviour {
         Poll::Pending
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::{Code::Sha2_256, MultihashDigest};
+    use fvm_ipld_encoding::DAG_CBOR;
+    use libp2p::kad::record::{store::RecordStore, ProviderRecord};
+
+    use super::*;
+
+    #[test]
+    fn provider_key_matches_published_records() {
+        let hash = Sha2_256.digest(b"block");
+        let provider = PeerId::random();
+        // Providers publish the multihash of the block, whatever its CID
+        let mut store = MemoryStore::new(PeerId::random());
+        store
+            .add_provider(ProviderRecord::new(
+                Key::new(&hash.to_bytes()),
+                provider,
+                vec![],
+            ))
+            .unwrap();
+
+        for cid in [Cid::new_v0(hash).unwrap(), Cid::new_v1(DAG_CBOR, hash)] {
+            let providers = store.providers(&provider_key(&cid));
+            assert_eq!(providers.len(), 1);
+            assert_eq!(providers[0].provider, provider);
+        }
+    }
+}
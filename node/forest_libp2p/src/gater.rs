@@ -0,0 +1,428 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Connection gater, denying connections at the swarm's connection
+//! establishment stage according to the operator's allow and deny lists, and
+//! to the connection limits.
+
+use std::{
+    net::IpAddr,
+    str::FromStr,
+    task::{Context, Poll},
+};
+
+use ahash::{HashMap, HashSet};
+use libp2p::{
+    core::{ConnectedPoint, Endpoint},
+    multiaddr::Protocol,
+    swarm::{
+        derive_prelude::*, dummy, ConnectionDenied, NetworkBehaviour, NetworkBehaviourAction,
+        PollParameters, THandler, THandlerInEvent, THandlerOutEvent,
+    },
+    Multiaddr, PeerId,
+};
+use log::warn;
+
+use crate::config::Libp2pConfig;
+
+/// Entry of the allow and deny lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GaterRule {
+    Peer(PeerId),
+    Ip(IpAddr),
+    Cidr(IpAddr, u8),
+}
+
+impl FromStr for GaterRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((ip, prefix_len)) = s.split_once('/') {
+            let ip: IpAddr = ip.parse()?;
+            let prefix_len: u8 = prefix_len.parse()?;
+            let max_len = if ip.is_ipv4() { 32 } else { 128 };
+            anyhow::ensure!(
+                prefix_len <= max_len,
+                "CIDR prefix length {prefix_len} exceeds {max_len}"
+            );
+            Ok(Self::Cidr(ip, prefix_len))
+        } else if let Ok(ip) = s.parse() {
+            Ok(Self::Ip(ip))
+        } else {
+            Ok(Self::Peer(s.parse()?))
+        }
+    }
+}
+
+impl GaterRule {
+    fn matches_peer(&self, peer: &PeerId) -> bool {
+        matches!(self, Self::Peer(p) if p == peer)
+    }
+
+    fn matches_ip(&self, ip: &IpAddr) -> bool {
+        match (self, ip) {
+            (Self::Ip(rule), ip) => rule == ip,
+            (Self::Cidr(IpAddr::V4(net), len), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - *len as u32).unwrap_or(0);
+                u32::from(*net) & mask == u32::from(*ip) & mask
+            }
+            (Self::Cidr(IpAddr::V6(net), len), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - *len as u32).unwrap_or(0);
+                u128::from(*net) & mask == u128::from(*ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Parses the allow or deny list entries, skipping the invalid ones.
+fn parse_rules(entries: &[String]) -> Vec<GaterRule> {
+    entries
+        .iter()
+        .filter_map(|entry| match entry.parse() {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                warn!("Ignoring invalid allow/deny list entry {entry}: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Returns the IP address of a multiaddr, if any.
+fn ip_of(addr: &Multiaddr) -> Option<IpAddr> {
+    addr.iter().find_map(|p| match p {
+        Protocol::Ip4(ip) => Some(ip.into()),
+        Protocol::Ip6(ip) => Some(ip.into()),
+        _ => None,
+    })
+}
+
+#[derive(Debug, thiserror::Error)]
+enum GaterError {
+    #[error("{0} is in the deny list")]
+    Denied(String),
+    #[error("too many connections from {0}")]
+    TooManyConnectionsPerIp(IpAddr),
+    #[error("maximum number of established {0} connections reached")]
+    TooManyConnections(&'static str),
+}
+
+/// Behaviour denying connections with the peers and IP addresses of the deny
+/// list, and enforcing the connection limits. Peers and IP addresses of the
+/// allow list, as well as protected peers, are exempt from both.
+pub(crate) struct ConnectionGater {
+    allow_list: Vec<GaterRule>,
+    deny_list: Vec<GaterRule>,
+    protected_peers: HashSet<PeerId>,
+    max_connections_per_ip: Option<u32>,
+    max_established_incoming: u32,
+    max_established_outgoing: u32,
+    connections_per_ip: HashMap<IpAddr, u32>,
+    established_incoming: u32,
+    established_outgoing: u32,
+}
+
+impl ConnectionGater {
+    pub fn new(config: &Libp2pConfig) -> Self {
+        ConnectionGater {
+            allow_list: parse_rules(&config.allow_list),
+            deny_list: parse_rules(&config.deny_list),
+            protected_peers: config.protected_peer_ids().into_iter().collect(),
+            max_connections_per_ip: config.max_connections_per_ip,
+            max_established_incoming: config.target_peer_count,
+            max_established_outgoing: config.target_peer_count,
+            connections_per_ip: Default::default(),
+            established_incoming: 0,
+            established_outgoing: 0,
+        }
+    }
+
+    fn is_exempt(&self, peer: &PeerId, ip: Option<&IpAddr>) -> bool {
+        self.protected_peers.contains(peer)
+            || self
+                .allow_list
+                .iter()
+                .any(|rule| rule.matches_peer(peer) || ip.map_or(false, |ip| rule.matches_ip(ip)))
+    }
+
+    fn check_ip(&self, ip: Option<&IpAddr>) -> Result<(), ConnectionDenied> {
+        match ip {
+            Some(ip)
+                if !self.allow_list.iter().any(|rule| rule.matches_ip(ip))
+                    && self.deny_list.iter().any(|rule| rule.matches_ip(ip)) =>
+            {
+                Err(ConnectionDenied::new(GaterError::Denied(ip.to_string())))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_peer(&self, peer: &PeerId) -> Result<(), ConnectionDenied> {
+        if self.protected_peers.contains(peer)
+            || self.allow_list.iter().any(|rule| rule.matches_peer(peer))
+        {
+            return Ok(());
+        }
+        if self.deny_list.iter().any(|rule| rule.matches_peer(peer)) {
+            return Err(ConnectionDenied::new(GaterError::Denied(peer.to_string())));
+        }
+        Ok(())
+    }
+
+    fn check_established(
+        &self,
+        peer: &PeerId,
+        remote_addr: &Multiaddr,
+        endpoint: Endpoint,
+    ) -> Result<(), ConnectionDenied> {
+        let ip = ip_of(remote_addr);
+        if self.is_exempt(peer, ip.as_ref()) {
+            return Ok(());
+        }
+        self.check_peer(peer)?;
+        self.check_ip(ip.as_ref())?;
+        if let (Some(ip), Some(max)) = (ip, self.max_connections_per_ip) {
+            if self
+                .connections_per_ip
+                .get(&ip)
+                .copied()
+                .unwrap_or_default()
+                >= max
+            {
+                return Err(ConnectionDenied::new(GaterError::TooManyConnectionsPerIp(
+                    ip,
+                )));
+            }
+        }
+        let (established, max, direction) = match endpoint {
+            Endpoint::Listener => (
+                self.established_incoming,
+                self.max_established_incoming,
+                "incoming",
+            ),
+            Endpoint::Dialer => (
+                self.established_outgoing,
+                self.max_established_outgoing,
+                "outgoing",
+            ),
+        };
+        if established >= max {
+            return Err(ConnectionDenied::new(GaterError::TooManyConnections(
+                direction,
+            )));
+        }
+        Ok(())
+    }
+
+    fn on_connection_change(&mut self, endpoint: &ConnectedPoint, established: bool) {
+        let counter = if endpoint.is_dialer() {
+            &mut self.established_outgoing
+        } else {
+            &mut self.established_incoming
+        };
+        if established {
+            *counter += 1;
+        } else {
+            *counter = counter.saturating_sub(1);
+        }
+        if let Some(ip) = ip_of(endpoint.get_remote_address()) {
+            let count = self.connections_per_ip.entry(ip).or_default();
+            if established {
+                *count += 1;
+            } else {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    self.connections_per_ip.remove(&ip);
+                }
+            }
+        }
+    }
+}
+
+impl NetworkBehaviour for ConnectionGater {
+    type ConnectionHandler = dummy::ConnectionHandler;
+    type OutEvent = std::convert::Infallible;
+
+    fn handle_pending_inbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        _local_addr: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<(), ConnectionDenied> {
+        self.check_ip(ip_of(remote_addr).as_ref())
+    }
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        peer: PeerId,
+        _local_addr: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.check_established(&peer, remote_addr, Endpoint::Listener)?;
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn handle_pending_outbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        maybe_peer: Option<PeerId>,
+        addresses: &[Multiaddr],
+        _effective_role: Endpoint,
+    ) -> Result<Vec<Multiaddr>, ConnectionDenied> {
+        if let Some(peer) = maybe_peer {
+            if self.is_exempt(&peer, None) {
+                return Ok(vec![]);
+            }
+            self.check_peer(&peer)?;
+        }
+        // Candidate addresses can not be removed from the dial, which is
+        // denied when all of them are on the deny list
+        let mut denied = None;
+        for addr in addresses {
+            match self.check_ip(ip_of(addr).as_ref()) {
+                Ok(()) => return Ok(vec![]),
+                Err(e) => denied = Some(e),
+            }
+        }
+        match denied {
+            Some(e) => Err(e),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        peer: PeerId,
+        addr: &Multiaddr,
+        _role_override: Endpoint,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.check_established(&peer, addr, Endpoint::Dialer)?;
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn on_swarm_event(&mut self, event: FromSwarm<Self::ConnectionHandler>) {
+        match event {
+            FromSwarm::ConnectionEstablished(e) => self.on_connection_change(e.endpoint, true),
+            FromSwarm::ConnectionClosed(e) => self.on_connection_change(e.endpoint, false),
+            _ => {}
+        }
+    }
+
+    fn on_connection_handler_event(
+        &mut self,
+        _peer_id: PeerId,
+        _connection: ConnectionId,
+        event: THandlerOutEvent<Self>,
+    ) {
+        match event {}
+    }
+
+    fn poll(
+        &mut self,
+        _cx: &mut Context,
+        _params: &mut impl PollParameters,
+    ) -> Poll<NetworkBehaviourAction<Self::OutEvent, THandlerInEvent<Self>>> {
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(ip: &str) -> Multiaddr {
+        format!("/ip4/{ip}/tcp/1234").parse().unwrap()
+    }
+
+    #[test]
+    fn rule_matching() {
+        let peer = PeerId::random();
+        let rule: GaterRule = peer.to_string().parse().unwrap();
+        assert!(rule.matches_peer(&peer));
+        assert!(!rule.matches_peer(&PeerId::random()));
+
+        let rule: GaterRule = "10.1.0.0/16".parse().unwrap();
+        assert!(rule.matches_ip(&"10.1.200.3".parse().unwrap()));
+        assert!(!rule.matches_ip(&"10.2.0.1".parse().unwrap()));
+        assert!(!rule.matches_ip(&"::1".parse().unwrap()));
+
+        let rule: GaterRule = "0.0.0.0/0".parse().unwrap();
+        assert!(rule.matches_ip(&"1.2.3.4".parse().unwrap()));
+
+        let rule: GaterRule = "2001:db8::/32".parse().unwrap();
+        assert!(rule.matches_ip(&"2001:db8::1".parse().unwrap()));
+        assert!(!rule.matches_ip(&"2001:db9::1".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<GaterRule>().is_err());
+        assert!("not a peer".parse::<GaterRule>().is_err());
+    }
+
+    #[test]
+    fn deny_and_limits() {
+        let protected = PeerId::random();
+        let config = Libp2pConfig {
+            allow_list: vec!["10.0.0.1".into()],
+            deny_list: vec!["10.0.0.0/8".into()],
+            protected_peers: vec![protected.to_string()],
+            max_connections_per_ip: Some(1),
+            ..Default::default()
+        };
+        let mut gater = ConnectionGater::new(&config);
+        let connection_id = ConnectionId::new_unchecked(0);
+        let local = addr("127.0.0.1");
+
+        assert!(gater
+            .handle_pending_inbound_connection(connection_id, &local, &addr("10.0.0.2"))
+            .is_err());
+        assert!(gater
+            .handle_pending_inbound_connection(connection_id, &local, &addr("10.0.0.1"))
+            .is_ok());
+        assert!(gater
+            .handle_pending_inbound_connection(connection_id, &local, &addr("192.168.0.1"))
+            .is_ok());
+
+        let remote = addr("192.168.0.1");
+        gater.on_connection_change(
+            &ConnectedPoint::Listener {
+                local_addr: local.clone(),
+                send_back_addr: remote.clone(),
+            },
+            true,
+        );
+        assert!(gater
+            .check_established(&PeerId::random(), &remote, Endpoint::Listener)
+            .is_err());
+        assert!(gater
+            .check_established(&protected, &remote, Endpoint::Listener)
+            .is_ok());
+    }
+
+    #[test]
+    fn deny_outbound_addresses() {
+        let protected = PeerId::random();
+        let config = Libp2pConfig {
+            deny_list: vec!["10.0.0.0/8".into()],
+            protected_peers: vec![protected.to_string()],
+            ..Default::default()
+        };
+        let mut gater = ConnectionGater::new(&config);
+        let connection_id = ConnectionId::new_unchecked(0);
+        let mut dial = |peer, addresses: &[Multiaddr]| {
+            gater.handle_pending_outbound_connection(
+                connection_id,
+                Some(peer),
+                addresses,
+                Endpoint::Dialer,
+            )
+        };
+
+        let denied = [addr("10.0.0.1"), addr("10.0.0.2")];
+        assert!(dial(PeerId::random(), &denied).is_err());
+        assert!(dial(PeerId::random(), &[addr("10.0.0.1"), addr("192.168.0.1")]).is_ok());
+        assert!(dial(PeerId::random(), &[]).is_ok());
+        assert!(dial(protected, &denied).is_ok());
+    }
+}
@@ -10,10 +10,19 @@ use libp2p::gossipsub::{
 use crate::{PUBSUB_BLOCK_STR, PUBSUB_MSG_STR};
 
 // All these parameters are copied from what Lotus has set for their Topic
-// scores. They are currently unused because enabling them causes GossipSub
-// blocks to come delayed usually by 1 second compared to when we have these
-// parameters disabled. Leaving these here so that we can enable and fix these
-// parameters when they are needed.
+// scores.
+//
+// Enabling them used to delay GossipSub blocks by about 1 second: Lotus
+// bootstrappers keep no mesh and prune every grafting peer with peer exchange,
+// which is only accepted from peers scoring above `accept_px_threshold`. Only
+// the application specific score can reach that threshold, and without it our
+// mesh stayed under-populated, so blocks were mostly received through `IHAVE`
+// gossip, sent on the next heartbeat. Bootstrap peers are thus given the same
+// application score as in Lotus, see [`BOOTSTRAP_PEER_APP_SCORE`].
+
+/// Application specific score of bootstrap peers, high enough to accept their
+/// peer exchange and to outweigh the penalties they may get.
+pub(crate) const BOOTSTRAP_PEER_APP_SCORE: f64 = 2500.0;
 
 fn build_msg_topic_config() -> TopicScoreParams {
     TopicScoreParams {
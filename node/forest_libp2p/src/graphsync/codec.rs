@@ -0,0 +1,211 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::io;
+
+use async_trait::async_trait;
+use futures::prelude::*;
+use libp2p::request_response;
+
+use super::{
+    provider::GRAPHSYNC_MAX_RESPONSE_BYTES, GraphsyncProtocolName, GraphsyncRequest,
+    GraphsyncResponse,
+};
+use crate::rpc::{encode_and_write, read_request_and_decode};
+
+/// Size of the blocks written in a chunk of a response, a chunk holding at
+/// least one block whatever its size.
+const CHUNK_BLOCK_BYTES: usize = 1 << 20;
+/// Maximum size of an encoded chunk read from a peer.
+const MAX_CHUNK_BYTES: usize = 4 << 20;
+
+/// Graphsync protocol codec. Responses are written as a sequence of length
+/// prefixed chunks, so that they are read in bounded chunks rather than
+/// buffered whole before being decoded.
+#[derive(Clone, Default)]
+pub struct GraphsyncCodec;
+
+impl GraphsyncResponse {
+    /// Splits the response into chunks of up to [`CHUNK_BLOCK_BYTES`] of
+    /// blocks each, the first one carrying the status message.
+    fn into_chunks(self) -> Vec<GraphsyncResponse> {
+        let mut chunks = vec![GraphsyncResponse {
+            status: self.status,
+            message: self.message,
+            blocks: vec![],
+        }];
+        let mut size = 0;
+        for block in self.blocks {
+            let len = block.data.bytes().len();
+            // Unwrapping is safe here because there is always at least one chunk
+            let chunk = chunks.last_mut().unwrap();
+            if !chunk.blocks.is_empty() && size + len > CHUNK_BLOCK_BYTES {
+                chunks.push(GraphsyncResponse {
+                    status: self.status,
+                    message: String::new(),
+                    blocks: vec![block],
+                });
+                size = len;
+            } else {
+                chunk.blocks.push(block);
+                size += len;
+            }
+        }
+        chunks
+    }
+}
+
+/// Reads the next chunk of a response, `None` once the stream is closed.
+async fn read_chunk<T>(io: &mut T) -> io::Result<Option<Vec<u8>>>
+where
+    T: AsyncRead + Unpin + Send,
+{
+    let mut len = [0; 4];
+    match io.read_exact(&mut len).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_CHUNK_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("Graphsync chunk of {len}B exceeds the maximum allowed {MAX_CHUNK_BYTES}B"),
+        ));
+    }
+    let mut bytes = vec![0; len];
+    io.read_exact(&mut bytes).await?;
+    Ok(Some(bytes))
+}
+
+#[async_trait]
+impl request_response::Codec for GraphsyncCodec {
+    type Protocol = GraphsyncProtocolName;
+    type Request = GraphsyncRequest;
+    type Response = GraphsyncResponse;
+
+    async fn read_request<T>(&mut self, _: &Self::Protocol, io: &mut T) -> io::Result<Self::Request>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_request_and_decode(io).await
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        _: &Self::Protocol,
+        io: &mut T,
+    ) -> io::Result<Self::Response>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let mut response: Option<GraphsyncResponse> = None;
+        let mut size = 0;
+        while let Some(bytes) = read_chunk(io).await? {
+            size += bytes.len();
+            if size > GRAPHSYNC_MAX_RESPONSE_BYTES + MAX_CHUNK_BYTES {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "Graphsync response exceeds the maximum allowed size",
+                ));
+            }
+            let chunk: GraphsyncResponse = fvm_ipld_encoding::from_slice(&bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+            match response.as_mut() {
+                Some(response) => response.blocks.extend(chunk.blocks),
+                None => response = Some(chunk),
+            }
+        }
+        response.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Graphsync response has no chunk",
+            )
+        })
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &Self::Protocol,
+        io: &mut T,
+        req: Self::Request,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        encode_and_write(io, req).await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &Self::Protocol,
+        io: &mut T,
+        res: Self::Response,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        for chunk in res.into_chunks() {
+            let bytes = fvm_ipld_encoding::to_vec(&chunk)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+            io.write_all(&(bytes.len() as u32).to_be_bytes()).await?;
+            io.write_all(&bytes).await?;
+        }
+        io.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::{
+        multihash::{Code::Blake2b256, MultihashDigest},
+        Cid,
+    };
+    use futures::io::Cursor;
+    use fvm_ipld_encoding::DAG_CBOR;
+    use request_response::Codec;
+
+    use super::*;
+    use crate::graphsync::{GraphsyncBlock, GraphsyncResponseStatus};
+
+    fn block(i: u8) -> GraphsyncBlock {
+        let data = vec![i; 300 << 10];
+        GraphsyncBlock {
+            cid: Cid::new_v1(DAG_CBOR, Blake2b256.digest(&data)),
+            data: data.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn chunked_response_roundtrip() {
+        let response = GraphsyncResponse {
+            status: GraphsyncResponseStatus::Partial,
+            message: "Response size limit reached".into(),
+            blocks: (0..10).map(block).collect(),
+        };
+        assert_eq!(response.clone().into_chunks().len(), 4);
+
+        let mut io = Cursor::new(vec![]);
+        GraphsyncCodec
+            .write_response(&GraphsyncProtocolName, &mut io, response.clone())
+            .await
+            .unwrap();
+        io.set_position(0);
+        let read = GraphsyncCodec
+            .read_response(&GraphsyncProtocolName, &mut io)
+            .await
+            .unwrap();
+        assert_eq!(read, response);
+    }
+
+    #[tokio::test]
+    async fn oversized_chunk() {
+        let mut bytes = ((MAX_CHUNK_BYTES + 1) as u32).to_be_bytes().to_vec();
+        bytes.resize(MAX_CHUNK_BYTES + 5, 0);
+        let mut io = Cursor::new(bytes);
+        assert!(GraphsyncCodec
+            .read_response(&GraphsyncProtocolName, &mut io)
+            .await
+            .is_err());
+    }
+}
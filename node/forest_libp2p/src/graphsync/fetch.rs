@@ -0,0 +1,330 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::time::Duration;
+
+use ahash::{HashMap, HashSet};
+use anyhow::Context;
+use async_trait::async_trait;
+use cid::Cid;
+use forest_ipld::{selector::LinkResolver, Ipld};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::RawBytes;
+use libp2p::PeerId;
+use log::debug;
+
+use super::{
+    provider::decode_block, recursive_all_selector, GraphsyncBlock, GraphsyncRequest,
+    GraphsyncResponseStatus, GRAPHSYNC_MAX_BLOCKS,
+};
+use crate::NetworkMessage;
+
+/// Timeout of graphsync requests, longer than that of chain exchange requests
+/// as responses carry whole graphs.
+pub const GRAPHSYNC_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Link resolver putting the blocks of a response in the store as the
+/// selector reaches them, so that blocks outside of the requested graph are
+/// dropped.
+struct BlockImporter<'a, DB> {
+    db: &'a DB,
+    received: HashMap<Cid, RawBytes>,
+    imported: usize,
+}
+
+#[async_trait]
+impl<'a, 'b, DB> LinkResolver for &'a mut BlockImporter<'b, DB>
+where
+    DB: Blockstore + Sync,
+{
+    async fn load_link(&mut self, link: &Cid) -> Result<Option<Ipld>, String> {
+        if let Some(data) = self.received.remove(link) {
+            let block = GraphsyncBlock { cid: *link, data };
+            if !block.is_valid() {
+                return Err(format!("block {link} does not match its CID"));
+            }
+            let ipld = decode_block(link, block.data.bytes())?;
+            self.db
+                .put_keyed(link, block.data.bytes())
+                .map_err(|e| e.to_string())?;
+            self.imported += 1;
+            return Ok(Some(ipld));
+        }
+        match self.db.get(link).map_err(|e| e.to_string())? {
+            Some(data) => decode_block(link, &data).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Fetches the blocks traversed by the selector of a request from a peer in
+/// a single graphsync request, and puts those the selector reaches from the
+/// root in the store. Returns the status of the response and the number of
+/// blocks put in the store.
+pub async fn fetch_graph<DB>(
+    network_send: &flume::Sender<NetworkMessage>,
+    db: &DB,
+    peer_id: PeerId,
+    request: GraphsyncRequest,
+) -> anyhow::Result<(GraphsyncResponseStatus, usize)>
+where
+    DB: Blockstore + Sync,
+{
+    let (tx, rx) = flume::bounded(1);
+    network_send
+        .send_async(NetworkMessage::GraphsyncRequest {
+            peer_id,
+            request: request.clone(),
+            response_channel: tx,
+        })
+        .await
+        .context("Failed to send graphsync request to network")?;
+
+    let response = match tokio::time::timeout(GRAPHSYNC_TIMEOUT, rx.recv_async()).await {
+        Ok(Ok(Ok(response))) => response,
+        Ok(Ok(Err(e))) => anyhow::bail!("Graphsync request to {peer_id} failed: {e:?}"),
+        Ok(Err(_)) | Err(_) => anyhow::bail!("Graphsync request to {peer_id} timed out"),
+    };
+    match response.status {
+        GraphsyncResponseStatus::Complete | GraphsyncResponseStatus::Partial => {}
+        status => anyhow::bail!(
+            "Graphsync request to {peer_id} failed with status {status:?}: {}",
+            response.message
+        ),
+    }
+
+    let mut importer = BlockImporter {
+        db,
+        received: response
+            .blocks
+            .into_iter()
+            .map(|block| (block.cid, block.data))
+            .collect(),
+        imported: 0,
+    };
+    request
+        .selector
+        .walk_all(&Ipld::Link(request.root), Some(&mut importer), |_, _, _| {
+            Ok(())
+        })
+        .await?;
+    Ok((response.status, importer.imported))
+}
+
+/// Link resolver loading blocks from the store and recording the links to
+/// blocks missing from it. Blocks already visited are not walked again.
+struct MissingLinks<'a, DB> {
+    db: &'a DB,
+    visited: &'a mut HashSet<Cid>,
+    missing: Vec<Cid>,
+}
+
+#[async_trait]
+impl<'a, 'b, DB> LinkResolver for &'a mut MissingLinks<'b, DB>
+where
+    DB: Blockstore + Sync,
+{
+    async fn load_link(&mut self, link: &Cid) -> Result<Option<Ipld>, String> {
+        if self.visited.contains(link) {
+            return Ok(None);
+        }
+        match self.db.get(link).map_err(|e| e.to_string())? {
+            Some(data) => {
+                self.visited.insert(*link);
+                decode_block(link, &data).map(Some)
+            }
+            None => {
+                self.missing.push(*link);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Returns the links to the blocks of the graph at `root` that are missing
+/// from the store, skipping the blocks in `visited` and adding those walked
+/// to it.
+async fn missing_links<DB>(
+    db: &DB,
+    visited: &mut HashSet<Cid>,
+    root: Cid,
+) -> anyhow::Result<Vec<Cid>>
+where
+    DB: Blockstore + Sync,
+{
+    let mut resolver = MissingLinks {
+        db,
+        visited,
+        missing: vec![],
+    };
+    recursive_all_selector()
+        .walk_all(&Ipld::Link(root), Some(&mut resolver), |_, _, _| Ok(()))
+        .await?;
+    Ok(resolver.missing)
+}
+
+/// Fetches every block reachable from `root` that is missing from the store,
+/// such as a whole state tree, with as many graphsync requests to `peers` as
+/// the response limits require. Each request asks for the graph at a link
+/// still missing after the previous ones. Blocks in `visited` are not walked
+/// again, so that graphs sharing blocks, such as consecutive state trees,
+/// are walked once when fetched with the same set. Returns the number of
+/// blocks put in the store.
+pub async fn fetch_dag<DB>(
+    network_send: &flume::Sender<NetworkMessage>,
+    db: &DB,
+    visited: &mut HashSet<Cid>,
+    peers: &[PeerId],
+    root: Cid,
+) -> anyhow::Result<usize>
+where
+    DB: Blockstore + Sync,
+{
+    let mut imported = 0;
+    let mut missing = missing_links(db, visited, root).await?;
+    while let Some(link) = missing.pop() {
+        // Links shared by several parents are fetched once
+        if db.has(&link)? {
+            continue;
+        }
+        let mut fetched = false;
+        for peer_id in peers {
+            let request = GraphsyncRequest {
+                root: link,
+                selector: recursive_all_selector(),
+                max_blocks: GRAPHSYNC_MAX_BLOCKS,
+            };
+            match fetch_graph(network_send, db, *peer_id, request).await {
+                Ok((_, 0)) => debug!("Graphsync request to {peer_id} returned no block of {link}"),
+                Ok((_, n)) => {
+                    imported += n;
+                    fetched = true;
+                    break;
+                }
+                Err(e) => debug!("{e}"),
+            }
+        }
+        anyhow::ensure!(fetched, "No peer returned the graph at {link}");
+        missing.extend(missing_links(db, visited, link).await?);
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::{Code::Blake2b256, MultihashDigest};
+    use forest_db::MemoryDB;
+    use fvm_ipld_encoding::DAG_CBOR;
+    use tokio::task::JoinHandle;
+
+    use super::*;
+    use crate::graphsync::{
+        make_graphsync_response, provider::tests::store_dag, GraphsyncResponse,
+    };
+
+    /// Answers the graphsync requests sent to the network out of `db`, with
+    /// at most `max_blocks` blocks per response passed through `tamper`.
+    /// Returns the number of requests answered once the network is dropped.
+    fn spawn_responder(
+        db: MemoryDB,
+        max_blocks: u64,
+        tamper: fn(&mut GraphsyncResponse),
+    ) -> (flume::Sender<NetworkMessage>, JoinHandle<usize>) {
+        let (network_send, network_recv) = flume::unbounded();
+        let responder = tokio::spawn(async move {
+            let mut answered = 0;
+            while let Ok(message) = network_recv.recv_async().await {
+                if let NetworkMessage::GraphsyncRequest {
+                    mut request,
+                    response_channel,
+                    ..
+                } = message
+                {
+                    request.max_blocks = request.max_blocks.min(max_blocks);
+                    let mut response = make_graphsync_response(&db, request).await;
+                    tamper(&mut response);
+                    response_channel.send(Ok(response)).unwrap();
+                    answered += 1;
+                }
+            }
+            answered
+        });
+        (network_send, responder)
+    }
+
+    fn request(root: Cid) -> GraphsyncRequest {
+        GraphsyncRequest {
+            root,
+            selector: recursive_all_selector(),
+            max_blocks: GRAPHSYNC_MAX_BLOCKS,
+        }
+    }
+
+    #[tokio::test]
+    async fn unreached_blocks_are_dropped() {
+        let remote = MemoryDB::default();
+        let root = store_dag(&remote);
+        let stray_data = fvm_ipld_encoding::to_vec(&"stray").unwrap();
+        let stray = Cid::new_v1(DAG_CBOR, Blake2b256.digest(&stray_data));
+        let (network_send, _) = spawn_responder(remote, GRAPHSYNC_MAX_BLOCKS, |response| {
+            let data = fvm_ipld_encoding::to_vec(&"stray").unwrap();
+            response.blocks.push(GraphsyncBlock {
+                cid: Cid::new_v1(DAG_CBOR, Blake2b256.digest(&data)),
+                data: data.into(),
+            });
+        });
+
+        let db = MemoryDB::default();
+        let (status, imported) = fetch_graph(&network_send, &db, PeerId::random(), request(root))
+            .await
+            .unwrap();
+        assert_eq!(status, GraphsyncResponseStatus::Complete);
+        assert_eq!(imported, 4);
+        assert!(db.has(&root).unwrap());
+        assert!(!db.has(&stray).unwrap());
+    }
+
+    #[tokio::test]
+    async fn forged_blocks_are_rejected() {
+        let remote = MemoryDB::default();
+        let root = store_dag(&remote);
+        let (network_send, _) = spawn_responder(remote, GRAPHSYNC_MAX_BLOCKS, |response| {
+            response.blocks[1].data = fvm_ipld_encoding::to_vec(&"forged").unwrap().into();
+        });
+
+        let db = MemoryDB::default();
+        let error = fetch_graph(&network_send, &db, PeerId::random(), request(root))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("does not match its CID"));
+        assert!(db.has(&root).unwrap());
+    }
+
+    #[tokio::test]
+    async fn partial_responses_are_followed_up() {
+        let remote = MemoryDB::default();
+        let root = store_dag(&remote);
+        let (network_send, responder) = spawn_responder(remote, 2, |_| {});
+
+        let db = MemoryDB::default();
+        let mut visited = HashSet::default();
+        let imported = fetch_dag(&network_send, &db, &mut visited, &[PeerId::random()], root)
+            .await
+            .unwrap();
+        assert_eq!(imported, 4);
+        assert_eq!(visited.len(), 4);
+        assert!(missing_links(&db, &mut HashSet::default(), root)
+            .await
+            .unwrap()
+            .is_empty());
+        // Graphs already walked are not requested again
+        let imported = fetch_dag(&network_send, &db, &mut visited, &[PeerId::random()], root)
+            .await
+            .unwrap();
+        assert_eq!(imported, 0);
+
+        drop(network_send);
+        // The root with its left branch, then the right branch
+        assert_eq!(responder.await.unwrap(), 2);
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use cid::{
+    multihash::{Code, MultihashDigest},
+    Cid,
+};
+use forest_ipld::selector::Selector;
+use fvm_ipld_encoding::RawBytes;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_tuple::{self, Deserialize_tuple, Serialize_tuple};
+
+/// The payload that gets sent to another node to request the blocks of an
+/// IPLD graph.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct GraphsyncRequest {
+    /// The [Cid] of the block to start the traversal from.
+    pub root: Cid,
+    /// Selector applied from the root, every block it traverses is returned.
+    pub selector: Selector,
+    /// Maximum number of blocks to return, capped by the responder.
+    pub max_blocks: u64,
+}
+
+/// Status codes of a graphsync response, the same as in the `go-graphsync`
+/// protocol.
+#[derive(Clone, Debug, PartialEq, Eq, Copy)]
+pub enum GraphsyncResponseStatus {
+    /// Every block traversed by the selector is in the response.
+    Complete,
+    /// Some blocks are missing, either because the responder does not have
+    /// them or because the response is capped.
+    Partial,
+    /// The responder does not have the root block.
+    NotFound,
+    /// Requester is making too many requests.
+    GoAway,
+    /// Traversal failed.
+    InternalError,
+    /// Other undefined response code.
+    Other(i32),
+}
+
+impl Serialize for GraphsyncResponseStatus {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use GraphsyncResponseStatus::*;
+        let code: i32 = match self {
+            Complete => 20,
+            Partial => 21,
+            GoAway => 30,
+            InternalError => 31,
+            NotFound => 34,
+            Other(i) => *i,
+        };
+        code.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GraphsyncResponseStatus {
+    fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let code: i32 = Deserialize::deserialize(deserializer)?;
+
+        use GraphsyncResponseStatus::*;
+        let status = match code {
+            20 => Complete,
+            21 => Partial,
+            30 => GoAway,
+            31 => InternalError,
+            34 => NotFound,
+            x => Other(x),
+        };
+        Ok(status)
+    }
+}
+
+/// A block of the graph, as stored by the responder.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct GraphsyncBlock {
+    pub cid: Cid,
+    pub data: RawBytes,
+}
+
+impl GraphsyncBlock {
+    /// Returns true if the data hashes to the multihash of the [Cid].
+    pub fn is_valid(&self) -> bool {
+        match Code::try_from(self.cid.hash().code()) {
+            Ok(code) => code.digest(self.data.bytes()) == *self.cid.hash(),
+            Err(_) => false,
+        }
+    }
+}
+
+/// The response to a graphsync request.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct GraphsyncResponse {
+    /// Status code of the response.
+    pub status: GraphsyncResponseStatus,
+    /// Status message indicating failure reason.
+    pub message: String,
+    /// The blocks traversed, in traversal order.
+    pub blocks: Vec<GraphsyncBlock>,
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::Code::Blake2b256;
+    use fvm_ipld_encoding::DAG_CBOR;
+
+    use super::*;
+
+    #[test]
+    fn block_validation() {
+        let data = fvm_ipld_encoding::to_vec(&"forest").unwrap();
+        let cid = Cid::new_v1(DAG_CBOR, Blake2b256.digest(&data));
+        let block = GraphsyncBlock {
+            cid,
+            data: data.into(),
+        };
+        assert!(block.is_valid());
+
+        let forged = GraphsyncBlock {
+            cid,
+            data: fvm_ipld_encoding::to_vec(&"lotus").unwrap().into(),
+        };
+        assert!(!forged.is_valid());
+    }
+
+    #[test]
+    fn status_codes_roundtrip() {
+        for status in [
+            GraphsyncResponseStatus::Complete,
+            GraphsyncResponseStatus::Partial,
+            GraphsyncResponseStatus::NotFound,
+            GraphsyncResponseStatus::GoAway,
+            GraphsyncResponseStatus::InternalError,
+            GraphsyncResponseStatus::Other(40),
+        ] {
+            let bytes = fvm_ipld_encoding::to_vec(&status).unwrap();
+            assert_eq!(
+                fvm_ipld_encoding::from_slice::<GraphsyncResponseStatus>(&bytes).unwrap(),
+                status
+            );
+        }
+    }
+}
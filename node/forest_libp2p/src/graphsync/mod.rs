@@ -0,0 +1,71 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Selector-driven transfer of IPLD graphs between Forest nodes. A request
+//! names a root and a selector, and the response carries every block the
+//! selector traverses from the root, so that a state subtree or a range of the
+//! chain is fetched in a single round-trip rather than one bitswap request per
+//! block.
+
+mod behaviour;
+mod codec;
+mod fetch;
+mod message;
+mod provider;
+pub use behaviour::*;
+use forest_ipld::selector::{RecursionLimit, Selector};
+use indexmap::IndexMap;
+use libp2p::core::ProtocolName;
+
+pub use self::{codec::GraphsyncCodec, fetch::*, message::*, provider::*};
+
+/// Libp2p protocol ID for graphsync.
+pub const GRAPHSYNC_PROTOCOL_ID: &[u8] = b"/forest/graphsync/0.0.1";
+
+/// Type to satisfy `ProtocolName` interface for graphsync RPC.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct GraphsyncProtocolName;
+
+impl ProtocolName for GraphsyncProtocolName {
+    fn protocol_name(&self) -> &[u8] {
+        GRAPHSYNC_PROTOCOL_ID
+    }
+}
+
+/// Selects every block reachable from the root, e.g. a whole state tree.
+pub fn recursive_all_selector() -> Selector {
+    Selector::ExploreRecursive {
+        sequence: Box::new(Selector::ExploreAll {
+            next: Box::new(Selector::ExploreRecursiveEdge),
+        }),
+        limit: RecursionLimit::None,
+        stop_at: None,
+        current: None,
+    }
+}
+
+/// Selects the headers of the blocks reachable from a block header through
+/// `depth` generations of parents, and their messages if `include_messages`
+/// is set.
+pub fn chain_range_selector(depth: u64, include_messages: bool) -> Selector {
+    // Indices of the fields in the tuple encoding of block headers
+    const PARENTS_FIELD: &str = "5";
+    const MESSAGES_FIELD: &str = "10";
+
+    let mut fields = IndexMap::new();
+    if include_messages {
+        fields.insert(MESSAGES_FIELD.to_owned(), recursive_all_selector());
+    }
+    fields.insert(
+        PARENTS_FIELD.to_owned(),
+        Selector::ExploreAll {
+            next: Box::new(Selector::ExploreRecursiveEdge),
+        },
+    );
+    Selector::ExploreRecursive {
+        sequence: Box::new(Selector::ExploreFields { fields }),
+        limit: RecursionLimit::Depth(depth),
+        stop_at: None,
+        current: None,
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use ahash::{HashSet, HashSetExt};
+use async_trait::async_trait;
+use cid::Cid;
+use forest_ipld::{selector::LinkResolver, Error as IpldError, Ipld};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::DAG_CBOR;
+use log::debug;
+
+use super::{GraphsyncBlock, GraphsyncRequest, GraphsyncResponse, GraphsyncResponseStatus};
+
+/// Maximum number of blocks in a response, whatever the request asks for.
+pub const GRAPHSYNC_MAX_BLOCKS: u64 = 100_000;
+/// Maximum total size of the blocks in a response.
+pub(super) const GRAPHSYNC_MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Error returned by the link resolver to stop the traversal once the
+/// response is full.
+const RESPONSE_FULL: &str = "response is full";
+
+/// Decodes a block into IPLD, blocks that are not DAG-CBOR are opaque bytes.
+pub(super) fn decode_block(cid: &Cid, data: &[u8]) -> Result<Ipld, String> {
+    if cid.codec() == DAG_CBOR {
+        fvm_ipld_encoding::from_slice(data).map_err(|e| format!("failed to decode {cid}: {e}"))
+    } else {
+        Ok(Ipld::Bytes(data.to_vec()))
+    }
+}
+
+/// Link resolver loading blocks from the store and collecting every distinct
+/// block loaded, up to the response limits.
+struct BlockCollector<'a, DB> {
+    db: &'a DB,
+    max_blocks: usize,
+    blocks: Vec<GraphsyncBlock>,
+    seen: HashSet<Cid>,
+    size: usize,
+    missing: usize,
+    full: bool,
+}
+
+#[async_trait]
+impl<'a, 'b, DB> LinkResolver for &'a mut BlockCollector<'b, DB>
+where
+    DB: Blockstore + Sync,
+{
+    async fn load_link(&mut self, link: &Cid) -> Result<Option<Ipld>, String> {
+        let data = match self.db.get(link).map_err(|e| e.to_string())? {
+            Some(data) => data,
+            None => {
+                self.missing += 1;
+                return Ok(None);
+            }
+        };
+        let ipld = decode_block(link, &data)?;
+        if self.seen.insert(*link) {
+            if self.blocks.len() >= self.max_blocks
+                || self.size + data.len() > GRAPHSYNC_MAX_RESPONSE_BYTES
+            {
+                self.full = true;
+                return Err(RESPONSE_FULL.into());
+            }
+            self.size += data.len();
+            self.blocks.push(GraphsyncBlock {
+                cid: *link,
+                data: data.into(),
+            });
+        }
+        Ok(Some(ipld))
+    }
+}
+
+/// Builds a graphsync response out of the blocks traversed by the selector of
+/// the request.
+pub async fn make_graphsync_response<DB>(db: &DB, request: GraphsyncRequest) -> GraphsyncResponse
+where
+    DB: Blockstore + Sync,
+{
+    match db.has(&request.root) {
+        Ok(true) => {}
+        Ok(false) => {
+            return GraphsyncResponse {
+                status: GraphsyncResponseStatus::NotFound,
+                message: format!("Root {} was not found in the database", request.root),
+                blocks: vec![],
+            }
+        }
+        Err(e) => {
+            debug!("Cannot look up graphsync root: {e}");
+            return GraphsyncResponse {
+                status: GraphsyncResponseStatus::InternalError,
+                message: "Can not fulfil the request".into(),
+                blocks: vec![],
+            };
+        }
+    }
+
+    let mut collector = BlockCollector {
+        db,
+        max_blocks: request.max_blocks.min(GRAPHSYNC_MAX_BLOCKS) as usize,
+        blocks: vec![],
+        seen: HashSet::new(),
+        size: 0,
+        missing: 0,
+        full: false,
+    };
+    let result = request
+        .selector
+        .walk_all(
+            &Ipld::Link(request.root),
+            Some(&mut collector),
+            |_, _, _| Ok(()),
+        )
+        .await;
+
+    let (status, message) = match result {
+        Err(IpldError::Link(_)) if collector.full => (
+            GraphsyncResponseStatus::Partial,
+            "Response size limit reached".to_owned(),
+        ),
+        Err(e) => {
+            debug!("Graphsync traversal from {} failed: {e}", request.root);
+            return GraphsyncResponse {
+                status: GraphsyncResponseStatus::InternalError,
+                message: "Traversal failed".into(),
+                blocks: vec![],
+            };
+        }
+        Ok(()) if collector.missing > 0 => (
+            GraphsyncResponseStatus::Partial,
+            format!("{} blocks were not found", collector.missing),
+        ),
+        Ok(()) => (GraphsyncResponseStatus::Complete, String::new()),
+    };
+    GraphsyncResponse {
+        status,
+        message,
+        blocks: collector.blocks,
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use std::collections::BTreeMap;
+
+    use cid::multihash::{
+        Code::{Blake2b256, Identity},
+        MultihashDigest,
+    };
+    use forest_db::MemoryDB;
+    use forest_ipld::selector::Selector;
+    use forest_utils::db::BlockstoreExt;
+
+    use super::*;
+    use crate::graphsync::recursive_all_selector;
+
+    /// Stores a DAG of three levels, with a leaf shared by two branches.
+    pub(in crate::graphsync) fn store_dag(db: &MemoryDB) -> Cid {
+        let leaf = db
+            .put_obj(&Ipld::String("leaf".into()), Blake2b256)
+            .unwrap();
+        let branch = |i| Ipld::List(vec![Ipld::Link(leaf), Ipld::Integer(i)]);
+        let left = db.put_obj(&branch(1), Blake2b256).unwrap();
+        let right = db.put_obj(&branch(2), Blake2b256).unwrap();
+        let root = BTreeMap::from([
+            ("l".to_owned(), Ipld::Link(left)),
+            ("r".to_owned(), Ipld::Link(right)),
+        ]);
+        db.put_obj(&Ipld::Map(root), Blake2b256).unwrap()
+    }
+
+    fn request(root: Cid, selector: Selector, max_blocks: u64) -> GraphsyncRequest {
+        GraphsyncRequest {
+            root,
+            selector,
+            max_blocks,
+        }
+    }
+
+    #[tokio::test]
+    async fn whole_dag() {
+        let db = MemoryDB::default();
+        let root = store_dag(&db);
+
+        let response =
+            make_graphsync_response(&db, request(root, recursive_all_selector(), 100)).await;
+        assert_eq!(response.status, GraphsyncResponseStatus::Complete);
+        assert_eq!(response.blocks.len(), 4);
+        assert_eq!(response.blocks[0].cid, root);
+        assert!(response.blocks.iter().all(GraphsyncBlock::is_valid));
+    }
+
+    #[tokio::test]
+    async fn capped_and_not_found() {
+        let db = MemoryDB::default();
+        let root = store_dag(&db);
+
+        let response =
+            make_graphsync_response(&db, request(root, recursive_all_selector(), 2)).await;
+        assert_eq!(response.status, GraphsyncResponseStatus::Partial);
+        assert_eq!(response.blocks.len(), 2);
+
+        let unknown = Cid::new_v1(DAG_CBOR, Identity.digest(&[]));
+        let response =
+            make_graphsync_response(&db, request(unknown, recursive_all_selector(), 2)).await;
+        assert_eq!(response.status, GraphsyncResponseStatus::NotFound);
+    }
+}
@@ -3,18 +3,24 @@
 
 #![recursion_limit = "1024"]
 
+mod bandwidth;
 mod behaviour;
 pub mod chain_exchange;
 mod config;
 mod discovery;
+mod gater;
 mod gossip_params;
+pub mod graphsync;
 pub mod hello;
 mod metrics;
+mod nat;
 mod peer_manager;
+mod rate_limit;
 pub mod rpc;
 mod service;
 
 // Re-export some libp2p types
+pub use forest_libp2p_bitswap::{BitswapSessionId, BitswapSessionStats};
 pub use libp2p::{
     identity::{ed25519, Keypair, PeerId},
     multiaddr::{Multiaddr, Protocol},
@@ -22,4 +28,11 @@ pub use libp2p::{
 pub use multihash::Multihash;
 
 pub(crate) use self::behaviour::*;
-pub use self::{config::*, peer_manager::*, service::*};
+pub use self::{
+    bandwidth::{BandwidthReport, BandwidthStats},
+    config::*,
+    discovery::{DhtBucket, DhtPeer},
+    nat::{NatInfo, Reachability},
+    peer_manager::*,
+    service::*,
+};
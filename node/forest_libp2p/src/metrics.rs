@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use lazy_static::lazy_static;
-use prometheus::core::{AtomicU64, GenericCounter, GenericGauge};
+use prometheus::core::{AtomicU64, GenericCounter, GenericCounterVec, GenericGauge, Opts};
 
 lazy_static! {
     pub static ref PEER_FAILURE_TOTAL: Box<GenericCounter<AtomicU64>> = {
@@ -46,4 +46,32 @@ lazy_static! {
             .expect("Registering the bad_peers metric with the metrics registry must succeed");
         bad_peers
     };
+    pub static ref BANDWIDTH_BYTES: Box<GenericCounterVec<AtomicU64>> = {
+        let bandwidth_bytes = Box::new(
+            GenericCounterVec::<AtomicU64>::new(
+                Opts::new(
+                    "bandwidth_bytes",
+                    "Bytes sent and received over libp2p for each protocol",
+                ),
+                &[labels::PROTOCOL, labels::DIRECTION],
+            )
+            .expect("Defining the bandwidth_bytes metric must succeed"),
+        );
+        prometheus::default_registry()
+            .register(bandwidth_bytes.clone())
+            .expect(
+                "Registering the bandwidth_bytes metric with the metrics registry must succeed",
+            );
+        bandwidth_bytes
+    };
+}
+
+pub mod values {
+    pub const INBOUND: &str = "in";
+    pub const OUTBOUND: &str = "out";
+}
+
+pub mod labels {
+    pub const PROTOCOL: &str = "protocol";
+    pub const DIRECTION: &str = "direction";
 }
@@ -0,0 +1,148 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Reachability of the node, and UPnP port mapping of its listening addresses
+//! on the local gateway.
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+use igd_next::PortMappingProtocol;
+use libp2p::{autonat::NatStatus, multiaddr::Protocol, Multiaddr};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+/// Lease of the port mappings, renewed halfway through.
+const UPNP_LEASE_DURATION: Duration = Duration::from_secs(60 * 60);
+const UPNP_DESCRIPTION: &str = "forest";
+
+/// Reachability of the node from the public internet, as detected by
+/// `AutoNAT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Reachability {
+    /// Not detected yet, or NAT traversal is disabled
+    Unknown,
+    Public,
+    Private,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct NatInfo {
+    pub reachability: Reachability,
+    /// Address confirmed to be reachable, when public
+    pub public_addr: Option<Multiaddr>,
+}
+
+impl From<Option<NatStatus>> for NatInfo {
+    fn from(status: Option<NatStatus>) -> Self {
+        let (reachability, public_addr) = match status {
+            Some(NatStatus::Public(addr)) => (Reachability::Public, Some(addr)),
+            Some(NatStatus::Private) => (Reachability::Private, None),
+            Some(NatStatus::Unknown) | None => (Reachability::Unknown, None),
+        };
+        NatInfo {
+            reachability,
+            public_addr,
+        }
+    }
+}
+
+/// Returns the transport protocol and socket address of a listening address
+/// on a private IPv4 network, which can be mapped on the gateway.
+fn upnp_mapping(listen_addr: &Multiaddr) -> Option<(PortMappingProtocol, SocketAddr)> {
+    let mut protocols = listen_addr.iter();
+    let ip = match protocols.next()? {
+        Protocol::Ip4(ip) if ip.is_private() => ip,
+        _ => return None,
+    };
+    match protocols.next()? {
+        Protocol::Tcp(port) => Some((PortMappingProtocol::TCP, (ip, port).into())),
+        Protocol::Udp(port) => Some((PortMappingProtocol::UDP, (ip, port).into())),
+        _ => None,
+    }
+}
+
+/// Maps the port on the gateway, returning the external IP address.
+async fn add_upnp_mapping(
+    protocol: PortMappingProtocol,
+    local_addr: SocketAddr,
+) -> anyhow::Result<IpAddr> {
+    let gateway = igd_next::aio::tokio::search_gateway(Default::default()).await?;
+    gateway
+        .add_port(
+            protocol,
+            local_addr.port(),
+            local_addr,
+            UPNP_LEASE_DURATION.as_secs() as u32,
+            UPNP_DESCRIPTION,
+        )
+        .await?;
+    Ok(gateway.get_external_ip().await?)
+}
+
+/// Maps the port of a listening address on the gateway for as long as the
+/// node runs, sending the external address once mapped. Does nothing for
+/// addresses that are not on a private IPv4 network.
+pub(crate) async fn map_upnp_port(
+    listen_addr: Multiaddr,
+    external_addr_tx: flume::Sender<Multiaddr>,
+) {
+    let (protocol, local_addr) = match upnp_mapping(&listen_addr) {
+        Some(mapping) => mapping,
+        None => return,
+    };
+    let mut mapped = false;
+    let mut interval = tokio::time::interval(UPNP_LEASE_DURATION / 2);
+    loop {
+        interval.tick().await;
+        match add_upnp_mapping(protocol, local_addr).await {
+            Ok(external_ip) if !mapped => {
+                mapped = true;
+                let external_addr: Multiaddr = listen_addr
+                    .iter()
+                    .map(|protocol| match protocol {
+                        Protocol::Ip4(_) => external_ip.into(),
+                        protocol => protocol,
+                    })
+                    .collect();
+                info!("UPnP mapped {listen_addr} to {external_addr}");
+                if external_addr_tx.send_async(external_addr).await.is_err() {
+                    return;
+                }
+            }
+            Ok(_) => debug!("UPnP mapping of {listen_addr} renewed"),
+            Err(e) if !mapped => {
+                warn!("UPnP mapping of {listen_addr} failed: {e}");
+                return;
+            }
+            Err(e) => warn!("UPnP mapping renewal of {listen_addr} failed: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upnp_mappable_addresses() {
+        let mapping = |addr: &str| upnp_mapping(&addr.parse().unwrap());
+        assert_eq!(
+            mapping("/ip4/192.168.1.5/tcp/1234"),
+            Some((
+                PortMappingProtocol::TCP,
+                "192.168.1.5:1234".parse().unwrap()
+            ))
+        );
+        assert_eq!(
+            mapping("/ip4/10.0.0.2/udp/4321/quic-v1"),
+            Some((PortMappingProtocol::UDP, "10.0.0.2:4321".parse().unwrap()))
+        );
+        assert_eq!(mapping("/ip4/127.0.0.1/tcp/1234"), None);
+        assert_eq!(mapping("/ip4/8.8.8.8/tcp/1234"), None);
+        assert_eq!(mapping("/ip6/::1/tcp/1234"), None);
+    }
+}
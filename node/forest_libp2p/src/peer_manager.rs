@@ -2,16 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::{
-    cmp::Ordering,
+    cmp::{Ordering, Reverse},
+    str::FromStr,
     sync::Arc,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use ahash::{HashMap, HashSet};
 use flume::{Receiver, Sender};
 use forest_blocks::Tipset;
-use log::{debug, trace, warn};
+use forest_db::Store;
+use libp2p::identify;
+use log::{debug, info, trace, warn};
 use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 
 use crate::*;
@@ -27,6 +31,20 @@ const LOCAL_INV_ALPHA: u32 = 5;
 /// Global duration multiplier, affects duration delta change.
 const GLOBAL_INV_ALPHA: u32 = 20;
 
+/// Default duration of the bans of misbehaving peers.
+pub const DEFAULT_BAN_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// Key of the known peers in the database.
+const PEER_STORE_KEY: &str = "/peer_manager/known_peers";
+/// Maximum number of known peers kept in the database, the most recently seen
+/// ones are kept.
+const MAX_KNOWN_PEERS: usize = 1000;
+/// Interval at which the known peers are written to the database.
+const PEER_STORE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Duration after which peers marked as bad are no longer restored from the
+/// database.
+const BAD_PEER_EXPIRY: Duration = Duration::from_secs(24 * 60 * 60);
+
 #[derive(Debug, Default)]
 /// Contains info about the peer's head [Tipset], as well as the request stats.
 struct PeerInfo {
@@ -40,15 +58,45 @@ struct PeerInfo {
     average_time: Duration,
 }
 
-impl PeerInfo {
-    fn new(head: Arc<Tipset>) -> Self {
-        Self {
-            head: Some(head),
-            successes: 0,
-            failures: 0,
-            average_time: Default::default(),
-        }
-    }
+/// Addresses and request stats of a peer, kept across restarts.
+#[derive(Debug, Default, Clone)]
+struct KnownPeer {
+    /// Addresses the peer was dialed at.
+    addrs: HashSet<Multiaddr>,
+    /// Number of successful requests.
+    successes: u32,
+    /// Number of failed requests.
+    failures: u32,
+    /// Average response time for the peer.
+    average_time: Duration,
+    /// Last time a connection was established with the peer.
+    last_seen: Option<SystemTime>,
+    /// Last time the peer was marked as bad.
+    marked_bad: Option<SystemTime>,
+}
+
+/// What a connected peer reported of itself through `identify`, and its
+/// latency measured by `ping`.
+#[derive(Debug, Default, Clone)]
+struct PeerIdentity {
+    info: Option<identify::Info>,
+    latency: Option<Duration>,
+}
+
+/// Information on a connected peer, from `identify` and `ping`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ExtendedPeerInfo {
+    #[serde(rename = "ID")]
+    pub peer_id: String,
+    /// Agent version, empty until identified.
+    pub agent: String,
+    pub protocol_version: String,
+    /// Addresses the peer listens on.
+    pub addrs: Vec<Multiaddr>,
+    pub protocols: Vec<String>,
+    /// Round-trip time of the last ping, in milliseconds.
+    pub latency_ms: Option<u64>,
 }
 
 /// Peer tracking sets, these are handled together to avoid race conditions or
@@ -60,6 +108,107 @@ struct PeerSets {
     /// Set of peers to ignore for being incompatible/ failing to accept
     /// connections.
     bad_peers: HashSet<PeerId>,
+    /// Every peer known to the node, connected or not. The request stats of
+    /// full peers are tracked in `full_peers` and copied here when they are
+    /// removed.
+    known_peers: HashMap<PeerId, KnownPeer>,
+}
+
+impl PeerSets {
+    /// Returns the info of a new full peer, starting from its known stats.
+    fn new_peer_info(&self, peer_id: &PeerId) -> PeerInfo {
+        match self.known_peers.get(peer_id) {
+            Some(known) => PeerInfo {
+                head: None,
+                successes: known.successes,
+                failures: known.failures,
+                average_time: known.average_time,
+            },
+            None => PeerInfo::default(),
+        }
+    }
+
+    /// Returns the known peer entry of a peer, inserting it if needed. When
+    /// `MAX_KNOWN_PEERS` are already known, the least recently seen one is
+    /// evicted to make room for it.
+    fn known_peer_mut(&mut self, peer_id: &PeerId) -> &mut KnownPeer {
+        if !self.known_peers.contains_key(peer_id) && self.known_peers.len() >= MAX_KNOWN_PEERS {
+            let oldest = self
+                .known_peers
+                .iter()
+                .min_by_key(|(_, known)| known.last_seen)
+                .map(|(peer_id, _)| *peer_id);
+            if let Some(oldest) = oldest {
+                self.known_peers.remove(&oldest);
+            }
+        }
+        self.known_peers.entry(*peer_id).or_default()
+    }
+
+    /// Copies the request stats of a full peer to its known peer entry.
+    fn save_stats(&mut self, peer_id: &PeerId, info: &PeerInfo) {
+        let known = self.known_peer_mut(peer_id);
+        known.successes = info.successes;
+        known.failures = info.failures;
+        known.average_time = info.average_time;
+    }
+}
+
+/// Persisted record of a known peer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PeerRecord {
+    #[serde(rename = "ID")]
+    pub peer_id: String,
+    pub addrs: Vec<Multiaddr>,
+    pub successes: u32,
+    pub failures: u32,
+    /// Average response time, in milliseconds.
+    pub average_time_ms: u64,
+    /// Whether the peer is marked as bad.
+    pub bad: bool,
+    /// Last time the peer was marked as bad, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub marked_bad_at: Option<u64>,
+    /// Reason of the ban of the peer, if banned.
+    #[serde(default)]
+    pub ban_reason: Option<String>,
+    /// Expiration of the ban of the peer, in seconds since the Unix epoch.
+    /// `None` for permanent bans.
+    pub banned_until: Option<u64>,
+    /// Last time a connection was established with the peer, in seconds since
+    /// the Unix epoch.
+    pub last_seen: Option<u64>,
+}
+
+/// Ban of a peer, lifted at its expiration time if any.
+#[derive(Debug, Clone)]
+struct PeerBan {
+    reason: String,
+    expiration: Option<Instant>,
+}
+
+/// Ban of a peer, as reported to operators.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PeerBanInfo {
+    #[serde(rename = "ID")]
+    pub peer_id: String,
+    pub reason: String,
+    /// Expiration of the ban, in seconds since the Unix epoch. `None` for
+    /// permanent bans.
+    pub banned_until: Option<u64>,
+}
+
+/// Converts an expiration instant into seconds since the Unix epoch.
+fn expiration_unix_secs(expiration: Instant) -> u64 {
+    unix_secs(SystemTime::now() + expiration.saturating_duration_since(Instant::now()))
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
 }
 
 /// Thread safe peer manager which handles peer management for the
@@ -73,8 +222,14 @@ pub struct PeerManager {
     peer_ops_tx: Sender<PeerOperation>,
     /// Peer operation receiver
     peer_ops_rx: Receiver<PeerOperation>,
-    /// Peer ban list, key is peer id, value is expiration time
-    peer_ban_list: RwLock<HashMap<PeerId, Option<Instant>>>,
+    /// Peer ban list, key is peer id, value is the reason and expiration time
+    peer_ban_list: RwLock<HashMap<PeerId, PeerBan>>,
+    /// Duration of the bans of misbehaving peers
+    ban_duration: Duration,
+    /// Peers that are never banned nor marked as bad
+    protected_peers: HashSet<PeerId>,
+    /// Identity and latency of connected peers
+    identities: RwLock<HashMap<PeerId, PeerIdentity>>,
 }
 
 impl Default for PeerManager {
@@ -86,11 +241,29 @@ impl Default for PeerManager {
             peer_ops_tx,
             peer_ops_rx,
             peer_ban_list: Default::default(),
+            ban_duration: DEFAULT_BAN_DURATION,
+            protected_peers: Default::default(),
+            identities: Default::default(),
         }
     }
 }
 
 impl PeerManager {
+    /// Creates a peer manager banning misbehaving peers for the given
+    /// duration, and never banning nor marking as bad the given peers.
+    pub fn new(ban_duration: Duration, protected_peers: impl IntoIterator<Item = PeerId>) -> Self {
+        PeerManager {
+            ban_duration,
+            protected_peers: protected_peers.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Duration of the bans of misbehaving peers.
+    pub fn ban_duration(&self) -> Duration {
+        self.ban_duration
+    }
+
     /// Updates peer's heaviest tipset. If the peer does not exist in the set, a
     /// new `PeerInfo` will be generated.
     pub async fn update_peer_head(&self, peer_id: PeerId, ts: Arc<Tipset>) {
@@ -99,7 +272,11 @@ impl PeerManager {
         if let Some(pi) = peers.full_peers.get_mut(&peer_id) {
             pi.head = Some(ts);
         } else {
-            peers.full_peers.insert(peer_id, PeerInfo::new(ts));
+            let info = PeerInfo {
+                head: Some(ts),
+                ..peers.new_peer_info(&peer_id)
+            };
+            peers.full_peers.insert(peer_id, info);
             metrics::FULL_PEERS.inc();
         }
     }
@@ -180,6 +357,8 @@ impl PeerManager {
         // If the peer is not already accounted for, increment full peer count
         if !peers.full_peers.contains_key(&peer) {
             metrics::FULL_PEERS.inc();
+            let info = peers.new_peer_info(&peer);
+            peers.full_peers.insert(peer, info);
         }
         let peer_stats = peers.full_peers.entry(peer).or_default();
         peer_stats.successes += 1;
@@ -195,6 +374,8 @@ impl PeerManager {
             metrics::PEER_FAILURE_TOTAL.inc();
             if !peers.full_peers.contains_key(&peer) {
                 metrics::FULL_PEERS.inc();
+                let info = peers.new_peer_info(&peer);
+                peers.full_peers.insert(peer, info);
             }
             let peer_stats = peers.full_peers.entry(peer).or_default();
             peer_stats.failures += 1;
@@ -205,6 +386,10 @@ impl PeerManager {
     /// Removes a peer from the set and returns true if the value was present
     /// previously
     pub async fn mark_peer_bad(&self, peer_id: PeerId) -> bool {
+        if self.protected_peers.contains(&peer_id) {
+            debug!("Not marking protected peer {peer_id} bad");
+            return false;
+        }
         let mut peers = self.peers.write().await;
         let removed = remove_peer(&mut peers, &peer_id);
         if removed {
@@ -213,6 +398,7 @@ impl PeerManager {
 
         // Add peer to bad peer set
         debug!("marked peer {} bad", peer_id);
+        peers.known_peer_mut(&peer_id).marked_bad = Some(SystemTime::now());
         if peers.bad_peers.insert(peer_id) {
             metrics::BAD_PEERS.inc();
         }
@@ -231,27 +417,268 @@ impl PeerManager {
         removed
     }
 
+    /// Records an address a peer was successfully dialed at.
+    pub async fn add_peer_address(&self, peer_id: PeerId, mut addr: Multiaddr) {
+        if let Some(Protocol::P2p(_)) = addr.iter().last() {
+            addr.pop();
+        }
+        let mut peers = self.peers.write().await;
+        let known = peers.known_peer_mut(&peer_id);
+        known.addrs.insert(addr);
+        known.last_seen = Some(SystemTime::now());
+    }
+
+    /// Returns the addresses of every known peer that is neither bad nor
+    /// banned, to seed peer discovery.
+    pub async fn known_peer_addresses(&self) -> Vec<(PeerId, Multiaddr)> {
+        let peers = self.peers.read().await;
+        let banned = self.peer_ban_list.read().await;
+        peers
+            .known_peers
+            .iter()
+            .filter(|(peer_id, _)| {
+                !peers.bad_peers.contains(peer_id) && !banned.contains_key(peer_id)
+            })
+            .flat_map(|(peer_id, known)| known.addrs.iter().map(|addr| (*peer_id, addr.clone())))
+            .collect()
+    }
+
+    /// Returns the records of every known peer, most recently seen first.
+    pub async fn peer_records(&self) -> Vec<PeerRecord> {
+        let peers = self.peers.read().await;
+        let banned = self.peer_ban_list.read().await;
+
+        let mut ids: HashSet<PeerId> = peers.known_peers.keys().copied().collect();
+        ids.extend(peers.full_peers.keys());
+        ids.extend(peers.bad_peers.iter());
+        ids.extend(banned.keys());
+
+        let mut records: Vec<PeerRecord> = ids
+            .into_iter()
+            .map(|peer_id| {
+                let known = peers.known_peers.get(&peer_id).cloned().unwrap_or_default();
+                let (successes, failures, average_time) = match peers.full_peers.get(&peer_id) {
+                    Some(info) => (info.successes, info.failures, info.average_time),
+                    None => (known.successes, known.failures, known.average_time),
+                };
+                let ban = banned.get(&peer_id);
+                let bad = peers.bad_peers.contains(&peer_id);
+                PeerRecord {
+                    peer_id: peer_id.to_string(),
+                    addrs: known.addrs.into_iter().collect(),
+                    successes,
+                    failures,
+                    average_time_ms: average_time.as_millis() as u64,
+                    bad,
+                    marked_bad_at: known.marked_bad.filter(|_| bad).map(unix_secs),
+                    ban_reason: ban.map(|ban| ban.reason.clone()),
+                    banned_until: ban.and_then(|ban| ban.expiration.map(expiration_unix_secs)),
+                    last_seen: known.last_seen.map(unix_secs),
+                }
+            })
+            .collect();
+        records.sort_by_key(|record| Reverse(record.last_seen));
+        records
+    }
+
+    /// Records what a connected peer reported of itself through `identify`.
+    pub async fn update_peer_identity(&self, peer_id: PeerId, info: identify::Info) {
+        let mut identities = self.identities.write().await;
+        identities.entry(peer_id).or_default().info = Some(info);
+    }
+
+    /// Records the round-trip time of a ping to a connected peer.
+    pub async fn update_peer_latency(&self, peer_id: PeerId, rtt: Duration) {
+        let mut identities = self.identities.write().await;
+        identities.entry(peer_id).or_default().latency = Some(rtt);
+    }
+
+    /// Forgets the identity and latency of a disconnected peer.
+    pub async fn forget_peer_identity(&self, peer_id: &PeerId) {
+        self.identities.write().await.remove(peer_id);
+    }
+
+    /// Returns the agent version of a connected peer, if identified.
+    pub async fn peer_agent_version(&self, peer_id: &PeerId) -> Option<String> {
+        let identities = self.identities.read().await;
+        identities
+            .get(peer_id)?
+            .info
+            .as_ref()
+            .map(|info| info.agent_version.clone())
+    }
+
+    /// Returns the identity and latency of a connected peer, `None` if neither
+    /// has been received yet.
+    pub async fn peer_info(&self, peer_id: &PeerId) -> Option<ExtendedPeerInfo> {
+        let identities = self.identities.read().await;
+        let identity = identities.get(peer_id)?;
+        let (agent, protocol_version, addrs, protocols) = match &identity.info {
+            Some(info) => (
+                info.agent_version.clone(),
+                info.protocol_version.clone(),
+                info.listen_addrs.clone(),
+                info.protocols.clone(),
+            ),
+            None => Default::default(),
+        };
+        Some(ExtendedPeerInfo {
+            peer_id: peer_id.to_string(),
+            agent,
+            protocol_version,
+            addrs,
+            protocols,
+            latency_ms: identity.latency.map(|rtt| rtt.as_millis() as u64),
+        })
+    }
+
+    /// Writes the known peers to the database, keeping the most recently seen
+    /// ones.
+    pub async fn save_peers<DB: Store>(&self, db: &DB) -> anyhow::Result<()> {
+        let mut records = self.peer_records().await;
+        records.truncate(MAX_KNOWN_PEERS);
+        db.write(PEER_STORE_KEY, serde_json::to_vec(&records)?)?;
+        Ok(())
+    }
+
+    /// Loads the known peers from the database, restoring their stats, and bad
+    /// peers and bans that have not expired yet.
+    pub async fn load_peers<DB: Store>(&self, db: &DB) -> anyhow::Result<()> {
+        let records: Vec<PeerRecord> = match db.read(PEER_STORE_KEY)? {
+            Some(bytes) => serde_json::from_slice(&bytes)?,
+            None => return Ok(()),
+        };
+        let now = SystemTime::now();
+        let mut bans = vec![];
+        {
+            let mut peers = self.peers.write().await;
+            for record in &records {
+                let peer_id = match PeerId::from_str(&record.peer_id) {
+                    Ok(peer_id) => peer_id,
+                    Err(e) => {
+                        warn!("Invalid peer ID {} in peer store: {e}", record.peer_id);
+                        continue;
+                    }
+                };
+                let marked_bad = record
+                    .marked_bad_at
+                    .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+                *peers.known_peer_mut(&peer_id) = KnownPeer {
+                    addrs: record.addrs.iter().cloned().collect(),
+                    successes: record.successes,
+                    failures: record.failures,
+                    average_time: Duration::from_millis(record.average_time_ms),
+                    last_seen: record
+                        .last_seen
+                        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+                    marked_bad,
+                };
+                // Peers marked as bad without a time, or too long ago, get another
+                // chance
+                let bad_expired = marked_bad
+                    .and_then(|marked| now.duration_since(marked).ok())
+                    .map_or(true, |elapsed| elapsed > BAD_PEER_EXPIRY);
+                if record.bad && !bad_expired && peers.bad_peers.insert(peer_id) {
+                    metrics::BAD_PEERS.inc();
+                }
+                if let Some(reason) = &record.ban_reason {
+                    match record.banned_until {
+                        // Permanent ban
+                        None => bans.push((peer_id, reason.clone(), None)),
+                        Some(secs) => {
+                            if let Ok(remaining) =
+                                (UNIX_EPOCH + Duration::from_secs(secs)).duration_since(now)
+                            {
+                                bans.push((peer_id, reason.clone(), Some(remaining)));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        for (peer_id, reason, remaining) in bans {
+            self.ban_peer(peer_id, reason, remaining).await;
+        }
+        info!("Loaded {} known peers from the peer store", records.len());
+        Ok(())
+    }
+
+    /// Periodically writes the known peers to the database.
+    pub async fn save_peers_task<DB: Store>(self: Arc<Self>, db: DB) -> anyhow::Result<()> {
+        let mut interval = tokio::time::interval(PEER_STORE_INTERVAL);
+        // The first tick completes immediately, there is nothing new to save yet
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.save_peers(&db).await {
+                warn!("Failed to save known peers: {e}");
+            }
+        }
+    }
+
     /// Gets peer operation receiver
     pub fn peer_ops_rx(&self) -> &Receiver<PeerOperation> {
         &self.peer_ops_rx
     }
 
-    /// Bans a peer with an optional duration
+    /// Bans a peer with an optional duration, the ban is permanent without
+    /// one. Protected peers are never banned, returns `false` for them.
     pub async fn ban_peer(
         &self,
         peer: PeerId,
         reason: impl Into<String>,
         duration: Option<Duration>,
-    ) {
+    ) -> bool {
+        if self.protected_peers.contains(&peer) {
+            debug!("Not banning protected peer {peer}");
+            return false;
+        }
+        let reason = reason.into();
         let mut locked = self.peer_ban_list.write().await;
-        locked.insert(peer, duration.and_then(|d| Instant::now().checked_add(d)));
+        locked.insert(
+            peer,
+            PeerBan {
+                reason: reason.clone(),
+                expiration: duration.and_then(|d| Instant::now().checked_add(d)),
+            },
+        );
         if let Err(e) = self
             .peer_ops_tx
-            .send_async(PeerOperation::Ban(peer, reason.into()))
+            .send_async(PeerOperation::Ban(peer, reason))
             .await
         {
             warn!("ban_peer err: {e}");
         }
+        true
+    }
+
+    /// Lifts the ban of a peer. Returns `false` if the peer was not banned.
+    pub async fn unban_peer(&self, peer: PeerId) -> bool {
+        if self.peer_ban_list.write().await.remove(&peer).is_none() {
+            return false;
+        }
+        if let Err(e) = self
+            .peer_ops_tx
+            .send_async(PeerOperation::Unban(peer))
+            .await
+        {
+            warn!("unban_peer err: {e}");
+        }
+        true
+    }
+
+    /// Returns the current peer bans.
+    pub async fn peer_bans(&self) -> Vec<PeerBanInfo> {
+        self.peer_ban_list
+            .read()
+            .await
+            .iter()
+            .map(|(peer_id, ban)| PeerBanInfo {
+                peer_id: peer_id.to_string(),
+                reason: ban.reason.clone(),
+                banned_until: ban.expiration.map(expiration_unix_secs),
+            })
+            .collect()
     }
 
     pub async fn peer_operation_event_loop_task(self: Arc<Self>) -> anyhow::Result<()> {
@@ -260,8 +687,8 @@ impl PeerManager {
             unban_list.clear();
 
             let now = Instant::now();
-            for (peer, expiration) in self.peer_ban_list.read().await.iter() {
-                if let Some(expiration) = expiration {
+            for (peer, ban) in self.peer_ban_list.read().await.iter() {
+                if let Some(expiration) = &ban.expiration {
                     if &now > expiration {
                         unban_list.push(*peer);
                     }
@@ -296,7 +723,13 @@ fn remove_peer(peers: &mut PeerSets, peer_id: &PeerId) -> bool {
         peers.full_peers.len()
     );
 
-    peers.full_peers.remove(peer_id).is_some()
+    match peers.full_peers.remove(peer_id) {
+        Some(info) => {
+            peers.save_stats(peer_id, &info);
+            true
+        }
+        None => false,
+    }
 }
 
 fn log_time(info: &mut PeerInfo, dur: Duration) {
@@ -315,3 +748,105 @@ pub enum PeerOperation {
     Ban(PeerId, String),
     Unban(PeerId),
 }
+
+#[cfg(test)]
+mod tests {
+    use forest_db::MemoryDB;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn peer_store_roundtrip() {
+        let db = MemoryDB::default();
+        let peer_manager = PeerManager::default();
+        let (good, bad, banned) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let addr: Multiaddr = "/ip4/1.2.3.4/tcp/1234".parse().unwrap();
+
+        peer_manager
+            .add_peer_address(good, addr.clone().with(Protocol::P2p(good.into())))
+            .await;
+        peer_manager
+            .log_success(good, Duration::from_millis(200))
+            .await;
+        peer_manager.log_failure(good, Duration::from_secs(1)).await;
+        peer_manager.mark_peer_bad(bad).await;
+        peer_manager
+            .ban_peer(banned, "test", Some(Duration::from_secs(3600)))
+            .await;
+        peer_manager.save_peers(&db).await.unwrap();
+
+        let reloaded = PeerManager::default();
+        reloaded.load_peers(&db).await.unwrap();
+        let good_record = record_of(&reloaded, good).await;
+        assert_eq!(good_record.addrs, vec![addr.clone()]);
+        assert_eq!((good_record.successes, good_record.failures), (1, 1));
+        assert_eq!(good_record.average_time_ms, 360);
+        assert!(good_record.last_seen.is_some());
+        assert!(record_of(&reloaded, bad).await.bad);
+        assert!(record_of(&reloaded, banned).await.banned_until.is_some());
+        assert_eq!(reloaded.known_peer_addresses().await, vec![(good, addr)]);
+
+        // Stats of known peers are carried over when they become full peers
+        reloaded.log_success(good, Duration::from_millis(360)).await;
+        assert_eq!(record_of(&reloaded, good).await.successes, 2);
+    }
+
+    #[tokio::test]
+    async fn expired_bad_peers_are_not_restored() {
+        let db = MemoryDB::default();
+        let (recent, expired, untimed) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let now = unix_secs(SystemTime::now());
+        let record = |peer_id: PeerId, marked_bad_at| PeerRecord {
+            peer_id: peer_id.to_string(),
+            addrs: vec![],
+            successes: 0,
+            failures: 0,
+            average_time_ms: 0,
+            bad: true,
+            marked_bad_at,
+            ban_reason: None,
+            banned_until: None,
+            last_seen: None,
+        };
+        let records = vec![
+            record(recent, Some(now - 60)),
+            record(expired, Some(now - BAD_PEER_EXPIRY.as_secs() - 60)),
+            record(untimed, None),
+        ];
+        db.write(PEER_STORE_KEY, serde_json::to_vec(&records).unwrap())
+            .unwrap();
+
+        let peer_manager = PeerManager::default();
+        peer_manager.load_peers(&db).await.unwrap();
+        assert!(record_of(&peer_manager, recent).await.bad);
+        assert!(!record_of(&peer_manager, expired).await.bad);
+        assert!(!record_of(&peer_manager, untimed).await.bad);
+    }
+
+    #[tokio::test]
+    async fn known_peers_are_bounded() {
+        let peer_manager = PeerManager::default();
+        let addr: Multiaddr = "/ip4/1.2.3.4/tcp/1234".parse().unwrap();
+        let first = PeerId::random();
+        peer_manager.add_peer_address(first, addr.clone()).await;
+        for _ in 0..MAX_KNOWN_PEERS {
+            peer_manager
+                .add_peer_address(PeerId::random(), addr.clone())
+                .await;
+        }
+
+        let peers = peer_manager.peers.read().await;
+        assert_eq!(peers.known_peers.len(), MAX_KNOWN_PEERS);
+        // The least recently seen peer is evicted
+        assert!(!peers.known_peers.contains_key(&first));
+    }
+
+    async fn record_of(peer_manager: &PeerManager, peer_id: PeerId) -> PeerRecord {
+        peer_manager
+            .peer_records()
+            .await
+            .into_iter()
+            .find(|record| record.peer_id == peer_id.to_string())
+            .unwrap()
+    }
+}
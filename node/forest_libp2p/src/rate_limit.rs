@@ -0,0 +1,209 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Token-bucket limits of the chain exchange and bitswap requests served to
+//! peers. Byte buckets are charged once responses are built, and may go into
+//! debt, in which case requests are refused until they have refilled.
+
+use std::time::Instant;
+
+use ahash::HashMap;
+use forest_libp2p_bitswap::ServingLimiter;
+use libp2p::PeerId;
+use parking_lot::Mutex;
+
+use crate::config::ServingLimits;
+
+/// Number of seconds worth of tokens that buckets hold, allowing bursts.
+const BURST_SECS: f64 = 10.0;
+
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket, `None` for a rate of 0 which disables the limit.
+    fn new(rate: u64, now: Instant) -> Option<Self> {
+        (rate > 0).then(|| TokenBucket {
+            rate: rate as f64,
+            tokens: rate as f64 * BURST_SECS,
+            updated: now,
+        })
+    }
+
+    fn capacity(&self) -> f64 {
+        self.rate * BURST_SECS
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity());
+        self.updated = now;
+    }
+
+    fn try_take(&mut self, amount: f64, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens >= amount {
+            self.tokens -= amount;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn has_tokens(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens > 0.0
+    }
+
+    fn charge(&mut self, amount: f64, now: Instant) {
+        self.refill(now);
+        self.tokens -= amount;
+    }
+
+    fn is_full(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.capacity()
+    }
+}
+
+#[derive(Debug)]
+struct PeerBuckets {
+    requests: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    global_bytes: Option<TokenBucket>,
+    peers: HashMap<PeerId, PeerBuckets>,
+}
+
+/// Per-peer request and byte rate limits, and global byte rate limit, of the
+/// requests served by the node.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    limits: ServingLimits,
+    state: Mutex<LimiterState>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: ServingLimits) -> Self {
+        let global_bytes = TokenBucket::new(limits.global_bytes_per_sec, Instant::now());
+        RateLimiter {
+            limits,
+            state: Mutex::new(LimiterState {
+                global_bytes,
+                peers: Default::default(),
+            }),
+        }
+    }
+
+    fn try_acquire_at(&self, peer: &PeerId, now: Instant) -> bool {
+        let mut state = self.state.lock();
+        if !state
+            .global_bytes
+            .as_mut()
+            .map_or(true, |bucket| bucket.has_tokens(now))
+        {
+            return false;
+        }
+        let limits = &self.limits;
+        let buckets = state.peers.entry(*peer).or_insert_with(|| PeerBuckets {
+            requests: TokenBucket::new(limits.peer_requests_per_sec, now),
+            bytes: TokenBucket::new(limits.peer_bytes_per_sec, now),
+        });
+        buckets
+            .bytes
+            .as_mut()
+            .map_or(true, |bucket| bucket.has_tokens(now))
+            && buckets
+                .requests
+                .as_mut()
+                .map_or(true, |bucket| bucket.try_take(1.0, now))
+    }
+
+    fn charge_at(&self, peer: &PeerId, bytes: usize, now: Instant) {
+        let mut state = self.state.lock();
+        if let Some(bucket) = state.global_bytes.as_mut() {
+            bucket.charge(bytes as f64, now);
+        }
+        if let Some(bucket) = state
+            .peers
+            .get_mut(peer)
+            .and_then(|buckets| buckets.bytes.as_mut())
+        {
+            bucket.charge(bytes as f64, now);
+        }
+    }
+
+    /// Forgets the peers whose buckets have refilled, which are in the same
+    /// state as the buckets of new peers.
+    pub fn prune(&self) {
+        let now = Instant::now();
+        self.state.lock().peers.retain(|_, buckets| {
+            !(buckets.requests.as_mut().map_or(true, |b| b.is_full(now))
+                && buckets.bytes.as_mut().map_or(true, |b| b.is_full(now)))
+        });
+    }
+}
+
+impl ServingLimiter for RateLimiter {
+    fn try_acquire(&self, peer: &PeerId) -> bool {
+        self.try_acquire_at(peer, Instant::now())
+    }
+
+    fn charge(&self, peer: &PeerId, bytes: usize) {
+        self.charge_at(peer, bytes, Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn peer_limits() {
+        let limiter = RateLimiter::new(ServingLimits {
+            peer_requests_per_sec: 1,
+            peer_bytes_per_sec: 100,
+            global_bytes_per_sec: 0,
+        });
+        let (peer, other) = (PeerId::random(), PeerId::random());
+        let now = Instant::now();
+
+        // A burst of 10 requests is served, the 11th is not
+        for _ in 0..10 {
+            assert!(limiter.try_acquire_at(&peer, now));
+        }
+        assert!(!limiter.try_acquire_at(&peer, now));
+        assert!(limiter.try_acquire_at(&other, now));
+
+        // Bytes in debt are paid back before serving again
+        let now = now + Duration::from_secs(5);
+        limiter.charge_at(&peer, 1500, now);
+        assert!(!limiter.try_acquire_at(&peer, now));
+        assert!(limiter.try_acquire_at(&peer, now + Duration::from_secs(6)));
+    }
+
+    #[test]
+    fn global_limit() {
+        let limiter = RateLimiter::new(ServingLimits {
+            peer_requests_per_sec: 0,
+            peer_bytes_per_sec: 0,
+            global_bytes_per_sec: 10,
+        });
+        let (peer, other) = (PeerId::random(), PeerId::random());
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire_at(&peer, now));
+        limiter.charge_at(&peer, 100, now);
+        assert!(!limiter.try_acquire_at(&other, now));
+        assert!(limiter.try_acquire_at(&other, now + Duration::from_secs(1)));
+    }
+}
@@ -145,7 +145,7 @@ where
 //
 // `io` is essentially [yamux::Stream](https://docs.rs/yamux/0.11.0/yamux/struct.Stream.html)
 //
-async fn read_request_and_decode<IO, T>(io: &mut IO) -> io::Result<T>
+pub(crate) async fn read_request_and_decode<IO, T>(io: &mut IO) -> io::Result<T>
 where
     IO: AsyncRead + Unpin,
     T: serde::de::DeserializeOwned,
@@ -167,7 +167,7 @@ where
     }
 }
 
-async fn encode_and_write<IO, T>(io: &mut IO, data: T) -> io::Result<()>
+pub(crate) async fn encode_and_write<IO, T>(io: &mut IO, data: T) -> io::Result<()>
 where
     IO: AsyncWrite + Unpin,
     T: serde::Serialize,
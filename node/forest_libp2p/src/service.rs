@@ -14,23 +14,27 @@ use flume::Sender;
 use forest_blocks::GossipBlock;
 use forest_chain::ChainStore;
 use forest_libp2p_bitswap::{
-    request_manager::BitswapRequestManager, BitswapStoreRead, BitswapStoreReadWrite,
+    request_manager::BitswapRequestManager, BitswapSessionId, BitswapSessionStats,
+    BitswapStoreRead, BitswapStoreReadWrite, ServingLimiter,
 };
 use forest_message::SignedMessage;
 use forest_utils::io::read_file_to_vec;
-use futures::{channel::oneshot::Sender as OneShotSender, select};
+use futures::{
+    channel::oneshot::Sender as OneShotSender, future::Either, select, AsyncRead, AsyncWrite,
+};
 use futures_util::stream::StreamExt;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_shared::clock::ChainEpoch;
 pub use libp2p::gossipsub::{IdentTopic, Topic};
 use libp2p::{
+    autonat::{self, NatStatus},
     core::{self, identity::Keypair, muxing::StreamMuxerBox, transport::Boxed, Multiaddr},
-    gossipsub,
+    gossipsub, identify,
     metrics::{Metrics, Recorder},
     multiaddr::Protocol,
-    noise, ping,
+    noise, ping, relay,
     request_response::{self, RequestId, ResponseChannel},
-    swarm::{ConnectionLimits, SwarmBuilder, SwarmEvent},
+    swarm::{AddressScore, ConnectionLimits, SwarmBuilder, SwarmEvent},
     yamux::YamuxConfig,
     PeerId, Swarm, Transport,
 };
@@ -38,20 +42,31 @@ use log::{debug, error, info, trace, warn};
 use tokio_stream::wrappers::IntervalStream;
 
 use super::{
-    chain_exchange::{make_chain_exchange_response, ChainExchangeRequest, ChainExchangeResponse},
-    ForestBehaviour, ForestBehaviourEvent, Libp2pConfig,
+    chain_exchange::{
+        make_chain_exchange_response, ChainExchangeRequest, ChainExchangeResponse,
+        ChainExchangeResponseStatus,
+    },
+    ForestBehaviour, ForestBehaviourEvent, Libp2pConfig, Libp2pTransport,
 };
 use crate::{
+    bandwidth::{BandwidthReport, BandwidthSinks, CountingMuxer},
     chain_exchange::ChainExchangeBehaviour,
-    discovery::DiscoveryEvent,
+    discovery::{peer_id_from_multiaddr, DhtBucket, DiscoveryEvent},
+    gossip_params::BOOTSTRAP_PEER_APP_SCORE,
+    graphsync::{
+        make_graphsync_response, GraphsyncBehaviour, GraphsyncRequest, GraphsyncResponse,
+        GraphsyncResponseStatus,
+    },
     hello::{HelloBehaviour, HelloRequest, HelloResponse},
+    nat::{map_upnp_port, NatInfo},
+    rate_limit::RateLimiter,
     rpc::RequestResponseError,
-    PeerManager, PeerOperation,
+    ExtendedPeerInfo, PeerBanInfo, PeerManager, PeerOperation, PeerRecord,
 };
 
 pub(crate) mod metrics {
     use lazy_static::lazy_static;
-    use prometheus::core::{AtomicU64, GenericGaugeVec, Opts};
+    use prometheus::core::{AtomicU64, GenericCounterVec, GenericGaugeVec, Opts};
     lazy_static! {
         pub static ref NETWORK_CONTAINER_CAPACITIES: Box<GenericGaugeVec<AtomicU64>> = {
             let network_container_capacities = Box::new(
@@ -69,15 +84,58 @@ pub(crate) mod metrics {
             );
             network_container_capacities
         };
+        pub static ref SERVING_THROTTLED_TOTAL: Box<GenericCounterVec<AtomicU64>> = {
+            let serving_throttled_total = Box::new(
+                GenericCounterVec::<AtomicU64>::new(
+                    Opts::new(
+                        "serving_throttled_total",
+                        "Number of inbound requests refused for exceeding the serving limits",
+                    ),
+                    &[labels::PROTOCOL],
+                )
+                .expect("Defining the serving_throttled_total metric must succeed"),
+            );
+            prometheus::default_registry().register(serving_throttled_total.clone()).expect(
+                "Registering the serving_throttled_total metric with the metrics registry must succeed"
+            );
+            serving_throttled_total
+        };
+        pub static ref PEER_CONNECTIONS: Box<GenericGaugeVec<AtomicU64>> = {
+            let peer_connections = Box::new(
+                GenericGaugeVec::<AtomicU64>::new(
+                    Opts::new(
+                        "peer_connections",
+                        "Number of established peer connections for each transport",
+                    ),
+                    &[labels::TRANSPORT],
+                )
+                .expect("Defining the peer_connections metric must succeed"),
+            );
+            prometheus::default_registry().register(peer_connections.clone()).expect(
+                "Registering the peer_connections metric with the metrics registry must succeed"
+            );
+            peer_connections
+        };
     }
 
     pub mod values {
         pub const HELLO_REQUEST_TABLE: &str = "hello_request_table";
         pub const CHAIN_EXCHANGE_REQUEST_TABLE: &str = "cx_request_table";
+        pub const GRAPHSYNC_REQUEST_TABLE: &str = "graphsync_request_table";
+
+        pub const QUIC: &str = "quic";
+        pub const TCP: &str = "tcp";
+        pub const WEBSOCKET: &str = "websocket";
+        pub const OTHER: &str = "other";
+
+        pub const CHAIN_EXCHANGE: &str = "chain_exchange";
+        pub const GRAPHSYNC: &str = "graphsync";
     }
 
     pub mod labels {
         pub const KIND: &str = "kind";
+        pub const TRANSPORT: &str = "transport";
+        pub const PROTOCOL: &str = "protocol";
     }
 }
 
@@ -90,8 +148,6 @@ const PUBSUB_TOPICS: [&str; 2] = [PUBSUB_BLOCK_STR, PUBSUB_MSG_STR];
 
 pub const BITSWAP_TIMEOUT: Duration = Duration::from_secs(10);
 
-const BAN_PEER_DURATION: Duration = Duration::from_secs(60 * 60); //1h
-
 /// Events emitted by this Service.
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
@@ -157,9 +213,15 @@ pub enum NetworkMessage {
         request: HelloRequest,
         response_channel: flume::Sender<HelloResponse>,
     },
+    GraphsyncRequest {
+        peer_id: PeerId,
+        request: GraphsyncRequest,
+        response_channel: flume::Sender<Result<GraphsyncResponse, RequestResponseError>>,
+    },
     BitswapRequest {
         epoch: ChainEpoch,
         cid: Cid,
+        session: Option<BitswapSessionId>,
         response_channel: flume::Sender<bool>,
     },
     JSONRPCRequest {
@@ -174,6 +236,18 @@ pub enum NetRPCMethods {
     NetPeers(OneShotSender<HashMap<PeerId, HashSet<Multiaddr>>>),
     NetConnect(OneShotSender<bool>, PeerId, HashSet<Multiaddr>),
     NetDisconnect(OneShotSender<()>, PeerId),
+    NetPubsubScores(OneShotSender<Vec<(PeerId, f64)>>),
+    NetPeerRecords(OneShotSender<Vec<PeerRecord>>),
+    NetBan(OneShotSender<bool>, PeerId, Option<Duration>),
+    NetUnban(OneShotSender<bool>, PeerId),
+    NetBans(OneShotSender<Vec<PeerBanInfo>>),
+    NetBandwidthStats(OneShotSender<BandwidthReport>),
+    NetAutoNatStatus(OneShotSender<NatInfo>),
+    NetFindPeer(OneShotSender<Vec<Multiaddr>>, PeerId),
+    NetPeerInfo(OneShotSender<Option<ExtendedPeerInfo>>, PeerId),
+    NetAgentVersion(OneShotSender<Option<String>>, PeerId),
+    NetDhtRoutingTable(OneShotSender<Vec<DhtBucket>>),
+    NetBitswapSessions(OneShotSender<Vec<BitswapSessionStats>>),
 }
 
 /// The `Libp2pService` listens to events from the libp2p swarm.
@@ -182,6 +256,8 @@ pub struct Libp2pService<DB> {
     swarm: Swarm<ForestBehaviour>,
     cs: Arc<ChainStore<DB>>,
     peer_manager: Arc<PeerManager>,
+    bandwidth: Arc<BandwidthSinks>,
+    rate_limiter: Arc<RateLimiter>,
     network_receiver_in: flume::Receiver<NetworkMessage>,
     network_sender_in: Sender<NetworkMessage>,
     network_receiver_out: flume::Receiver<NetworkEvent>,
@@ -204,19 +280,32 @@ where
     ) -> Self {
         let peer_id = PeerId::from(net_keypair.public());
 
-        let transport =
-            build_transport(net_keypair.clone()).expect("Failed to build libp2p transport");
+        let (relay_transport, relay_client) = match config.nat_traversal {
+            true => {
+                let (transport, client) = relay::client::new(peer_id);
+                (Some(transport), Some(client))
+            }
+            false => (None, None),
+        };
+        let bandwidth = Arc::new(BandwidthSinks::default());
+        let transport = build_transport(
+            net_keypair.clone(),
+            &config.transports,
+            relay_transport,
+            bandwidth.clone(),
+        )
+        .expect("Failed to build libp2p transport");
 
         let limits = ConnectionLimits::default()
             .with_max_pending_incoming(Some(10))
             .with_max_pending_outgoing(Some(30))
-            .with_max_established_incoming(Some(config.target_peer_count))
-            .with_max_established_outgoing(Some(config.target_peer_count))
             .with_max_established_per_peer(Some(5));
 
+        let rate_limiter = Arc::new(RateLimiter::new(config.serving_limits));
+
         let mut swarm = SwarmBuilder::with_tokio_executor(
             transport,
-            ForestBehaviour::new(&net_keypair, &config, network_name),
+            ForestBehaviour::new(&net_keypair, &config, network_name, relay_client),
             peer_id,
         )
         .connection_limits(limits)
@@ -238,6 +327,8 @@ where
             swarm,
             cs,
             peer_manager,
+            bandwidth,
+            rate_limiter,
             network_receiver_in,
             network_sender_in,
             network_receiver_out,
@@ -257,6 +348,11 @@ where
             }
         }
 
+        // Seed discovery with the peers known from previous runs
+        for (peer_id, addr) in self.peer_manager.known_peer_addresses().await {
+            self.swarm.behaviour_mut().add_known_address(&peer_id, addr);
+        }
+
         // Bootstrap with Kademlia
         if let Err(e) = self.swarm.behaviour_mut().bootstrap() {
             warn!("Failed to bootstrap with Kademlia: {e}");
@@ -273,11 +369,23 @@ where
         let (cx_response_tx, cx_response_rx) = flume::unbounded();
 
         let mut cx_response_rx_stream = cx_response_rx.stream().fuse();
+        let (gs_response_tx, gs_response_rx) = flume::unbounded();
+        let mut gs_response_rx_stream = gs_response_rx.stream().fuse();
         let mut bitswap_outbound_request_rx_stream = bitswap_request_manager
             .outbound_request_rx()
             .stream()
             .fuse();
+        let mut bitswap_provider_query_rx_stream =
+            bitswap_request_manager.provider_query_rx().stream().fuse();
         let mut peer_ops_rx_stream = self.peer_manager.peer_ops_rx().stream().fuse();
+        let (upnp_tx, upnp_rx) = flume::unbounded();
+        let mut upnp_rx_stream = upnp_rx.stream().fuse();
+        let bootstrap_peers: HashSet<PeerId> = self
+            .config
+            .bootstrap_peers
+            .iter()
+            .filter_map(peer_id_from_multiaddr)
+            .collect();
         let mut libp2p_registry = Default::default();
         let metrics = Metrics::new(&mut libp2p_registry);
         forest_metrics::add_metrics_registry("libp2p".into(), libp2p_registry).await;
@@ -291,14 +399,44 @@ where
                             swarm_stream.get_mut(),
                             &bitswap_request_manager,
                             &self.peer_manager,
+                            &self.rate_limiter,
+                            &self.config.relays,
                             event,
                             &self.cs,
                             &self.genesis_cid,
                             &self.network_sender_out,
                             cx_response_tx.clone(),
+                            gs_response_tx.clone(),
                             &pubsub_block_str,
                             &pubsub_msg_str,).await;
                     },
+                    Some(SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. }) => {
+                        if endpoint.is_dialer() {
+                            self.peer_manager
+                                .add_peer_address(peer_id, endpoint.get_remote_address().clone())
+                                .await;
+                        }
+                        if bootstrap_peers.contains(&peer_id) {
+                            swarm_stream
+                                .get_mut()
+                                .behaviour_mut()
+                                .set_pubsub_app_score(&peer_id, BOOTSTRAP_PEER_APP_SCORE);
+                        }
+                        metrics::PEER_CONNECTIONS
+                            .with_label_values(&[transport_label(endpoint.get_remote_address())])
+                            .inc();
+                    },
+                    Some(SwarmEvent::ConnectionClosed { endpoint, .. }) => {
+                        metrics::PEER_CONNECTIONS
+                            .with_label_values(&[transport_label(endpoint.get_remote_address())])
+                            .dec();
+                    },
+                    Some(SwarmEvent::NewListenAddr { address, .. }) => {
+                        info!("Listening on {address}");
+                        if self.config.upnp {
+                            tokio::spawn(map_upnp_port(address, upnp_tx.clone()));
+                        }
+                    },
                     None => { break; },
                     _ => { },
                 },
@@ -307,6 +445,8 @@ where
                     Some(message) => {
                         handle_network_message(
                             swarm_stream.get_mut(),
+                            &self.peer_manager,
+                            &self.bandwidth,
                             self.cs.clone(),
                             bitswap_request_manager.clone(),
                             message,
@@ -315,6 +455,8 @@ where
                     None => { break; }
                 },
                 interval_event = interval.next() => if interval_event.is_some() {
+                    self.bandwidth.sample();
+                    self.rate_limiter.prune();
                     // Print peer count on an interval.
                     debug!("Peers connected: {}", swarm_stream.get_mut().behaviour_mut().peers().len());
                 },
@@ -326,12 +468,30 @@ where
                         }
                     }
                 },
+                gs_response_opt = gs_response_rx_stream.next() => {
+                    if let Some((channel, gs_response)) = gs_response_opt {
+                        let behaviour = swarm_stream.get_mut().behaviour_mut();
+                        if let Err(e) = behaviour.graphsync.send_response(channel, gs_response) {
+                            warn!("Error sending graphsync response: {:?}", e.status);
+                        }
+                    }
+                },
                 bitswap_outbound_request_opt = bitswap_outbound_request_rx_stream.next() => {
                     if let Some((peer, request)) = bitswap_outbound_request_opt {
                         let bitswap = &mut swarm_stream.get_mut().behaviour_mut().bitswap;
                         bitswap.send_request(&peer, request);
                     }
                 }
+                bitswap_provider_query_opt = bitswap_provider_query_rx_stream.next() => {
+                    if let Some(cid) = bitswap_provider_query_opt {
+                        swarm_stream.get_mut().behaviour_mut().find_providers(cid);
+                    }
+                }
+                external_addr_opt = upnp_rx_stream.next() => {
+                    if let Some(external_addr) = external_addr_opt {
+                        swarm_stream.get_mut().add_external_address(external_addr, AddressScore::Infinite);
+                    }
+                },
                 peer_ops_opt = peer_ops_rx_stream.next() => {
                     if let Some(peer_ops) = peer_ops_opt {
                         handle_peer_ops(swarm_stream.get_mut(), peer_ops);
@@ -369,6 +529,8 @@ fn handle_peer_ops(swarm: &mut Swarm<ForestBehaviour>, peer_ops: PeerOperation)
 
 async fn handle_network_message(
     swarm: &mut Swarm<ForestBehaviour>,
+    peer_manager: &PeerManager,
+    bandwidth: &BandwidthSinks,
     store: Arc<impl BitswapStoreReadWrite>,
     bitswap_request_manager: Arc<BitswapRequestManager>,
     message: NetworkMessage,
@@ -412,12 +574,32 @@ async fn handle_network_message(
             )
             .await;
         }
+        NetworkMessage::GraphsyncRequest {
+            peer_id,
+            request,
+            response_channel,
+        } => {
+            swarm
+                .behaviour_mut()
+                .graphsync
+                .send_request(&peer_id, request, response_channel);
+        }
         NetworkMessage::BitswapRequest {
             epoch: _,
             cid,
+            session,
             response_channel,
         } => {
-            bitswap_request_manager.get_block(store, cid, BITSWAP_TIMEOUT, Some(response_channel));
+            // The blocks requested by the node are messages, which are not
+            // announced in the DHT
+            bitswap_request_manager.get_block_in_session(
+                store,
+                cid,
+                BITSWAP_TIMEOUT,
+                session,
+                false,
+                Some(response_channel),
+            );
         }
         NetworkMessage::JSONRPCRequest { method } => match method {
             NetRPCMethods::NetAddrsListen(response_channel) => {
@@ -456,12 +638,84 @@ async fn handle_network_message(
                     warn!("Failed to disconnect from a peer");
                 }
             }
+            NetRPCMethods::NetPubsubScores(response_channel) => {
+                let scores = swarm.behaviour().pubsub_scores();
+                if response_channel.send(scores).is_err() {
+                    warn!("Failed to get GossipSub peer scores");
+                }
+            }
+            NetRPCMethods::NetPeerRecords(response_channel) => {
+                let records = peer_manager.peer_records().await;
+                if response_channel.send(records).is_err() {
+                    warn!("Failed to get known peers");
+                }
+            }
+            NetRPCMethods::NetBan(response_channel, peer_id, duration) => {
+                let banned = peer_manager
+                    .ban_peer(peer_id, "Banned by operator", duration)
+                    .await;
+                if response_channel.send(banned).is_err() {
+                    warn!("Failed to ban a peer");
+                }
+            }
+            NetRPCMethods::NetUnban(response_channel, peer_id) => {
+                let unbanned = peer_manager.unban_peer(peer_id).await;
+                if response_channel.send(unbanned).is_err() {
+                    warn!("Failed to unban a peer");
+                }
+            }
+            NetRPCMethods::NetBans(response_channel) => {
+                let bans = peer_manager.peer_bans().await;
+                if response_channel.send(bans).is_err() {
+                    warn!("Failed to get peer bans");
+                }
+            }
+            NetRPCMethods::NetBandwidthStats(response_channel) => {
+                if response_channel.send(bandwidth.report()).is_err() {
+                    warn!("Failed to get bandwidth stats");
+                }
+            }
+            NetRPCMethods::NetAutoNatStatus(response_channel) => {
+                let nat_info = swarm.behaviour().nat_status().into();
+                if response_channel.send(nat_info).is_err() {
+                    warn!("Failed to get NAT status");
+                }
+            }
+            NetRPCMethods::NetFindPeer(response_channel, peer_id) => {
+                swarm.behaviour_mut().find_peer(peer_id, response_channel);
+            }
+            NetRPCMethods::NetPeerInfo(response_channel, peer_id) => {
+                let info = peer_manager.peer_info(&peer_id).await;
+                if response_channel.send(info).is_err() {
+                    warn!("Failed to get peer info");
+                }
+            }
+            NetRPCMethods::NetAgentVersion(response_channel, peer_id) => {
+                let agent_version = peer_manager.peer_agent_version(&peer_id).await;
+                if response_channel.send(agent_version).is_err() {
+                    warn!("Failed to get peer agent version");
+                }
+            }
+            NetRPCMethods::NetDhtRoutingTable(response_channel) => {
+                let buckets = swarm.behaviour_mut().routing_table();
+                if response_channel.send(buckets).is_err() {
+                    warn!("Failed to get DHT routing table");
+                }
+            }
+            NetRPCMethods::NetBitswapSessions(response_channel) => {
+                let sessions = bitswap_request_manager.session_stats();
+                if response_channel.send(sessions).is_err() {
+                    warn!("Failed to get bitswap sessions");
+                }
+            }
         },
     }
 }
 
 async fn handle_discovery_event(
     discovery_out: DiscoveryEvent,
+    peer_manager: &Arc<PeerManager>,
+    bitswap_request_manager: &Arc<BitswapRequestManager>,
     network_sender_out: &Sender<NetworkEvent>,
 ) {
     match discovery_out {
@@ -471,8 +725,13 @@ async fn handle_discovery_event(
         }
         DiscoveryEvent::PeerDisconnected(peer_id) => {
             debug!("Peer disconnected, {:?}", peer_id);
+            peer_manager.forget_peer_identity(&peer_id).await;
             emit_event(network_sender_out, NetworkEvent::PeerDisconnected(peer_id)).await;
         }
+        DiscoveryEvent::ProvidersFound(cid, providers) => {
+            debug!("Found {} providers of {cid}", providers.len());
+            bitswap_request_manager.on_providers_found(cid, providers);
+        }
     }
 }
 
@@ -568,7 +827,7 @@ async fn handle_hello_event(
                                 "Genesis hash mismatch: {} received, {genesis_cid} expected",
                                 request.genesis_cid
                             ),
-                            Some(BAN_PEER_DURATION),
+                            Some(peer_manager.ban_duration()),
                         )
                         .await;
                 } else {
@@ -634,6 +893,7 @@ async fn handle_ping_event(ping_event: ping::Event, peer_manager: &Arc<PeerManag
                 ping_event.peer.to_base58(),
                 rtt.as_millis()
             );
+            peer_manager.update_peer_latency(ping_event.peer, rtt).await;
         }
         Ok(ping::Success::Pong) => {
             trace!("PingSuccess::Pong from {}", ping_event.peer.to_base58());
@@ -654,7 +914,7 @@ async fn handle_ping_event(ping_event: ping::Event, peer_manager: &Arc<PeerManag
                     .ban_peer(
                         ping_event.peer,
                         format!("Ping protocol err: {err}"),
-                        Some(BAN_PEER_DURATION),
+                        Some(peer_manager.ban_duration()),
                     )
                     .await;
             }
@@ -662,10 +922,42 @@ async fn handle_ping_event(ping_event: ping::Event, peer_manager: &Arc<PeerManag
     }
 }
 
+/// Builds the response to a chain exchange request of `peer`, charging its
+/// encoded size to the peer, or a `GoAway` response if the peer is over its
+/// serving limits.
+fn make_rate_limited_chain_exchange_response<DB>(
+    db: &ChainStore<DB>,
+    rate_limiter: &RateLimiter,
+    peer: &PeerId,
+    request: &ChainExchangeRequest,
+) -> ChainExchangeResponse
+where
+    DB: Blockstore + Clone + Sync + Send + 'static,
+{
+    if !rate_limiter.try_acquire(peer) {
+        debug!("Rate limiting chain_exchange requests of {peer}");
+        metrics::SERVING_THROTTLED_TOTAL
+            .with_label_values(&[metrics::values::CHAIN_EXCHANGE])
+            .inc();
+        return ChainExchangeResponse {
+            chain: vec![],
+            status: ChainExchangeResponseStatus::GoAway,
+            message: "Rate limit exceeded".into(),
+        };
+    }
+    let response = make_chain_exchange_response(db, request);
+    match fvm_ipld_encoding::to_vec(&response) {
+        Ok(bytes) => rate_limiter.charge(peer, bytes.len()),
+        Err(e) => warn!("Failed to encode ChainExchangeResponse: {e}"),
+    }
+    response
+}
+
 async fn handle_chain_exchange_event<DB>(
     chain_exchange: &mut ChainExchangeBehaviour,
     ce_event: request_response::Event<ChainExchangeRequest, ChainExchangeResponse>,
     db: &Arc<ChainStore<DB>>,
+    rate_limiter: &Arc<RateLimiter>,
     network_sender_out: &Sender<NetworkEvent>,
     cx_response_tx: Sender<(
         RequestId,
@@ -690,12 +982,15 @@ async fn handle_chain_exchange_event<DB>(
                     )
                     .await;
                     let db = db.clone();
+                    let rate_limiter = rate_limiter.clone();
                     tokio::task::spawn(async move {
-                        if let Err(e) = cx_response_tx.send((
-                            request_id,
-                            channel,
-                            make_chain_exchange_response(db.as_ref(), &request),
-                        )) {
+                        let response = make_rate_limited_chain_exchange_response(
+                            db.as_ref(),
+                            rate_limiter.as_ref(),
+                            &peer,
+                            &request,
+                        );
+                        if let Err(e) = cx_response_tx.send((request_id, channel, response)) {
                             debug!("Failed to send ChainExchangeResponse: {e:?}");
                         }
                     });
@@ -742,11 +1037,89 @@ async fn handle_chain_exchange_event<DB>(
     }
 }
 
+async fn handle_graphsync_event<DB>(
+    graphsync: &mut GraphsyncBehaviour,
+    gs_event: request_response::Event<GraphsyncRequest, GraphsyncResponse>,
+    db: &Arc<ChainStore<DB>>,
+    rate_limiter: &Arc<RateLimiter>,
+    gs_response_tx: Sender<(ResponseChannel<GraphsyncResponse>, GraphsyncResponse)>,
+) where
+    DB: Blockstore + Clone + Sync + Send + 'static,
+{
+    match gs_event {
+        request_response::Event::Message { peer, message } => match message {
+            request_response::Message::Request {
+                request,
+                channel,
+                request_id,
+            } => {
+                trace!("Received graphsync request (request_id:{request_id}, peer_id: {peer:?})");
+                if !rate_limiter.try_acquire(&peer) {
+                    debug!("Rate limiting graphsync requests of {peer}");
+                    metrics::SERVING_THROTTLED_TOTAL
+                        .with_label_values(&[metrics::values::GRAPHSYNC])
+                        .inc();
+                    let response = GraphsyncResponse {
+                        status: GraphsyncResponseStatus::GoAway,
+                        message: "Rate limit exceeded".into(),
+                        blocks: vec![],
+                    };
+                    if let Err(e) = gs_response_tx.send((channel, response)) {
+                        debug!("Failed to send GraphsyncResponse: {e:?}");
+                    }
+                } else {
+                    let db = db.clone();
+                    let rate_limiter = rate_limiter.clone();
+                    tokio::task::spawn(async move {
+                        let response = make_graphsync_response(db.blockstore(), request).await;
+                        rate_limiter.charge(
+                            &peer,
+                            response
+                                .blocks
+                                .iter()
+                                .map(|block| block.data.bytes().len())
+                                .sum(),
+                        );
+                        if let Err(e) = gs_response_tx.send((channel, response)) {
+                            debug!("Failed to send GraphsyncResponse: {e:?}");
+                        }
+                    });
+                }
+            }
+            request_response::Message::Response {
+                request_id,
+                response,
+            } => {
+                graphsync
+                    .handle_inbound_response(&request_id, response)
+                    .await;
+            }
+        },
+        request_response::Event::OutboundFailure {
+            peer: _,
+            request_id,
+            error,
+        } => {
+            graphsync.on_outbound_error(&request_id, error);
+        }
+        request_response::Event::InboundFailure {
+            peer,
+            error,
+            request_id: _,
+        } => {
+            debug!("Graphsync inbound error (peer: {peer:?}): {error:?}");
+        }
+        request_response::Event::ResponseSent { .. } => (),
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn handle_forest_behaviour_event<DB>(
     swarm: &mut Swarm<ForestBehaviour>,
     bitswap_request_manager: &Arc<BitswapRequestManager>,
     peer_manager: &Arc<PeerManager>,
+    rate_limiter: &Arc<RateLimiter>,
+    relays: &[Multiaddr],
     event: ForestBehaviourEvent,
     db: &Arc<ChainStore<DB>>,
     genesis_cid: &Cid,
@@ -756,6 +1129,7 @@ async fn handle_forest_behaviour_event<DB>(
         ResponseChannel<ChainExchangeResponse>,
         ChainExchangeResponse,
     )>,
+    gs_response_tx: Sender<(ResponseChannel<GraphsyncResponse>, GraphsyncResponse)>,
     pubsub_block_str: &str,
     pubsub_msg_str: &str,
 ) where
@@ -763,7 +1137,13 @@ async fn handle_forest_behaviour_event<DB>(
 {
     match event {
         ForestBehaviourEvent::Discovery(discovery_out) => {
-            handle_discovery_event(discovery_out, network_sender_out).await
+            handle_discovery_event(
+                discovery_out,
+                peer_manager,
+                bitswap_request_manager,
+                network_sender_out,
+            )
+            .await
         }
         ForestBehaviourEvent::Gossipsub(e) => {
             handle_gossip_event(e, network_sender_out, pubsub_block_str, pubsub_msg_str).await
@@ -779,21 +1159,44 @@ async fn handle_forest_behaviour_event<DB>(
             .await
         }
         ForestBehaviourEvent::Bitswap(event) => {
-            if let Err(e) = bitswap_request_manager.handle_event(
+            if let Err(e) = bitswap_request_manager.handle_event_with_limiter(
                 &mut swarm.behaviour_mut().bitswap,
                 db.blockstore(),
                 event,
+                rate_limiter.as_ref(),
             ) {
                 warn!("bitswap: {e}");
             }
         }
         ForestBehaviourEvent::Ping(ping_event) => handle_ping_event(ping_event, peer_manager).await,
+        ForestBehaviourEvent::Identify(identify::Event::Received { peer_id, info }) => {
+            peer_manager.update_peer_identity(peer_id, info).await
+        }
         ForestBehaviourEvent::Identify(_) => {}
+        ForestBehaviourEvent::Gater(never) => match never {},
+        ForestBehaviourEvent::Autonat(autonat_event) => {
+            handle_autonat_event(swarm, autonat_event, relays)
+        }
+        ForestBehaviourEvent::RelayClient(relay_event) => {
+            debug!("Relay client event: {relay_event:?}")
+        }
+        ForestBehaviourEvent::Dcutr(dcutr_event) => debug!("DCUtR event: {dcutr_event:?}"),
+        ForestBehaviourEvent::Graphsync(gs_event) => {
+            handle_graphsync_event(
+                &mut swarm.behaviour_mut().graphsync,
+                gs_event,
+                db,
+                rate_limiter,
+                gs_response_tx,
+            )
+            .await
+        }
         ForestBehaviourEvent::ChainExchange(ce_event) => {
             handle_chain_exchange_event(
                 &mut swarm.behaviour_mut().chain_exchange,
                 ce_event,
                 db,
+                rate_limiter,
                 network_sender_out,
                 cx_response_tx,
             )
@@ -802,27 +1205,112 @@ async fn handle_forest_behaviour_event<DB>(
     }
 }
 
+fn handle_autonat_event(
+    swarm: &mut Swarm<ForestBehaviour>,
+    event: autonat::Event,
+    relays: &[Multiaddr],
+) {
+    if let autonat::Event::StatusChanged { old, new } = event {
+        info!("NAT status changed from {old:?} to {new:?}");
+        let listening_on_relay = Swarm::listeners(swarm)
+            .any(|addr| addr.iter().any(|p| matches!(p, Protocol::P2pCircuit)));
+        if matches!(new, NatStatus::Private) && !listening_on_relay {
+            // Unreachable: obtain relayed addresses from the relays
+            for relay in relays {
+                let circuit_addr = relay.clone().with(Protocol::P2pCircuit);
+                if let Err(e) = Swarm::listen_on(swarm, circuit_addr) {
+                    warn!("Failed to listen through relay {relay}: {e}");
+                }
+            }
+        }
+    }
+}
+
 async fn emit_event(sender: &Sender<NetworkEvent>, event: NetworkEvent) {
     if sender.send_async(event).await.is_err() {
         error!("Failed to emit event: Network channel receiver has been dropped");
     }
 }
 
-/// Builds the transport stack that libp2p will communicate over. When support
-/// of other protocols like `udp`, `quic`, `http` are added, remember to update
-/// code comment in [Libp2pConfig].
+/// Builds the transport stack that libp2p will communicate over, made of the
+/// given transports, with their traffic recorded in the bandwidth sinks. When
+/// support of other protocols like `webrtc`, `http` are added, remember to
+/// update code comment in [Libp2pConfig].
 ///
 /// As a reference `lotus` uses the default `go-libp2p` transport builder which
 /// has all above protocols enabled.
-pub fn build_transport(local_key: Keypair) -> anyhow::Result<Boxed<(PeerId, StreamMuxerBox)>> {
+pub fn build_transport(
+    local_key: Keypair,
+    transports: &[Libp2pTransport],
+    relay_transport: Option<relay::client::Transport>,
+    bandwidth: Arc<BandwidthSinks>,
+) -> anyhow::Result<Boxed<(PeerId, StreamMuxerBox)>> {
     let build_tcp = || libp2p::tcp::tokio::Transport::new(libp2p::tcp::Config::new().nodelay(true));
     let build_dns_tcp = || libp2p::dns::TokioDnsConfig::system(build_tcp());
-    let transport =
-        libp2p::websocket::WsConfig::new(build_dns_tcp()?).or_transport(build_dns_tcp()?);
 
+    let enabled = |transport| transports.contains(&transport);
+    // QUIC comes first so that it handles `quic-v1` addresses, TCP based
+    // transports are the fallback for peers without QUIC addresses
+    let mut boxed = vec![];
+    // The relay transport only handles `p2p-circuit` addresses, and comes
+    // first so that they are not dialed as plain relay addresses
+    if let Some(relay_transport) = relay_transport {
+        boxed.push(upgrade_transport(relay_transport, &local_key)?);
+    }
+    if enabled(Libp2pTransport::Quic) {
+        boxed.push(
+            libp2p::quic::tokio::Transport::new(libp2p::quic::Config::new(&local_key))
+                .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+                .boxed(),
+        );
+    }
+    if enabled(Libp2pTransport::WebSocket) {
+        boxed.push(upgrade_transport(
+            libp2p::websocket::WsConfig::new(build_dns_tcp()?),
+            &local_key,
+        )?);
+    }
+    if enabled(Libp2pTransport::Tcp) {
+        boxed.push(upgrade_transport(build_dns_tcp()?, &local_key)?);
+    }
+
+    boxed
+        .into_iter()
+        .reduce(|transport, fallback| {
+            transport
+                .or_transport(fallback)
+                .map(|output, _| match output {
+                    Either::Left(output) | Either::Right(output) => output,
+                })
+                .boxed()
+        })
+        .context("At least one libp2p transport must be enabled")
+        .map(move |transport| {
+            transport
+                .map(move |(peer_id, muxer), _| {
+                    let muxer = CountingMuxer::new(muxer, bandwidth.clone(), peer_id);
+                    (peer_id, StreamMuxerBox::new(muxer))
+                })
+                .boxed()
+        })
+}
+
+/// Secures a stream based transport with `noise` and multiplexes it with
+/// `yamux`. QUIC does both on its own.
+fn upgrade_transport<T>(
+    transport: T,
+    local_key: &Keypair,
+) -> anyhow::Result<Boxed<(PeerId, StreamMuxerBox)>>
+where
+    T: Transport + Send + Unpin + 'static,
+    T::Output: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    T::Error: Send + Sync + 'static,
+    T::Dial: Send + 'static,
+    T::ListenerUpgrade: Send + 'static,
+{
     let auth_config = {
         let dh_keys = noise::Keypair::<noise::X25519Spec>::new()
-            .into_authentic(&local_key)
+            .into_authentic(local_key)
             .context("Noise key generation failed")?;
         noise::NoiseConfig::xx(dh_keys).into_authenticated()
     };
@@ -835,6 +1323,21 @@ pub fn build_transport(local_key: Keypair) -> anyhow::Result<Boxed<(PeerId, Stre
         .boxed())
 }
 
+/// Returns the transport label of a connection, given the address of the
+/// remote peer.
+fn transport_label(addr: &Multiaddr) -> &'static str {
+    let mut label = metrics::values::OTHER;
+    for protocol in addr.iter() {
+        match protocol {
+            Protocol::Quic | Protocol::QuicV1 => return metrics::values::QUIC,
+            Protocol::Ws(_) | Protocol::Wss(_) => return metrics::values::WEBSOCKET,
+            Protocol::Tcp(_) => label = metrics::values::TCP,
+            _ => {}
+        }
+    }
+    label
+}
+
 /// Fetch key-pair from disk, returning none if it cannot be decoded.
 pub fn get_keypair(path: &Path) -> Option<Keypair> {
     match read_file_to_vec(path) {
@@ -856,3 +1359,163 @@ pub fn get_keypair(path: &Path) -> Option<Keypair> {
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use forest_blocks::BlockHeader;
+    use forest_db::MemoryDB;
+    use forest_genesis::EXPORT_SR_40;
+    use forest_networks::ChainConfig;
+    use forest_shim::address::Address;
+    use fvm_ipld_car::load_car;
+    use libp2p::core::transport::TransportError;
+    use tempfile::TempDir;
+    use tokio::io::BufReader;
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    use super::*;
+    use crate::{chain_exchange::HEADERS, config::ServingLimits};
+
+    #[test]
+    fn transport_labels() {
+        let label = |addr: &str| transport_label(&addr.parse().unwrap());
+        assert_eq!(label("/ip4/127.0.0.1/tcp/1234"), metrics::values::TCP);
+        assert_eq!(
+            label("/ip4/127.0.0.1/udp/1234/quic-v1"),
+            metrics::values::QUIC
+        );
+        assert_eq!(
+            label("/dns4/example.com/tcp/443/wss"),
+            metrics::values::WEBSOCKET
+        );
+        // Relayed connections are counted under the transport to the relay
+        let circuit = format!(
+            "/ip4/127.0.0.1/tcp/1234/p2p/{}/p2p-circuit/p2p/{}",
+            PeerId::random(),
+            PeerId::random()
+        );
+        assert_eq!(label(&circuit), metrics::values::TCP);
+        assert_eq!(label("/ip4/127.0.0.1"), metrics::values::OTHER);
+    }
+
+    fn build(
+        transports: &[Libp2pTransport],
+        relay_transport: Option<relay::client::Transport>,
+    ) -> anyhow::Result<Boxed<(PeerId, StreamMuxerBox)>> {
+        build_transport(
+            Keypair::generate_ed25519(),
+            transports,
+            relay_transport,
+            Arc::new(BandwidthSinks::default()),
+        )
+    }
+
+    /// Listens on each address with a transport built from the given
+    /// transports, and the relay transport if `relay` is set, returning whether
+    /// they support them.
+    fn supported(transports: &[Libp2pTransport], relay: bool, addrs: &[Multiaddr]) -> Vec<bool> {
+        // The relay client is kept alive for its transport to accept listeners
+        let (relay_transport, _relay_client) = match relay {
+            true => {
+                let (transport, client) = relay::client::new(PeerId::random());
+                (Some(transport), Some(client))
+            }
+            false => (None, None),
+        };
+        let mut transport = build(transports, relay_transport).unwrap();
+        addrs
+            .iter()
+            .map(|addr| match transport.listen_on(addr.clone()) {
+                Ok(_) => true,
+                Err(TransportError::MultiaddrNotSupported(_)) => false,
+                Err(e) => panic!("Failed to listen on {addr}: {e}"),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn configured_transports() {
+        let circuit = format!(
+            "/ip4/127.0.0.1/tcp/1234/p2p/{}/p2p-circuit",
+            PeerId::random()
+        );
+        let addrs: Vec<Multiaddr> = [
+            "/ip4/127.0.0.1/tcp/0",
+            "/ip4/127.0.0.1/udp/0/quic-v1",
+            "/ip4/127.0.0.1/tcp/0/ws",
+            circuit.as_str(),
+        ]
+        .iter()
+        .map(|addr| addr.parse().unwrap())
+        .collect();
+
+        use Libp2pTransport::*;
+        assert_eq!(
+            supported(&[Tcp], false, &addrs),
+            [true, false, false, false]
+        );
+        assert_eq!(
+            supported(&[Quic], false, &addrs),
+            [false, true, false, false]
+        );
+        assert_eq!(
+            supported(&[WebSocket], false, &addrs),
+            [false, false, true, false]
+        );
+        assert_eq!(
+            supported(&[Quic, Tcp], false, &addrs),
+            [true, true, false, false]
+        );
+        assert_eq!(
+            supported(&Libp2pTransport::ALL, false, &addrs),
+            [true, true, true, false]
+        );
+        // Circuit addresses are only handled by the relay transport
+        assert_eq!(supported(&[Tcp], true, &addrs), [true, false, false, true]);
+        assert_eq!(
+            supported(&Libp2pTransport::ALL, true, &addrs),
+            [true, true, true, true]
+        );
+
+        assert!(build(&[], None).is_err());
+    }
+
+    #[tokio::test]
+    async fn chain_exchange_over_budget_peer_gets_go_away() {
+        let db = MemoryDB::default();
+        let reader = BufReader::<&[u8]>::new(EXPORT_SR_40);
+        let start = load_car(&db, reader.compat()).await.unwrap();
+        let genesis = BlockHeader::builder()
+            .miner_address(Address::new_id(0))
+            .build()
+            .unwrap();
+        let chain_store_root = TempDir::new().unwrap();
+        let chain_store = ChainStore::new(
+            db,
+            Arc::new(ChainConfig::default()),
+            &genesis,
+            chain_store_root.path(),
+        )
+        .unwrap();
+        let request = ChainExchangeRequest {
+            start,
+            request_len: 2,
+            options: HEADERS,
+        };
+        // 10 bytes of burst, less than a response
+        let rate_limiter = RateLimiter::new(ServingLimits {
+            peer_requests_per_sec: 0,
+            peer_bytes_per_sec: 1,
+            global_bytes_per_sec: 0,
+        });
+        let (peer, other) = (PeerId::random(), PeerId::random());
+        let serve = |peer| {
+            make_rate_limited_chain_exchange_response(&chain_store, &rate_limiter, &peer, &request)
+                .status
+        };
+
+        assert_eq!(serve(peer), ChainExchangeResponseStatus::Success);
+        assert_eq!(serve(peer), ChainExchangeResponseStatus::GoAway);
+        assert_eq!(serve(other), ChainExchangeResponseStatus::Success);
+    }
+}
@@ -3,23 +3,27 @@
 
 use std::sync::Arc;
 
-use ahash::HashSet;
+use ahash::{HashMap, HashSet};
 use cid::Cid;
 use fil_actor_interface::market::{DealProposal, DealState};
 use forest_beacon::{Beacon, BeaconSchedule};
 use forest_blocks::{tipset_keys_json::TipsetKeysJson, Tipset};
-use forest_chain::ChainStore;
-use forest_chain_sync::{BadBlockCache, SyncState};
+use forest_chain::{ChainStore, ReorgRecord};
+use forest_chain_sync::{
+    BadBlockCache, BadBlockRecord, ConsensusFault, ConsensusFaultType, ExplainBlock, FailedCheck,
+    Slasher, SyncState,
+};
 use forest_ipld::json::IpldJson;
 use forest_json::{cid::CidJson, message_receipt::json::ReceiptJson, token_amount::json};
-use forest_key_management::KeyStore;
+use forest_key_management::{KeyStore, Signer};
 pub use forest_libp2p::{Multiaddr, Protocol};
 use forest_libp2p::{Multihash, NetworkMessage};
 use forest_message::signed_message::SignedMessage;
 use forest_message_pool::{MessagePool, MpoolRpcProvider};
-use forest_shim::{econ::TokenAmount, message::Message};
+use forest_shim::{address::Address, econ::TokenAmount, message::Message};
 use forest_state_manager::StateManager;
 use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::clock::ChainEpoch;
 use jsonrpc_v2::{MapRouter as JsonRpcMapRouter, Server as JsonRpcServer};
 use parking_lot::RwLock as SyncRwLock;
 use serde::{Deserialize, Serialize};
@@ -33,6 +37,8 @@ where
     B: Beacon,
 {
     pub keystore: Arc<RwLock<KeyStore>>,
+    /// Signer used for addresses whose keys are not in the local key-store
+    pub remote_signer: Option<Arc<dyn Signer>>,
     pub chain_store: Arc<ChainStore<DB>>,
     pub state_manager: Arc<StateManager<DB>>,
     pub mpool: Arc<MessagePool<MpoolRpcProvider<DB>>>,
@@ -43,6 +49,10 @@ where
     pub new_mined_block_tx: flume::Sender<Arc<Tipset>>,
     pub beacon: Arc<BeaconSchedule<B>>,
     pub gc_event_tx: flume::Sender<flume::Sender<anyhow::Result<()>>>,
+    /// Consensus fault detector, if enabled
+    pub slasher: Option<Arc<Slasher>>,
+    /// Validator explaining why blocks are bad, if available
+    pub block_explainer: Option<Arc<dyn ExplainBlock>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -92,6 +102,46 @@ pub struct MessageLookup {
     pub return_dec: IpldJson,
 }
 
+// Multisig API
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MsigTransaction {
+    #[serde(rename = "ID")]
+    pub id: i64,
+    #[serde(with = "forest_json::address::json")]
+    pub to: Address,
+    #[serde(with = "json")]
+    pub value: TokenAmount,
+    pub method: u64,
+    /// Base64 encoded method parameters
+    pub params: String,
+    #[serde(with = "forest_json::address::json::vec")]
+    pub approved: Vec<Address>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MsigVesting {
+    #[serde(with = "json")]
+    pub initial_balance: TokenAmount,
+    pub start_epoch: ChainEpoch,
+    pub unlock_duration: ChainEpoch,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MsigInfo {
+    #[serde(with = "json")]
+    pub balance: TokenAmount,
+    #[serde(with = "json")]
+    pub locked: TokenAmount,
+    #[serde(with = "forest_json::address::json::vec")]
+    pub signers: Vec<Address>,
+    pub threshold: u64,
+    pub vesting: MsigVesting,
+    pub pending: Vec<MsigTransaction>,
+}
+
 // Net API
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -101,6 +151,22 @@ pub struct AddrInfo {
     pub addrs: HashSet<Multiaddr>,
 }
 
+/// `GossipSub` score of a peer. Only the total score is tracked, other fields
+/// of the Lotus snapshot are not exposed by `libp2p`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PubsubScore {
+    #[serde(rename = "ID")]
+    pub id: String,
+    pub score: PeerScoreSnapshot,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PeerScoreSnapshot {
+    pub score: f64,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct PeerID {
     pub multihash: Multihash,
@@ -125,3 +191,165 @@ impl Version {
         Self((major as u32) << 16 | (minor as u32) << 8 | (patch as u32))
     }
 }
+
+/// Filter of the events emitted by actors. Unset fields match everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ActorEventFilter {
+    /// Emitters of the events to match.
+    #[serde(with = "forest_json::address::json::vec")]
+    pub addresses: Vec<Address>,
+    /// Values to match for the keys of the indexed entries of the events.
+    pub fields: HashMap<String, Vec<ActorEventBlock>>,
+    pub from_height: Option<ChainEpoch>,
+    pub to_height: Option<ChainEpoch>,
+    /// Only match the events of a tipset, in place of the height range.
+    #[serde(rename = "TipSetKey")]
+    pub tipset_key: Option<TipsetKeysJson>,
+}
+
+/// Value of an entry of an actor event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ActorEventBlock {
+    #[serde(default)]
+    pub codec: u64,
+    /// Base64 encoded value.
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ActorEventEntry {
+    pub flags: u64,
+    pub key: String,
+    /// Base64 encoded value.
+    pub value: String,
+}
+
+/// Event emitted by an actor, as returned by `Filecoin.GetActorEvents`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ActorEvent {
+    pub entries: Vec<ActorEventEntry>,
+    #[serde(with = "forest_json::address::json")]
+    pub emitter: Address,
+    pub reverted: bool,
+    pub height: ChainEpoch,
+    #[serde(rename = "TipSetKey")]
+    pub tipset_key: TipsetKeysJson,
+    pub msg_cid: CidJson,
+}
+
+/// Re-org of the heaviest chain, as returned by `Filecoin.ChainReorgs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ChainReorg {
+    pub timestamp: u64,
+    pub depth: ChainEpoch,
+    pub old_head: TipsetKeysJson,
+    pub old_height: ChainEpoch,
+    pub new_head: TipsetKeysJson,
+    pub new_height: ChainEpoch,
+    pub dropped: Vec<TipsetKeysJson>,
+}
+
+impl From<ReorgRecord> for ChainReorg {
+    fn from(record: ReorgRecord) -> Self {
+        Self {
+            timestamp: record.timestamp,
+            depth: record.depth,
+            old_head: record.old_head.into(),
+            old_height: record.old_epoch,
+            new_head: record.new_head.into(),
+            new_height: record.new_epoch,
+            dropped: record.dropped.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Consensus fault detected by the slasher, as returned by
+/// `Filecoin.SyncConsensusFaults`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ConsensusFaultJson {
+    pub fault_type: ConsensusFaultType,
+    #[serde(with = "forest_json::address::json")]
+    pub miner: Address,
+    pub height: ChainEpoch,
+    pub block1: CidJson,
+    pub block2: CidJson,
+    pub block_extra: Option<CidJson>,
+    pub detected_at: u64,
+}
+
+impl From<ConsensusFault> for ConsensusFaultJson {
+    fn from(fault: ConsensusFault) -> Self {
+        Self {
+            fault_type: fault.fault_type,
+            miner: fault.miner,
+            height: fault.epoch,
+            block1: CidJson(*fault.header1.cid()),
+            block2: CidJson(*fault.header2.cid()),
+            block_extra: fault.header_extra.map(|header| CidJson(*header.cid())),
+            detected_at: fault.detected_at,
+        }
+    }
+}
+
+/// Checks a block fails when validated again, and the record of why it was
+/// marked bad if it was, as returned by `Filecoin.SyncExplainBlock`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BlockExplanation {
+    pub failed_checks: Vec<FailedCheck>,
+    pub bad_block: Option<BadBlockRecord>,
+}
+
+/// Either a single value or a list of values, as accepted by the Ethereum
+/// filters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EthOneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> EthOneOrMany<T> {
+    pub fn into_vec(self) -> Vec<T> {
+        match self {
+            EthOneOrMany::One(value) => vec![value],
+            EthOneOrMany::Many(values) => values,
+        }
+    }
+}
+
+/// Filter of `eth_newFilter` and `eth_getLogs`. Blocks are hexadecimal
+/// numbers or one of `earliest`, `latest`, `safe`, `finalized` and `pending`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EthFilterSpec {
+    pub from_block: Option<String>,
+    pub to_block: Option<String>,
+    /// Ethereum addresses of the emitters to match.
+    pub address: Option<EthOneOrMany<String>>,
+    /// Topics to match at each position, `null` matching any topic.
+    pub topics: Vec<Option<EthOneOrMany<String>>>,
+    /// Only match the logs of a block, in place of the block range.
+    pub block_hash: Option<String>,
+}
+
+/// Log of an Ethereum transaction, i.e. an event emitted by an actor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EthLog {
+    pub address: String,
+    pub data: String,
+    pub topics: Vec<String>,
+    pub removed: bool,
+    pub log_index: String,
+    pub transaction_index: String,
+    pub transaction_hash: String,
+    pub block_hash: String,
+    pub block_number: String,
+}
@@ -40,6 +40,8 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     access.insert(chain_api::CHAIN_GET_TIPSET_HASH, Access::Read);
     access.insert(chain_api::CHAIN_VALIDATE_TIPSET_CHECKPOINTS, Access::Read);
     access.insert(chain_api::CHAIN_GET_NAME, Access::Read);
+    access.insert(chain_api::CHAIN_REORGS, Access::Read);
+    access.insert(chain_api::CHAIN_BACKFILL_MESSAGE_INDEX, Access::Admin);
 
     // Message Pool API
     access.insert(mpool_api::MPOOL_PENDING, Access::Read);
@@ -49,7 +51,12 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     // Sync API
     access.insert(sync_api::SYNC_CHECK_BAD, Access::Read);
     access.insert(sync_api::SYNC_MARK_BAD, Access::Admin);
+    access.insert(sync_api::SYNC_UNMARK_BAD, Access::Admin);
+    access.insert(sync_api::SYNC_UNMARK_ALL_BAD, Access::Admin);
     access.insert(sync_api::SYNC_STATE, Access::Read);
+    access.insert(sync_api::SYNC_CONSENSUS_FAULTS, Access::Read);
+    // Validating a block computes the state of its parent
+    access.insert(sync_api::SYNC_EXPLAIN_BLOCK, Access::Write);
 
     // Wallet API
     access.insert(wallet_api::WALLET_BALANCE, Access::Write);
@@ -62,6 +69,20 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     access.insert(wallet_api::WALLET_SET_DEFAULT, Access::Write);
     access.insert(wallet_api::WALLET_SIGN, Access::Sign);
     access.insert(wallet_api::WALLET_VERIFY, Access::Read);
+    access.insert(wallet_api::WALLET_CHANGE_PASSPHRASE, Access::Admin);
+    access.insert(wallet_api::WALLET_ENCRYPT_KEYSTORE, Access::Admin);
+    access.insert(wallet_api::WALLET_DECRYPT_KEYSTORE, Access::Admin);
+    access.insert(wallet_api::WALLET_BACKUP_KEYSTORE, Access::Admin);
+
+    // Multisig API
+    access.insert(msig_api::MSIG_CREATE, Access::Sign);
+    access.insert(msig_api::MSIG_PROPOSE, Access::Sign);
+    access.insert(msig_api::MSIG_APPROVE, Access::Sign);
+    access.insert(msig_api::MSIG_CANCEL, Access::Sign);
+    access.insert(msig_api::MSIG_GET_PENDING, Access::Read);
+    access.insert(msig_api::MSIG_GET_AVAILABLE_BALANCE, Access::Read);
+    access.insert(msig_api::MSIG_GET_VESTING_SCHEDULE, Access::Read);
+    access.insert(msig_api::MSIG_INSPECT, Access::Read);
 
     // State API
     access.insert(state_api::STATE_CALL, Access::Read);
@@ -70,8 +91,18 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     access.insert(state_api::STATE_MARKET_DEALS, Access::Read);
     access.insert(state_api::STATE_GET_RECEIPT, Access::Read);
     access.insert(state_api::STATE_WAIT_MSG, Access::Read);
+    access.insert(state_api::STATE_SEARCH_MSG, Access::Read);
     access.insert(state_api::STATE_NETWORK_NAME, Access::Read);
     access.insert(state_api::STATE_NETWORK_VERSION, Access::Read);
+    access.insert(state_api::STATE_GET_RANDOMNESS_FROM_TICKETS, Access::Read);
+    access.insert(state_api::STATE_GET_RANDOMNESS_FROM_BEACON, Access::Read);
+
+    // Event API
+    access.insert(event_api::GET_ACTOR_EVENTS, Access::Read);
+    access.insert(event_api::ETH_GET_LOGS, Access::Read);
+    access.insert(event_api::ETH_NEW_FILTER, Access::Read);
+    access.insert(event_api::ETH_GET_FILTER_CHANGES, Access::Read);
+    access.insert(event_api::ETH_UNINSTALL_FILTER, Access::Read);
 
     // Gas API
     access.insert(gas_api::GAS_ESTIMATE_GAS_LIMIT, Access::Read);
@@ -88,6 +119,18 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     access.insert(net_api::NET_PEERS, Access::Read);
     access.insert(net_api::NET_CONNECT, Access::Write);
     access.insert(net_api::NET_DISCONNECT, Access::Write);
+    access.insert(net_api::NET_PUBSUB_SCORES, Access::Read);
+    access.insert(net_api::NET_KNOWN_PEERS, Access::Read);
+    access.insert(net_api::NET_BAN, Access::Admin);
+    access.insert(net_api::NET_UNBAN, Access::Admin);
+    access.insert(net_api::NET_BANS, Access::Read);
+    access.insert(net_api::NET_BANDWIDTH_STATS, Access::Read);
+    access.insert(net_api::NET_AUTO_NAT_STATUS, Access::Read);
+    access.insert(net_api::NET_FIND_PEER, Access::Read);
+    access.insert(net_api::NET_PEER_INFO, Access::Read);
+    access.insert(net_api::NET_AGENT_VERSION, Access::Read);
+    access.insert(net_api::NET_DHT_ROUTING_TABLE, Access::Read);
+    access.insert(net_api::NET_BITSWAP_SESSIONS, Access::Read);
 
     // DB API
     access.insert(db_api::DB_GC, Access::Write);
@@ -154,7 +197,7 @@ pub mod chain_api {
     use fvm_shared::clock::ChainEpoch;
     use serde::{Deserialize, Serialize};
 
-    use crate::data_types::BlockMessages;
+    use crate::data_types::{BlockMessages, ChainReorg};
 
     pub const CHAIN_GET_MESSAGE: &str = "Filecoin.ChainGetMessage";
     pub type ChainGetMessageParams = (CidJson,);
@@ -217,6 +260,14 @@ pub mod chain_api {
     pub const CHAIN_GET_NAME: &str = "Filecoin.ChainGetName";
     pub type ChainGetNameParams = ();
     pub type ChainGetNameResult = String;
+
+    pub const CHAIN_REORGS: &str = "Filecoin.ChainReorgs";
+    pub type ChainReorgsParams = ();
+    pub type ChainReorgsResult = Vec<ChainReorg>;
+
+    pub const CHAIN_BACKFILL_MESSAGE_INDEX: &str = "Filecoin.ChainBackfillMessageIndex";
+    pub type ChainBackfillMessageIndexParams = (ChainEpoch, ChainEpoch);
+    pub type ChainBackfillMessageIndexResult = usize;
 }
 
 /// Message Pool API
@@ -247,7 +298,7 @@ pub mod mpool_api {
 pub mod sync_api {
     use forest_json::cid::CidJson;
 
-    use crate::data_types::RPCSyncState;
+    use crate::data_types::{BlockExplanation, ConsensusFaultJson, RPCSyncState};
 
     pub const SYNC_CHECK_BAD: &str = "Filecoin.SyncCheckBad";
     pub type SyncCheckBadParams = (CidJson,);
@@ -257,9 +308,25 @@ pub mod sync_api {
     pub type SyncMarkBadParams = (CidJson,);
     pub type SyncMarkBadResult = ();
 
+    pub const SYNC_UNMARK_BAD: &str = "Filecoin.SyncUnmarkBad";
+    pub type SyncUnmarkBadParams = (CidJson,);
+    pub type SyncUnmarkBadResult = ();
+
+    pub const SYNC_UNMARK_ALL_BAD: &str = "Filecoin.SyncUnmarkAllBad";
+    pub type SyncUnmarkAllBadParams = ();
+    pub type SyncUnmarkAllBadResult = ();
+
     pub const SYNC_STATE: &str = "Filecoin.SyncState";
     pub type SyncStateParams = ();
     pub type SyncStateResult = RPCSyncState;
+
+    pub const SYNC_CONSENSUS_FAULTS: &str = "Filecoin.SyncConsensusFaults";
+    pub type SyncConsensusFaultsParams = ();
+    pub type SyncConsensusFaultsResult = Vec<ConsensusFaultJson>;
+
+    pub const SYNC_EXPLAIN_BLOCK: &str = "Filecoin.SyncExplainBlock";
+    pub type SyncExplainBlockParams = (CidJson,);
+    pub type SyncExplainBlockResult = BlockExplanation;
 }
 
 /// Wallet API
@@ -309,6 +376,66 @@ pub mod wallet_api {
     pub const WALLET_VERIFY: &str = "Filecoin.WalletVerify";
     pub type WalletVerifyParams = (AddressJson, Vec<u8>, SignatureJson);
     pub type WalletVerifyResult = bool;
+
+    pub const WALLET_CHANGE_PASSPHRASE: &str = "Filecoin.WalletChangePassphrase";
+    pub type WalletChangePassphraseParams = (String, String);
+    pub type WalletChangePassphraseResult = ();
+
+    pub const WALLET_ENCRYPT_KEYSTORE: &str = "Filecoin.WalletEncryptKeystore";
+    pub type WalletEncryptKeystoreParams = (String,);
+    pub type WalletEncryptKeystoreResult = ();
+
+    pub const WALLET_DECRYPT_KEYSTORE: &str = "Filecoin.WalletDecryptKeystore";
+    pub type WalletDecryptKeystoreParams = (String,);
+    pub type WalletDecryptKeystoreResult = ();
+
+    pub const WALLET_BACKUP_KEYSTORE: &str = "Filecoin.WalletBackupKeystore";
+    pub type WalletBackupKeystoreParams = (String,);
+    pub type WalletBackupKeystoreResult = ();
+}
+
+/// Multisig API
+pub mod msig_api {
+    use forest_blocks::tipset_keys_json::TipsetKeysJson;
+    use forest_json::{
+        address::json::{vec::AddressJsonVec, AddressJson},
+        cid::CidJson,
+    };
+    use fvm_shared::clock::ChainEpoch;
+
+    use crate::data_types::{MsigInfo, MsigTransaction, MsigVesting};
+
+    pub const MSIG_CREATE: &str = "Filecoin.MsigCreate";
+    pub type MsigCreateParams = (u64, AddressJsonVec, ChainEpoch, String, AddressJson, String);
+    pub type MsigCreateResult = CidJson;
+
+    pub const MSIG_PROPOSE: &str = "Filecoin.MsigPropose";
+    pub type MsigProposeParams = (AddressJson, AddressJson, String, AddressJson, u64, Vec<u8>);
+    pub type MsigProposeResult = CidJson;
+
+    pub const MSIG_APPROVE: &str = "Filecoin.MsigApprove";
+    pub type MsigApproveParams = (AddressJson, u64, AddressJson);
+    pub type MsigApproveResult = CidJson;
+
+    pub const MSIG_CANCEL: &str = "Filecoin.MsigCancel";
+    pub type MsigCancelParams = (AddressJson, u64, AddressJson);
+    pub type MsigCancelResult = CidJson;
+
+    pub const MSIG_GET_PENDING: &str = "Filecoin.MsigGetPending";
+    pub type MsigGetPendingParams = (AddressJson, TipsetKeysJson);
+    pub type MsigGetPendingResult = Vec<MsigTransaction>;
+
+    pub const MSIG_GET_AVAILABLE_BALANCE: &str = "Filecoin.MsigGetAvailableBalance";
+    pub type MsigGetAvailableBalanceParams = (AddressJson, TipsetKeysJson);
+    pub type MsigGetAvailableBalanceResult = String;
+
+    pub const MSIG_GET_VESTING_SCHEDULE: &str = "Filecoin.MsigGetVestingSchedule";
+    pub type MsigGetVestingScheduleParams = (AddressJson, TipsetKeysJson);
+    pub type MsigGetVestingScheduleResult = MsigVesting;
+
+    pub const MSIG_INSPECT: &str = "Filecoin.MsigInspect";
+    pub type MsigInspectParams = (AddressJson, TipsetKeysJson);
+    pub type MsigInspectResult = MsigInfo;
 }
 
 /// State API
@@ -321,6 +448,7 @@ pub mod state_api {
     };
     use forest_shim::version::NetworkVersion;
     use forest_state_manager::{InvocResult, MarketBalance};
+    use fvm_shared::clock::ChainEpoch;
 
     use crate::data_types::{MarketDeal, MessageLookup};
 
@@ -355,6 +483,46 @@ pub mod state_api {
     pub const STATE_WAIT_MSG: &str = "Filecoin.StateWaitMsg";
     pub type StateWaitMsgParams = (CidJson, i64);
     pub type StateWaitMsgResult = MessageLookup;
+
+    pub const STATE_SEARCH_MSG: &str = "Filecoin.StateSearchMsg";
+    pub type StateSearchMsgParams = (CidJson,);
+    pub type StateSearchMsgResult = Option<MessageLookup>;
+
+    /// Domain separation tag, epoch, base64 encoded entropy and tipset to draw
+    /// randomness from. Randomness is returned base64 encoded.
+    pub const STATE_GET_RANDOMNESS_FROM_TICKETS: &str = "Filecoin.StateGetRandomnessFromTickets";
+    pub type StateGetRandomnessFromTicketsParams = (i64, ChainEpoch, String, TipsetKeysJson);
+    pub type StateGetRandomnessFromTicketsResult = String;
+
+    pub const STATE_GET_RANDOMNESS_FROM_BEACON: &str = "Filecoin.StateGetRandomnessFromBeacon";
+    pub type StateGetRandomnessFromBeaconParams = (i64, ChainEpoch, String, TipsetKeysJson);
+    pub type StateGetRandomnessFromBeaconResult = String;
+}
+
+/// Event API
+pub mod event_api {
+    use crate::data_types::{ActorEvent, ActorEventFilter, EthFilterSpec, EthLog};
+
+    pub const GET_ACTOR_EVENTS: &str = "Filecoin.GetActorEvents";
+    pub type GetActorEventsParams = (ActorEventFilter,);
+    pub type GetActorEventsResult = Vec<ActorEvent>;
+
+    pub const ETH_GET_LOGS: &str = "eth_getLogs";
+    pub type EthGetLogsParams = (EthFilterSpec,);
+    pub type EthGetLogsResult = Vec<EthLog>;
+
+    pub const ETH_NEW_FILTER: &str = "eth_newFilter";
+    pub type EthNewFilterParams = (EthFilterSpec,);
+    /// Hexadecimal ID of the filter.
+    pub type EthNewFilterResult = String;
+
+    pub const ETH_GET_FILTER_CHANGES: &str = "eth_getFilterChanges";
+    pub type EthGetFilterChangesParams = (String,);
+    pub type EthGetFilterChangesResult = Vec<EthLog>;
+
+    pub const ETH_UNINSTALL_FILTER: &str = "eth_uninstallFilter";
+    pub type EthUninstallFilterParams = (String,);
+    pub type EthUninstallFilterResult = bool;
 }
 
 /// Gas API
@@ -396,7 +564,12 @@ pub mod common_api {
 
 /// Net API
 pub mod net_api {
-    use crate::data_types::AddrInfo;
+    use forest_libp2p::{
+        BandwidthReport, BitswapSessionStats, DhtBucket, ExtendedPeerInfo, NatInfo, PeerBanInfo,
+        PeerRecord,
+    };
+
+    use crate::data_types::{AddrInfo, PubsubScore};
 
     pub const NET_ADDRS_LISTEN: &str = "Filecoin.NetAddrsListen";
     pub type NetAddrsListenParams = ();
@@ -413,6 +586,55 @@ pub mod net_api {
     pub const NET_DISCONNECT: &str = "Filecoin.NetDisconnect";
     pub type NetDisconnectParams = (String,);
     pub type NetDisconnectResult = ();
+
+    pub const NET_PUBSUB_SCORES: &str = "Filecoin.NetPubsubScores";
+    pub type NetPubsubScoresParams = ();
+    pub type NetPubsubScoresResult = Vec<PubsubScore>;
+
+    pub const NET_KNOWN_PEERS: &str = "Filecoin.NetKnownPeers";
+    pub type NetKnownPeersParams = ();
+    pub type NetKnownPeersResult = Vec<PeerRecord>;
+
+    pub const NET_BAN: &str = "Filecoin.NetBan";
+    /// Peer ID and ban duration in seconds, the ban is permanent without one.
+    pub type NetBanParams = (String, Option<u64>);
+    pub type NetBanResult = ();
+
+    pub const NET_UNBAN: &str = "Filecoin.NetUnban";
+    pub type NetUnbanParams = (String,);
+    pub type NetUnbanResult = bool;
+
+    pub const NET_BANS: &str = "Filecoin.NetBans";
+    pub type NetBansParams = ();
+    pub type NetBansResult = Vec<PeerBanInfo>;
+
+    pub const NET_BANDWIDTH_STATS: &str = "Filecoin.NetBandwidthStats";
+    pub type NetBandwidthStatsParams = ();
+    pub type NetBandwidthStatsResult = BandwidthReport;
+
+    pub const NET_AUTO_NAT_STATUS: &str = "Filecoin.NetAutoNatStatus";
+    pub type NetAutoNatStatusParams = ();
+    pub type NetAutoNatStatusResult = NatInfo;
+
+    pub const NET_FIND_PEER: &str = "Filecoin.NetFindPeer";
+    pub type NetFindPeerParams = (String,);
+    pub type NetFindPeerResult = AddrInfo;
+
+    pub const NET_PEER_INFO: &str = "Filecoin.NetPeerInfo";
+    pub type NetPeerInfoParams = (String,);
+    pub type NetPeerInfoResult = ExtendedPeerInfo;
+
+    pub const NET_AGENT_VERSION: &str = "Filecoin.NetAgentVersion";
+    pub type NetAgentVersionParams = (String,);
+    pub type NetAgentVersionResult = String;
+
+    pub const NET_DHT_ROUTING_TABLE: &str = "Filecoin.NetDhtRoutingTable";
+    pub type NetDhtRoutingTableParams = ();
+    pub type NetDhtRoutingTableResult = Vec<DhtBucket>;
+
+    pub const NET_BITSWAP_SESSIONS: &str = "Filecoin.NetBitswapSessions";
+    pub type NetBitswapSessionsParams = ();
+    pub type NetBitswapSessionsResult = Vec<BitswapSessionStats>;
 }
 
 /// DB API
@@ -71,3 +71,17 @@ pub async fn chain_get_name(
 ) -> Result<ChainGetNameResult, Error> {
     call(CHAIN_GET_NAME, params, auth_token).await
 }
+
+pub async fn chain_reorgs(
+    params: ChainReorgsParams,
+    auth_token: &Option<String>,
+) -> Result<ChainReorgsResult, Error> {
+    call(CHAIN_REORGS, params, auth_token).await
+}
+
+pub async fn chain_backfill_message_index(
+    params: ChainBackfillMessageIndexParams,
+    auth_token: &Option<String>,
+) -> Result<ChainBackfillMessageIndexResult, Error> {
+    call(CHAIN_BACKFILL_MESSAGE_INDEX, params, auth_token).await
+}
@@ -7,6 +7,7 @@ pub mod chain_ops;
 pub mod common_ops;
 pub mod db_ops;
 pub mod mpool_ops;
+pub mod msig_ops;
 pub mod net_ops;
 pub mod state_ops;
 pub mod sync_ops;
@@ -31,8 +32,8 @@ pub const DEFAULT_URL: &str = "http://127.0.0.1:1234/rpc/v0";
 pub const RPC_ENDPOINT: &str = "rpc/v0";
 
 pub use self::{
-    auth_ops::*, chain_ops::*, common_ops::*, mpool_ops::*, net_ops::*, state_ops::*, sync_ops::*,
-    wallet_ops::*,
+    auth_ops::*, chain_ops::*, common_ops::*, mpool_ops::*, msig_ops::*, net_ops::*, state_ops::*,
+    sync_ops::*, wallet_ops::*,
 };
 
 pub struct ApiInfo {
@@ -0,0 +1,63 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use forest_rpc_api::msig_api::*;
+use jsonrpc_v2::Error;
+
+use crate::call;
+
+pub async fn msig_create(
+    params: MsigCreateParams,
+    auth_token: &Option<String>,
+) -> Result<MsigCreateResult, Error> {
+    call(MSIG_CREATE, params, auth_token).await
+}
+
+pub async fn msig_propose(
+    params: MsigProposeParams,
+    auth_token: &Option<String>,
+) -> Result<MsigProposeResult, Error> {
+    call(MSIG_PROPOSE, params, auth_token).await
+}
+
+pub async fn msig_approve(
+    params: MsigApproveParams,
+    auth_token: &Option<String>,
+) -> Result<MsigApproveResult, Error> {
+    call(MSIG_APPROVE, params, auth_token).await
+}
+
+pub async fn msig_cancel(
+    params: MsigCancelParams,
+    auth_token: &Option<String>,
+) -> Result<MsigCancelResult, Error> {
+    call(MSIG_CANCEL, params, auth_token).await
+}
+
+pub async fn msig_get_pending(
+    params: MsigGetPendingParams,
+    auth_token: &Option<String>,
+) -> Result<MsigGetPendingResult, Error> {
+    call(MSIG_GET_PENDING, params, auth_token).await
+}
+
+pub async fn msig_get_available_balance(
+    params: MsigGetAvailableBalanceParams,
+    auth_token: &Option<String>,
+) -> Result<MsigGetAvailableBalanceResult, Error> {
+    call(MSIG_GET_AVAILABLE_BALANCE, params, auth_token).await
+}
+
+pub async fn msig_get_vesting_schedule(
+    params: MsigGetVestingScheduleParams,
+    auth_token: &Option<String>,
+) -> Result<MsigGetVestingScheduleResult, Error> {
+    call(MSIG_GET_VESTING_SCHEDULE, params, auth_token).await
+}
+
+pub async fn msig_inspect(
+    params: MsigInspectParams,
+    auth_token: &Option<String>,
+) -> Result<MsigInspectResult, Error> {
+    call(MSIG_INSPECT, params, auth_token).await
+}
@@ -33,3 +33,87 @@ pub async fn net_disconnect(
 ) -> Result<NetDisconnectResult, Error> {
     call(NET_DISCONNECT, params, auth_token).await
 }
+
+pub async fn net_pubsub_scores(
+    params: NetPubsubScoresParams,
+    auth_token: &Option<String>,
+) -> Result<NetPubsubScoresResult, Error> {
+    call(NET_PUBSUB_SCORES, params, auth_token).await
+}
+
+pub async fn net_known_peers(
+    params: NetKnownPeersParams,
+    auth_token: &Option<String>,
+) -> Result<NetKnownPeersResult, Error> {
+    call(NET_KNOWN_PEERS, params, auth_token).await
+}
+
+pub async fn net_ban(
+    params: NetBanParams,
+    auth_token: &Option<String>,
+) -> Result<NetBanResult, Error> {
+    call(NET_BAN, params, auth_token).await
+}
+
+pub async fn net_unban(
+    params: NetUnbanParams,
+    auth_token: &Option<String>,
+) -> Result<NetUnbanResult, Error> {
+    call(NET_UNBAN, params, auth_token).await
+}
+
+pub async fn net_bans(
+    params: NetBansParams,
+    auth_token: &Option<String>,
+) -> Result<NetBansResult, Error> {
+    call(NET_BANS, params, auth_token).await
+}
+
+pub async fn net_bandwidth_stats(
+    params: NetBandwidthStatsParams,
+    auth_token: &Option<String>,
+) -> Result<NetBandwidthStatsResult, Error> {
+    call(NET_BANDWIDTH_STATS, params, auth_token).await
+}
+
+pub async fn net_auto_nat_status(
+    params: NetAutoNatStatusParams,
+    auth_token: &Option<String>,
+) -> Result<NetAutoNatStatusResult, Error> {
+    call(NET_AUTO_NAT_STATUS, params, auth_token).await
+}
+
+pub async fn net_find_peer(
+    params: NetFindPeerParams,
+    auth_token: &Option<String>,
+) -> Result<NetFindPeerResult, Error> {
+    call(NET_FIND_PEER, params, auth_token).await
+}
+
+pub async fn net_peer_info(
+    params: NetPeerInfoParams,
+    auth_token: &Option<String>,
+) -> Result<NetPeerInfoResult, Error> {
+    call(NET_PEER_INFO, params, auth_token).await
+}
+
+pub async fn net_agent_version(
+    params: NetAgentVersionParams,
+    auth_token: &Option<String>,
+) -> Result<NetAgentVersionResult, Error> {
+    call(NET_AGENT_VERSION, params, auth_token).await
+}
+
+pub async fn net_dht_routing_table(
+    params: NetDhtRoutingTableParams,
+    auth_token: &Option<String>,
+) -> Result<NetDhtRoutingTableResult, Error> {
+    call(NET_DHT_ROUTING_TABLE, params, auth_token).await
+}
+
+pub async fn net_bitswap_sessions(
+    params: NetBitswapSessionsParams,
+    auth_token: &Option<String>,
+) -> Result<NetBitswapSessionsResult, Error> {
+    call(NET_BITSWAP_SESSIONS, params, auth_token).await
+}
@@ -1,2 +1,28 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
+
+use forest_rpc_api::state_api::*;
+use jsonrpc_v2::Error;
+
+use crate::call;
+
+pub async fn state_search_msg(
+    params: StateSearchMsgParams,
+    auth_token: &Option<String>,
+) -> Result<StateSearchMsgResult, Error> {
+    call(STATE_SEARCH_MSG, params, auth_token).await
+}
+
+pub async fn state_get_randomness_from_tickets(
+    params: StateGetRandomnessFromTicketsParams,
+    auth_token: &Option<String>,
+) -> Result<StateGetRandomnessFromTicketsResult, Error> {
+    call(STATE_GET_RANDOMNESS_FROM_TICKETS, params, auth_token).await
+}
+
+pub async fn state_get_randomness_from_beacon(
+    params: StateGetRandomnessFromBeaconParams,
+    auth_token: &Option<String>,
+) -> Result<StateGetRandomnessFromBeaconResult, Error> {
+    call(STATE_GET_RANDOMNESS_FROM_BEACON, params, auth_token).await
+}
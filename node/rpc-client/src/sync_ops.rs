@@ -20,9 +20,37 @@ pub async fn sync_mark_bad(
     call(SYNC_MARK_BAD, params, auth_token).await
 }
 
+pub async fn sync_unmark_bad(
+    params: SyncUnmarkBadParams,
+    auth_token: &Option<String>,
+) -> Result<SyncUnmarkBadResult, JsonRpcError> {
+    call(SYNC_UNMARK_BAD, params, auth_token).await
+}
+
+pub async fn sync_unmark_all_bad(
+    params: SyncUnmarkAllBadParams,
+    auth_token: &Option<String>,
+) -> Result<SyncUnmarkAllBadResult, JsonRpcError> {
+    call(SYNC_UNMARK_ALL_BAD, params, auth_token).await
+}
+
 pub async fn sync_status(
     params: SyncStateParams,
     auth_token: &Option<String>,
 ) -> Result<SyncStateResult, JsonRpcError> {
     call(SYNC_STATE, params, auth_token).await
 }
+
+pub async fn sync_consensus_faults(
+    params: SyncConsensusFaultsParams,
+    auth_token: &Option<String>,
+) -> Result<SyncConsensusFaultsResult, JsonRpcError> {
+    call(SYNC_CONSENSUS_FAULTS, params, auth_token).await
+}
+
+pub async fn sync_explain_block(
+    params: SyncExplainBlockParams,
+    auth_token: &Option<String>,
+) -> Result<SyncExplainBlockResult, JsonRpcError> {
+    call(SYNC_EXPLAIN_BLOCK, params, auth_token).await
+}
@@ -75,3 +75,31 @@ pub async fn wallet_verify(
 ) -> Result<WalletVerifyResult, Error> {
     call(WALLET_VERIFY, message, auth_token).await
 }
+
+pub async fn wallet_change_passphrase(
+    params: WalletChangePassphraseParams,
+    auth_token: &Option<String>,
+) -> Result<WalletChangePassphraseResult, Error> {
+    call(WALLET_CHANGE_PASSPHRASE, params, auth_token).await
+}
+
+pub async fn wallet_encrypt_keystore(
+    params: WalletEncryptKeystoreParams,
+    auth_token: &Option<String>,
+) -> Result<WalletEncryptKeystoreResult, Error> {
+    call(WALLET_ENCRYPT_KEYSTORE, params, auth_token).await
+}
+
+pub async fn wallet_decrypt_keystore(
+    params: WalletDecryptKeystoreParams,
+    auth_token: &Option<String>,
+) -> Result<WalletDecryptKeystoreResult, Error> {
+    call(WALLET_DECRYPT_KEYSTORE, params, auth_token).await
+}
+
+pub async fn wallet_backup_keystore(
+    params: WalletBackupKeystoreParams,
+    auth_token: &Option<String>,
+) -> Result<WalletBackupKeystoreResult, Error> {
+    call(WALLET_BACKUP_KEYSTORE, params, auth_token).await
+}
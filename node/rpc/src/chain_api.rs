@@ -313,6 +313,48 @@ where
     Ok("Ok".to_string())
 }
 
+/// Returns the recent re-orgs of the heaviest chain, oldest first.
+pub(crate) async fn chain_reorgs<DB, B>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<ChainReorgsResult, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    Ok(data
+        .state_manager
+        .chain_store()
+        .reorgs()
+        .into_iter()
+        .map(Into::into)
+        .collect())
+}
+
+/// Indexes the messages of the heaviest chain from epoch `from` down to epoch
+/// `to`, returning the number of tipsets indexed.
+pub(crate) async fn chain_backfill_message_index<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<ChainBackfillMessageIndexParams>,
+) -> Result<ChainBackfillMessageIndexResult, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (from, to) = params;
+    let cs = data.state_manager.chain_store().clone();
+    if cs.message_index().is_none() {
+        return Err("the message index is disabled".into());
+    }
+    let indexed = tokio::task::spawn_blocking(move || -> Result<_> {
+        let head = cs.tipset_by_height(from, cs.heaviest_tipset(), false)?;
+        // Checked above
+        let index = cs.message_index().expect("message index is enabled");
+        Ok(index.backfill(&cs, head, to)?)
+    })
+    .await??;
+    Ok(indexed)
+}
+
 pub(crate) async fn chain_get_name<DB, B>(
     data: Data<RPCState<DB, B>>,
 ) -> Result<ChainGetNameResult, JsonRpcError>
@@ -0,0 +1,385 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+#![allow(clippy::unused_async)]
+
+use std::{str::FromStr, sync::Arc};
+
+use ahash::{HashMap, HashMapExt};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use cid::Cid;
+use forest_beacon::Beacon;
+use forest_blocks::Tipset;
+use forest_chain::{EventFilter, EventIndex, EventMatch};
+use forest_json::cid::CidJson;
+use forest_key_management::{Eip1559Transaction, EthAddress};
+use forest_message::{ChainMessage, Message};
+use forest_rpc_api::{
+    data_types::{ActorEvent, ActorEventEntry, EthFilterSpec, EthLog, RPCState},
+    event_api::*,
+};
+use forest_shim::address::Address;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::Cbor;
+use fvm_ipld_encoding3::RawBytes;
+use fvm_shared3::{clock::ChainEpoch, ActorID, MethodNum};
+use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
+
+/// Prefix of the Ethereum addresses of actors without a delegated address.
+const MASKED_ID_PREFIX: [u8; 12] = [0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+/// Actor ID of the Ethereum Address Manager.
+const EAM_ACTOR_ID: ActorID = 10;
+
+/// Method of the Ethereum Address Manager creating a contract from an
+/// Ethereum account, called by the transactions without a recipient.
+const EAM_CREATE_EXTERNAL: MethodNum = 4;
+
+/// Returns the events emitted by actors that match a filter.
+pub(crate) async fn get_actor_events<DB: Blockstore + Clone + Send + Sync + 'static, B: Beacon>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<GetActorEventsParams>,
+) -> Result<GetActorEventsResult, JsonRpcError> {
+    let (filter,) = params;
+    let index = event_index(&data)?;
+    let head = data.chain_store.heaviest_tipset();
+    let emitters = filter
+        .addresses
+        .iter()
+        .map(|addr| emitter_id(&data, addr, &head))
+        .collect::<Result<_, _>>()?;
+    let mut fields = vec![];
+    for (key, blocks) in filter.fields {
+        let values = blocks
+            .into_iter()
+            .map(|block| BASE64_STANDARD.decode(block.value))
+            .collect::<Result<_, _>>()?;
+        fields.push((key, values));
+    }
+    let filter = EventFilter {
+        from_epoch: filter.from_height,
+        to_epoch: filter.to_height,
+        tipset: filter.tipset_key.map(Into::into),
+        emitters,
+        fields,
+    };
+
+    Ok(index
+        .events(&data.chain_store, &filter)?
+        .into_iter()
+        .map(|m| ActorEvent {
+            entries: m
+                .event
+                .entries
+                .into_iter()
+                .map(|entry| ActorEventEntry {
+                    flags: entry.flags,
+                    key: entry.key,
+                    value: BASE64_STANDARD.encode(entry.value.bytes()),
+                })
+                .collect(),
+            emitter: Address::new_id(m.event.emitter),
+            reverted: m.reverted,
+            height: m.epoch,
+            tipset_key: m.tipset.into(),
+            msg_cid: CidJson(m.event.message),
+        })
+        .collect())
+}
+
+/// Returns the logs matching a filter.
+pub(crate) async fn eth_get_logs<DB: Blockstore + Clone + Send + Sync + 'static, B: Beacon>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<EthGetLogsParams>,
+) -> Result<EthGetLogsResult, JsonRpcError> {
+    let (spec,) = params;
+    let index = event_index(&data)?;
+    let filter = eth_event_filter(&data, index, spec)?;
+    let matches = index.events(&data.chain_store, &filter)?;
+    eth_logs(&data, matches)
+}
+
+/// Installs a filter of logs, polled with `eth_getFilterChanges`.
+pub(crate) async fn eth_new_filter<DB: Blockstore + Clone + Send + Sync + 'static, B: Beacon>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<EthNewFilterParams>,
+) -> Result<EthNewFilterResult, JsonRpcError> {
+    let (spec,) = params;
+    let index = event_index(&data)?;
+    let filter = eth_event_filter(&data, index, spec)?;
+    Ok(format!("{:#x}", index.install_filter(filter)))
+}
+
+/// Returns the logs matched by a filter since it was last polled, including
+/// the logs of reverted blocks, flagged as removed.
+pub(crate) async fn eth_get_filter_changes<
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<EthGetFilterChangesParams>,
+) -> Result<EthGetFilterChangesResult, JsonRpcError> {
+    let (id,) = params;
+    let index = event_index(&data)?;
+    let matches = index
+        .filter_changes(parse_filter_id(&id)?)
+        .ok_or_else(|| format!("Filter {id} not found"))?;
+    eth_logs(&data, matches)
+}
+
+/// Removes a filter, returning true if it was installed.
+pub(crate) async fn eth_uninstall_filter<
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<EthUninstallFilterParams>,
+) -> Result<EthUninstallFilterResult, JsonRpcError> {
+    let (id,) = params;
+    Ok(event_index(&data)?.uninstall_filter(parse_filter_id(&id)?))
+}
+
+fn event_index<DB, B>(data: &RPCState<DB, B>) -> Result<&Arc<EventIndex>, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    Ok(data
+        .chain_store
+        .event_index()
+        .ok_or("The event index is not enabled")?)
+}
+
+fn parse_filter_id(id: &str) -> Result<u64, JsonRpcError> {
+    id.strip_prefix("0x")
+        .and_then(|id| u64::from_str_radix(id, 16).ok())
+        .ok_or_else(|| format!("Invalid filter ID: {id}").into())
+}
+
+fn parse_hex(hex_str: &str) -> Result<Vec<u8>, JsonRpcError> {
+    let invalid = || JsonRpcError::from(format!("Invalid hexadecimal value: {hex_str}"));
+    hex::decode(hex_str.strip_prefix("0x").ok_or_else(invalid)?).map_err(|_| invalid())
+}
+
+/// Parses a block of an Ethereum filter into an epoch, `None` standing for
+/// the latest one. Safe and finalized blocks are not tracked, the latest block
+/// is used in their place.
+fn parse_eth_block(block: Option<&str>) -> Result<Option<ChainEpoch>, JsonRpcError> {
+    match block {
+        None | Some("latest" | "safe" | "finalized" | "pending") => Ok(None),
+        Some("earliest") => Ok(Some(0)),
+        Some(number) => number
+            .strip_prefix("0x")
+            .and_then(|number| ChainEpoch::from_str_radix(number, 16).ok())
+            .map(Some)
+            .ok_or_else(|| format!("Invalid block number: {number}").into()),
+    }
+}
+
+/// Returns the ID of the actor emitting events from an address.
+fn emitter_id<DB, B>(
+    data: &RPCState<DB, B>,
+    addr: &Address,
+    head: &Tipset,
+) -> Result<ActorID, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let id_addr = data
+        .state_manager
+        .lookup_id(addr, head)?
+        .ok_or_else(|| format!("Actor {addr} not found"))?;
+    Ok(id_addr.id()?)
+}
+
+fn eth_event_filter<DB, B>(
+    data: &RPCState<DB, B>,
+    index: &EventIndex,
+    spec: EthFilterSpec,
+) -> Result<EventFilter, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let head = data.chain_store.heaviest_tipset();
+    let mut emitters = vec![];
+    for eth_addr in spec.address.map(|a| a.into_vec()).unwrap_or_default() {
+        let eth_addr = EthAddress::from_str(&eth_addr)?;
+        let addr = if eth_addr.0[..12] == MASKED_ID_PREFIX {
+            let mut id = [0; 8];
+            id.copy_from_slice(&eth_addr.0[12..]);
+            Address::new_id(u64::from_be_bytes(id))
+        } else {
+            eth_addr.to_filecoin_address()?
+        };
+        emitters.push(emitter_id(data, &addr, &head)?);
+    }
+
+    // Topics are the `t1` to `t4` entries of the events emitted by the EVM
+    let mut fields = vec![];
+    for (position, topics) in spec.topics.into_iter().enumerate() {
+        if let Some(topics) = topics {
+            let values = topics
+                .into_vec()
+                .iter()
+                .map(String::as_str)
+                .map(parse_hex)
+                .collect::<Result<_, _>>()?;
+            fields.push((format!("t{}", position + 1), values));
+        }
+    }
+
+    let tipset = match spec.block_hash {
+        Some(hash) => Some(
+            index
+                .tipset_by_hash(&parse_hex(&hash)?)?
+                .ok_or_else(|| format!("Block {hash} not found"))?,
+        ),
+        None => None,
+    };
+
+    Ok(EventFilter {
+        from_epoch: parse_eth_block(spec.from_block.as_deref())?,
+        to_epoch: parse_eth_block(spec.to_block.as_deref())?,
+        tipset,
+        emitters,
+        fields,
+    })
+}
+
+/// Converts the events emitted by the EVM into Ethereum logs. Other events are
+/// skipped.
+fn eth_logs<DB, B>(
+    data: &RPCState<DB, B>,
+    matches: Vec<EventMatch>,
+) -> Result<Vec<EthLog>, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let head = data.chain_store.heaviest_tipset();
+    let mut eth_addresses = HashMap::new();
+    let mut tx_hashes = HashMap::new();
+    let mut logs = vec![];
+    for m in matches {
+        let mut topics = vec![];
+        let mut log_data = vec![];
+        let mut is_evm_log = true;
+        for entry in &m.event.entries {
+            match entry.key.as_str() {
+                "t1" | "t2" | "t3" | "t4" => topics.push((entry.key.clone(), entry.value.bytes())),
+                "d" => log_data = entry.value.bytes().to_vec(),
+                _ => is_evm_log = false,
+            }
+        }
+        if !is_evm_log {
+            continue;
+        }
+        topics.sort();
+
+        let address = match eth_addresses.get(&m.event.emitter) {
+            Some(address) => *address,
+            None => {
+                let address = eth_address(data, m.event.emitter, &head)?;
+                eth_addresses.insert(m.event.emitter, address);
+                address
+            }
+        };
+        let transaction_hash = match tx_hashes.get(&m.event.message) {
+            Some(hash) => String::clone(hash),
+            None => {
+                let hash = format!("0x{}", hex::encode(eth_tx_hash(data, &m.event.message)?));
+                tx_hashes.insert(m.event.message, hash.clone());
+                hash
+            }
+        };
+        logs.push(EthLog {
+            address: address.to_string(),
+            data: format!("0x{}", hex::encode(log_data)),
+            topics: topics
+                .into_iter()
+                .map(|(_, topic)| format!("0x{}", hex::encode(topic)))
+                .collect(),
+            removed: m.reverted,
+            log_index: format!("{:#x}", m.index),
+            transaction_index: format!("{:#x}", m.event.message_index),
+            transaction_hash,
+            block_hash: format!("0x{}", hex::encode(m.tipset.cid()?.hash().digest())),
+            block_number: format!("{:#x}", m.epoch),
+        });
+    }
+    Ok(logs)
+}
+
+/// Returns the Ethereum address of an actor: its delegated address if it has
+/// one, or its ID masked with [`MASKED_ID_PREFIX`].
+fn eth_address<DB, B>(
+    data: &RPCState<DB, B>,
+    id: ActorID,
+    head: &Tipset,
+) -> Result<EthAddress, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let delegated = data
+        .state_manager
+        .get_actor(&Address::new_id(id), *head.parent_state())?
+        .and_then(|actor| actor.delegated_address)
+        .and_then(|addr| EthAddress::from_filecoin_address(&addr.into()));
+    Ok(delegated.unwrap_or_else(|| masked_id_address(id)))
+}
+
+/// Returns the Ethereum address of an actor ID, masked with
+/// [`MASKED_ID_PREFIX`].
+fn masked_id_address(id: ActorID) -> EthAddress {
+    let mut eth_addr = [0; 20];
+    eth_addr[..12].copy_from_slice(&MASKED_ID_PREFIX);
+    eth_addr[12..].copy_from_slice(&id.to_be_bytes());
+    EthAddress(eth_addr)
+}
+
+/// Returns the hash of the Ethereum transaction of a message: the hash of the
+/// signed EIP-1559 transaction for the messages signed with a delegated key,
+/// the digest of the message [`Cid`] for the other messages.
+fn eth_tx_hash<DB, B>(data: &RPCState<DB, B>, cid: &Cid) -> Result<Vec<u8>, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let smsg = match forest_chain::get_chain_message(data.chain_store.blockstore(), cid)? {
+        ChainMessage::Signed(smsg) if smsg.is_delegated() => smsg,
+        _ => return Ok(cid.hash().digest().to_vec()),
+    };
+    let create =
+        smsg.to().id().ok() == Some(EAM_ACTOR_ID) && smsg.method_num() == EAM_CREATE_EXTERNAL;
+    let to = if create {
+        None
+    } else {
+        let to = smsg.to();
+        match EthAddress::from_filecoin_address(&to) {
+            Some(eth_addr) => Some(eth_addr),
+            None => Some(masked_id_address(to.id()?)),
+        }
+    };
+    // The input of the transaction is passed as CBOR bytes
+    let params = smsg.params().bytes();
+    let input = if params.is_empty() {
+        vec![]
+    } else {
+        fvm_ipld_encoding3::from_slice::<RawBytes>(params)?
+            .bytes()
+            .to_vec()
+    };
+    let tx = Eip1559Transaction {
+        chain_id: data.state_manager.chain_config().eth_chain_id,
+        nonce: smsg.sequence(),
+        max_priority_fee_per_gas: smsg.gas_premium().atto().to_biguint().unwrap_or_default(),
+        max_fee_per_gas: smsg.gas_fee_cap().atto().to_biguint().unwrap_or_default(),
+        gas_limit: smsg.gas_limit(),
+        to,
+        value: smsg.value().atto().to_biguint().unwrap_or_default(),
+        data: input,
+    };
+    Ok(tx.signed_hash(smsg.signature())?.to_vec())
+}
@@ -6,8 +6,10 @@ mod beacon_api;
 mod chain_api;
 mod common_api;
 mod db_api;
+mod event_api;
 mod gas_api;
 mod mpool_api;
+mod msig_api;
 mod net_api;
 mod rpc_http_handler;
 mod rpc_util;
@@ -23,13 +25,15 @@ use forest_beacon::Beacon;
 use forest_chain::Scale;
 use forest_rpc_api::{
     auth_api::*, beacon_api::*, chain_api::*, common_api::*, data_types::RPCState, db_api::*,
-    gas_api::*, mpool_api::*, net_api::*, state_api::*, sync_api::*, wallet_api::*,
+    event_api::*, gas_api::*, mpool_api::*, msig_api::*, net_api::*, state_api::*, sync_api::*,
+    wallet_api::*,
 };
 use fvm_ipld_blockstore::Blockstore;
 use jsonrpc_v2::{Data, Error as JSONRPCError, Server};
 use log::info;
 use tokio::sync::mpsc::Sender;
 
+pub use crate::sync_api::report_consensus_faults;
 use crate::{
     beacon_api::beacon_get_entry,
     common_api::{shutdown, version},
@@ -53,6 +57,7 @@ where
     use chain_api::*;
     use gas_api::*;
     use mpool_api::*;
+    use msig_api::*;
     use sync_api::*;
     use wallet_api::*;
 
@@ -85,6 +90,11 @@ where
             .with_method(CHAIN_HEAD, chain_head::<DB, B>)
             .with_method(CHAIN_GET_BLOCK, chain_api::chain_get_block::<DB, B>)
             .with_method(CHAIN_GET_NAME, chain_api::chain_get_name::<DB, B>)
+            .with_method(CHAIN_REORGS, chain_api::chain_reorgs::<DB, B>)
+            .with_method(
+                CHAIN_BACKFILL_MESSAGE_INDEX,
+                chain_api::chain_backfill_message_index::<DB, B>,
+            )
             // Message Pool API
             .with_method(MPOOL_PENDING, mpool_pending::<DB, B>)
             .with_method(MPOOL_PUSH, mpool_push::<DB, B>)
@@ -92,7 +102,11 @@ where
             // Sync API
             .with_method(SYNC_CHECK_BAD, sync_check_bad::<DB, B>)
             .with_method(SYNC_MARK_BAD, sync_mark_bad::<DB, B>)
+            .with_method(SYNC_UNMARK_BAD, sync_unmark_bad::<DB, B>)
+            .with_method(SYNC_UNMARK_ALL_BAD, sync_unmark_all_bad::<DB, B>)
             .with_method(SYNC_STATE, sync_state::<DB, B>)
+            .with_method(SYNC_CONSENSUS_FAULTS, sync_consensus_faults::<DB, B>)
+            .with_method(SYNC_EXPLAIN_BLOCK, sync_explain_block::<DB, B>)
             // Wallet API
             .with_method(WALLET_BALANCE, wallet_balance::<DB, B>)
             .with_method(WALLET_DEFAULT_ADDRESS, wallet_default_address::<DB, B>)
@@ -104,6 +118,25 @@ where
             .with_method(WALLET_SET_DEFAULT, wallet_set_default::<DB, B>)
             .with_method(WALLET_SIGN, wallet_sign::<DB, B>)
             .with_method(WALLET_VERIFY, wallet_verify::<DB, B>)
+            .with_method(WALLET_CHANGE_PASSPHRASE, wallet_change_passphrase::<DB, B>)
+            .with_method(WALLET_ENCRYPT_KEYSTORE, wallet_encrypt_keystore::<DB, B>)
+            .with_method(WALLET_DECRYPT_KEYSTORE, wallet_decrypt_keystore::<DB, B>)
+            .with_method(WALLET_BACKUP_KEYSTORE, wallet_backup_keystore::<DB, B>)
+            // Multisig API
+            .with_method(MSIG_CREATE, msig_create::<DB, B>)
+            .with_method(MSIG_PROPOSE, msig_propose::<DB, B>)
+            .with_method(MSIG_APPROVE, msig_approve::<DB, B>)
+            .with_method(MSIG_CANCEL, msig_cancel::<DB, B>)
+            .with_method(MSIG_GET_PENDING, msig_get_pending::<DB, B>)
+            .with_method(
+                MSIG_GET_AVAILABLE_BALANCE,
+                msig_get_available_balance::<DB, B>,
+            )
+            .with_method(
+                MSIG_GET_VESTING_SCHEDULE,
+                msig_get_vesting_schedule::<DB, B>,
+            )
+            .with_method(MSIG_INSPECT, msig_inspect::<DB, B>)
             // State API
             .with_method(STATE_CALL, state_call::<DB, B>)
             .with_method(STATE_REPLAY, state_replay::<DB, B>)
@@ -114,6 +147,27 @@ where
             .with_method(STATE_MARKET_DEALS, state_market_deals::<DB, B>)
             .with_method(STATE_GET_RECEIPT, state_get_receipt::<DB, B>)
             .with_method(STATE_WAIT_MSG, state_wait_msg::<DB, B>)
+            .with_method(STATE_SEARCH_MSG, state_search_msg::<DB, B>)
+            .with_method(
+                STATE_GET_RANDOMNESS_FROM_TICKETS,
+                state_get_randomness_from_tickets::<DB, B>,
+            )
+            .with_method(
+                STATE_GET_RANDOMNESS_FROM_BEACON,
+                state_get_randomness_from_beacon::<DB, B>,
+            )
+            // Event API
+            .with_method(GET_ACTOR_EVENTS, event_api::get_actor_events::<DB, B>)
+            .with_method(ETH_GET_LOGS, event_api::eth_get_logs::<DB, B>)
+            .with_method(ETH_NEW_FILTER, event_api::eth_new_filter::<DB, B>)
+            .with_method(
+                ETH_GET_FILTER_CHANGES,
+                event_api::eth_get_filter_changes::<DB, B>,
+            )
+            .with_method(
+                ETH_UNINSTALL_FILTER,
+                event_api::eth_uninstall_filter::<DB, B>,
+            )
             // Gas API
             .with_method(GAS_ESTIMATE_FEE_CAP, gas_estimate_fee_cap::<DB, B>)
             .with_method(GAS_ESTIMATE_GAS_LIMIT, gas_estimate_gas_limit::<DB, B>)
@@ -127,6 +181,21 @@ where
             .with_method(NET_PEERS, net_api::net_peers::<DB, B>)
             .with_method(NET_CONNECT, net_api::net_connect::<DB, B>)
             .with_method(NET_DISCONNECT, net_api::net_disconnect::<DB, B>)
+            .with_method(NET_PUBSUB_SCORES, net_api::net_pubsub_scores::<DB, B>)
+            .with_method(NET_KNOWN_PEERS, net_api::net_known_peers::<DB, B>)
+            .with_method(NET_BAN, net_api::net_ban::<DB, B>)
+            .with_method(NET_UNBAN, net_api::net_unban::<DB, B>)
+            .with_method(NET_BANS, net_api::net_bans::<DB, B>)
+            .with_method(NET_BANDWIDTH_STATS, net_api::net_bandwidth_stats::<DB, B>)
+            .with_method(NET_AUTO_NAT_STATUS, net_api::net_auto_nat_status::<DB, B>)
+            .with_method(NET_FIND_PEER, net_api::net_find_peer::<DB, B>)
+            .with_method(NET_PEER_INFO, net_api::net_peer_info::<DB, B>)
+            .with_method(NET_AGENT_VERSION, net_api::net_agent_version::<DB, B>)
+            .with_method(
+                NET_DHT_ROUTING_TABLE,
+                net_api::net_dht_routing_table::<DB, B>,
+            )
+            .with_method(NET_BITSWAP_SESSIONS, net_api::net_bitswap_sessions::<DB, B>)
             // DB API
             .with_method(DB_GC, db_api::db_gc::<DB, B>)
             .finish_unwrapped(),
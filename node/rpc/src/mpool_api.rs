@@ -2,8 +2,6 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 #![allow(clippy::unused_async)]
 
-use std::convert::TryFrom;
-
 use ahash::{HashSet, HashSetExt};
 use forest_beacon::Beacon;
 use forest_blocks::TipsetKeys;
@@ -13,13 +11,17 @@ use forest_json::{
     signed_message::json::SignedMessageJson,
 };
 use forest_message::SignedMessage;
-use forest_rpc_api::{data_types::RPCState, mpool_api::*};
-use forest_shim::address::Protocol;
+use forest_rpc_api::{
+    data_types::{MessageSendSpec, RPCState},
+    mpool_api::*,
+};
+use forest_shim::{address::Protocol, message::Message};
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::Cbor;
 use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
+use tokio::sync::Mutex;
 
-use super::gas_api::estimate_message_gas;
+use super::{gas_api::estimate_message_gas, wallet_api::sign_with_wallet};
 
 /// Return `Vec` of pending messages in `mpool`
 pub(crate) async fn mpool_pending<DB, B>(
@@ -108,9 +110,31 @@ where
 {
     let (MessageJson(umsg), spec) = params;
 
+    let smsg = sign_and_push_message(&data, umsg, spec).await?;
+
+    Ok(SignedMessageJson(smsg))
+}
+
+/// Estimates gas for the given message, assigns it the next nonce of the
+/// sender, signs it with the sender's key and adds it to `mpool`
+pub(crate) async fn sign_and_push_message<DB, B>(
+    data: &Data<RPCState<DB, B>>,
+    umsg: Message,
+    spec: Option<MessageSendSpec>,
+) -> Result<SignedMessage, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    lazy_static::lazy_static! {
+        static ref LOCK: Mutex<()> = Mutex::new(());
+    }
+
     let from = umsg.from;
 
-    let mut keystore = data.keystore.as_ref().write().await;
+    // Keeps the nonce assignment and the push atomic, without holding the
+    // key-store lock while waiting for a remote signer.
+    let _locked = LOCK.lock().await;
     let heaviest_tipset = data.state_manager.chain_store().heaviest_tipset();
     let key_addr = data
         .state_manager
@@ -122,7 +146,7 @@ where
             "Expected nonce for MpoolPushMessage is 0, and will be calculated for you.".into(),
         );
     }
-    let mut umsg = estimate_message_gas::<DB, B>(&data, umsg, spec, Default::default()).await?;
+    let mut umsg = estimate_message_gas::<DB, B>(data, umsg, spec, Default::default()).await?;
     if umsg.gas_premium > umsg.gas_fee_cap {
         return Err("After estimation, gas premium is greater than gas fee cap".into());
     }
@@ -132,19 +156,17 @@ where
     }
     let nonce = data.mpool.get_sequence(&from.into())?;
     umsg.sequence = nonce;
-    let key = forest_key_management::Key::try_from(forest_key_management::try_find(
+    let sig = sign_with_wallet(
+        &data.keystore,
+        data.remote_signer.clone(),
         &key_addr,
-        &mut keystore,
-    )?)?;
-    let sig = forest_key_management::sign(
-        *key.key_info.key_type(),
-        key.key_info.private_key(),
-        umsg.cid().unwrap().to_bytes().as_slice(),
-    )?;
+        umsg.cid().unwrap().to_bytes(),
+    )
+    .await?;
 
     let smsg = SignedMessage::new_from_parts(umsg, sig)?;
 
     data.mpool.as_ref().push(smsg.clone()).await?;
 
-    Ok(SignedMessageJson(smsg))
+    Ok(smsg)
 }
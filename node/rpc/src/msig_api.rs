@@ -0,0 +1,230 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+#![allow(clippy::unused_async)]
+
+use std::str::FromStr;
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use forest_beacon::Beacon;
+use forest_blocks::tipset_keys_json::TipsetKeysJson;
+use forest_json::{
+    address::json::{vec::AddressJsonVec, AddressJson},
+    cid::CidJson,
+};
+use forest_message::Message as MessageTrait;
+use forest_rpc_api::{
+    data_types::{MsigInfo, MsigTransaction, MsigVesting, RPCState},
+    msig_api::*,
+};
+use forest_shim::{econ::TokenAmount, message::Message};
+use forest_state_manager::multisig::{
+    method, multisig_propose_message, multisig_txn_message, MultisigState, ProposeParams,
+};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::Cbor;
+use fvm_ipld_encoding3::RawBytes;
+use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
+use num::BigInt;
+
+use crate::mpool_api::sign_and_push_message;
+
+fn parse_token_amount(amount: &str) -> Result<TokenAmount, JsonRpcError> {
+    Ok(TokenAmount::from_atto(BigInt::from_str(amount)?))
+}
+
+fn txn_id(id: u64) -> Result<i64, JsonRpcError> {
+    i64::try_from(id).map_err(|_| format!("Invalid transaction ID: {id}").into())
+}
+
+async fn push_msig_message<DB, B>(
+    data: &Data<RPCState<DB, B>>,
+    msg: Message,
+) -> Result<CidJson, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let smsg = sign_and_push_message(data, msg, None).await?;
+    Ok(CidJson(smsg.cid()?))
+}
+
+fn pending_transactions<DB, B>(
+    data: &Data<RPCState<DB, B>>,
+    state: &MultisigState,
+) -> Result<Vec<MsigTransaction>, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    Ok(data
+        .state_manager
+        .multisig_pending_transactions(state)?
+        .into_iter()
+        .map(|(id, txn)| MsigTransaction {
+            id,
+            to: txn.to,
+            value: txn.value,
+            method: txn.method,
+            params: BASE64_STANDARD.encode(txn.params.bytes()),
+            approved: txn.approved,
+        })
+        .collect())
+}
+
+fn vesting_schedule(state: &MultisigState) -> MsigVesting {
+    MsigVesting {
+        initial_balance: state.initial_balance.clone(),
+        start_epoch: state.start_epoch,
+        unlock_duration: state.unlock_duration,
+    }
+}
+
+/// Create a new multisig actor with the given signers and approval threshold,
+/// return the CID of the creation message
+pub(crate) async fn msig_create<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigCreateParams>,
+) -> Result<MsigCreateResult, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (threshold, AddressJsonVec(signers), unlock_duration, value, AddressJson(from), gas_price) =
+        params;
+    let heaviest_tipset = data.state_manager.chain_store().heaviest_tipset();
+    let mut msg = data.state_manager.multisig_create_message(
+        &heaviest_tipset,
+        from,
+        signers,
+        threshold,
+        unlock_duration,
+        heaviest_tipset.epoch(),
+        parse_token_amount(&value)?,
+    )?;
+    // A zero gas price leaves the premium to the gas estimation
+    msg.set_gas_premium(parse_token_amount(&gas_price)?);
+    push_msig_message(&data, msg).await
+}
+
+/// Propose a transaction to a multisig actor, return the CID of the proposal
+/// message
+pub(crate) async fn msig_propose<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigProposeParams>,
+) -> Result<MsigProposeResult, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (AddressJson(msig), AddressJson(to), value, AddressJson(from), method, params) = params;
+    let msg = multisig_propose_message(
+        msig,
+        from,
+        ProposeParams {
+            to,
+            value: parse_token_amount(&value)?,
+            method,
+            params: RawBytes::new(params),
+        },
+    )?;
+    push_msig_message(&data, msg).await
+}
+
+/// Approve a pending multisig transaction, return the CID of the approval
+/// message
+pub(crate) async fn msig_approve<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigApproveParams>,
+) -> Result<MsigApproveResult, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (AddressJson(msig), id, AddressJson(from)) = params;
+    let msg = multisig_txn_message(msig, from, method::APPROVE, txn_id(id)?)?;
+    push_msig_message(&data, msg).await
+}
+
+/// Cancel a pending multisig transaction, return the CID of the cancellation
+/// message
+pub(crate) async fn msig_cancel<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigCancelParams>,
+) -> Result<MsigCancelResult, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (AddressJson(msig), id, AddressJson(from)) = params;
+    let msg = multisig_txn_message(msig, from, method::CANCEL, txn_id(id)?)?;
+    push_msig_message(&data, msg).await
+}
+
+/// Return the pending transactions of a multisig actor
+pub(crate) async fn msig_get_pending<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigGetPendingParams>,
+) -> Result<MsigGetPendingResult, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (AddressJson(msig), TipsetKeysJson(tsk)) = params;
+    let ts = data.chain_store.tipset_from_keys(&tsk)?;
+    let (_, state) = data.state_manager.load_multisig(&msig, &ts)?;
+    pending_transactions(&data, &state)
+}
+
+/// Return the portion of a multisig actor's balance that is not locked by its
+/// vesting schedule
+pub(crate) async fn msig_get_available_balance<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigGetAvailableBalanceParams>,
+) -> Result<MsigGetAvailableBalanceResult, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (AddressJson(msig), TipsetKeysJson(tsk)) = params;
+    let ts = data.chain_store.tipset_from_keys(&tsk)?;
+    let available = data.state_manager.multisig_available_balance(&msig, &ts)?;
+    Ok(available.atto().to_string())
+}
+
+/// Return the vesting schedule of a multisig actor
+pub(crate) async fn msig_get_vesting_schedule<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigGetVestingScheduleParams>,
+) -> Result<MsigGetVestingScheduleResult, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (AddressJson(msig), TipsetKeysJson(tsk)) = params;
+    let ts = data.chain_store.tipset_from_keys(&tsk)?;
+    let (_, state) = data.state_manager.load_multisig(&msig, &ts)?;
+    Ok(vesting_schedule(&state))
+}
+
+/// Return the signers, threshold, balances, vesting schedule and pending
+/// transactions of a multisig actor
+pub(crate) async fn msig_inspect<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigInspectParams>,
+) -> Result<MsigInspectResult, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (AddressJson(msig), TipsetKeysJson(tsk)) = params;
+    let ts = data.chain_store.tipset_from_keys(&tsk)?;
+    let (actor, state) = data.state_manager.load_multisig(&msig, &ts)?;
+    Ok(MsigInfo {
+        balance: TokenAmount::from(&actor.balance),
+        locked: state.amount_locked(ts.epoch()),
+        signers: state.signers.clone(),
+        threshold: state.num_approvals_threshold,
+        vesting: vesting_schedule(&state),
+        pending: pending_transactions(&data, &state)?,
+    })
+}
@@ -1,12 +1,12 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::str::FromStr;
+use std::{str::FromStr, time::Duration};
 
 use forest_beacon::Beacon;
 use forest_libp2p::{NetRPCMethods, NetworkMessage, PeerId};
 use forest_rpc_api::{
-    data_types::{AddrInfo, RPCState},
+    data_types::{AddrInfo, PeerScoreSnapshot, PubsubScore, RPCState},
     net_api::*,
 };
 use futures::channel::oneshot;
@@ -94,3 +94,200 @@ pub(crate) async fn net_disconnect<DB: Blockstore + Clone + Send + Sync + 'stati
 
     Ok(())
 }
+
+pub(crate) async fn net_pubsub_scores<DB: Blockstore + Clone + Send + Sync + 'static, B: Beacon>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<NetPubsubScoresResult, JsonRpcError> {
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetPubsubScores(tx),
+    };
+
+    data.network_send.send_async(req).await?;
+    let scores = rx.await?;
+
+    Ok(scores
+        .into_iter()
+        .map(|(id, score)| PubsubScore {
+            id: id.to_string(),
+            score: PeerScoreSnapshot { score },
+        })
+        .collect())
+}
+
+pub(crate) async fn net_known_peers<DB: Blockstore + Clone + Send + Sync + 'static, B: Beacon>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<NetKnownPeersResult, JsonRpcError> {
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetPeerRecords(tx),
+    };
+
+    data.network_send.send_async(req).await?;
+    Ok(rx.await?)
+}
+
+pub(crate) async fn net_ban<DB: Blockstore + Clone + Send + Sync + 'static, B: Beacon>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<NetBanParams>,
+) -> Result<NetBanResult, JsonRpcError> {
+    let (id, duration_secs) = params;
+    let peer_id = PeerId::from_str(&id)?;
+
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetBan(tx, peer_id, duration_secs.map(Duration::from_secs)),
+    };
+
+    data.network_send.send_async(req).await?;
+    if rx.await? {
+        Ok(())
+    } else {
+        Err(format!("Peer {peer_id} is protected and cannot be banned").into())
+    }
+}
+
+pub(crate) async fn net_unban<DB: Blockstore + Clone + Send + Sync + 'static, B: Beacon>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<NetUnbanParams>,
+) -> Result<NetUnbanResult, JsonRpcError> {
+    let (id,) = params;
+    let peer_id = PeerId::from_str(&id)?;
+
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetUnban(tx, peer_id),
+    };
+
+    data.network_send.send_async(req).await?;
+    Ok(rx.await?)
+}
+
+pub(crate) async fn net_bans<DB: Blockstore + Clone + Send + Sync + 'static, B: Beacon>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<NetBansResult, JsonRpcError> {
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetBans(tx),
+    };
+
+    data.network_send.send_async(req).await?;
+    Ok(rx.await?)
+}
+
+pub(crate) async fn net_bandwidth_stats<
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<NetBandwidthStatsResult, JsonRpcError> {
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetBandwidthStats(tx),
+    };
+
+    data.network_send.send_async(req).await?;
+    Ok(rx.await?)
+}
+
+pub(crate) async fn net_auto_nat_status<
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<NetAutoNatStatusResult, JsonRpcError> {
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetAutoNatStatus(tx),
+    };
+
+    data.network_send.send_async(req).await?;
+    Ok(rx.await?)
+}
+
+pub(crate) async fn net_find_peer<DB: Blockstore + Clone + Send + Sync + 'static, B: Beacon>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<NetFindPeerParams>,
+) -> Result<NetFindPeerResult, JsonRpcError> {
+    let (id,) = params;
+    let peer_id = PeerId::from_str(&id)?;
+
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetFindPeer(tx, peer_id),
+    };
+
+    data.network_send.send_async(req).await?;
+    let addrs = rx.await?;
+    if addrs.is_empty() {
+        return Err(format!("Peer {peer_id} not found").into());
+    }
+    Ok(AddrInfo {
+        id,
+        addrs: addrs.into_iter().collect(),
+    })
+}
+
+pub(crate) async fn net_peer_info<DB: Blockstore + Clone + Send + Sync + 'static, B: Beacon>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<NetPeerInfoParams>,
+) -> Result<NetPeerInfoResult, JsonRpcError> {
+    let (id,) = params;
+    let peer_id = PeerId::from_str(&id)?;
+
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetPeerInfo(tx, peer_id),
+    };
+
+    data.network_send.send_async(req).await?;
+    rx.await?
+        .ok_or_else(|| format!("No information on peer {peer_id}, is it connected?").into())
+}
+
+pub(crate) async fn net_agent_version<DB: Blockstore + Clone + Send + Sync + 'static, B: Beacon>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<NetAgentVersionParams>,
+) -> Result<NetAgentVersionResult, JsonRpcError> {
+    let (id,) = params;
+    let peer_id = PeerId::from_str(&id)?;
+
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetAgentVersion(tx, peer_id),
+    };
+
+    data.network_send.send_async(req).await?;
+    rx.await?
+        .ok_or_else(|| format!("Peer {peer_id} has not been identified").into())
+}
+
+pub(crate) async fn net_dht_routing_table<
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<NetDhtRoutingTableResult, JsonRpcError> {
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetDhtRoutingTable(tx),
+    };
+
+    data.network_send.send_async(req).await?;
+    Ok(rx.await?)
+}
+
+pub(crate) async fn net_bitswap_sessions<
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<NetBitswapSessionsResult, JsonRpcError> {
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetBitswapSessions(tx),
+    };
+
+    data.network_send.send_async(req).await?;
+    Ok(rx.await?)
+}
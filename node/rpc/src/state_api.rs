@@ -3,17 +3,18 @@
 #![allow(clippy::unused_async)]
 
 use ahash::{HashMap, HashMapExt};
+use base64::{prelude::BASE64_STANDARD, Engine};
 use cid::Cid;
 use fil_actor_interface::market;
 use forest_beacon::Beacon;
-use forest_blocks::tipset_keys_json::TipsetKeysJson;
+use forest_blocks::{tipset_keys_json::TipsetKeysJson, Tipset};
 use forest_ipld::json::IpldJson;
 use forest_json::cid::CidJson;
 use forest_rpc_api::{
     data_types::{MarketDeal, MessageLookup, RPCState},
     state_api::*,
 };
-use forest_shim::address::Address;
+use forest_shim::{address::Address, executor::Receipt};
 use forest_state_manager::InvocResult;
 use fvm_ipld_blockstore::Blockstore;
 use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
@@ -172,6 +173,67 @@ pub(crate) async fn state_wait_msg<DB: Blockstore + Clone + Send + Sync + 'stati
     let (tipset, receipt) = state_manager.wait_for_message(cid, confidence).await?;
     let tipset = tipset.ok_or("wait for msg returned empty tuple")?;
     let receipt = receipt.ok_or("wait for msg returned empty receipt")?;
+    message_lookup(cid, &tipset, receipt)
+}
+
+/// Looks up a message that is already on chain, from the message index when
+/// it is indexed, returning `None` if the message has not been executed yet.
+pub(crate) async fn state_search_msg<DB: Blockstore + Clone + Send + Sync + 'static, B: Beacon>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<StateSearchMsgParams>,
+) -> Result<StateSearchMsgResult, JsonRpcError> {
+    let (CidJson(cid),) = params;
+    let state_manager = data.state_manager.clone();
+    match tokio::task::spawn_blocking(move || state_manager.search_for_message(cid)).await?? {
+        Some((tipset, receipt)) => Ok(Some(message_lookup(cid, &tipset, receipt)?)),
+        None => Ok(None),
+    }
+}
+
+/// Draws randomness from the ticket chain of a tipset, as the actors do.
+pub(crate) async fn state_get_randomness_from_tickets<
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<StateGetRandomnessFromTicketsParams>,
+) -> Result<StateGetRandomnessFromTicketsResult, JsonRpcError> {
+    let (pers, round, entropy, TipsetKeysJson(tsk)) = params;
+    let tipset = data.chain_store.tipset_from_keys(&tsk)?;
+    let randomness = data.state_manager.get_chain_randomness(
+        tipset.key(),
+        pers,
+        round,
+        &BASE64_STANDARD.decode(entropy)?,
+    )?;
+    Ok(BASE64_STANDARD.encode(randomness))
+}
+
+/// Draws randomness from the beacon entries of the chain of a tipset, as the
+/// actors do.
+pub(crate) async fn state_get_randomness_from_beacon<
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<StateGetRandomnessFromBeaconParams>,
+) -> Result<StateGetRandomnessFromBeaconResult, JsonRpcError> {
+    let (pers, round, entropy, TipsetKeysJson(tsk)) = params;
+    let tipset = data.chain_store.tipset_from_keys(&tsk)?;
+    let randomness = data.state_manager.get_beacon_randomness(
+        tipset.key(),
+        pers,
+        round,
+        &BASE64_STANDARD.decode(entropy)?,
+    )?;
+    Ok(BASE64_STANDARD.encode(randomness))
+}
+
+fn message_lookup(
+    cid: Cid,
+    tipset: &Tipset,
+    receipt: Receipt,
+) -> Result<MessageLookup, JsonRpcError> {
     let ipld: Ipld = if receipt.return_data().bytes().is_empty() {
         Ipld::Null
     } else {
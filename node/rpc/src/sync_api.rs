@@ -2,17 +2,25 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 #![allow(clippy::unused_async)]
 
+use std::sync::Arc;
+
 use forest_beacon::Beacon;
-use forest_chain_sync::SyncState;
+use forest_chain_sync::{BadBlockRecord, ConsensusFault, SyncState, REPORT_CONSENSUS_FAULT_METHOD};
 use forest_json::cid::CidJson;
 use forest_rpc_api::{
-    data_types::{RPCState, RPCSyncState},
+    data_types::{BlockExplanation, RPCState, RPCSyncState},
     sync_api::*,
 };
+use forest_shim::address::Address;
 use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::Cbor;
+use fvm_ipld_encoding3::RawBytes;
 use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
+use log::{info, warn};
 use parking_lot::RwLock;
 
+use crate::mpool_api::sign_and_push_message;
+
 /// Checks if a given block is marked as bad.
 pub(crate) async fn sync_check_bad<DB, B>(
     data: Data<RPCState<DB, B>>,
@@ -23,7 +31,11 @@ where
     B: Beacon,
 {
     let (CidJson(cid),) = params;
-    Ok(data.bad_blocks.peek(&cid).unwrap_or_default())
+    Ok(data
+        .bad_blocks
+        .peek(&cid)
+        .map(|record| record.reason)
+        .unwrap_or_default())
 }
 
 /// Marks a block as bad, meaning it will never be synced.
@@ -36,15 +48,84 @@ where
     B: Beacon,
 {
     let (CidJson(cid),) = params;
-    data.bad_blocks
-        .put(cid, "Marked bad manually through RPC API".to_string());
+    data.bad_blocks.put(
+        cid,
+        BadBlockRecord::new("Marked bad manually through RPC API", vec![]),
+    );
+    Ok(())
+}
+
+/// Unmarks a bad block, and the blocks marked bad for descending from it, so
+/// that they are validated again when synced.
+pub(crate) async fn sync_unmark_bad<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<SyncUnmarkBadParams>,
+) -> Result<SyncUnmarkBadResult, JsonRpcError>
+where
+    DB: Blockstore,
+    B: Beacon,
+{
+    let (CidJson(cid),) = params;
+    if data.bad_blocks.remove(&cid).is_some() {
+        info!("Unmarked bad block {cid}");
+    }
+    Ok(())
+}
+
+/// Unmarks all the bad blocks.
+pub(crate) async fn sync_unmark_all_bad<DB, B>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<SyncUnmarkAllBadResult, JsonRpcError>
+where
+    DB: Blockstore,
+    B: Beacon,
+{
+    data.bad_blocks.clear();
+    info!("Unmarked all bad blocks");
     Ok(())
 }
 
+/// Validates a block again and returns the checks it fails, along with the
+/// record of why it was marked bad, if it was.
+pub(crate) async fn sync_explain_block<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<SyncExplainBlockParams>,
+) -> Result<SyncExplainBlockResult, JsonRpcError>
+where
+    DB: Blockstore,
+    B: Beacon,
+{
+    let (CidJson(cid),) = params;
+    let explainer = data
+        .block_explainer
+        .as_ref()
+        .ok_or("Block validation is not available")?;
+    Ok(BlockExplanation {
+        failed_checks: explainer.explain_block(cid).await?,
+        bad_block: data.bad_blocks.peek(&cid),
+    })
+}
+
 async fn clone_state(state: &RwLock<SyncState>) -> SyncState {
     state.read().clone()
 }
 
+/// Returns the consensus faults recently detected by the slasher, oldest
+/// first.
+pub(crate) async fn sync_consensus_faults<DB, B>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<SyncConsensusFaultsResult, JsonRpcError>
+where
+    DB: Blockstore,
+    B: Beacon,
+{
+    let slasher = data
+        .slasher
+        .as_ref()
+        .ok_or("Consensus fault detection is disabled")?;
+    Ok(slasher.faults().into_iter().map(Into::into).collect())
+}
+
 /// Returns the current status of the `ChainSync` process.
 pub(crate) async fn sync_state<DB, B>(
     data: Data<RPCState<DB, B>>,
@@ -57,6 +138,67 @@ where
     Ok(RPCSyncState { active_syncs })
 }
 
+/// Reports the consensus faults detected by the slasher to the miner actors,
+/// with messages sent from `reporter`. Returns when the slasher is disabled.
+pub async fn report_consensus_faults<DB, B>(
+    state: Arc<RPCState<DB, B>>,
+    reporter: Address,
+) -> anyhow::Result<()>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let Some(slasher) = state.slasher.clone() else {
+        return Ok(());
+    };
+    let faults = slasher.fault_receiver();
+    let data = Data(state);
+    while let Ok(fault) = faults.recv_async().await {
+        match report_consensus_fault(&data, reporter, &fault).await {
+            Ok(cid) => info!(
+                "Reported {:?} consensus fault of miner {} at epoch {} in message {cid}",
+                fault.fault_type, fault.miner, fault.epoch
+            ),
+            Err(e) => warn!(
+                "Failed to report {:?} consensus fault of miner {} at epoch {}: {e:?}",
+                fault.fault_type, fault.miner, fault.epoch
+            ),
+        }
+    }
+    Ok(())
+}
+
+async fn report_consensus_fault<DB, B>(
+    data: &Data<RPCState<DB, B>>,
+    reporter: Address,
+    fault: &ConsensusFault,
+) -> Result<cid::Cid, JsonRpcError>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    // Only blocks signed by the miner's worker are a proof of its fault
+    let head = data.state_manager.chain_store().heaviest_tipset();
+    let worker = data
+        .state_manager
+        .get_miner_work_addr(*head.parent_state(), &fault.miner)?;
+    for header in [&fault.header1, &fault.header2] {
+        header
+            .check_block_signature(&worker)
+            .map_err(|e| format!("Block {} is not signed by the miner: {e}", header.cid()))?;
+    }
+
+    let msg = fvm_shared3::message::Message {
+        from: reporter.into(),
+        to: fault.miner.into(),
+        method_num: REPORT_CONSENSUS_FAULT_METHOD,
+        params: RawBytes::new(fault.report_params()?.into()),
+        ..Default::default()
+    };
+    let smsg = sign_and_push_message(data, msg.into(), None).await?;
+    Ok(smsg.cid()?)
+}
+
 #[cfg(test)]
 mod tests {
     use std::{sync::Arc, time::Duration};
@@ -155,6 +297,7 @@ mod tests {
         let state = Arc::new(RPCState {
             state_manager,
             keystore: Arc::new(RwLock::new(KeyStore::new(KeyStoreConfig::Memory).unwrap())),
+            remote_signer: None,
             mpool: Arc::new(pool),
             bad_blocks: Default::default(),
             sync_state: Arc::new(parking_lot::RwLock::new(Default::default())),
@@ -164,6 +307,8 @@ mod tests {
             beacon,
             new_mined_block_tx,
             gc_event_tx,
+            slasher: None,
+            block_explainer: None,
         });
         (state, network_rx)
     }
@@ -184,10 +329,28 @@ mod tests {
         assert!(sync_mark_bad(Data(state.clone()), Params((cid.clone(),)))
             .await
             .is_ok());
-        match sync_check_bad(Data(state), Params((cid,))).await {
+        match sync_check_bad(Data(state.clone()), Params((cid.clone(),))).await {
             Ok(reason) => assert_eq!(reason, "Marked bad manually through RPC API"),
             Err(e) => std::panic::panic_any(e),
         }
+
+        // And unmark it
+        assert!(sync_unmark_bad(Data(state.clone()), Params((cid.clone(),)))
+            .await
+            .is_ok());
+        match sync_check_bad(Data(state.clone()), Params((cid.clone(),))).await {
+            Ok(reason) => assert_eq!(reason, ""),
+            Err(e) => std::panic::panic_any(e),
+        }
+
+        assert!(sync_mark_bad(Data(state.clone()), Params((cid.clone(),)))
+            .await
+            .is_ok());
+        assert!(sync_unmark_all_bad(Data(state.clone())).await.is_ok());
+        match sync_check_bad(Data(state), Params((cid,))).await {
+            Ok(reason) => assert_eq!(reason, ""),
+            Err(e) => std::panic::panic_any(e),
+        }
     }
 
     #[tokio::test]
@@ -1,17 +1,18 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 #![allow(clippy::unused_async)]
-use std::{convert::TryFrom, str::FromStr};
+use std::{convert::TryFrom, path::Path, str::FromStr, sync::Arc};
 
 use base64::{prelude::BASE64_STANDARD, Engine};
 use forest_beacon::Beacon;
 use forest_json::{address::json::AddressJson, signature::json::SignatureJson};
-use forest_key_management::{json::KeyInfoJson, Error, Key};
+use forest_key_management::{json::KeyInfoJson, Error, Key, KeyStore, Signer};
 use forest_rpc_api::{data_types::RPCState, wallet_api::*};
-use forest_shim::{address::Address, econ::TokenAmount, state_tree::StateTree};
+use forest_shim::{address::Address, crypto::Signature, econ::TokenAmount, state_tree::StateTree};
 use fvm_ipld_blockstore::Blockstore;
 use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
 use num_traits::Zero;
+use tokio::sync::RwLock;
 
 /// Return the balance from `StateManager` for a given `Address`
 pub(crate) async fn wallet_balance<DB, B>(
@@ -86,10 +87,14 @@ where
     let (addr_str,) = params;
     let addr = Address::from_str(&addr_str)?;
 
-    let keystore = data.keystore.read().await;
+    if data.keystore.read().await.has_key(&addr)? {
+        return Ok(true);
+    }
 
-    let key = forest_key_management::find_key(&addr, &keystore).is_ok();
-    Ok(key)
+    match data.remote_signer.clone() {
+        Some(remote) => Ok(tokio::task::spawn_blocking(move || remote.has_key(&addr)).await??),
+        None => Ok(false),
+    }
 }
 
 /// Import `KeyInfo` to the Wallet, return the Address that corresponds to it
@@ -133,11 +138,15 @@ where
     DB: Blockstore,
     B: Beacon,
 {
-    let keystore = data.keystore.read().await;
-    Ok(forest_key_management::list_addrs(&keystore)?
-        .into_iter()
-        .map(AddressJson::from)
-        .collect())
+    let mut addrs = forest_key_management::list_addrs(&*data.keystore.read().await)?;
+    if let Some(remote) = data.remote_signer.clone() {
+        for addr in tokio::task::spawn_blocking(move || remote.list_addrs()).await?? {
+            if !addrs.contains(&addr) {
+                addrs.push(addr);
+            }
+        }
+    }
+    Ok(addrs.into_iter().map(AddressJson::from).collect())
 }
 
 /// Generate a new Address that is stored in the Wallet
@@ -198,24 +207,41 @@ where
     let key_addr = state_manager
         .resolve_to_key_addr(&address, &heaviest_tipset)
         .await?;
-    let keystore = &mut *data.keystore.write().await;
-    let key = match forest_key_management::find_key(&key_addr, keystore) {
-        Ok(key) => key,
-        Err(_) => {
-            let key_info = forest_key_management::try_find(&key_addr, keystore)?;
-            Key::try_from(key_info)?
-        }
-    };
-
-    let sig = forest_key_management::sign(
-        *key.key_info.key_type(),
-        key.key_info.private_key(),
-        &BASE64_STANDARD.decode(msg_string)?,
-    )?;
+    let sig = sign_with_wallet(
+        &data.keystore,
+        data.remote_signer.clone(),
+        &key_addr,
+        BASE64_STANDARD.decode(msg_string)?,
+    )
+    .await?;
 
     Ok(SignatureJson(sig))
 }
 
+/// Sign a message with the key of the given address. Keys missing from the
+/// local key-store are looked up in the remote signer, if one is configured.
+/// The key-store is not locked while waiting for the remote signer.
+pub(crate) async fn sign_with_wallet(
+    keystore: &RwLock<KeyStore>,
+    remote_signer: Option<Arc<dyn Signer>>,
+    addr: &Address,
+    msg: Vec<u8>,
+) -> Result<Signature, JsonRpcError> {
+    {
+        let keystore = keystore.read().await;
+        if keystore.has_key(addr)? {
+            return Ok(keystore.sign(addr, &msg)?);
+        }
+    }
+    match remote_signer {
+        Some(remote) => {
+            let addr = *addr;
+            Ok(tokio::task::spawn_blocking(move || remote.sign(&addr, &msg)).await??)
+        }
+        None => Err(Error::KeyNotExists.into()),
+    }
+}
+
 /// Verify a Signature, true if verified, false otherwise
 pub(crate) async fn wallet_verify<DB, B>(
     _data: Data<RPCState<DB, B>>,
@@ -231,3 +257,63 @@ where
     let ret = sig.verify(&msg, &address.into()).is_ok();
     Ok(ret)
 }
+
+/// Re-encrypt the key-store with a new passphrase
+pub(crate) async fn wallet_change_passphrase<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<WalletChangePassphraseParams>,
+) -> Result<WalletChangePassphraseResult, JsonRpcError>
+where
+    DB: Blockstore,
+    B: Beacon,
+{
+    let (passphrase, new_passphrase) = params;
+    let mut keystore = data.keystore.write().await;
+    keystore.change_passphrase(&passphrase, &new_passphrase)?;
+    Ok(())
+}
+
+/// Convert a clear text key-store into an encrypted one
+pub(crate) async fn wallet_encrypt_keystore<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<WalletEncryptKeystoreParams>,
+) -> Result<WalletEncryptKeystoreResult, JsonRpcError>
+where
+    DB: Blockstore,
+    B: Beacon,
+{
+    let (passphrase,) = params;
+    let mut keystore = data.keystore.write().await;
+    keystore.encrypt(&passphrase)?;
+    Ok(())
+}
+
+/// Convert an encrypted key-store into a clear text one
+pub(crate) async fn wallet_decrypt_keystore<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<WalletDecryptKeystoreParams>,
+) -> Result<WalletDecryptKeystoreResult, JsonRpcError>
+where
+    DB: Blockstore,
+    B: Beacon,
+{
+    let (passphrase,) = params;
+    let mut keystore = data.keystore.write().await;
+    keystore.decrypt(&passphrase)?;
+    Ok(())
+}
+
+/// Write a verified copy of the key-store to the given path on the node's host
+pub(crate) async fn wallet_backup_keystore<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<WalletBackupKeystoreParams>,
+) -> Result<WalletBackupKeystoreResult, JsonRpcError>
+where
+    DB: Blockstore,
+    B: Beacon,
+{
+    let (path,) = params;
+    let keystore = data.keystore.read().await;
+    keystore.backup(Path::new(&path))?;
+    Ok(())
+}
@@ -7,7 +7,7 @@ use fvm3::executor::ApplyRet as ApplyRet_v3;
 use fvm_ipld_encoding3::RawBytes;
 use fvm_shared::receipt::Receipt as Receipt_v2;
 use fvm_shared3::error::ExitCode;
-pub use fvm_shared3::receipt::Receipt as Receipt_v3;
+pub use fvm_shared3::{event::StampedEvent, receipt::Receipt as Receipt_v3};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::econ::TokenAmount;
@@ -58,6 +58,15 @@ impl ApplyRet {
             ApplyRet::V3(v3) => Receipt::V3(v3.msg_receipt.clone()),
         }
     }
+
+    /// Returns the events emitted by actors during the execution. Events are
+    /// only supported from FVM v3.
+    pub fn events(&self) -> Vec<StampedEvent> {
+        match self {
+            ApplyRet::V2(_) => vec![],
+            ApplyRet::V3(v3) => v3.events.clone(),
+        }
+    }
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -7,7 +7,7 @@ use cid::Cid;
 use fvm::state_tree::{ActorState as ActorStateV2, StateTree as StateTreeV2};
 use fvm3::state_tree::{ActorState as ActorStateV3, StateTree as StateTreeV3};
 use fvm_ipld_blockstore::Blockstore;
-pub use fvm_shared3::ActorID;
+pub use fvm_shared3::{state::StateTreeVersion, ActorID};
 use serde::{Deserialize, Serialize};
 
 use crate::{address::Address, econ::TokenAmount, Inner};
@@ -28,6 +28,15 @@ impl<S> StateTree<S>
 where
     S: Blockstore + Clone,
 {
+    /// Constructor for an empty state tree, e.g. to build a genesis state.
+    /// Only the state tree version used by FVM3 is supported.
+    pub fn new(store: S, version: StateTreeVersion) -> anyhow::Result<Self> {
+        match version {
+            StateTreeVersion::V5 => Ok(StateTree::V3(StateTreeV3::new(store, version)?)),
+            _ => bail!("Can't create an empty state tree of version {version:?}. Only V5 is supported at the moment."),
+        }
+    }
+
     /// Constructor for a hamt state tree given an IPLD store
     pub fn new_from_root(store: S, c: &Cid) -> anyhow::Result<Self> {
         if let Ok(st) = StateTreeV3::new_from_root(store.clone(), c) {
@@ -114,3 +114,15 @@ pub trait BlockstoreBufferedWriteExt: Blockstore + Sized {
 }
 
 impl<T: fvm_ipld_blockstore::Blockstore> BlockstoreBufferedWriteExt for T {}
+
+/// Object safe key-value store, for the stores kept behind trait objects such
+/// as those of the indexes and of the data persisted across restarts.
+pub trait KeyValueStore: Send + Sync {
+    fn read_value(&self, key: &[u8]) -> anyhow::Result<Option<Vec<u8>>>;
+
+    fn write_values(&self, values: Vec<(Vec<u8>, Vec<u8>)>) -> anyhow::Result<()>;
+
+    fn write_value(&self, key: &[u8], value: &[u8]) -> anyhow::Result<()> {
+        self.write_values(vec![(key.to_vec(), value.to_vec())])
+    }
+}
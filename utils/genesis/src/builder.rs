@@ -0,0 +1,621 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, ensure, Context};
+use cid::{multihash::Code::Blake2b256, Cid};
+use forest_beacon::BeaconEntry;
+use forest_blocks::{BlockHeader, Ticket, Tipset};
+use forest_interpreter::{RewardActorMessageCalc, VM};
+use forest_ipld::{should_save_block_to_snapshot, walk_snapshot};
+use forest_json::{address::json as address_json, token_amount::json as token_json, vrf::VRFProof};
+use forest_networks::ChainConfig;
+use forest_shim::{
+    address::Address,
+    econ::TokenAmount,
+    message::Message_v3,
+    state_tree::{ActorState, StateTree, StateTreeVersion},
+    version::NetworkVersion,
+};
+use forest_utils::db::BlockstoreExt;
+use futures::AsyncWriteExt;
+use fvm_ipld_amt::Amtv0 as Amt;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_car::{load_car, CarHeader};
+use fvm_ipld_encoding3::{BytesSer, RawBytes};
+use fvm_shared::{clock::ChainEpoch, BLOCK_GAS_LIMIT, METHOD_CONSTRUCTOR, METHOD_SEND};
+use fvm_shared3::sector::RegisteredPoStProof;
+use log::info;
+use num::Zero;
+use serde::{de::IgnoredAny, Deserialize};
+use tokio::{
+    fs::File,
+    io::{AsyncWrite, BufReader},
+};
+use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+
+// Methods of the builtin actors invoked while building the genesis.
+const POWER_CREATE_MINER_METHOD: u64 = 2;
+const POWER_ON_EPOCH_TICK_END_METHOD: u64 = 5;
+const MARKET_CRON_TICK_METHOD: u64 = 9;
+
+/// Miners are numbered from this ID on, like in Lotus, while the accounts get
+/// the IDs right after the singleton actors.
+const FIRST_MINER_ID: u64 = 1000;
+
+/// See <https://github.com/filecoin-project/lotus/blob/v1.20.0/build/params_shared_vals.go>
+const INITIAL_BASE_FEE: u64 = 100_000_000;
+/// Funds of the reward actor, out of which the block rewards are paid.
+const REWARD_ACTOR_BALANCE_FIL: u64 = 1_100_000_000;
+/// Funds of the reserve, accounted for by the circulating supply.
+const RESERVE_ACTOR_BALANCE_FIL: u64 = 300_000_000;
+
+/// Describes the genesis of a network, in the format of the templates made by
+/// `lotus-seed genesis new`. On top of that, it names the bundle of builtin
+/// actors the network starts with.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GenesisTemplate {
+    pub network_name: String,
+    pub network_version: NetworkVersion,
+    /// CAR file of the builtin actors, e.g. `builtin-actors-mainnet.car` of
+    /// the release matching the network version. Miners with small sectors,
+    /// like 2 KiB, need the `builtin-actors-devnet.car` bundle, as the actors
+    /// of the other networks reject their proof types.
+    pub actors_bundle: PathBuf,
+    #[serde(default)]
+    pub accounts: Vec<GenesisAccount>,
+    #[serde(default)]
+    pub miners: Vec<GenesisMiner>,
+    /// Unix timestamp of the genesis block; the time of building it if zero.
+    #[serde(default)]
+    pub timestamp: u64,
+}
+
+impl GenesisTemplate {
+    /// Reads a template from a JSON file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read genesis template {}", path.display()))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+/// An account funded at genesis.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GenesisAccount {
+    /// Only `account` is supported.
+    #[serde(rename = "Type")]
+    pub actor_type: String,
+    #[serde(with = "token_json")]
+    pub balance: TokenAmount,
+    pub meta: GenesisAccountMeta,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GenesisAccountMeta {
+    #[serde(default, with = "address_json::opt")]
+    pub owner: Option<Address>,
+}
+
+/// A miner created at genesis.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GenesisMiner {
+    /// Expected ID of the miner; miners are numbered in order of appearance.
+    #[serde(rename = "ID", default, with = "address_json::opt")]
+    pub id: Option<Address>,
+    #[serde(with = "address_json")]
+    pub owner: Address,
+    /// Has to be a BLS address.
+    #[serde(with = "address_json")]
+    pub worker: Address,
+    pub peer_id: String,
+    pub sector_size: u64,
+    /// Pre-sealed sectors, which are not supported, see [`build_genesis`].
+    #[serde(default)]
+    pub sectors: Vec<IgnoredAny>,
+}
+
+/// Builds the state and block of the genesis described by `template` into the
+/// store. The chain has to run the network version of the template at epoch
+/// zero, and it has to be served by FVM3.
+///
+/// The singleton actors are set up by their constructors, the accounts are
+/// funded by transfers and the miners are created by the power actor, all
+/// through implicit messages. Templates with pre-sealed sectors are rejected,
+/// as they are not committed, so no miner has any power. This suits consensus
+/// protocols not relying on storage power, like delegated consensus.
+pub async fn build_genesis<DB>(
+    template: &GenesisTemplate,
+    chain_config: Arc<ChainConfig>,
+    db: &DB,
+) -> anyhow::Result<BlockHeader>
+where
+    DB: Blockstore + Clone + Send + Sync + 'static,
+{
+    ensure!(
+        template.network_version >= NetworkVersion::V18,
+        "Can't build a genesis of network version {:?}, only 18 and later are supported",
+        *template.network_version
+    );
+    ensure!(
+        chain_config.network_version(0) == template.network_version,
+        "The genesis template is for network version {:?}, but chain {} runs version {:?} at genesis",
+        *template.network_version,
+        chain_config.name,
+        *chain_config.network_version(0)
+    );
+    for (i, miner) in template.miners.iter().enumerate() {
+        ensure!(
+            miner.sectors.is_empty(),
+            "Genesis miner {i} has {} pre-sealed sectors, which are not supported",
+            miner.sectors.len()
+        );
+    }
+    let timestamp = match template.timestamp {
+        0 => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        timestamp => timestamp,
+    };
+
+    let (actors_cid, actors) = load_actors_bundle(&template.actors_bundle, db).await?;
+    let code = |name: &str| {
+        actors
+            .iter()
+            .find(|(actor, _)| actor == name)
+            .map(|(_, code)| *code)
+            .with_context(|| format!("Actors bundle has no {name} actor"))
+    };
+
+    // Singleton actors are constructed by messages, so they start with the
+    // empty state their constructors expect.
+    let empty_state = db.put_obj(&Vec::<()>::new(), Blake2b256)?;
+    let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5)?;
+    let total_balance = template
+        .accounts
+        .iter()
+        .fold(TokenAmount::zero(), |total, account| {
+            total + &account.balance
+        });
+    let system_state = db.put_obj(&(actors_cid,), Blake2b256)?;
+    state_tree.set_actor(
+        &Address::SYSTEM_ACTOR,
+        ActorState::new(code("system")?, system_state, total_balance, 0, None),
+    )?;
+    for (address, name, balance) in [
+        (Address::INIT_ACTOR, "init", TokenAmount::zero()),
+        (
+            Address::REWARD_ACTOR,
+            "reward",
+            TokenAmount::from_whole(REWARD_ACTOR_BALANCE_FIL),
+        ),
+        (Address::CRON_ACTOR, "cron", TokenAmount::zero()),
+        (Address::POWER_ACTOR, "storagepower", TokenAmount::zero()),
+        (Address::MARKET_ACTOR, "storagemarket", TokenAmount::zero()),
+        (
+            Address::VERIFIED_REGISTRY_ACTOR,
+            "verifiedregistry",
+            TokenAmount::zero(),
+        ),
+        (Address::DATACAP_TOKEN_ACTOR, "datacap", TokenAmount::zero()),
+        (
+            Address::ETHEREUM_ACCOUNT_MANAGER_ACTOR,
+            "eam",
+            TokenAmount::zero(),
+        ),
+    ] {
+        state_tree.set_actor(
+            &address,
+            ActorState::new(code(name)?, empty_state, balance, 0, None),
+        )?;
+    }
+    for (address, balance) in [
+        (
+            Address::RESERVE_ACTOR,
+            TokenAmount::from_whole(RESERVE_ACTOR_BALANCE_FIL),
+        ),
+        (Address::BURNT_FUNDS_ACTOR, TokenAmount::zero()),
+    ] {
+        let state = db.put_obj(&(address,), Blake2b256)?;
+        state_tree.set_actor(
+            &address,
+            ActorState::new(code("account")?, state, balance, 0, None),
+        )?;
+    }
+    let root = state_tree.flush()?;
+
+    let engine_v2 = fvm::machine::MultiEngine::new();
+    let engine_v3 = fvm3::engine::MultiEngine::new(1);
+    let new_vm = |root| {
+        VM::new(
+            root,
+            db.clone(),
+            0,
+            GenesisRand,
+            TokenAmount::zero(),
+            TokenAmount::zero(),
+            Arc::new(RewardActorMessageCalc),
+            Box::new(|_: ChainEpoch| -> anyhow::Result<Cid> {
+                bail!("There are no tipsets before the genesis")
+            }),
+            &engine_v2,
+            &engine_v3,
+            chain_config.clone(),
+            timestamp,
+        )
+    };
+
+    let mut vm = new_vm(root)?;
+    for (address, params) in [
+        (
+            Address::INIT_ACTOR,
+            RawBytes::serialize((&template.network_name,))?,
+        ),
+        // Without the realized power, which is zero anyway.
+        (Address::REWARD_ACTOR, RawBytes::serialize(())?),
+        (
+            Address::CRON_ACTOR,
+            RawBytes::serialize((vec![
+                (Address::POWER_ACTOR, POWER_ON_EPOCH_TICK_END_METHOD),
+                (Address::MARKET_ACTOR, MARKET_CRON_TICK_METHOD),
+            ],))?,
+        ),
+        (Address::POWER_ACTOR, RawBytes::default()),
+        (Address::MARKET_ACTOR, RawBytes::default()),
+        // Nobody can add verifiers on a network built from a template.
+        (
+            Address::VERIFIED_REGISTRY_ACTOR,
+            RawBytes::serialize(Address::SYSTEM_ACTOR)?,
+        ),
+        (
+            Address::DATACAP_TOKEN_ACTOR,
+            RawBytes::serialize(Address::VERIFIED_REGISTRY_ACTOR)?,
+        ),
+        (Address::ETHEREUM_ACCOUNT_MANAGER_ACTOR, RawBytes::default()),
+    ] {
+        apply_implicit(
+            &mut vm,
+            Address::SYSTEM_ACTOR,
+            address,
+            METHOD_CONSTRUCTOR,
+            params,
+            TokenAmount::zero(),
+        )?;
+    }
+    for account in &template.accounts {
+        ensure!(
+            account.actor_type == "account",
+            "Genesis accounts of type {} are not supported",
+            account.actor_type
+        );
+        let owner = account
+            .meta
+            .owner
+            .context("Genesis account without an owner")?;
+        // Sending funds to a key address creates its account actor.
+        apply_implicit(
+            &mut vm,
+            Address::SYSTEM_ACTOR,
+            owner,
+            METHOD_SEND,
+            RawBytes::default(),
+            account.balance.clone(),
+        )?;
+    }
+    let root = reserve_miner_ids(db, vm.flush()?)?;
+
+    let mut vm = new_vm(root)?;
+    for miner in &template.miners {
+        // Owner and worker have to be accounts.
+        for address in [miner.owner, miner.worker] {
+            apply_implicit(
+                &mut vm,
+                Address::SYSTEM_ACTOR,
+                address,
+                METHOD_SEND,
+                RawBytes::default(),
+                TokenAmount::zero(),
+            )?;
+        }
+        let peer_id = multibase::Base::Base58Btc
+            .decode(&miner.peer_id)
+            .with_context(|| format!("Invalid peer ID {}", miner.peer_id))?;
+        let params = RawBytes::serialize((
+            miner.owner,
+            miner.worker,
+            window_post_proof(miner.sector_size)?,
+            BytesSer(&peer_id),
+            // No multiaddresses.
+            Vec::<()>::new(),
+        ))?;
+        let ret = apply_implicit(
+            &mut vm,
+            miner.owner,
+            Address::POWER_ACTOR,
+            POWER_CREATE_MINER_METHOD,
+            params,
+            TokenAmount::zero(),
+        )?;
+        let (id_address, _robust_address): (Address, Address) = ret.deserialize()?;
+        if let Some(id) = miner.id {
+            ensure!(
+                id == id_address,
+                "Genesis miner {id} has been created as {id_address}"
+            );
+        }
+        info!(
+            "Created genesis miner {id_address} with worker {}",
+            miner.worker
+        );
+    }
+    let state_root = vm.flush()?;
+
+    let messages = forest_chain::persist_block_messages(db, vec![])?;
+    let receipts = Amt::<Cid, _>::new(db).flush()?;
+    let genesis = BlockHeader::builder()
+        .miner_address(Address::SYSTEM_ACTOR)
+        .state_root(state_root)
+        .messages(messages.msg_cid)
+        .message_receipts(receipts)
+        .ticket(Some(Ticket::new(VRFProof::new(vec![0; 32]))))
+        .beacon_entries(vec![BeaconEntry::new(0, vec![0; 32])])
+        .parent_base_fee(TokenAmount::from_atto(INITIAL_BASE_FEE))
+        .timestamp(timestamp)
+        .build()?;
+    db.put_obj(&genesis, Blake2b256)?;
+
+    info!(
+        "Built genesis {} of {}",
+        genesis.cid(),
+        template.network_name
+    );
+    Ok(genesis)
+}
+
+/// Writes the genesis block and its state to a CAR file, which the nodes of
+/// the network can be started with.
+pub async fn export_genesis<DB, W>(db: &DB, genesis: &BlockHeader, writer: W) -> anyhow::Result<()>
+where
+    DB: Blockstore + Sync,
+    W: AsyncWrite + Send + Unpin,
+{
+    let mut blocks = Vec::new();
+    // Including the roots of the genesis messages, unlike a snapshot would.
+    walk_snapshot(&Tipset::from(genesis), 1, |cid| {
+        let block = db
+            .get(&cid)
+            .and_then(|block| block.with_context(|| format!("Cid {cid} not found in blockstore")));
+        if let Ok(data) = &block {
+            if should_save_block_to_snapshot(&cid) {
+                blocks.push((cid, data.clone()));
+            }
+        }
+        futures::future::ready(block)
+    })
+    .await?;
+
+    let mut writer = writer.compat_write();
+    CarHeader::from(vec![*genesis.cid()])
+        .write_stream_async(&mut writer, &mut futures::stream::iter(blocks))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to write genesis: {e}"))?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Loads the actors bundle into the store, returning the CID of its list of
+/// actors, which the system actor refers to, and the names and code CIDs of
+/// the actors.
+async fn load_actors_bundle<DB>(path: &Path, db: &DB) -> anyhow::Result<(Cid, Vec<(String, Cid)>)>
+where
+    DB: Blockstore,
+{
+    let file = File::open(path)
+        .await
+        .with_context(|| format!("Failed to open actors bundle {}", path.display()))?;
+    let roots = load_car(db, BufReader::new(file).compat()).await?;
+    let manifest = roots.first().context("Actors bundle without a manifest")?;
+    let (_version, actors_cid): (u32, Cid) = db
+        .get_obj(manifest)?
+        .context("Manifest of the actors bundle not found")?;
+    let actors = db
+        .get_obj(&actors_cid)?
+        .context("Actors of the bundle not found")?;
+    Ok((actors_cid, actors))
+}
+
+/// Makes the init actor assign the following IDs from [`FIRST_MINER_ID`] on.
+fn reserve_miner_ids<DB>(db: &DB, root: Cid) -> anyhow::Result<Cid>
+where
+    DB: Blockstore + Clone,
+{
+    let mut state_tree = StateTree::new_from_root(db.clone(), &root)?;
+    let mut init = state_tree
+        .get_actor(&Address::INIT_ACTOR)?
+        .context("Init actor not found")?;
+    let (address_map, next_id, network_name): (Cid, u64, String) = db
+        .get_obj(&init.state)?
+        .context("Init actor state not found")?;
+    ensure!(
+        next_id <= FIRST_MINER_ID,
+        "Too many genesis accounts, account IDs reached {next_id}"
+    );
+    init.state = db.put_obj(&(address_map, FIRST_MINER_ID, network_name), Blake2b256)?;
+    state_tree.set_actor(&Address::INIT_ACTOR, init)?;
+    state_tree.flush()
+}
+
+fn apply_implicit<DB>(
+    vm: &mut VM<DB>,
+    from: Address,
+    to: Address,
+    method_num: u64,
+    params: RawBytes,
+    value: TokenAmount,
+) -> anyhow::Result<RawBytes>
+where
+    DB: Blockstore + Clone,
+{
+    let msg = Message_v3 {
+        from: from.into(),
+        to: to.into(),
+        method_num,
+        params,
+        value: value.into(),
+        gas_limit: BLOCK_GAS_LIMIT as u64 * 10000,
+        ..Default::default()
+    };
+    let ret = vm.apply_implicit_message(&msg.into())?;
+    let receipt = ret.msg_receipt();
+    if !receipt.exit_code().is_success() {
+        bail!(
+            "Genesis message to {to} calling method {method_num} failed (exit: {:?}): {}",
+            receipt.exit_code(),
+            ret.failure_info().unwrap_or_default()
+        );
+    }
+    Ok(receipt.return_data())
+}
+
+fn window_post_proof(sector_size: u64) -> anyhow::Result<RegisteredPoStProof> {
+    use RegisteredPoStProof::*;
+    Ok(match sector_size {
+        0x800 => StackedDRGWindow2KiBV1,
+        0x80_0000 => StackedDRGWindow8MiBV1,
+        0x2000_0000 => StackedDRGWindow512MiBV1,
+        0x8_0000_0000 => StackedDRGWindow32GiBV1,
+        0x10_0000_0000 => StackedDRGWindow64GiBV1,
+        _ => bail!("Unsupported sector size {sector_size}"),
+    })
+}
+
+/// There is neither a chain nor a beacon to draw randomness from at genesis.
+struct GenesisRand;
+
+impl fvm::externs::Rand for GenesisRand {
+    fn get_chain_randomness(
+        &self,
+        _pers: i64,
+        _round: ChainEpoch,
+        _entropy: &[u8],
+    ) -> anyhow::Result<[u8; 32]> {
+        Ok([0; 32])
+    }
+
+    fn get_beacon_randomness(
+        &self,
+        _pers: i64,
+        _round: ChainEpoch,
+        _entropy: &[u8],
+    ) -> anyhow::Result<[u8; 32]> {
+        Ok([0; 32])
+    }
+}
+
+impl fvm3::externs::Rand for GenesisRand {
+    fn get_chain_randomness(
+        &self,
+        _pers: i64,
+        _round: ChainEpoch,
+        _entropy: &[u8],
+    ) -> anyhow::Result<[u8; 32]> {
+        Ok([0; 32])
+    }
+
+    fn get_beacon_randomness(
+        &self,
+        _pers: i64,
+        _round: ChainEpoch,
+        _entropy: &[u8],
+    ) -> anyhow::Result<[u8; 32]> {
+        Ok([0; 32])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forest_db::MemoryDB;
+
+    use super::*;
+
+    // Trimmed output of `lotus-seed genesis new` and `genesis add-miner`.
+    const TEMPLATE: &str = r#"{
+      "NetworkVersion": 18,
+      "ActorsBundle": "builtin-actors-devnet.car",
+      "Accounts": [
+        {
+          "Type": "account",
+          "Balance": "50000000000000000000000000",
+          "Meta": {
+            "Owner": "f3vvrnnenu67ux2nk7tdiop53lvn3ruayaa4n5bcimewef76ocgchx2ivd2k7hoaxmzme7dfc53vi7bofzz2eq"
+          }
+        }
+      ],
+      "Miners": [
+        {
+          "ID": "f01000",
+          "Owner": "f3vvrnnenu67ux2nk7tdiop53lvn3ruayaa4n5bcimewef76ocgchx2ivd2k7hoaxmzme7dfc53vi7bofzz2eq",
+          "Worker": "f3vvrnnenu67ux2nk7tdiop53lvn3ruayaa4n5bcimewef76ocgchx2ivd2k7hoaxmzme7dfc53vi7bofzz2eq",
+          "PeerId": "12D3KooWEGdCsa4DAP8HG4r33ymxXFcXtui192zJnY48Hjzr4KKF",
+          "MarketBalance": "0",
+          "PowerBalance": "0",
+          "SectorSize": 2048,
+          "Sectors": [{ "SectorID": 0, "ProofType": 0 }]
+        }
+      ],
+      "NetworkName": "localnet-514af5d5-6517-4e40-b4da-258d3200b9f3",
+      "VerifregRootKey": { "Type": "multisig", "Balance": "0" }
+    }"#;
+
+    #[test]
+    fn parse_lotus_template() {
+        let template: GenesisTemplate = serde_json::from_str(TEMPLATE).unwrap();
+        assert_eq!(template.network_version, NetworkVersion::V18);
+        assert_eq!(template.accounts.len(), 1);
+        assert_eq!(
+            template.accounts[0].balance,
+            TokenAmount::from_whole(50_000_000)
+        );
+        assert_eq!(template.miners.len(), 1);
+        assert_eq!(template.miners[0].id, Some(Address::new_id(1000)));
+        assert_eq!(template.miners[0].sectors.len(), 1);
+        assert_eq!(
+            window_post_proof(template.miners[0].sector_size).unwrap(),
+            RegisteredPoStProof::StackedDRGWindow2KiBV1
+        );
+        assert_eq!(template.timestamp, 0);
+    }
+
+    #[tokio::test]
+    async fn reject_network_version_of_other_chain() {
+        let template: GenesisTemplate = serde_json::from_str(TEMPLATE).unwrap();
+        let err = build_genesis(
+            &template,
+            Arc::new(ChainConfig::default()),
+            &MemoryDB::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("runs version"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn reject_pre_sealed_sectors() {
+        let template: GenesisTemplate = serde_json::from_str(TEMPLATE).unwrap();
+        let err = build_genesis(
+            &template,
+            Arc::new(ChainConfig::devnet()),
+            &MemoryDB::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("pre-sealed sectors"), "{err}");
+    }
+}
@@ -1,6 +1,8 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+mod builder;
+
 use std::{sync::Arc, time};
 
 use anyhow::bail;
@@ -21,6 +23,11 @@ use tokio::{
 use tokio_util::compat::TokioAsyncReadCompatExt;
 use url::Url;
 
+pub use self::builder::{
+    build_genesis, export_genesis, GenesisAccount, GenesisAccountMeta, GenesisMiner,
+    GenesisTemplate,
+};
+
 #[cfg(feature = "testing")]
 pub const EXPORT_SR_40: &[u8] = std::include_bytes!("export40.car");
 